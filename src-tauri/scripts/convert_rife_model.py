@@ -0,0 +1,140 @@
+"""Convert a PyTorch RIFE checkpoint into an ncnn model folder (flownet.param / flownet.bin).
+
+Usage: convert_rife_model.py --input <flownet.pkl | checkpoint folder> --output <folder>
+
+The checkpoint folder must also hold the network definition (IFNet_*.py), as Practical-RIFE
+releases do. Needs torch, pnnx and ncnn (pip install torch pnnx ncnn). The converted model
+takes in0/in1 (the two frames) and in2 (the timestep) at any size and writes out0, which is
+what the app's built-in backend feeds it; rife-ncnn-vulkan can't run it.
+
+Before anything is written to --output, the ncnn model is run at a size other than the one it
+was traced at and compared against PyTorch, so a model that converts but computes something
+else is caught here rather than in a job.
+
+Exit status is non-zero on failure, with the reason as the last line of stderr.
+"""
+
+import argparse
+import glob
+import importlib.util
+import inspect
+import os
+import shutil
+import sys
+import tempfile
+
+
+def fail(msg):
+    print(msg, file=sys.stderr)
+    sys.exit(1)
+
+
+def find_checkpoint(path):
+    if os.path.isfile(path):
+        return path
+    for pattern in ("flownet.pkl", "*.pkl", "*.pth", "*.pt"):
+        for base in (path, os.path.join(path, "train_log")):
+            found = sorted(glob.glob(os.path.join(base, pattern)))
+            if found:
+                return found[0]
+    fail(f"No .pkl/.pth checkpoint in {path}")
+
+
+def load_ifnet_class(folder):
+    sources = sorted(glob.glob(os.path.join(folder, "IFNet*.py")))
+    if not sources:
+        fail(f"No IFNet_*.py next to the checkpoint in {folder}; it defines the network and is needed to convert it")
+    sys.path.insert(0, folder)
+    spec = importlib.util.spec_from_file_location("ifnet", sources[0])
+    module = importlib.util.module_from_spec(spec)
+    spec.loader.exec_module(module)
+    if not hasattr(module, "IFNet"):
+        fail(f"{sources[0]} has no IFNet class")
+    return module.IFNet
+
+
+def main():
+    parser = argparse.ArgumentParser()
+    parser.add_argument("--input", required=True)
+    parser.add_argument("--output", required=True)
+    args = parser.parse_args()
+
+    try:
+        import ncnn
+        import numpy
+        import pnnx
+        import torch
+    except ImportError as e:
+        fail(f"Missing Python package: {e.name} (pip install torch pnnx ncnn)")
+
+    checkpoint = find_checkpoint(args.input)
+    folder = os.path.dirname(os.path.abspath(checkpoint))
+    print(f"Checkpoint: {checkpoint}", flush=True)
+
+    net = load_ifnet_class(folder)()
+    state = torch.load(checkpoint, map_location="cpu")
+    # Checkpoints saved from DataParallel prefix every key with "module.".
+    state = {k.replace("module.", "", 1): v for k, v in state.items()}
+    # Strict: a checkpoint for another IFNet version would otherwise convert into a model
+    # with random weights.
+    try:
+        net.load_state_dict(state, strict=True)
+    except RuntimeError as e:
+        fail(f"The checkpoint doesn't match {folder}'s IFNet: {e}")
+    net.eval()
+    takes_timestep = "timestep" in inspect.signature(net.forward).parameters
+
+    class Interpolate(torch.nn.Module):
+        def __init__(self, net):
+            super().__init__()
+            self.net = net
+
+        def forward(self, in0, in1, in2):
+            x = torch.cat((in0, in1), 1)
+            # in2 is a full-size timestep plane; IFNet expands a 1x1 timestep itself.
+            out = self.net(x, in2[:, :, :1, :1]) if takes_timestep else self.net(x)
+            merged = out[2] if isinstance(out, tuple) else out
+            return merged[-1] if isinstance(merged, (list, tuple)) else merged
+
+    def sample(h, w):
+        return torch.rand(1, 3, h, w), torch.rand(1, 3, h, w), torch.full((1, 1, h, w), 0.5)
+
+    model = Interpolate(net)
+    with tempfile.TemporaryDirectory() as work:
+        traced = os.path.join(work, "flownet.pt")
+        print("Tracing and converting with pnnx…", flush=True)
+        # A second input shape makes pnnx keep the spatial sizes dynamic.
+        pnnx.export(model, traced, sample(256, 256), inputs2=sample(192, 320))
+        produced = {ext: os.path.join(work, f"flownet.ncnn.{ext}") for ext in ("param", "bin")}
+        for ext, path in produced.items():
+            if not os.path.isfile(path):
+                fail(f"pnnx did not produce flownet.ncnn.{ext}")
+
+        print("Test inference…", flush=True)
+        inputs = sample(224, 384)
+        with torch.no_grad():
+            expected = model(*inputs)[0].numpy()
+        ncnn_net = ncnn.Net()
+        ncnn_net.load_param(produced["param"])
+        ncnn_net.load_model(produced["bin"])
+        ex = ncnn_net.create_extractor()
+        for i, t in enumerate(inputs):
+            ex.input(f"in{i}", ncnn.Mat(numpy.ascontiguousarray(t[0].numpy())))
+        ret, out = ex.extract("out0")
+        if ret != 0:
+            fail(f"The converted model failed to run in ncnn (error {ret})")
+        got = numpy.array(out)
+        if got.shape != expected.shape:
+            fail(f"The converted model gives {got.shape} frames for {expected.shape} input; its size isn't dynamic")
+        diff = float(numpy.abs(got - expected).max())
+        if diff > 0.02:
+            fail(f"The converted model's output differs from PyTorch's by up to {diff:.3f}")
+
+        os.makedirs(args.output, exist_ok=True)
+        for ext, path in produced.items():
+            shutil.move(path, os.path.join(args.output, f"flownet.{ext}"))
+    print(f"Wrote {args.output}", flush=True)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,344 @@
+//! Error classification: the catalog of known failures and the codes the frontend keys on.
+
+/// Raw process output translated into something a user can act on.
+#[derive(Clone, serde::Serialize)]
+pub(crate) struct ActionableError {
+    /// Stable machine-readable identifier, e.g. "vulkan_unavailable".
+    pub(crate) code: String,
+    pub(crate) title: String,
+    pub(crate) suggestion: String,
+    /// The raw line that matched.
+    pub(crate) detail: String,
+}
+
+/// (lowercase needles, code, title, suggestion), one entry per code. The first entry with a
+/// matching needle wins, so specific patterns go first. Entries without needles are codes set
+/// directly (see `catalog_error`), listed so the catalog has their text.
+pub(crate) const ERROR_PATTERNS: &[(&[&str], &str, &str, &str)] = &[
+    (
+        &["interpolated frames look blank"],
+        "blank_interpolation",
+        "RIFE produced blank frames",
+        "Try another model, update the GPU driver, or turn off UHD mode. If the source really is that dark or flat, the output is fine.",
+    ),
+    (
+        &["vkcreateinstance failed"],
+        "vulkan_unavailable",
+        "Vulkan is not available",
+        "Install or update your GPU driver with Vulkan support. On virtual machines and remote desktops Vulkan is often missing.",
+    ),
+    (
+        &["vk_error_out_of_device_memory"],
+        "gpu_out_of_memory",
+        "The GPU ran out of memory",
+        "Lower the thread count, enable UHD mode, or close other GPU-heavy applications.",
+    ),
+    (
+        &["vk_error_device_lost"],
+        "gpu_device_lost",
+        "The GPU driver reset during interpolation",
+        "Update the GPU driver and reduce the thread count; overclocked or overheating GPUs also cause this.",
+    ),
+    (
+        &["vk_error_out_of_host_memory", "cannot allocate memory"],
+        "host_out_of_memory",
+        "The system ran out of memory",
+        "Lower the thread count or close other memory-heavy applications.",
+    ),
+    (
+        &["resource temporarily unavailable"],
+        "too_many_threads",
+        "The system refused to start more threads",
+        "Lower the thread count (-j) or close other applications.",
+    ),
+    (
+        &["hwaccel initialisation returned error", "failed setup for format"],
+        "hwaccel_failed",
+        "Hardware decoding failed",
+        "Update the GPU driver; the file can still be decoded in software.",
+    ),
+    (
+        &["openencodesessionex failed", "no capable devices found"],
+        "hw_encoder_unavailable",
+        "The GPU encoder could not start",
+        "Pick a software encoder (libx264/libx265) or update the GPU driver. Consumer NVIDIA cards also limit concurrent encode sessions.",
+    ),
+    (
+        &["_wfopen"],
+        "model_files_missing",
+        "RIFE could not open the model files",
+        "Reinstall RIFE or pick a model folder that contains flownet.param and flownet.bin.",
+    ),
+    (
+        &["0xc0000135"],
+        "missing_runtime_dll",
+        "A required system DLL is missing",
+        "Install the latest Microsoft Visual C++ Redistributable (x64) and the GPU driver, then retry.",
+    ),
+    (
+        &["0xc0000005"],
+        "process_crashed",
+        "The tool crashed (access violation)",
+        "Update the GPU driver, then try fewer threads or a different model.",
+    ),
+    (
+        &["decoder (codec"],
+        "decoder_missing",
+        "This ffmpeg can't decode the input",
+        "Install a full ffmpeg build that includes a decoder for this codec, or convert the source to H.264/HEVC/ProRes first.",
+    ),
+    (
+        &["doesn't support hardware accelerated av1"],
+        "av1_decoder_missing",
+        "This ffmpeg can't decode AV1 in software",
+        "Install an ffmpeg build with libdav1d, or update the GPU driver for hardware AV1 decoding.",
+    ),
+    (
+        &["invalid data found when processing input"],
+        "input_unreadable",
+        "The input could not be read",
+        "The file may be damaged, incomplete or not a video; re-download or re-export it.",
+    ),
+    (
+        &["no space left on device", "there is not enough space on the disk"],
+        "disk_full",
+        "The disk is full",
+        "Free up space on the drive holding the app temp folder or the output, then retry.",
+    ),
+    (
+        &["permission denied", "access is denied"],
+        "permission_denied",
+        "Permission denied",
+        "Choose a folder you can write to, or check that the file is not open in another program.",
+    ),
+    (
+        &["incompatible pixel format", "unsupported pixel format"],
+        "unsupported_pixel_format",
+        "Unsupported pixel format for this encoder",
+        "Pick a different output format or encoder; some encoders only accept 8-bit 4:2:0 input.",
+    ),
+    (
+        &["unknown encoder"],
+        "encoder_missing",
+        "This ffmpeg build lacks the requested encoder",
+        "Install a full ffmpeg build (e.g. ffmpeg-full) or choose another encoder.",
+    ),
+    (
+        &["no such file or directory"],
+        "file_not_found",
+        "A file or folder could not be found",
+        "Check that the input still exists and that the tools are installed correctly.",
+    ),
+    (
+        &[],
+        "decode_failed",
+        "The input's video could not be decoded",
+        "Install a full ffmpeg build, or convert the source to H.264/HEVC first.",
+    ),
+    (
+        &[],
+        "install_damaged",
+        "An installed tool or model has changed since it was installed",
+        "Repair the tool from the Tools panel or re-import the model; if this keeps happening, check the disk and your antivirus quarantine.",
+    ),
+    (
+        &["checksum mismatch"],
+        "checksum_mismatch",
+        "A download or bundle failed its checksum",
+        "Download it again or copy the bundle again; if it keeps failing, the source may have been tampered with.",
+    ),
+    (
+        &["checksum file", "lists no sha-256 checksum"],
+        "checksum_missing",
+        "There is no checksum to verify against",
+        "Copy the bundle's .sha256 file along with it, or install updates from a catalog that lists checksums.",
+    ),
+    (
+        &["couldn't fetch", "download failed"],
+        "network_unavailable",
+        "The download failed",
+        "Check the internet connection and the proxy settings, then retry.",
+    ),
+    (
+        &["ca bundle not found"],
+        "ca_bundle_missing",
+        "The CA bundle file is missing",
+        "Pick an existing CA bundle in the network settings, or clear the setting.",
+    ),
+    (
+        &["update checks are off"],
+        "update_checks_off",
+        "Update checks are turned off",
+        "Turn on update checks in settings to look for new tool and model versions.",
+    ),
+    (
+        &["running job(s); wait for them", "before switching profiles", "is still running; cancel it"],
+        "jobs_running",
+        "Jobs are still running",
+        "Wait for the running jobs to finish, or cancel them, then try again.",
+    ),
+    (
+        &["plugins (including vapoursynth scripts) are disabled", "stage plugins are disabled"],
+        "plugins_disabled",
+        "Stage plugins are turned off",
+        "Turn on stage plugins in settings if you trust the plugin or script; they run with your user's rights.",
+    ),
+    (
+        &["did not finish within"],
+        "tool_timeout",
+        "A tool stopped responding",
+        "The tool was stopped after its time limit. Check that the file isn't on a disconnected drive, then retry.",
+    ),
+    (
+        &["frames are gone", "no longer exists; start a new job"],
+        "intermediates_missing",
+        "The job's intermediate files are gone",
+        "Start the job again; keep intermediates to retry or analyze stages later.",
+    ),
+    (
+        &["invalid job id", "invalid model name", "invalid profile name", "invalid plugin name", "invalid update entry", "invalid version in bundle", "invalid path in bundle"],
+        "invalid_name",
+        "A name contains characters that aren't allowed",
+        "Use only letters, digits, '-' and '_', without path separators.",
+    ),
+    (
+        &["is not listed in the bundle manifest", "unsupported bundle format", "bundle contains an unknown tool", "not a bundle"],
+        "bundle_invalid",
+        "The bundle can't be imported",
+        "Export the bundle again with the same app version and copy it in full.",
+    ),
+    (
+        &["too old for tensorrt", "couldn't read the compute capability"],
+        "tensorrt_unsupported_gpu",
+        "This GPU can't run the TensorRT backend",
+        "Use the Vulkan (ncnn) backend; TensorRT needs an NVIDIA RTX/Turing or newer card.",
+    ),
+    (
+        &["core ml builds need"],
+        "coreml_unsupported",
+        "Core ML needs Apple Silicon",
+        "Use the Vulkan (ncnn) backend on this Mac.",
+    ),
+    (
+        &["decode errors in the input"],
+        "too_many_decode_errors",
+        "The input has too many damaged frames",
+        "Raise the corrupt-input error limit, or repair or re-export the source.",
+    ),
+    (
+        &["segment seam at frame", "across its seam"],
+        "segment_seam_mismatch",
+        "Segmented extraction lost frames at a seam",
+        "Turn off segmented extraction for this source.",
+    ),
+    (
+        &["source policy doesn't allow"],
+        "source_policy_blocked",
+        "The source policy blocks this source",
+        "Change the source policy for this kind of source in settings, or convert the source first.",
+    ),
+    (
+        &["av1an can't", "av1an encodes", "av1an is only available", "can't be written by av1an"],
+        "av1an_unsupported",
+        "av1an can't encode this job",
+        "Use the ffmpeg encoder, or switch to libsvtav1 with a CRF for av1an.",
+    ),
+    (
+        &["keyframe interval must", "keyframe interval can't", "b-frame count can't", "b-frames are supported", "must be fewer than the keyframe interval", "quality must be 0-51", "is set by the profile", ": preset is not supported", "target file size needs"],
+        "encode_settings_invalid",
+        "The encode settings don't fit this encoder",
+        "Adjust the encoder settings to what the chosen encoder supports.",
+    ),
+    (
+        &["is newer than this version supports"],
+        "newer_format",
+        "The file was made by a newer version",
+        "Update the app, then import it again.",
+    ),
+    (
+        &["output file already exists", "already exists; pick another name"],
+        "output_exists",
+        "The output already exists",
+        "Pick another output name, or choose to rename or overwrite on conflict.",
+    ),
+];
+
+/// Error of a command the frontend needs to tell apart without parsing the message: a
+/// catalogued `code` (see `ERROR_PATTERNS`) and the message. Plain `String` errors convert
+/// with the code "error", so `?` keeps working inside these commands.
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct CommandError {
+    pub(crate) code: String,
+    pub(crate) message: String,
+}
+
+impl CommandError {
+    pub(crate) fn new(code: &str, message: String) -> CommandError {
+        CommandError { code: code.to_string(), message }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::new("error", message)
+    }
+}
+
+impl From<&str> for CommandError {
+    fn from(message: &str) -> Self {
+        CommandError::new("error", message.to_string())
+    }
+}
+
+impl From<CommandError> for String {
+    fn from(e: CommandError) -> Self {
+        e.message
+    }
+}
+
+/// The catalogued error `code` with `detail` as the raw text.
+pub(crate) fn catalog_error(code: &str, detail: String) -> ActionableError {
+    let (_, _, title, suggestion) = ERROR_PATTERNS.iter().find(|e| e.1 == code).copied().unwrap_or((&[], code, code, ""));
+    ActionableError { code: code.to_string(), title: title.to_string(), suggestion: suggestion.to_string(), detail }
+}
+
+/// Match raw stderr/exit-status text against the known error table.
+pub(crate) fn classify_process_error(text: &str) -> Option<ActionableError> {
+    for line in text.lines().rev() {
+        let lower = line.to_lowercase();
+        for (needles, code, title, suggestion) in ERROR_PATTERNS {
+            if needles.iter().any(|n| lower.contains(n)) {
+                return Some(ActionableError {
+                    code: code.to_string(),
+                    title: title.to_string(),
+                    suggestion: suggestion.to_string(),
+                    detail: line.trim().to_string(),
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Keep the last `max` lines of a process' output for error reporting.
+pub(crate) fn push_tail(tail: &mut Vec<String>, line: &str, max: usize) {
+    tail.push(line.to_string());
+    if tail.len() > max {
+        let excess = tail.len() - max;
+        tail.drain(0..excess);
+    }
+}
+
+/// A failure message with the exit status and the last few lines of output appended.
+pub(crate) fn failure_message(what: &str, status: Option<std::process::ExitStatus>, tail: &[String]) -> String {
+    let mut msg = match status {
+        Some(st) => format!("{what} ({st})"),
+        None => what.to_string(),
+    };
+    let start = tail.len().saturating_sub(8);
+    for line in &tail[start..] {
+        msg.push('\n');
+        msg.push_str(line);
+    }
+    msg
+}
@@ -0,0 +1,172 @@
+//! Output library: finished jobs filed under `<year>/<month>/<job name>/` with a manifest.
+
+use super::*;
+
+/// Manifest file in each library item folder.
+const LIBRARY_MANIFEST: &str = "library.json";
+
+/// Root of the output library: the configured folder, else `Output/` in the app folder.
+pub(crate) fn library_root(root: &Path, settings: &AppSettings) -> PathBuf {
+    match settings.library_dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(d) => PathBuf::from(d),
+        None => root.join("Output"),
+    }
+}
+
+/// Folder for a new library item: `<year>/<month>/<name>`, numbered when the name is already
+/// taken this month.
+pub(crate) fn library_item_dir(library: &Path, name: &str) -> PathBuf {
+    let now = chrono::Local::now();
+    let month = library.join(now.format("%Y").to_string()).join(now.format("%m").to_string());
+    let mut dir = month.join(name);
+    let mut n = 2;
+    while dir.exists() {
+        dir = month.join(format!("{name} ({n})"));
+        n += 1;
+    }
+    dir
+}
+
+fn in_library(library: &Path, path: &Path) -> bool {
+    canonicalize_lenient(path).starts_with(canonicalize_lenient(library))
+}
+
+/// Claim the item folder of an output that goes into the library and return the output path
+/// inside it. The app owns those folders, so a suggested path doesn't have to exist yet; the
+/// folder is made with `create_dir`, and one that already exists (an earlier item, or another
+/// job started from the same suggestion) moves the output to the next free `<name> (n)`.
+pub(crate) fn claim_library_output(root: &Path, settings: &AppSettings, output: &Path) -> Result<PathBuf, String> {
+    let library = library_root(root, settings);
+    let (Some(dir), Some(file)) = (output.parent(), output.file_name()) else { return Ok(output.to_path_buf()) };
+    let (Some(month), Some(name)) = (dir.parent(), dir.file_name()) else { return Ok(output.to_path_buf()) };
+    // Only `<year>/<month>/<name>` item folders; other outputs in the library stay put.
+    let depth = canonicalize_lenient(month)
+        .strip_prefix(canonicalize_lenient(&library))
+        .map(|rel| rel.components().count())
+        .ok();
+    if depth != Some(2) {
+        return Ok(output.to_path_buf());
+    }
+    fs::create_dir_all(month).map_err(|e| fs_error("create", month, &e))?;
+    let name = name.to_string_lossy();
+    // A suggested "<name> (2)" counts on from 2 rather than becoming "<name> (2) (2)".
+    let base = match name.rsplit_once(" (") {
+        Some((base, n)) if n.strip_suffix(')').is_some_and(|n| n.parse::<u32>().is_ok()) => base,
+        _ => &name,
+    };
+    for n in 1..10_000 {
+        let candidate = if n == 1 { month.join(&*name) } else { month.join(format!("{base} ({n})")) };
+        match fs::create_dir(&candidate) {
+            Ok(()) => return Ok(candidate.join(file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(fs_error("create", &candidate, &e)),
+        }
+    }
+    Err(format!("Could not find a free library folder for {name}"))
+}
+
+/// What a library item folder holds. `job_id` links it back to the job history.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct LibraryManifest {
+    job_id: String,
+    /// Output file name inside the item folder.
+    output: String,
+    input: String,
+    created_at: String,
+    model: String,
+    source_duration_secs: f64,
+    frames_out: u64,
+}
+
+/// Write the manifest next to a finished job's output when that output is in the library.
+pub(crate) fn write_library_manifest(root: &Path, settings: &AppSettings, job_id: &str) {
+    let Some(r) = load_history(root).into_iter().find(|r| r.job_id == job_id) else { return };
+    let output = PathBuf::from(&r.output);
+    let Some(dir) = output.parent() else { return };
+    if !output.is_file() || !in_library(&library_root(root, settings), &output) {
+        return;
+    }
+    let manifest = LibraryManifest {
+        job_id: r.job_id,
+        output: output.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        input: r.input,
+        created_at: r.finished_at.unwrap_or(r.started_at),
+        model: r.model,
+        source_duration_secs: r.source_duration_secs,
+        frames_out: r.frames_out,
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&manifest) {
+        let _ = fs::write(dir.join(LIBRARY_MANIFEST), json);
+    }
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct LibraryItem {
+    /// Item folder; what `delete_library_item` takes.
+    path: String,
+    year: String,
+    month: String,
+    manifest: LibraryManifest,
+    size_bytes: u64,
+    /// The job's history entry, unless it has been cleared since.
+    job: Option<JobRecord>,
+}
+
+fn load_library(root: &Path, settings: &AppSettings) -> Vec<LibraryItem> {
+    let history = load_history(root);
+    let subdirs = |dir: &Path| -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .map(|rd| rd.flatten().map(|e| e.path()).filter(|p| p.is_dir()).collect())
+            .unwrap_or_default()
+    };
+    let name = |p: &Path| p.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut items = Vec::new();
+    for year in subdirs(&library_root(root, settings)) {
+        for month in subdirs(&year) {
+            for dir in subdirs(&month) {
+                let Some(manifest) = fs::read_to_string(dir.join(LIBRARY_MANIFEST))
+                    .ok()
+                    .and_then(|s| serde_json::from_str::<LibraryManifest>(&s).ok())
+                else {
+                    continue;
+                };
+                items.push(LibraryItem {
+                    path: dir.to_string_lossy().to_string(),
+                    year: name(&year),
+                    month: name(&month),
+                    size_bytes: dir_size_bytes(&dir),
+                    job: history.iter().find(|r| r.job_id == manifest.job_id).cloned(),
+                    manifest,
+                });
+            }
+        }
+    }
+    items.sort_by(|a, b| b.manifest.created_at.cmp(&a.manifest.created_at));
+    items
+}
+
+/// Outputs in the library, newest first.
+#[tauri::command]
+pub(crate) fn list_library(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<LibraryItem>, String> {
+    let root = app_root(&app)?;
+    Ok(load_library(&root, &state.settings(&root)))
+}
+
+/// Delete a library item folder (output and manifest), then its month and year folders once
+/// empty. The job's history entry is kept.
+#[tauri::command]
+pub(crate) fn delete_library_item(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<Vec<LibraryItem>, String> {
+    let root = app_root(&app)?;
+    let settings = state.settings(&root);
+    let library = canonicalize_lenient(&library_root(&root, &settings));
+    let dir = canonicalize_lenient(Path::new(path.trim()));
+    if dir == library || !dir.starts_with(&library) || !dir.join(LIBRARY_MANIFEST).is_file() {
+        return Err(format!("Not a library item: {}", dir.to_string_lossy()));
+    }
+    fs::remove_dir_all(&dir).map_err(|e| fs_error("delete", &dir, &e))?;
+    for parent in dir.ancestors().skip(1).take(2) {
+        let _ = fs::remove_dir(parent);
+    }
+    Ok(load_library(&root, &settings))
+}
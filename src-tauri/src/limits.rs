@@ -0,0 +1,120 @@
+//! Per-job resource limits: thread, VRAM and temp-space ceilings a job declares.
+
+use super::*;
+
+/// Ceilings a job declares so it can share the machine: threads for ffmpeg and for each RIFE
+/// `-j` count, the VRAM RIFE is sized for, and the temp space its frames may take. Unset
+/// fields don't limit anything.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct ResourceLimits {
+    max_threads: Option<u32>,
+    max_vram_mb: Option<u64>,
+    max_temp_gb: Option<f64>,
+}
+
+impl ResourceLimits {
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.max_threads.is_some_and(|t| t == 0) {
+            return Err("The thread limit must be at least 1".into());
+        }
+        if self.max_vram_mb.is_some_and(|mb| mb < 256) {
+            return Err("The VRAM limit must be at least 256 MB".into());
+        }
+        if self.max_temp_gb.is_some_and(|gb| gb.is_nan() || gb <= 0.0) {
+            return Err("The temp space limit must be more than 0 GB".into());
+        }
+        Ok(())
+    }
+
+    /// Decode and filter threads of an ffmpeg run; goes before its inputs.
+    pub(crate) fn push_ffmpeg_args(&self, cmd: &mut Command) {
+        if let Some(n) = self.max_threads {
+            cmd.arg("-threads").arg(n.to_string()).arg("-filter_threads").arg(n.to_string());
+        }
+    }
+
+    /// Encoder threads; goes after the encoder options.
+    pub(crate) fn push_encoder_args(&self, cmd: &mut Command) {
+        if let Some(n) = self.max_threads {
+            cmd.arg("-threads").arg(n.to_string());
+        }
+    }
+
+    /// av1an runs one encoder per worker: that many workers, each pinned to a single thread.
+    pub(crate) fn push_av1an_args(&self, cmd: &mut Command) {
+        if let Some(n) = self.max_threads {
+            cmd.arg("--workers").arg(n.to_string()).arg("--set-thread-affinity").arg("1");
+        }
+    }
+
+    pub(crate) fn cap_rife(&self, threads: RifeThreads) -> RifeThreads {
+        match self.max_threads {
+            Some(n) => RifeThreads { load: threads.load.min(n), proc: threads.proc.min(n), save: threads.save.min(n) },
+            None => threads,
+        }
+    }
+
+    /// GPU memory RIFE is fitted to: the free VRAM, capped at `max_vram_mb`. With a cap and no
+    /// readable GPU, the cap itself.
+    pub(crate) fn gpu_memory(&self, gpu: Option<GpuMemory>) -> Option<GpuMemory> {
+        let Some(cap) = self.max_vram_mb.map(|mb| mb * 1024 * 1024) else { return gpu };
+        match gpu {
+            Some(g) if g.free_bytes <= cap => Some(g),
+            Some(g) => Some(GpuMemory { name: format!("{} (job limit)", g.name), free_bytes: cap }),
+            None => Some(GpuMemory { name: "GPU (job limit)".into(), free_bytes: cap }),
+        }
+    }
+
+    /// `format` if its frames fit `max_temp_gb`, else WebP when `webp_ok` and it fits (with the
+    /// note to log), else an error. `png_bytes` is the `estimate_frame_bytes` estimate.
+    pub(crate) fn fit_frame_format(&self, format: FrameFormat, png_bytes: Option<u64>, webp_ok: bool) -> Result<(FrameFormat, Option<String>), String> {
+        let (Some(budget), Some(png_bytes)) = (self.max_temp_gb, png_bytes) else { return Ok((format, None)) };
+        let gb = |f: FrameFormat| png_bytes as f64 * f.size_factor() / (1024.0 * 1024.0 * 1024.0);
+        if gb(format) <= budget {
+            return Ok((format, None));
+        }
+        if format == FrameFormat::Png && webp_ok && gb(FrameFormat::Webp) <= budget {
+            let note = format!(
+                "Limits: PNG frames need ~{:.1} GB, over the {budget} GB temp budget; using WebP (~{:.1} GB)",
+                gb(FrameFormat::Png),
+                gb(FrameFormat::Webp)
+            );
+            return Ok((FrameFormat::Webp, Some(note)));
+        }
+        Err(format!(
+            "{} frames need ~{:.1} GB of temp space, over the job's {budget} GB budget. Raise the budget, \
+             interpolate a region, or use draft quality",
+            format.ext().to_uppercase(),
+            gb(format)
+        ))
+    }
+
+    /// Job log lines describing the ceilings in effect; `threads` is the `-j` the job asked for.
+    pub(crate) fn describe(&self, threads: RifeThreads) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(n) = self.max_threads {
+            lines.push(format!("Limits: ffmpeg runs with -threads {n}"));
+            let capped = self.cap_rife(threads);
+            if capped != threads {
+                lines.push(format!("Limits: RIFE -j {threads} capped to {capped}"));
+            }
+        }
+        if let Some(mb) = self.max_vram_mb {
+            lines.push(format!("Limits: RIFE is sized for at most {mb} MB of VRAM"));
+        }
+        if let Some(gb) = self.max_temp_gb {
+            lines.push(format!("Limits: frames may take up to {gb} GB of temp space"));
+        }
+        lines
+    }
+}
+
+impl SmoothJob {
+    /// ffmpeg for one of the job's steps, within its thread limit.
+    pub(crate) fn ffmpeg_command(&self) -> Command {
+        let mut cmd = tool_command(&self.ffmpeg);
+        self.limits.push_ffmpeg_args(&mut cmd);
+        cmd
+    }
+}
@@ -1,6 +1,95 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+/// Emit a pipeline event. The untagged event keeps the single-job UI working; when the thread
+/// belongs to a job, a `job_event` copy carries the job id so concurrent jobs can be told apart.
+///
+/// `pipeline_progress` and `pipeline_log` are queued and sent coalesced (see `EventCoalescer`);
+/// any other event first flushes what its job has queued, so it never overtakes earlier logs.
+fn emit_pipeline<S: serde::Serialize + Clone>(app: &tauri::AppHandle, event: &'static str, payload: S) {
+    let job_id = current_job_id();
+    match event {
+        "pipeline_progress" => {
+            if let Some(pct) = serde_json::to_value(&payload).ok().and_then(|v| v.as_f64()) {
+                coalesce_event(app, job_id, |p| p.progress = Some(pct));
+                return;
+            }
+        }
+        "pipeline_log" => {
+            if let Some(line) = serde_json::to_value(&payload).ok().and_then(|v| v.as_str().map(String::from)) {
+                coalesce_event(app, job_id, |p| p.logs.push(line));
+                return;
+            }
+        }
+        _ => {}
+    }
+    flush_job_events(&job_id, event == "pipeline_done");
+    if event == "pipeline_stage" {
+        coalesce_event(app, job_id.clone(), PendingEvents::new_stage);
+    }
+    emit_tagged(app, job_id, event, payload);
+}
+
+fn emit_tagged<S: serde::Serialize + Clone>(app: &tauri::AppHandle, job_id: Option<String>, event: &'static str, payload: S) {
+    if let Some(job_id) = job_id {
+        let _ = app.emit("job_event", JobEvent { job_id, event, payload: payload.clone() });
+    }
+    let _ = app.emit(event, payload);
+}
+
+/// Progress that also reports how many frames are done, so the status event can carry fps.
+fn emit_progress_frames(app: &tauri::AppHandle, pct: f64, frames: u64) {
+    coalesce_event(app, current_job_id(), |p| {
+        p.progress = Some(pct);
+        p.note_frames(frames);
+    });
+}
+
+#[derive(Clone, serde::Serialize)]
+struct JobEvent<S> {
+    job_id: String,
+    event: &'static str,
+    payload: S,
+}
+
+thread_local! {
+    static CURRENT_JOB: std::cell::RefCell<Option<String>> = const { std::cell::RefCell::new(None) };
+}
+
+fn current_job_id() -> Option<String> {
+    CURRENT_JOB.with(|j| j.borrow().clone())
+}
+
+/// Tags pipeline events emitted on this thread with a job id until dropped.
+struct JobScope(Option<String>);
+
+impl JobScope {
+    fn enter(job_id: Option<String>) -> Self {
+        JobScope(CURRENT_JOB.with(|j| j.replace(job_id)))
+    }
+}
+
+impl Drop for JobScope {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT_JOB.with(|j| *j.borrow_mut() = previous);
+    }
+}
+
+/// `std::thread::spawn` for helper threads of a job (log readers etc.) so their events keep
+/// the job id.
+fn spawn_in_job<F, T>(f: F) -> std::thread::JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let job_id = current_job_id();
+    std::thread::spawn(move || {
+        let _scope = JobScope::enter(job_id);
+        f()
+    })
+}
+
 fn emit_log_limited(app: &tauri::AppHandle, msg: &str) {
     let mut s = msg.trim().to_string();
     if s.len() > 400 {
@@ -8,1102 +97,12874 @@ fn emit_log_limited(app: &tauri::AppHandle, msg: &str) {
         s.push_str("…");
     }
     if !s.is_empty() {
-        let _ = app.emit("pipeline_log", s);
+        emit_pipeline(app, "pipeline_log", s);
     }
 }
 
-fn emit_stage(app: &tauri::AppHandle, msg: &str) {
-    let _ = app.emit("pipeline_stage", msg.to_string());
+/// What a job is doing, as sent in `pipeline_stage_info`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StageId {
+    Scheduled,
+    QuietHours,
+    WaitingForSlot,
+    PowerHold,
+    Extract,
+    Interpolate,
+    /// Interpolating and encoding in one pass (streamed frames).
+    InterpolateEncode,
+    Encode,
+    Paused,
+    PostHook,
+    Remux,
+    /// A stage plugin (see `run_stage_plugins`).
+    Plugin,
 }
 
-
-fn parse_ffmpeg_progress_line(line: &str) -> Option<(&str, &str)> {
-    let mut it = line.splitn(2, '=');
-    let k = it.next()?.trim();
-    let v = it.next()?.trim();
-    if k.is_empty() { return None; }
-    Some((k, v))
+impl StageId {
+    /// Position among the three pipeline steps, for the stages that are one.
+    fn step(self) -> Option<u32> {
+        match self {
+            StageId::Extract => Some(1),
+            StageId::Interpolate => Some(2),
+            StageId::InterpolateEncode | StageId::Encode => Some(3),
+            _ => None,
+        }
+    }
 }
 
-fn probe_duration_and_fps(ffmpeg: &Path, input: &Path) -> Option<(f64, f64)> {
-    let ffprobe = ffmpeg.parent()?.join("ffprobe");
-    if !ffprobe.exists() { return None; }
+const PIPELINE_STEPS: u32 = 3;
 
-    let dur_out = Command::new(&ffprobe)
-        .arg("-v").arg("error")
-        .arg("-show_entries").arg("format=duration")
-        .arg("-of").arg("default=noprint_wrappers=1:nokey=1")
-        .arg(input)
-        .output().ok()?;
-    let duration = String::from_utf8_lossy(&dur_out.stdout).trim().parse::<f64>().ok()?;
+#[derive(Clone, serde::Serialize)]
+struct StageEvent {
+    stage_id: StageId,
+    index: Option<u32>,
+    total: Option<u32>,
+    /// English display text, without the step counter.
+    label: String,
+    /// `label` as a catalog message, for translation.
+    message: Message,
+}
 
-    let fps_out = Command::new(&ffprobe)
-        .arg("-v").arg("error")
-        .arg("-select_streams").arg("v:0")
-        .arg("-show_entries").arg("stream=r_frame_rate")
-        .arg("-of").arg("default=noprint_wrappers=1:nokey=1")
-        .arg(input)
-        .output().ok()?;
-    let fps_s = String::from_utf8_lossy(&fps_out.stdout).trim().to_string();
-    let fps = if let Some((a,b)) = fps_s.split_once('/') {
-        let na = a.parse::<f64>().ok()?;
-        let nb = b.parse::<f64>().ok()?;
-        if nb != 0.0 { na/nb } else { 0.0 }
-    } else {
-        fps_s.parse::<f64>().unwrap_or(0.0)
+/// Announce a stage: `pipeline_stage_info` carries the stage id and step for the UI and
+/// automation; `pipeline_stage` keeps the display string ("… (step 1/3)") for older listeners.
+fn emit_stage(app: &tauri::AppHandle, stage: StageId, message: Message) {
+    let index = stage.step();
+    let text = match index {
+        Some(i) => format!("{} (step {i}/{PIPELINE_STEPS})", message.text),
+        None => message.text.clone(),
     };
+    emit_pipeline(app, "pipeline_stage", text);
+    emit_pipeline(app, "pipeline_stage_info", StageEvent {
+        stage_id: stage,
+        index,
+        total: index.map(|_| PIPELINE_STEPS),
+        label: message.text.clone(),
+        message,
+    });
+}
 
-    Some((duration, fps))
+/// `logs/` under the app data dir, set once at startup; per-job command logs live here.
+static JOB_LOG_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct LoggedCommand {
+    at: String,
+    stage: String,
+    cwd: Option<String>,
+    command_line: String,
 }
 
+fn command_log_path(dir: &Path, job_id: &str) -> PathBuf {
+    dir.join(format!("{job_id}.commands.jsonl"))
+}
 
-fn preferred_ffmpeg_path() -> Option<PathBuf> {
-    let candidates = [
-        "/opt/homebrew/opt/ffmpeg-full/bin/ffmpeg",
-        "/opt/homebrew/bin/ffmpeg",
-        "/usr/local/bin/ffmpeg",
-        "/usr/bin/ffmpeg",
-    ];
-    for c in candidates {
-        let p = PathBuf::from(c);
-        if p.exists() {
-            return Some(p);
-        }
+/// Quote one argument for the user's shell: single quotes for sh, double quotes for cmd.exe.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,+%@".contains(c));
+    if plain {
+        arg.to_string()
+    } else if cfg!(windows) {
+        format!("\"{}\"", arg.replace('"', "\\\""))
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
     }
-    None
 }
 
+/// Command line that reproduces `cmd` in a terminal, including its working directory.
+fn reproducible_command_line(cmd: &Command) -> String {
+    let mut parts = vec![shell_quote(&cmd.get_program().to_string_lossy())];
+    parts.extend(cmd.get_args().map(|a| shell_quote(&a.to_string_lossy())));
+    let line = parts.join(" ");
+    match cmd.get_current_dir() {
+        Some(dir) => format!("cd {} && {line}", shell_quote(&dir.to_string_lossy())),
+        None => line,
+    }
+}
 
-use std::fs;
-use std::io::BufRead;
-use std::io::BufReader;
-use std::process::Stdio;
-use std::path::{Path, PathBuf};
-use std::process::Command;
+/// Append `cmd` to the current job's command log and hand it back for chaining. Outside a job
+/// nothing is recorded.
+fn log_command<'a>(stage: &str, cmd: &'a mut Command) -> &'a mut Command {
+    let (Some(job_id), Some(dir)) = (current_job_id(), JOB_LOG_DIR.get()) else { return cmd };
+    let entry = LoggedCommand {
+        at: chrono::Local::now().to_rfc3339(),
+        stage: stage.to_string(),
+        cwd: cmd.get_current_dir().map(|d| d.to_string_lossy().to_string()),
+        command_line: reproducible_command_line(cmd),
+    };
+    if let Ok(line) = serde_json::to_string(&entry) {
+        let _ = fs::create_dir_all(dir);
+        if let Ok(mut f) = fs::OpenOptions::new().create(true).append(true).open(command_log_path(dir, &job_id)) {
+            use std::io::Write;
+            let _ = writeln!(f, "{line}");
+        }
+    }
+    cmd
+}
 
-use tauri::{AppHandle, Manager};
-use tauri::Emitter;
+/// `log_command` for a pipeline stage's main process, which is also shown in the UI log.
+fn log_stage_command<'a>(app: &AppHandle, stage: &str, cmd: &'a mut Command) -> &'a mut Command {
+    emit_pipeline(app, "pipeline_log", format!("$ {}", reproducible_command_line(cmd)));
+    log_command(stage, cmd)
+}
 
+/// The last command a job ran for `stage` (any stage when empty), ready to paste into a terminal.
 #[tauri::command]
-fn check_environment() -> String {
-    let os = std::env::consts::OS;
-    let arch = std::env::consts::ARCH;
-    format!("Environment OK | OS: {} | ARCH: {}", os, arch)
+fn copy_command(app: AppHandle, job_id: String, stage: Option<String>) -> Result<String, String> {
+    validate_job_id(&job_id)?;
+    let dir = app_root(&app)?.join("logs");
+    let text = fs::read_to_string(command_log_path(&dir, &job_id))
+        .map_err(|_| format!("No commands were logged for {job_id}"))?;
+    let stage = stage.unwrap_or_default();
+    text.lines()
+        .rev()
+        .filter_map(|l| serde_json::from_str::<LoggedCommand>(l).ok())
+        .find(|c| stage.is_empty() || c.stage == stage)
+        .map(|c| c.command_line)
+        .ok_or_else(|| format!("Job {job_id} has no logged command for stage '{stage}'"))
 }
 
 
-#[tauri::command]
-fn get_max_threads_string() -> String {
-    let n = std::thread::available_parallelism().map(|v| v.get()).unwrap_or(4);
-    format!("{0}:{0}:{0}", n)
+fn parse_ffmpeg_progress_line(line: &str) -> Option<(&str, &str)> {
+    let mut it = line.splitn(2, '=');
+    let k = it.next()?.trim();
+    let v = it.next()?.trim();
+    if k.is_empty() { return None; }
+    Some((k, v))
 }
 
-#[tauri::command]
-fn get_default_rife_model_dir(app: AppHandle) -> Option<String> {
-    let root = app_root(&app).ok()?;
-    ensure_dirs(&root).ok()?;
-    let (_ffmpeg, _rife, rife_models) = find_installed_tool_paths(&root);
-    rife_models.map(|p| p.to_string_lossy().to_string())
+/// ffprobe is expected to sit next to the resolved ffmpeg binary.
+fn ffprobe_path(ffmpeg: &Path) -> Option<PathBuf> {
+    let name = if cfg!(windows) { "ffprobe.exe" } else { "ffprobe" };
+    let ffprobe = ffmpeg.parent()?.join(name);
+    if ffprobe.exists() { Some(ffprobe) } else { None }
 }
 
+// -------------------- Tool processes --------------------
 
-fn app_root(app: &AppHandle) -> Result<PathBuf, String> {
-    let data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
-    Ok(data_dir.join("RIFE-Interpolator"))
-}
+/// Variables from the user's environment that change how ffmpeg logs (report files, colour
+/// codes in the output we parse); removed for every tool.
+const SCRUBBED_ENV: &[&str] = &["FFREPORT", "AV_LOG_FORCE_COLOR", "AV_LOG_FORCE_256COLOR", "AV_LOG_FORCE_NOCOLOR"];
 
-fn ensure_dirs(root: &Path) -> Result<(), String> {
-    let dirs = [
-        root.join("bin"),
-        root.join("bin/ffmpeg"),
-        root.join("bin/rife"),
-        root.join("models"),
-        root.join("temp"),
-        root.join("cache"),
-    ];
+/// Limit for ffprobe and other reads of a media file, which can stall on network volumes.
+const PROBE_TIMEOUT_SECS: u64 = 60;
+/// Limit for quick system queries (nvidia-smi, df, battery, process lists).
+const QUERY_TIMEOUT_SECS: u64 = 10;
+/// Limit for downloading, packing and unpacking tools and models, and converting a model.
+const INSTALL_TIMEOUT_SECS: u64 = 3600;
 
-    for d in dirs {
-        fs::create_dir_all(&d)
-            .map_err(|e| format!("Failed to create dir {}: {e}", d.to_string_lossy()))?;
-    }
-    Ok(())
+/// Limit for an analysis pass decoding `frames` frames (black bars, scene cuts, holds): room
+/// for 8K sources at a couple of frames a second, but a hung ffmpeg still ends.
+fn analysis_timeout_secs(frames: f64) -> u64 {
+    PROBE_TIMEOUT_SECS + (frames.max(0.0) / 2.0) as u64
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
-    fs::create_dir_all(dst).map_err(|e| e.to_string())?;
+/// `Command::new` for every external program the app runs: stdin closed (callers that feed
+/// a tool set their own), `SCRUBBED_ENV` removed and, on Windows, no console window.
+fn tool_command(program: impl AsRef<std::ffi::OsStr>) -> Command {
+    let mut cmd = Command::new(program);
+    cmd.stdin(Stdio::null());
+    for var in SCRUBBED_ENV {
+        cmd.env_remove(var);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    cmd
+}
 
-    for entry in fs::read_dir(src).map_err(|e| e.to_string())? {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
+/// `Command::output` for a one-shot tool, killing it if it runs longer than `timeout_secs`.
+fn run_tool(cmd: &mut Command, timeout_secs: u64) -> Result<std::process::Output, String> {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let mut child = cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("{program} failed to start: {e}"))?;
+    // Both pipes are drained as the tool runs so a full one can't stall it.
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|o| Box::new(o) as _));
+    let stderr = drain(child.stderr.take().map(|e| Box::new(e) as _));
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(timeout_secs);
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| format!("Failed waiting for {program}: {e}"))? {
+            break status;
+        }
+        if std::time::Instant::now() >= deadline {
+            // Reaches the rest of the group when the tool leads one (see `new_process_group`).
+            if cfg!(unix) {
+                signal_process(child.id(), "-KILL");
+            }
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(format!("{program} did not finish within {timeout_secs}s"));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
 
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path).map_err(|e| e.to_string())?;
+/// `run_tool`'s time limit for a tool whose output is read as it runs: kills the process
+/// once `timeout_secs` pass without `finish`.
+struct ToolDeadline {
+    finished: std::sync::mpsc::Sender<()>,
+    watchdog: std::thread::JoinHandle<bool>,
+}
 
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                let mut perms =
-                    fs::metadata(&dst_path).map_err(|e| e.to_string())?.permissions();
-                perms.set_mode(0o755);
-                fs::set_permissions(&dst_path, perms).map_err(|e| e.to_string())?;
+impl ToolDeadline {
+    fn start(pid: u32, timeout_secs: u64) -> Self {
+        let (finished, watched) = std::sync::mpsc::channel::<()>();
+        let watchdog = std::thread::spawn(move || {
+            let timeout = std::time::Duration::from_secs(timeout_secs);
+            let timed_out = watched.recv_timeout(timeout) == Err(std::sync::mpsc::RecvTimeoutError::Timeout);
+            if timed_out {
+                force_kill(pid);
             }
-        }
+            timed_out
+        });
+        ToolDeadline { finished, watchdog }
+    }
+
+    /// Call once the process has exited; whether it was killed for running over.
+    fn finish(self) -> bool {
+        drop(self.finished);
+        self.watchdog.join().unwrap_or(false)
     }
-    Ok(())
 }
 
-#[tauri::command]
-fn get_app_paths(app: AppHandle) -> Result<Vec<String>, String> {
-    let root = app_root(&app)?;
-    ensure_dirs(&root)?;
+// -------------------- Probe cache --------------------
 
-    let paths = vec![
-        root.clone(),
-        root.join("bin/ffmpeg"),
-        root.join("bin/rife"),
-        root.join("models"),
-        root.join("temp"),
-        root.join("cache"),
-    ];
+/// Where ffprobe results are cached; set once the app root is known.
+static PROBE_CACHE_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+/// Cached results older than this are probed again even if the file looks unchanged.
+const PROBE_CACHE_TTL_SECS: u64 = 7 * 24 * 3600;
 
-    Ok(paths
-        .into_iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect())
+#[derive(serde::Serialize, serde::Deserialize)]
+struct ProbeCacheEntry {
+    args: Vec<String>,
+    size: u64,
+    mtime_ms: u128,
+    cached_at: u64,
+    stdout: String,
 }
 
-fn has_rife_executable(dir: &Path) -> bool {
-    if let Ok(entries) = fs::read_dir(dir) {
-        for e in entries.flatten() {
-            let p = e.path();
-            if p.is_file() {
-                if let Some(name) = p.file_name().and_then(|n| n.to_str()) {
-                    if name.to_ascii_lowercase().starts_with("rife") {
-                        return true;
-                    }
-                }
-            }
-        }
-    }
-    false
+fn unix_now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
-#[tauri::command]
-fn tool_status(app: AppHandle, tool: String) -> Result<String, String> {
-    let root = app_root(&app)?;
-    let tool_dir = root.join("bin").join(&tool);
+/// Size and modification time identifying the current contents of a file.
+fn file_identity(path: &Path) -> Option<(u64, u128)> {
+    let meta = fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some((meta.len(), mtime.as_millis()))
+}
 
-    if !tool_dir.exists() {
-        return Ok("missing".into());
+/// File name for a persisted cache entry: the first 16 hex digits of the SHA-256 of `parts`,
+/// each followed by a NUL. Unlike `DefaultHasher` it stays the same across Rust releases.
+fn cache_key<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+        hasher.update([0]);
     }
+    format!("{:x}", hasher.finalize())[..16].to_string()
+}
 
-    if let Ok(versions) = fs::read_dir(&tool_dir) {
-        for v in versions.flatten() {
-            let p = v.path();
-            if !p.is_dir() {
-                continue;
-            }
+/// Cache directory for one input path; each probe query is a file inside it.
+fn probe_cache_dir_for(input: &Path) -> Option<PathBuf> {
+    let path = fs::canonicalize(input).unwrap_or_else(|_| input.to_path_buf());
+    Some(PROBE_CACHE_DIR.get()?.join(cache_key([path.as_os_str().as_encoded_bytes()])))
+}
 
-            if tool == "rife" {
-                if has_rife_executable(&p) {
-                    return Ok("installed".into());
-                }
-            } else {
-                // ffmpeg: any file in any version folder counts as installed
-                if let Ok(files) = fs::read_dir(&p) {
-                    for f in files.flatten() {
-                        if f.path().is_file() {
-                            return Ok("installed".into());
-                        }
-                    }
-                }
-            }
+/// stdout of `ffprobe -v error <args> <input>`, or None if ffprobe fails. Results are cached in
+/// `cache/probe` and reused while the file's size and mtime match and the entry is within the TTL.
+fn run_ffprobe(ffprobe: &Path, args: &[&str], input: &Path) -> Option<String> {
+    let identity = file_identity(input);
+    let cache_file = probe_cache_dir_for(input)
+        .map(|dir| dir.join(format!("{}.json", cache_key(args.iter().map(|a| a.as_bytes())))));
+    if let (Some((size, mtime_ms)), Some(file)) = (identity, &cache_file) {
+        let hit = fs::read_to_string(file)
+            .ok()
+            .and_then(|t| serde_json::from_str::<ProbeCacheEntry>(&t).ok())
+            .filter(|e| {
+                e.size == size
+                    && e.mtime_ms == mtime_ms
+                    && e.args.iter().map(String::as_str).eq(args.iter().copied())
+                    && unix_now_secs().saturating_sub(e.cached_at) < PROBE_CACHE_TTL_SECS
+            });
+        if let Some(entry) = hit {
+            touch_cache_entry(file);
+            return Some(entry.stdout);
         }
     }
 
-    Ok("missing".into())
+    let out = run_tool(log_command("probe", tool_command(ffprobe)
+        .arg("-v").arg("error")
+        .args(args)
+        .arg(input)), PROBE_TIMEOUT_SECS).ok()?;
+    if !out.status.success() { return None; }
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+
+    if let (Some((size, mtime_ms)), Some(file)) = (identity, cache_file) {
+        let entry = ProbeCacheEntry {
+            args: args.iter().map(|a| a.to_string()).collect(),
+            size,
+            mtime_ms,
+            cached_at: unix_now_secs(),
+            stdout: stdout.clone(),
+        };
+        if let (Some(dir), Ok(json)) = (file.parent(), serde_json::to_string(&entry)) {
+            let _ = fs::create_dir_all(dir).and_then(|_| fs::write(&file, json));
+        }
+    }
+    Some(stdout)
 }
 
+/// Drop cached probe results for a file, e.g. after it was replaced in place.
 #[tauri::command]
-fn install_tool(
-    app: AppHandle,
-    source_path: String,
-    tool: String,
-    version: String,
-) -> Result<String, String> {
-    let src = Path::new(&source_path);
-    if !src.exists() {
-        return Err("Source path does not exist".into());
+fn invalidate_probe_cache(path: String) -> Result<(), String> {
+    let Some(dir) = probe_cache_dir_for(Path::new(&path)) else { return Ok(()) };
+    match fs::remove_dir_all(&dir) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to clear probe cache for {path}: {e}")),
     }
+}
 
-    let root = app_root(&app)?;
-    ensure_dirs(&root)?;
+// -------------------- Cache size --------------------
+//
+// Every folder in `cache/` is a category (probe, tensorrt, contact_sheets, downloads, bundles)
+// and each of its children an entry, evicted as a whole. Last use is the newest mtime inside
+// the entry; readers that reuse an entry without rewriting it call `touch_cache_entry`.
+// Only categories in `EVICTABLE_CACHES` are evicted.
 
-    let dest_dir = root.join("bin").join(&tool).join(&version);
-    fs::create_dir_all(&dest_dir).map_err(|e| e.to_string())?;
+/// Cache categories rebuilt on demand. Downloads and bundles are what repair reinstalls from,
+/// and TensorRT engines take minutes to build, so those only go when the user clears them.
+const EVICTABLE_CACHES: &[&str] = &["probe", "contact_sheets"];
 
-    if src.is_dir() {
-        // RIFE-style folder install (binary + models)
-        copy_dir_recursive(src, &dest_dir)?;
-        Ok(dest_dir.to_string_lossy().to_string())
-    } else {
-        // ffmpeg-style single binary
-        let filename = src.file_name().ok_or("Invalid file")?;
-        let dest = dest_dir.join(filename);
-        fs::copy(src, &dest).map_err(|e| e.to_string())?;
+/// Cache entries a job or install is writing or reading right now; eviction skips them.
+static CACHE_IN_USE: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
 
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            let mut perms =
-                fs::metadata(&dest).map_err(|e| e.to_string())?.permissions();
-            perms.set_mode(0o755);
-            fs::set_permissions(&dest, perms).map_err(|e| e.to_string())?;
-        }
+/// Marks a cache entry in use until dropped.
+struct CacheInUse(PathBuf);
 
-        Ok(dest.to_string_lossy().to_string())
+impl CacheInUse {
+    fn hold(path: &Path) -> CacheInUse {
+        lock(&CACHE_IN_USE).push(path.to_path_buf());
+        CacheInUse(path.to_path_buf())
     }
 }
 
-// -------------------- Validation helpers --------------------
-
-fn find_ffmpeg_in_version_dir(dir: &Path) -> Option<PathBuf> {
-    let entries = fs::read_dir(dir).ok()?;
-    for e in entries.flatten() {
-        let p = e.path();
-        if p.is_file() {
-            return Some(p);
+impl Drop for CacheInUse {
+    fn drop(&mut self) {
+        let mut held = lock(&CACHE_IN_USE);
+        if let Some(i) = held.iter().position(|p| *p == self.0) {
+            held.remove(i);
         }
     }
-    None
 }
 
-fn find_rife_in_version_dir(dir: &Path) -> Option<PathBuf> {
-    let entries = fs::read_dir(dir).ok()?;
-    for e in entries.flatten() {
-        let p = e.path();
-        if !p.is_file() {
-            continue;
-        }
-        let name = p.file_name()?.to_str()?.to_ascii_lowercase();
-        if name.starts_with("rife") {
-            return Some(p);
-        }
+/// Mark a cache file as used so eviction keeps it.
+fn touch_cache_entry(path: &Path) {
+    let _ = fs::File::options().append(true).open(path).and_then(|f| f.set_modified(std::time::SystemTime::now()));
+}
+
+fn newest_mtime(path: &Path) -> std::time::SystemTime {
+    let own = fs::metadata(path).and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH);
+    if !path.is_dir() {
+        return own;
     }
-    None
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| newest_mtime(&e.path()))
+        .fold(own, std::cmp::max)
 }
 
+struct CacheEntry {
+    category: String,
+    path: PathBuf,
+    bytes: u64,
+    last_used: std::time::SystemTime,
+}
 
-fn resolve_rife_model_path(models_root_or_model: &str) -> PathBuf {
-    let p = PathBuf::from(models_root_or_model);
-    if p.join("flownet.bin").exists() || p.join("model.param").exists() {
-        return p;
-    }
-    // Prefer rife-v2.3 if present
-    let preferred = p.join("rife-v2.3");
-    if preferred.exists() {
-        return preferred;
-    }
-    // Otherwise pick the first subdirectory
-    if let Ok(rd) = std::fs::read_dir(&p) {
-        for e in rd.flatten() {
-            let path = e.path();
-            if path.is_dir() {
-                return path;
-            }
+fn cache_entries(root: &Path) -> Vec<CacheEntry> {
+    let mut entries = Vec::new();
+    for cat in fs::read_dir(root.join("cache")).into_iter().flatten().flatten() {
+        let category = cat.file_name().to_string_lossy().to_string();
+        let children: Vec<PathBuf> = if cat.path().is_dir() {
+            fs::read_dir(cat.path()).into_iter().flatten().flatten().map(|e| e.path()).collect()
+        } else {
+            vec![cat.path()]
+        };
+        for path in children {
+            let bytes = if path.is_dir() { dir_size_bytes(&path) } else { fs::metadata(&path).map(|m| m.len()).unwrap_or(0) };
+            let last_used = newest_mtime(&path);
+            entries.push(CacheEntry { category: category.clone(), path, bytes, last_used });
         }
     }
-    p
+    entries
 }
 
-/// Some Windows builds of rife-ncnn-vulkan treat drive-letter absolute paths (e.g. `C:\\...`)
-/// as *relative* paths when loading model files, resulting in `_wfopen .../flownet.param failed`.
-///
-/// The most reliable way to run these builds is:
-/// - set the working directory to the directory containing the RIFE executable
-/// - pass a *relative* model folder name (e.g. `rife-v4.6`) to `-m`
-///
-/// If the selected model folder is not next to the executable, we fall back to passing an
-/// absolute path, and (on Windows) we optionally prefix with `\\?\`.
-fn compute_rife_cwd_and_model_arg(rife_bin: &Path, model_path: &Path) -> (Option<PathBuf>, std::ffi::OsString) {
-    let rife_dir = rife_bin.parent().map(|p| p.to_path_buf());
-
-    if let Some(ref dir) = rife_dir {
-        if let Some(model_parent) = model_path.parent() {
-            if model_parent == dir {
-                if let Some(name) = model_path.file_name() {
-                    return (Some(dir.clone()), name.to_os_string());
-                }
-            }
-        }
+/// Evict least recently used entries of the regenerable caches until `cache/` fits
+/// `cache_limit_mb`. Returns the bytes freed. Entries in use, and those that can't be removed
+/// (e.g. open on Windows), are skipped.
+fn enforce_cache_limit(root: &Path, settings: &AppSettings) -> u64 {
+    if settings.cache_limit_mb == 0 {
+        return 0;
     }
-
-    // Fallback: absolute model path.
-    #[cfg(windows)]
-    {
-        use std::os::windows::ffi::OsStrExt;
-        // If it's already a verbatim path (\\?\), leave it.
-        let s = model_path.to_string_lossy();
-        if s.starts_with("\\\\?\\") {
-            return (rife_dir, model_path.as_os_str().to_os_string());
+    let limit = settings.cache_limit_mb * 1024 * 1024;
+    let mut entries = cache_entries(root);
+    let mut total: u64 = entries.iter().map(|e| e.bytes).sum();
+    entries.sort_by_key(|e| e.last_used);
+    let mut freed = 0;
+    for e in entries {
+        if total <= limit {
+            break;
+        }
+        if !EVICTABLE_CACHES.contains(&e.category.as_str()) || lock(&CACHE_IN_USE).iter().any(|p| p.starts_with(&e.path)) {
+            continue;
+        }
+        let removed = if e.path.is_dir() { fs::remove_dir_all(&e.path) } else { fs::remove_file(&e.path) };
+        if removed.is_ok() {
+            total -= e.bytes;
+            freed += e.bytes;
         }
-        // Prefix with \\?\ to avoid drive-letter absolute path mishandling.
-        let mut wide: Vec<u16> = "\\\\?\\".encode_utf16().collect();
-        wide.extend(model_path.as_os_str().encode_wide());
-        return (rife_dir, std::ffi::OsString::from_wide(&wide));
-    }
-
-    #[cfg(not(windows))]
-    {
-        (rife_dir, model_path.as_os_str().to_os_string())
     }
+    freed
 }
 
-fn find_models_dir(dir: &Path) -> Option<PathBuf> {
-    // Accept common RIFE layouts:
-    // - folders like 'rife-v2.3', 'rife-v4', 'rife-anime', 'rife-UHD', etc.
-    // - models/ (some older builds)
-
-    let direct = dir.join("models");
-    if direct.is_dir() {
-        return Some(direct);
-    }
+#[derive(Clone, Debug, serde::Serialize)]
+struct CacheCategory {
+    name: String,
+    bytes: u64,
+    entries: usize,
+    /// RFC 3339 time of the least recently used entry, the next to be evicted.
+    oldest_used: Option<String>,
+    /// Whether the cache limit evicts this category (see `EVICTABLE_CACHES`).
+    evictable: bool,
+}
 
-    // Prefer the default model folder if present
-    let preferred = dir.join("rife-v2.3");
-    if preferred.is_dir() {
-        return Some(preferred);
-    }
+#[derive(Clone, Debug, serde::Serialize)]
+struct CacheBreakdown {
+    total_bytes: u64,
+    /// None when the cache is uncapped.
+    limit_bytes: Option<u64>,
+    /// Largest first.
+    categories: Vec<CacheCategory>,
+}
 
-    // Otherwise pick the first folder starting with "rife-"
-    let mut candidates: Vec<PathBuf> = Vec::new();
-    if let Ok(entries) = fs::read_dir(dir) {
-        for e in entries.flatten() {
-            let p = e.path();
-            if p.is_dir() {
-                if let Some(name) = p.file_name().and_then(|n| n.to_str()) {
-                    if name.starts_with("rife-") {
-                        candidates.push(p);
-                    }
+#[tauri::command]
+fn get_cache_breakdown(app: AppHandle, state: State<'_, AppState>) -> Result<CacheBreakdown, String> {
+    let root = app_root(&app)?;
+    let settings = state.settings(&root);
+    let mut categories: Vec<CacheCategory> = Vec::new();
+    for e in cache_entries(&root) {
+        let used = chrono::DateTime::<chrono::Local>::from(e.last_used).to_rfc3339();
+        match categories.iter_mut().find(|c| c.name == e.category) {
+            Some(c) => {
+                c.bytes += e.bytes;
+                c.entries += 1;
+                if c.oldest_used.as_ref().is_none_or(|o| used < *o) {
+                    c.oldest_used = Some(used);
                 }
             }
+            None => categories.push(CacheCategory {
+                evictable: EVICTABLE_CACHES.contains(&e.category.as_str()),
+                name: e.category,
+                bytes: e.bytes,
+                entries: 1,
+                oldest_used: Some(used),
+            }),
         }
     }
-    candidates.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-    candidates.into_iter().next()
+    categories.sort_by_key(|c| std::cmp::Reverse(c.bytes));
+    Ok(CacheBreakdown {
+        total_bytes: categories.iter().map(|c| c.bytes).sum(),
+        limit_bytes: (settings.cache_limit_mb > 0).then(|| settings.cache_limit_mb * 1024 * 1024),
+        categories,
+    })
 }
 
-fn find_installed_tool_paths(root: &Path) -> (Option<PathBuf>, Option<PathBuf>, Option<PathBuf>) {
-    let ffmpeg_root = root.join("bin/ffmpeg");
-    let rife_root = root.join("bin/rife");
-
-    let mut ffmpeg_path: Option<PathBuf> = None;
-    let mut rife_path: Option<PathBuf> = None;
-    let mut rife_models: Option<PathBuf> = None;
+// -------------------- Batch probe --------------------
+
+/// Most ffprobe processes a batch probe runs at once.
+const PROBE_BATCH_WORKERS: usize = 8;
+
+/// One file of a `probe_media_batch`, sent as a `probe_result` event.
+#[derive(Clone, Debug, serde::Serialize)]
+struct MediaProbe {
+    /// Position of the file in the requested list.
+    index: usize,
+    path: String,
+    duration_secs: Option<f64>,
+    fps: Option<f64>,
+    width: Option<u32>,
+    height: Option<u32>,
+    has_audio: bool,
+    /// Set when the file couldn't be probed; the other fields are then empty.
+    error: Option<String>,
+}
 
-    if let Ok(versions) = fs::read_dir(&ffmpeg_root) {
-        for v in versions.flatten() {
-            let p = v.path();
-            if p.is_dir() {
-                if let Some(bin) = find_ffmpeg_in_version_dir(&p) {
-                    ffmpeg_path = Some(bin);
-                    break;
-                }
-            }
-        }
+fn probe_media(ffmpeg: &Path, index: usize, path: &Path) -> MediaProbe {
+    let mut probe = MediaProbe {
+        index,
+        path: path.to_string_lossy().to_string(),
+        duration_secs: None,
+        fps: None,
+        width: None,
+        height: None,
+        has_audio: false,
+        error: None,
+    };
+    if !path.is_file() {
+        probe.error = Some("File does not exist".into());
+        return probe;
+    }
+    let Some((duration, fps)) = probe_duration_and_fps(ffmpeg, path) else {
+        probe.error = Some("Not a readable video file".into());
+        return probe;
+    };
+    probe.duration_secs = Some(duration);
+    probe.fps = Some(fps).filter(|f| *f > 0.0);
+    if let Some((w, h)) = probe_video_size(ffmpeg, path) {
+        probe.width = Some(w);
+        probe.height = Some(h);
     }
+    probe.has_audio = probe_has_audio(ffmpeg, path);
+    probe
+}
 
-    if let Ok(versions) = fs::read_dir(&rife_root) {
-        for v in versions.flatten() {
-            let p = v.path();
-            if p.is_dir() {
-                if let Some(bin) = find_rife_in_version_dir(&p) {
-                    rife_path = Some(bin);
-                    rife_models = find_models_dir(&p);
-                    break;
-                }
-            }
-        }
+/// Probe many files on a bounded pool of workers. Each result is emitted as a `probe_result`
+/// event as soon as it is ready (in completion order, see `index`), then `probe_batch_done`
+/// carries the number of files. Returns the number of files queued.
+#[tauri::command]
+fn probe_media_batch(app: AppHandle, state: State<'_, AppState>, paths: Vec<String>) -> Result<usize, String> {
+    let root = app_root(&app)?;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    if ffprobe_path(&ffmpeg).is_none() {
+        return Err("ffprobe not found next to ffmpeg".into());
     }
+    let paths: Vec<PathBuf> = paths.iter().map(|p| PathBuf::from(p.trim())).collect();
+    let total = paths.len();
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(PROBE_BATCH_WORKERS)
+        .min(total);
 
-    (ffmpeg_path, rife_path, rife_models)
+    std::thread::spawn(move || {
+        let paths = std::sync::Arc::new(paths);
+        let next = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                let (app, ffmpeg, paths, next) = (app.clone(), ffmpeg.clone(), paths.clone(), next.clone());
+                std::thread::spawn(move || loop {
+                    let i = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    let Some(path) = paths.get(i) else { break };
+                    let _ = app.emit("probe_result", probe_media(&ffmpeg, i, path));
+                })
+            })
+            .collect();
+        for h in handles {
+            let _ = h.join();
+        }
+        let _ = app.emit("probe_batch_done", total);
+    });
+    Ok(total)
 }
 
-#[derive(serde::Serialize)]
-struct ToolValidation {
-    ok: bool,
-    path: Option<String>,
-    output: String,
+// -------------------- Frame-rate conformance --------------------
+
+/// How a job's output rate is chosen, so a batch of mixed-rate sources comes out consistent.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum FpsPolicy {
+    /// Twice each file's own rate, snapped to the standard rate it is meant to be.
+    PerFile,
+    /// Every output at `fps`, whatever the source rate.
+    Normalize { fps: f64 },
 }
 
-#[derive(serde::Serialize)]
-struct ValidateToolsResult {
-    ffmpeg: ToolValidation,
-    rife: ToolValidation,
+/// Rates an output is snapped to when twice the source rate is within 0.2% of one.
+const STANDARD_RATES: &[f64] = &[
+    24000.0 / 1001.0, 24.0, 25.0, 30000.0 / 1001.0, 30.0, 48000.0 / 1001.0, 48.0, 50.0,
+    60000.0 / 1001.0, 60.0, 100.0, 120000.0 / 1001.0, 120.0,
+];
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct FpsDecision {
+    source_fps: f64,
+    output_fps: f64,
+    /// How the rate was reached, for the job entry.
+    note: String,
 }
 
-#[derive(serde::Serialize)]
-struct ExtractFramesResult {
-    ok: bool,
-    frames_dir: String,
-    frame_pattern: String,
-    output: String,
+/// `timestep`: the job's model can interpolate straight to any rate (RIFE `-n`), so rates
+/// above twice the source get real in-between frames instead of repeated ones.
+fn conform_fps(source_fps: f64, policy: FpsPolicy, timestep: bool) -> FpsDecision {
+    let doubled = source_fps * 2.0;
+    let near = |a: f64, b: f64| (a / b - 1.0).abs() < 0.002;
+    let (output_fps, note) = match policy {
+        FpsPolicy::PerFile => match STANDARD_RATES.iter().copied().find(|r| near(*r, doubled)) {
+            Some(r) => (r, format!("twice the source rate ({r:.3} fps)")),
+            None => (doubled, format!("twice the source rate ({doubled:.3} fps, not a standard rate)")),
+        },
+        FpsPolicy::Normalize { fps } if near(fps, doubled) => (fps, "twice the source rate matches the batch rate".into()),
+        FpsPolicy::Normalize { fps } if fps < doubled => {
+            (fps, format!("interpolated to {doubled:.3} fps, then frames dropped to the batch rate"))
+        }
+        FpsPolicy::Normalize { fps } if timestep => (fps, "interpolated straight to the batch rate".into()),
+        FpsPolicy::Normalize { fps } => (
+            fps,
+            format!("interpolated to {doubled:.3} fps, then frames repeated to reach the batch rate (the model only makes midpoints)"),
+        ),
+    };
+    FpsDecision { source_fps, output_fps, note }
 }
 
-fn make_job_id() -> String {
-    use std::time::{SystemTime, UNIX_EPOCH};
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default();
-    format!("job-{}-{}", now.as_secs(), now.subsec_nanos())
+#[derive(Clone, Debug, serde::Serialize)]
+struct FpsPlanEntry {
+    path: String,
+    decision: Option<FpsDecision>,
+    error: Option<String>,
 }
 
-fn count_files_in_dir(dir: &Path) -> usize {
-    match fs::read_dir(dir) {
-        Ok(rd) => rd.flatten().filter(|e| e.path().is_file()).count(),
-        Err(_) => 0,
-    }
+#[derive(Clone, Debug, serde::Serialize)]
+struct FpsPlan {
+    /// Rate for `Normalize`: twice the most common source rate.
+    suggested_fps: Option<f64>,
+    files: Vec<FpsPlanEntry>,
 }
 
+/// Preview the output rate `policy` gives each file of a batch before it is queued, for jobs
+/// on `model` (a timestep model interpolates above twice the source rate).
 #[tauri::command]
-fn run_rife_pipeline(
+fn plan_fps_conformance(
     app: AppHandle,
-    input_frames: String,
-    output_frames: String,
-    model_dir: String,
-    threads: String,
-) -> Result<(), String> {
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    policy: FpsPolicy,
+    model: Option<String>,
+) -> Result<FpsPlan, String> {
     let root = app_root(&app)?;
-    ensure_dirs(&root)?;
-
-    let (_ffmpeg_path, rife_path, _rife_models) = find_installed_tool_paths(&root);
-    let rife_bin = rife_path.ok_or("rife not installed (install rife first)")?;
-
-    let model_path = resolve_rife_model_path(model_dir.trim());
-    if !model_path.exists() {
-        return Err(format!("Model path does not exist: {}", model_path.to_string_lossy()));
+    let timestep = model.as_deref().is_some_and(|m| model_profile(&root, m).supports_timestep(m));
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    let files: Vec<FpsPlanEntry> = paths
+        .iter()
+        .map(|p| {
+            let fps = probe_duration_and_fps(&ffmpeg, Path::new(p.trim())).map(|(_, f)| f).filter(|f| *f > 0.0);
+            FpsPlanEntry {
+                path: p.clone(),
+                decision: fps.map(|f| conform_fps(f, policy, timestep)),
+                error: fps.is_none().then(|| "Could not read the frame rate".to_string()),
+            }
+        })
+        .collect();
+    let mut counts: Vec<(f64, usize)> = Vec::new();
+    for d in files.iter().filter_map(|f| f.decision.as_ref()) {
+        let doubled = conform_fps(d.source_fps, FpsPolicy::PerFile, false).output_fps;
+        match counts.iter_mut().find(|(r, _)| (r - doubled).abs() < 0.01) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((doubled, 1)),
+        }
     }
+    let suggested_fps = counts.iter().max_by_key(|(_, n)| *n).map(|(r, _)| *r);
+    Ok(FpsPlan { suggested_fps, files })
+}
 
-    let in_dir = PathBuf::from(input_frames.trim());
-    if !in_dir.exists() {
-        return Err(format!("Input frames dir does not exist: {}", in_dir.to_string_lossy()));
-    }
+// -------------------- Source conditions --------------------
+
+/// Source properties that usually need a decision before interpolating. Unattended batches
+/// can't ask, so `SourcePolicy` decides for them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SourceCondition {
+    /// PQ or HLG transfer.
+    Hdr,
+    /// Variable frame rate (average and nominal rates disagree).
+    Vfr,
+    Interlaced,
+    /// Display-matrix or `rotate` tag rotation.
+    Rotation,
+    /// Above `HIGH_FPS`, where doubling mostly makes huge files.
+    HighFps,
+}
 
-    let out_dir = PathBuf::from(output_frames.trim());
-    std::fs::create_dir_all(&out_dir)
-        .map_err(|e| format!("Failed to create output frames dir: {e}"))?;
+impl SourceCondition {
+    fn label(self) -> &'static str {
+        match self {
+            SourceCondition::Hdr => "HDR",
+            SourceCondition::Vfr => "variable frame rate",
+            SourceCondition::Interlaced => "interlaced",
+            SourceCondition::Rotation => "rotated",
+            SourceCondition::HighFps => "very high frame rate",
+        }
+    }
+}
 
-    let app_for_task = app.clone();
-    tauri::async_runtime::spawn_blocking(move || {
-        let _ = app_for_task.emit("pipeline_log", "Starting RIFE (GPU/Vulkan)…");
-        let _ = app_for_task.emit("pipeline_log", format!("RIFE: {}", rife_bin.to_string_lossy()));
-        let _ = app_for_task.emit("pipeline_log", format!("Model: {}", model_path.to_string_lossy()));
-        let _ = app_for_task.emit("pipeline_log", format!("Threads (-j): {}", threads));
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SourceAction {
+    /// Process the file as it is.
+    #[default]
+    Ignore,
+    /// Apply the usual fix: tonemap, constant-rate output, deinterlace, capped output rate.
+    Fix,
+    /// Don't queue the file; `smooth_video` returns `skipped` instead of a job.
+    Skip,
+    /// Refuse the job with an error.
+    Fail,
+}
 
-        let (cwd, model_arg) = compute_rife_cwd_and_model_arg(&rife_bin, &model_path);
-        if let Some(ref d) = cwd {
-            let _ = app_for_task.emit(
-                "pipeline_log",
-                format!("Working dir: {}", d.to_string_lossy()),
-            );
-        }
-        let _ = app_for_task.emit(
-            "pipeline_log",
-            format!("Model arg (-m): {}", model_arg.to_string_lossy()),
-        );
+/// What to do for each `SourceCondition`. The one in the settings applies unless the job
+/// passes its own.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct SourcePolicy {
+    hdr: SourceAction,
+    vfr: SourceAction,
+    interlaced: SourceAction,
+    rotation: SourceAction,
+    high_fps: SourceAction,
+}
 
-        let mut cmd = Command::new(&rife_bin);
-        if let Some(d) = cwd {
-            cmd.current_dir(d);
+impl SourcePolicy {
+    fn action(&self, condition: SourceCondition) -> SourceAction {
+        match condition {
+            SourceCondition::Hdr => self.hdr,
+            SourceCondition::Vfr => self.vfr,
+            SourceCondition::Interlaced => self.interlaced,
+            SourceCondition::Rotation => self.rotation,
+            SourceCondition::HighFps => self.high_fps,
         }
-        cmd.arg("-v")
-            .arg("-i").arg(&in_dir)
-            .arg("-o").arg(&out_dir)
-            .arg("-m").arg(model_arg)
-            .arg("-f").arg("%08d.png")
-            .arg("-j").arg(&threads)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    }
+}
 
-        let mut child = match cmd.spawn() {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = app_for_task.emit("pipeline_log", format!("RIFE failed to start: {e}"));
-                let _ = app_for_task.emit("pipeline_done", "failed");
-                return;
-            }
-        };
+/// Source rate above which `SourceCondition::HighFps` is reported.
+const HIGH_FPS: f64 = 100.0;
+/// Output rate a fixed `HighFps` source is capped at.
+const HIGH_FPS_OUTPUT_CAP: f64 = 120.0;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct SourceDecision {
+    condition: SourceCondition,
+    /// What was detected, e.g. "smpte2084" or "rotation -90°".
+    detail: String,
+    action: SourceAction,
+}
 
-        let stdout = child.stdout.take();
-        let stderr = child.stderr.take();
+fn parse_rate(v: &str) -> Option<f64> {
+    match v.split_once('/') {
+        Some((a, b)) => {
+            let b = b.parse::<f64>().ok()?;
+            (b != 0.0).then_some(a.parse::<f64>().ok()? / b)
+        }
+        None => v.parse().ok(),
+    }
+}
 
-        let app_stdout = app_for_task.clone();
-        let t1 = std::thread::spawn(move || {
-            if let Some(out) = stdout {
-                let reader = BufReader::new(out);
-                for line in reader.lines().flatten() {
-                    if !line.trim().is_empty() {
-                        let _ = app_stdout.emit("pipeline_log", line);
-                    }
-                }
+/// Conditions of the first video stream of `input`, with what was seen.
+fn detect_source_conditions(ffmpeg: &Path, input: &Path, fps: f64) -> Vec<(SourceCondition, String)> {
+    let mut found = Vec::new();
+    if fps > HIGH_FPS {
+        found.push((SourceCondition::HighFps, format!("{fps:.2} fps")));
+    }
+    let Some(ffprobe) = ffprobe_path(ffmpeg) else { return found };
+    let Some(out) = run_ffprobe(
+        &ffprobe,
+        &["-select_streams", "v:0", "-show_entries", "stream=r_frame_rate,avg_frame_rate,field_order,color_transfer:stream_side_data=rotation:stream_tags=rotate", "-of", "default"],
+        input,
+    ) else {
+        return found;
+    };
+    let (mut nominal, mut average) = (None, None);
+    for line in out.lines() {
+        let Some((k, v)) = line.trim().split_once('=') else { continue };
+        match k {
+            "r_frame_rate" => nominal = parse_rate(v),
+            "avg_frame_rate" => average = parse_rate(v),
+            "color_transfer" if v == "smpte2084" || v == "arib-std-b67" => found.push((SourceCondition::Hdr, v.to_string())),
+            "field_order" if matches!(v, "tt" | "bb" | "tb" | "bt") => {
+                found.push((SourceCondition::Interlaced, format!("field order {v}")))
             }
-        });
-
-        let app_stderr = app_for_task.clone();
-        let t2 = std::thread::spawn(move || {
-            if let Some(err) = stderr {
-                let reader = BufReader::new(err);
-                for line in reader.lines().flatten() {
-                    if !line.trim().is_empty() {
-                        let _ = app_stderr.emit("pipeline_log", line);
+            "rotation" | "TAG:rotate" => {
+                if let Some(deg) = clockwise_rotation(k, v) {
+                    if !found.iter().any(|(c, _)| *c == SourceCondition::Rotation) {
+                        found.push((SourceCondition::Rotation, format!("rotation {deg}°")));
                     }
                 }
             }
-        });
+            _ => {}
+        }
+    }
+    if let (Some(n), Some(a)) = (nominal, average) {
+        if n > 0.0 && a > 0.0 && (n / a - 1.0).abs() > 0.01 {
+            found.push((SourceCondition::Vfr, format!("nominal {n:.3} fps, average {a:.3} fps")));
+        }
+    }
+    found
+}
 
-        let status = match child.wait() {
-            Ok(s) => s,
-            Err(e) => {
-                let _ = app_for_task.emit("pipeline_log", format!("Failed waiting for RIFE: {e}"));
-                let _ = app_for_task.emit("pipeline_done", "failed");
-                return;
-            }
-        };
+/// Clockwise turn players give the picture, from an ffprobe `rotation` (display matrix,
+/// counter-clockwise) or `TAG:rotate` (clockwise) line; None when it is upright.
+fn clockwise_rotation(key: &str, value: &str) -> Option<i32> {
+    let deg = value.parse::<f64>().ok()?.round() as i32;
+    let deg = if key == "rotation" { -deg } else { deg };
+    Some(deg.rem_euclid(360)).filter(|d| *d != 0)
+}
 
-        let _ = t1.join();
-        let _ = t2.join();
+/// Clockwise rotation of the first video stream of `input` (see `clockwise_rotation`).
+fn probe_rotation(ffmpeg: &Path, input: &Path) -> Option<i32> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "stream_side_data=rotation:stream_tags=rotate", "-of", "default"], input)?;
+    out.lines().find_map(|line| {
+        let (k, v) = line.trim().split_once('=')?;
+        matches!(k, "rotation" | "TAG:rotate").then(|| clockwise_rotation(k, v)).flatten()
+    })
+}
 
-        if status.success() {
-            let _ = app_for_task.emit("pipeline_done", "ok");
-        } else {
-            let _ = app_for_task.emit("pipeline_log", format!("RIFE exited with {}", status));
-            let _ = app_for_task.emit("pipeline_done", "failed");
+/// Apply the fix for `condition` to the job's filters; returns what was done, for the log.
+fn fix_source_condition(condition: SourceCondition, filters: &mut FilterOptions, fps_in: f64, rotation: Option<i32>) -> String {
+    match condition {
+        SourceCondition::Hdr => {
+            filters.tonemap = true;
+            "tonemapping to SDR".into()
         }
-    });
-
-    Ok(())
+        SourceCondition::Vfr => {
+            let fps = *filters.output_fps.get_or_insert(conform_fps(fps_in, FpsPolicy::PerFile, false).output_fps);
+            format!("writing a constant {fps:.3} fps output")
+        }
+        SourceCondition::Interlaced => {
+            filters.deinterlace = true;
+            "deinterlacing".into()
+        }
+        // The turn goes into the pixels; the output carries no rotation of its own.
+        SourceCondition::Rotation => match rotation {
+            Some(deg) => {
+                filters.rotate = Some(deg);
+                format!("turning the frames {deg}° clockwise")
+            }
+            None => "no rotation found; processing as is".into(),
+        },
+        // Doubling an already high rate mostly makes huge files: keep the source rate, capped,
+        // and don't interpolate (see `SmoothJob::skip_interpolation`).
+        SourceCondition::HighFps => {
+            let fps = filters.output_fps.unwrap_or(fps_in).min(HIGH_FPS_OUTPUT_CAP);
+            if fps > fps_in {
+                filters.output_fps = Some(fps);
+                format!("capping the output at {fps:.3} fps")
+            } else {
+                filters.output_fps = (fps < fps_in).then_some(fps);
+                format!("not interpolating; encoding at {fps:.3} fps")
+            }
+        }
+    }
 }
 
+fn probe_duration_and_fps(ffmpeg: &Path, input: &Path) -> Option<(f64, f64)> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
 
+    let dur_out = run_ffprobe(&ffprobe, &["-show_entries", "format=duration", "-of", "default=noprint_wrappers=1:nokey=1"], input)?;
+    let duration = dur_out.trim().parse::<f64>().ok()?;
 
-#[tauri::command]
-fn extract_frames(app: AppHandle, video_path: String) -> Result<ExtractFramesResult, String> {
-    // IMPORTANT: non-blocking. We return immediately and run ffmpeg in a background thread.
-    let root = app_root(&app)?;
-    ensure_dirs(&root)?;
+    let fps_out = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "stream=r_frame_rate", "-of", "default=noprint_wrappers=1:nokey=1"], input)?;
+    let fps_s = fps_out.trim().to_string();
+    let fps = if let Some((a,b)) = fps_s.split_once('/') {
+        let na = a.parse::<f64>().ok()?;
+        let nb = b.parse::<f64>().ok()?;
+        if nb != 0.0 { na/nb } else { 0.0 }
+    } else {
+        fps_s.parse::<f64>().unwrap_or(0.0)
+    };
 
-    let (ffmpeg_path, _rife_path, _rife_models) = find_installed_tool_paths(&root);
-    let ffmpeg = preferred_ffmpeg_path()
-        .or(ffmpeg_path)
-        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    Some((duration, fps))
+}
 
-    let input = PathBuf::from(video_path.trim());
-    if !input.exists() {
-        return Err("Input video does not exist".into());
+/// Width and height of the first video stream.
+fn probe_video_size(ffmpeg: &Path, input: &Path) -> Option<(u32, u32)> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "stream=width,height", "-of", "csv=p=0"], input)?;
+    let (w, h) = out.trim().split_once(',')?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+/// `probe_video_size` as the frames come out of the decoder: a quarter-turned source is
+/// decoded upright, so its sides swap.
+fn upright_video_size(ffmpeg: &Path, input: &Path) -> Option<(u32, u32)> {
+    let (w, h) = probe_video_size(ffmpeg, input)?;
+    match probe_rotation(ffmpeg, input) {
+        Some(90 | 270) => Some((h, w)),
+        _ => Some((w, h)),
     }
+}
 
-    let job_id = make_job_id();
-    let frames_dir = root.join("temp").join("frames_in").join(&job_id);
-    fs::create_dir_all(&frames_dir).map_err(|e| e.to_string())?;
+/// Codec types ("video", "audio", …) of every stream in the file, in stream order.
+fn probe_stream_types(ffmpeg: &Path, input: &Path) -> Option<Vec<String>> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-show_entries", "stream=codec_type", "-of", "default=noprint_wrappers=1:nokey=1"], input)?;
+    Some(
+        out.lines()
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+    )
+}
 
-    let ext = "jpg";
-    let pattern = frames_dir.join(format!("%08d.{ext}"));
+/// Durations of the first video and first audio stream, when the container reports them.
+fn probe_stream_durations(ffmpeg: &Path, input: &Path) -> (Option<f64>, Option<f64>) {
+    let Some(ffprobe) = ffprobe_path(ffmpeg) else { return (None, None) };
+    let Some(out) = run_ffprobe(&ffprobe, &["-show_entries", "stream=codec_type,duration", "-of", "csv=p=0"], input) else {
+        return (None, None);
+    };
+    let mut video = None;
+    let mut audio = None;
+    for line in out.lines() {
+        let mut parts = line.trim().split(',');
+        let kind = parts.next().unwrap_or("");
+        let dur = parts.next().and_then(|d| d.parse::<f64>().ok());
+        match kind {
+            "video" if video.is_none() => video = dur,
+            "audio" if audio.is_none() => audio = dur,
+            _ => {}
+        }
+    }
+    (video, audio)
+}
 
-    // Tiny, safe UI notes (no streaming logs).
-    emit_log_limited(&app, &format!("Frames folder: {}", frames_dir.to_string_lossy()));
-    emit_log_limited(&app, &format!("Extract format: {}", ext));
+/// Bitrate of the first audio stream in kbps, when the container reports one.
+fn probe_audio_bitrate_kbps(ffmpeg: &Path, input: &Path) -> Option<u32> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "a:0", "-show_entries", "stream=bit_rate", "-of", "default=noprint_wrappers=1:nokey=1"], input)?;
+    let bps = out.trim().parse::<u64>().ok()?;
+    Some((bps / 1000) as u32)
+}
 
-    // Spawn background worker so the UI stays responsive.
-    let app_clone = app.clone();
-    let ffmpeg_clone = ffmpeg.clone();
-    let input_clone = input.clone();
-    let frames_dir_clone = frames_dir.clone();
-    let pattern_clone = pattern.clone();
+/// Colour tags of the first video stream; "unknown"/"unspecified" values are left unset.
+fn probe_color_tags(ffmpeg: &Path, input: &Path) -> ColorTags {
+    let mut tags = ColorTags::default();
+    let Some(ffprobe) = ffprobe_path(ffmpeg) else { return tags };
+    let Some(out) = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "stream=color_primaries,color_transfer,color_space,color_range", "-of", "default=noprint_wrappers=1"], input) else {
+        return tags;
+    };
+    for line in out.lines() {
+        let Some((k, v)) = line.trim().split_once('=') else { continue };
+        if v.is_empty() || v == "unknown" || v == "unspecified" {
+            continue;
+        }
+        let v = Some(v.to_string());
+        match k {
+            "color_primaries" => tags.primaries = v,
+            "color_transfer" => tags.transfer = v,
+            "color_space" => tags.matrix = v,
+            "color_range" => tags.range = v,
+            _ => {}
+        }
+    }
+    tags
+}
 
-    std::thread::spawn(move || {
-        let done = match extract_frames_worker(
-            &app_clone,
-            &ffmpeg_clone,
-            &input_clone,
-            &frames_dir_clone,
-            &pattern_clone
-        ) {
-            Ok(msg) => PipelineDoneEvent {
-                ok: true,
-                message: msg,
-                frames_dir: frames_dir_clone.to_string_lossy().to_string(),
-                frame_pattern: pattern_clone.to_string_lossy().to_string(),
-            },
-            Err(err) => PipelineDoneEvent {
-                ok: false,
-                message: err,
-                frames_dir: frames_dir_clone.to_string_lossy().to_string(),
-                frame_pattern: pattern_clone.to_string_lossy().to_string(),
-            },
-        };
+/// First video stream of a file, as `match_source` needs it.
+#[derive(Clone, Debug)]
+struct VideoStreamInfo {
+    codec: String,
+    /// ffprobe's profile name, e.g. "High", "Main 10", "HQ".
+    profile: Option<String>,
+    pix_fmt: String,
+    /// Stream bitrate, or the container bitrate minus audio when the stream doesn't say.
+    bit_rate_kbps: Option<u32>,
+}
 
-        let _ = app_clone.emit("pipeline_done", done);
+fn probe_video_stream(ffmpeg: &Path, input: &Path) -> Option<VideoStreamInfo> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "stream=codec_name,profile,pix_fmt,bit_rate:format=bit_rate", "-of", "default"], input)?;
+    let mut codec = None;
+    let mut profile = None;
+    let mut pix_fmt = String::new();
+    let mut stream_kbps = None;
+    let mut format_kbps = None;
+    let mut in_format = false;
+    for line in out.lines() {
+        let line = line.trim();
+        match line {
+            "[FORMAT]" => in_format = true,
+            "[STREAM]" => in_format = false,
+            _ => {}
+        }
+        let Some((k, v)) = line.split_once('=') else { continue };
+        let kbps = || v.parse::<u64>().ok().map(|b| (b / 1000) as u32);
+        match k {
+            "codec_name" => codec = Some(v.to_string()),
+            "profile" if !v.is_empty() && v != "unknown" => profile = Some(v.to_string()),
+            "pix_fmt" => pix_fmt = v.to_string(),
+            "bit_rate" if in_format => format_kbps = kbps(),
+            "bit_rate" => stream_kbps = kbps(),
+            _ => {}
+        }
+    }
+    let bit_rate_kbps = stream_kbps.or_else(|| {
+        let audio = probe_audio_bitrate_kbps(ffmpeg, input).unwrap_or(0);
+        format_kbps.map(|f| f.saturating_sub(audio)).filter(|k| *k > 0)
     });
-
-    // Return immediately.
-    Ok(ExtractFramesResult {
-        ok: true, // accepted / started
-        frames_dir: frames_dir.to_string_lossy().to_string(),
-        frame_pattern: pattern.to_string_lossy().to_string(),
-        output: "Started frame extraction in background".to_string(),
-    })
+    Some(VideoStreamInfo { codec: codec?, profile, pix_fmt, bit_rate_kbps })
 }
 
-
-
-
-
-
+/// Result of test-decoding the first video frame of an input.
 #[derive(Clone, serde::Serialize)]
-struct PipelineDoneEvent {
+struct DecodeCheck {
     ok: bool,
-    message: String,
-    frames_dir: String,
-    frame_pattern: String,
+    codec: Option<String>,
+    profile: Option<String>,
+    /// Why the frame didn't decode, with a suggested fix.
+    error: Option<ActionableError>,
 }
 
-fn extract_frames_worker(
-    app: &tauri::AppHandle,
-    ffmpeg: &PathBuf,
-    input: &PathBuf,
-    frames_dir: &PathBuf,
-    pattern: &PathBuf,
-) -> Result<String, String> {
-    let (duration_secs, fps) = probe_duration_and_fps(ffmpeg, input).unwrap_or((0.0, 0.0));
-    let total_frames_est = if duration_secs > 0.0 && fps > 0.0 {
-        (duration_secs * fps).round() as i64
-    } else {
-        0
-    };
-
-    if total_frames_est > 0 {
-        emit_log_limited(app, &format!("Estimated frames: {}", total_frames_est));
-    }
-
-    let mut cmd = Command::new(ffmpeg);
-    cmd.arg("-hide_banner")
-        .arg("-y")
-        .arg("-nostdin")
-        .arg("-loglevel").arg("error")
-        .arg("-stats_period").arg("0.5")
+/// Decode one frame of `input` with `ffmpeg`. Some codecs (ProRes RAW, AV1 without dav1d)
+/// probe fine but don't decode with a given build; this finds out before a long job starts.
+fn check_decode(ffmpeg: &Path, input: &Path) -> DecodeCheck {
+    let stream = probe_video_stream(ffmpeg, input);
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin")
+        .arg("-v").arg("error")
         .arg("-i").arg(input)
-        .arg("-fps_mode").arg("passthrough")
-        .arg("-progress").arg("pipe:1");
-
-// Hardware decode when available (platform default).
-let jpg_quality = 2;
-
-// hwaccel name (ffmpeg): macOS=videotoolbox, Windows=d3d11va (fallback).
-let hwaccel = if cfg!(target_os = "macos") {
-    Some("videotoolbox")
-} else if cfg!(target_os = "windows") {
-    Some("d3d11va")
-} else {
-    None
-};
-
-if let Some(hw) = hwaccel {
-    cmd.arg("-hwaccel").arg(hw);
+        .arg("-map").arg("0:v:0")
+        .arg("-frames:v").arg("1")
+        .arg("-vf").arg("scale=64:-2")
+        .arg("-f").arg("rawvideo").arg("-pix_fmt").arg("rgb24").arg("-");
+    // Some decoders log their failure but still exit 0: a frame on stdout is the real test.
+    let (ok, log) = match run_tool(log_command("decode_check", &mut cmd), PROBE_TIMEOUT_SECS) {
+        Ok(out) => (out.status.success() && !out.stdout.is_empty(), String::from_utf8_lossy(&out.stderr).to_string()),
+        Err(e) => (false, e),
+    };
+    let (codec, profile) = stream.map(|s| (Some(s.codec), s.profile)).unwrap_or_default();
+    let error = (!ok).then(|| {
+        let detail = log.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("no frame decoded").trim().to_string();
+        let mut error = classify_process_error(&log).unwrap_or_else(|| catalog_error("decode_failed", detail));
+        if let (Some(codec), "decoder_missing" | "av1_decoder_missing") = (&codec, error.code.as_str()) {
+            let profile = profile.as_deref().map(|p| format!(" ({p})")).unwrap_or_default();
+            error.title = format!("This ffmpeg can't decode {codec}{profile}");
+        }
+        error
+    });
+    DecodeCheck { ok, codec, profile, error }
 }
 
-// Fast path for interpolation: decode frames as JPG (much faster IO than PNG/WebP lossless).
-cmd.arg("-threads").arg("0")
-    .arg("-c:v").arg("mjpeg")
-    .arg("-q:v").arg(jpg_quality.to_string());
-    cmd.arg(pattern.as_os_str())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped());
-
-    let mut child = cmd.spawn().map_err(|e| format!("Failed to start ffmpeg: {e}"))?;
-
-    // Drain stderr (collect a short snippet for errors).
-    let stderr_snippet = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
-    if let Some(err) = child.stderr.take() {
-        let snip = stderr_snippet.clone();
-        std::thread::spawn(move || {
-            let reader = BufReader::new(err);
-            for line in reader.lines().flatten() {
-                let mut s = snip.lock().unwrap();
-                if s.len() < 1200 {
-                    if !s.is_empty() { s.push('\n'); }
-                    s.push_str(&line);
-                } else {
-                    break;
-                }
-            }
-        });
+/// Test-decode the first frame of `path` with the ffmpeg jobs would use. Runs on a blocking
+/// task so a slow decoder doesn't hold up other commands.
+#[tauri::command]
+async fn check_input_decode(app: AppHandle, path: String) -> Result<DecodeCheck, String> {
+    let root = app_root(&app)?;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(app.state::<AppState>().tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    let input = PathBuf::from(path.trim());
+    if !input.exists() {
+        return Err("Input video does not exist".into());
     }
+    tauri::async_runtime::spawn_blocking(move || check_decode(&ffmpeg, &input))
+        .await
+        .map_err(|e| format!("Decode check failed: {e}"))
+}
 
-    // Drain progress output so ffmpeg can't block on full buffers.
-    if let Some(out) = child.stdout.take() {
-        let reader = BufReader::new(out);
-        let mut last_emit = std::time::Instant::now();
-        let mut frame = 0i64;
-
-        for line in reader.lines().flatten() {
-            if let Some((k, v)) = parse_ffmpeg_progress_line(&line) {
-                if k == "frame" {
-                    frame = v.parse::<i64>().unwrap_or(frame);
-                }
-                // throttle UI events
-                if last_emit.elapsed().as_millis() >= 250 {
-                    if total_frames_est > 0 && frame > 0 {
-                        let pct = ((frame as f64 / total_frames_est as f64) * 100.0).min(100.0);
-                        let _ = app.emit("pipeline_progress", pct);
-                    }
-                    last_emit = std::time::Instant::now();
-                }
-            }
+/// Resolve `match_source` against the input and check the chosen encoder is in this ffmpeg build.
+fn resolve_encode_settings(
+    app: &AppHandle,
+    state: &AppState,
+    encode: EncodeSettings,
+    ffmpeg: &Path,
+    input: &Path,
+) -> Result<EncodeSettings, String> {
+    let encode = match_encode_settings(app, encode, ffmpeg, input)?;
+    // Capabilities that can't be read shouldn't block a job; ffmpeg will report it instead.
+    if let Ok(caps) = state.ffmpeg_capabilities(ffmpeg) {
+        let name = encode.encoder().ffmpeg_name();
+        if !caps.has_encoder(name) {
+            return Err(format!("This ffmpeg build has no {name} encoder; pick another encoder"));
         }
     }
+    Ok(encode)
+}
 
-    let status = child.wait().map_err(|e| format!("Failed waiting for ffmpeg: {e}"))?;
-    let frame_count = count_files_in_dir(frames_dir);
-
-    if frame_count > 0 {
-        let _ = app.emit("pipeline_progress", 100.0f64);
+/// Apply `match_source`, logging what was picked.
+fn match_encode_settings(app: &AppHandle, encode: EncodeSettings, ffmpeg: &Path, input: &Path) -> Result<EncodeSettings, String> {
+    if !encode.match_source {
+        return Ok(encode);
     }
+    let src = probe_video_stream(ffmpeg, input).ok_or("Match source: could not probe the input video")?;
+    let matched = encode.matched_to_source(&src);
+    emit_log_limited(app, &format!(
+        "Match source: {} {} {} {} → {} {} {}",
+        src.codec,
+        src.profile.as_deref().unwrap_or("-"),
+        src.pix_fmt,
+        src.bit_rate_kbps.map(|k| format!("{k} kbps")).unwrap_or_else(|| "unknown bitrate".into()),
+        matched.encoder().ffmpeg_name(),
+        matched.profile.as_deref().unwrap_or("default profile"),
+        matched.bitrate_kbps.map(|k| format!("{k} kbps")).unwrap_or_else(|| "default quality".into()),
+    ));
+    matched.validate().map_err(|e| format!("Match source: {e}"))?;
+    Ok(matched)
+}
 
-    if !status.success() {
-        let snip = stderr_snippet.lock().unwrap().trim().to_string();
-        if !snip.is_empty() {
-            emit_log_limited(app, &format!("ffmpeg error: {}", snip.lines().next().unwrap_or("")));
-            return Err(format!("ffmpeg exited with {status}\n{snip}"));
+/// Codec and channel layout of the first audio stream; None when there is no audio.
+fn probe_audio_stream(ffmpeg: &Path, input: &Path) -> Option<AudioStreamInfo> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "a:0", "-show_entries", "stream=codec_name,channels,channel_layout", "-of", "default=noprint_wrappers=1"], input)?;
+    let mut codec = None;
+    let mut channels = 2;
+    let mut layout = None;
+    for line in out.lines() {
+        let Some((k, v)) = line.trim().split_once('=') else { continue };
+        match k {
+            "codec_name" if !v.is_empty() => codec = Some(v.to_string()),
+            "channels" => channels = v.parse().unwrap_or(2),
+            "channel_layout" if !v.is_empty() && v != "unknown" => layout = Some(v.to_string()),
+            _ => {}
         }
-        emit_log_limited(app, &format!("ffmpeg exited with {status}"));
-        return Err(format!("ffmpeg exited with {status}"));
-    }
-
-    if frame_count <= 0 {
-        return Err("No frames were extracted".to_string());
     }
+    Some(AudioStreamInfo { codec: codec?, channels, layout })
+}
 
-    Ok(format!("Frames extracted: {frame_count}"))
+fn probe_has_audio(ffmpeg: &Path, input: &Path) -> bool {
+    probe_stream_types(ffmpeg, input)
+        .map(|t| t.iter().any(|c| c == "audio"))
+        .unwrap_or(false)
 }
 
+// -------------------- Environment overrides --------------------
+//
+// For scripted deployments such as render-farm images: read once, they win over the
+// system/installed tools and the default locations without touching the settings file.
+
+struct EnvOverrides {
+    /// `RIFE_INTERP_FFMPEG`: ffmpeg binary to use instead of the system or installed one.
+    ffmpeg: Option<PathBuf>,
+    /// `RIFE_INTERP_TEMP_DIR`: folder used in place of `temp/` under the app root.
+    temp_dir: Option<PathBuf>,
+    /// `RIFE_INTERP_GPU`: GPU index for rife-ncnn-vulkan (`-g`).
+    gpu: Option<u32>,
+    /// Variables that were set but can't be used; they are ignored and listed in the startup
+    /// report.
+    problems: Vec<String>,
+}
 
+static ENV_OVERRIDES: std::sync::OnceLock<EnvOverrides> = std::sync::OnceLock::new();
 
-fn run_and_capture(mut cmd: Command) -> ToolValidation {
-    match cmd.output() {
-        Ok(out) => {
-            let mut text = String::new();
-            if !out.stdout.is_empty() {
-                text.push_str(&String::from_utf8_lossy(&out.stdout));
+fn env_overrides() -> &'static EnvOverrides {
+    ENV_OVERRIDES.get_or_init(|| {
+        let var = |name: &str| std::env::var(name).ok().map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+        let mut problems = Vec::new();
+        let ffmpeg = match var("RIFE_INTERP_FFMPEG").map(PathBuf::from) {
+            Some(p) if !p.is_file() => {
+                problems.push(format!("RIFE_INTERP_FFMPEG: {} is not a file", p.display()));
+                None
             }
-            if !out.stderr.is_empty() {
-                if !text.is_empty() {
-                    text.push('\n');
+            p => p,
+        };
+        let temp_dir = match var("RIFE_INTERP_TEMP_DIR").map(PathBuf::from) {
+            Some(p) => match fs::create_dir_all(&p) {
+                Ok(()) => Some(p),
+                Err(e) => {
+                    problems.push(format!("RIFE_INTERP_TEMP_DIR: can't create {}: {e}", p.display()));
+                    None
                 }
-                text.push_str(&String::from_utf8_lossy(&out.stderr));
-            }
-            ToolValidation {
-                ok: out.status.success(),
-                path: None,
-                output: text.trim().to_string(),
-            }
+            },
+            None => None,
+        };
+        let gpu = match var("RIFE_INTERP_GPU") {
+            Some(v) => match v.parse::<u32>() {
+                Ok(g) => Some(g),
+                Err(_) => {
+                    problems.push(format!("RIFE_INTERP_GPU: expected a GPU index, got {v:?}"));
+                    None
+                }
+            },
+            None => None,
+        };
+        EnvOverrides { ffmpeg, temp_dir, gpu, problems }
+    })
+}
+
+/// Folder for job frames, job specs and other scratch work: `temp/`, unless overridden.
+fn temp_root(root: &Path) -> PathBuf {
+    env_overrides().temp_dir.clone().unwrap_or_else(|| root.join("temp"))
+}
+
+fn preferred_ffmpeg_path() -> Option<PathBuf> {
+    if let Some(p) = &env_overrides().ffmpeg {
+        return Some(p.clone());
+    }
+    let candidates = [
+        "/opt/homebrew/opt/ffmpeg-full/bin/ffmpeg",
+        "/opt/homebrew/bin/ffmpeg",
+        "/usr/local/bin/ffmpeg",
+        "/usr/bin/ffmpeg",
+    ];
+    for c in candidates {
+        let p = PathBuf::from(c);
+        if p.exists() {
+            return Some(p);
         }
-        Err(e) => ToolValidation {
-            ok: false,
-            path: None,
-            output: format!("Failed to run: {e}"),
-        },
     }
+    None
 }
 
 
-#[tauri::command]
-fn smooth_video(
-    app: AppHandle,
-    video_path: String,
-    output_path: String,
-    max_threads: Option<i32>,
-) -> Result<ExtractFramesResult, String> {
-    // Non-blocking: returns immediately; work is done on a background thread.
-    let root = app_root(&app)?;
-    ensure_dirs(&root)?;
+use std::collections::HashMap;
+use std::fs;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::process::Stdio;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
 
-    let (ffmpeg_path, rife_path, rife_models) = find_installed_tool_paths(&root);
-    let ffmpeg = preferred_ffmpeg_path()
-        .or(ffmpeg_path)
-        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
-    let rife_bin = rife_path.ok_or("rife not installed (install rife first)")?;
-    let model_dir = rife_models.ok_or("RIFE models folder not found (install rife first)")?;
+use tauri::{AppHandle, Manager, State};
+use tauri::Emitter;
 
-    let input = PathBuf::from(video_path.trim());
-    if !input.exists() {
-        return Err("Input video does not exist".into());
-    }
-    let output = PathBuf::from(output_path.trim());
-    if output_path.trim().is_empty() {
-        return Err("Output path is required".into());
-    }
+mod errors;
+mod library;
+mod limits;
+mod messages;
+mod plugins;
 
-    // Create a job folder
-    let job_id = format!("job-{}", chrono::Utc::now().timestamp_millis());
-    let frames_in_dir = root.join("temp").join("frames_in").join(&job_id);
-    let frames_out_dir = root.join("temp").join("frames_out").join(&job_id);
-    std::fs::create_dir_all(&frames_in_dir).map_err(|e| format!("Failed to create frames_in dir: {e}"))?;
-    std::fs::create_dir_all(&frames_out_dir).map_err(|e| format!("Failed to create frames_out dir: {e}"))?;
+use errors::{catalog_error, classify_process_error, failure_message, push_tail, ActionableError, CommandError, ERROR_PATTERNS};
+use library::{claim_library_output, library_item_dir, library_root, write_library_manifest};
+use limits::ResourceLimits;
+use messages::{message, paused_message, Message};
+use plugins::{discard_frame_timestamps, load_plugin, run_stage_plugins, PluginPosition, PluginProgress};
 
-    let pattern = frames_in_dir.join("%08d.png");
-    let frames_dir_str = frames_in_dir.to_string_lossy().to_string();
-    let frame_pattern_str = pattern.to_string_lossy().to_string();
+#[tauri::command]
+fn check_environment() -> String {
+    let os = std::env::consts::OS;
+    let arch = std::env::consts::ARCH;
+    format!("Environment OK | OS: {} | ARCH: {}", os, arch)
+}
 
-    // Make thread string for RIFE (-j x:x:x)
-    let threads = match max_threads.unwrap_or(0) {
-        t if t <= 0 => "2:2:2".to_string(),
-        t => {
-            // Clamp to sane range
-            let t = t.clamp(1, 12);
-            format!("{t}:{t}:{t}")
-        }
-    };
 
-    // Emit initial stage immediately
-    emit_stage(&app, "Extracting frames… (step 1/3)");
-    let _ = app.emit("pipeline_progress", 0.0_f64);
-    let _ = app.emit("pipeline_log", format!("Smooth Video job: {}", job_id));
+#[tauri::command]
+fn get_max_rife_threads() -> RifeThreads {
+    let n = std::thread::available_parallelism().map(|v| v.get()).unwrap_or(4);
+    RifeThreads::uniform((n as u32).min(RIFE_MAX_THREADS))
+}
 
-    let app_for_task = app.clone();
-    let ffmpeg_for_task = ffmpeg.clone();
-    let rife_for_task = rife_bin.clone();
-    let model_dir_for_task = model_dir.clone();
-    let input_for_task = input.clone();
-    let output_for_task = output.clone();
-    let frames_in_for_task = frames_in_dir.clone();
-    let frames_out_for_task = frames_out_dir.clone();
-    let threads_for_task = threads.clone();
-    let frames_dir_for_task = frames_dir_str.clone();
-    let frame_pattern_for_task = frame_pattern_str.clone();
+#[tauri::command]
+fn get_default_rife_model_dir(app: AppHandle, state: State<'_, AppState>) -> Option<String> {
+    let root = app_root(&app).ok()?;
+    ensure_dirs(&root).ok()?;
+    state.tools(&root).rife_models.map(|p| p.to_string_lossy().to_string())
+}
 
-    tauri::async_runtime::spawn_blocking(move || {
-        // STEP 1: Extract frames
-        let _ = app_for_task.emit("pipeline_log", format!("FFmpeg: {}", ffmpeg_for_task.to_string_lossy()));
-        let _ = app_for_task.emit("pipeline_log", format!("Input: {}", input_for_task.to_string_lossy()));
-        let _ = app_for_task.emit("pipeline_log", format!("Frames in: {}", frames_in_for_task.to_string_lossy()));
-
-        let mut cmd = Command::new(&ffmpeg_for_task);
-        cmd.arg("-hide_banner").arg("-y")
-            .arg("-i").arg(&input_for_task)
-            // png is a good middle-ground for now
-            .arg("-vsync").arg("0")
-            .arg(frames_in_for_task.join("%08d.png"))
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped());
 
-        let mut child = match cmd.spawn() {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
+fn app_root(app: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {e}"))?;
+    Ok(data_dir.join("RIFE-Interpolator"))
+}
+
+fn ensure_dirs(root: &Path) -> Result<(), String> {
+    let dirs = [
+        root.join("bin"),
+        root.join("bin/ffmpeg"),
+        root.join("bin/rife"),
+        root.join("models"),
+        temp_root(root),
+        root.join("cache"),
+    ];
+
+    for d in dirs {
+        fs::create_dir_all(&d)
+            .map_err(|e| fs_error("create dir", &d, &e))?;
+    }
+    Ok(())
+}
+
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
+    fs::create_dir_all(dst).map_err(|e| e.to_string())?;
+
+    for entry in fs::read_dir(src).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+
+        if src_path.is_dir() {
+            copy_dir_recursive(&src_path, &dst_path)?;
+        } else {
+            fs::copy(&src_path, &dst_path).map_err(|e| e.to_string())?;
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mut perms =
+                    fs::metadata(&dst_path).map_err(|e| e.to_string())?.permissions();
+                perms.set_mode(0o755);
+                fs::set_permissions(&dst_path, perms).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_app_paths(app: AppHandle) -> Result<Vec<String>, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    let paths = vec![
+        root.clone(),
+        root.join("bin/ffmpeg"),
+        root.join("bin/rife"),
+        root.join("models"),
+        temp_root(&root),
+        root.join("cache"),
+    ];
+
+    Ok(paths
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+// -------------------- Event coalescing --------------------
+
+/// How often queued progress and log events reach the webview.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EventMode {
+    #[default]
+    Responsive,
+    /// Fewer, larger updates for slow machines or many concurrent jobs.
+    LowOverhead,
+}
+
+impl EventMode {
+    fn interval_ms(self) -> u64 {
+        match self {
+            EventMode::Responsive => 100,
+            EventMode::LowOverhead => 1000,
+        }
+    }
+}
+
+/// Milliseconds between coalesced emissions; set from the settings (see `apply_event_settings`).
+static EVENT_INTERVAL_MS: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(100);
+
+fn apply_event_settings(settings: &AppSettings) {
+    let ms = settings.event_interval_ms.unwrap_or(settings.event_mode.interval_ms()).clamp(16, 10_000);
+    EVENT_INTERVAL_MS.store(ms, std::sync::atomic::Ordering::Relaxed);
+}
+
+/// How often and how finely a job reports progress, from its estimated wall time: a 12-hour
+/// job gains nothing from sub-percent updates twice a second, a 10-second clip does.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct ProgressResolution {
+    /// ffmpeg's `-stats_period`, in seconds.
+    stats_period_secs: f64,
+    /// Least time between the job's `pipeline_status` events; the event interval setting
+    /// still applies when it is longer.
+    interval_ms: u64,
+    /// Decimal places of the reported percentage.
+    decimals: u32,
+}
+
+impl Default for ProgressResolution {
+    fn default() -> Self {
+        Self { stats_period_secs: 0.5, interval_ms: 0, decimals: 1 }
+    }
+}
+
+impl ProgressResolution {
+    /// Aim for roughly a thousand progress reports over the job, and a last digit that moves
+    /// at most a few times a second.
+    fn for_estimate(secs: f64) -> Self {
+        let secs = if secs.is_finite() { secs.max(0.0) } else { 0.0 };
+        let decimals = match secs {
+            s if s < 60.0 => 2,
+            s if s < 2.0 * 3600.0 => 1,
+            _ => 0,
+        };
+        Self {
+            stats_period_secs: (secs / 2000.0).clamp(0.25, 5.0),
+            interval_ms: (secs.round() as u64).clamp(50, 2000),
+            decimals,
+        }
+    }
+
+    fn round(&self, pct: f64) -> f64 {
+        let scale = 10f64.powi(self.decimals as i32);
+        (pct * scale).round() / scale
+    }
+
+    /// ffmpeg options for `-progress` reporting at this resolution.
+    fn push_args(&self, cmd: &mut Command) {
+        cmd.arg("-stats_period").arg(format!("{:.2}", self.stats_period_secs));
+    }
+}
+
+/// Use `resolution` for the current job's progress events until it finishes.
+fn set_progress_resolution(app: &AppHandle, resolution: ProgressResolution) {
+    coalesce_event(app, current_job_id(), |p| p.resolution = Some(resolution));
+}
+
+/// Time constant of the per-stage speed and ETA smoothing: a reading this many seconds old
+/// weighs about a third as much as a fresh one.
+const SPEED_SMOOTHING_SECS: f64 = 8.0;
+
+/// Fold `sample`, taken `dt` seconds after the previous one, into an exponential moving
+/// average. Weighting by elapsed time keeps irregular event spacing from skewing it.
+fn ema(avg: Option<f64>, sample: f64, dt: f64) -> f64 {
+    match avg {
+        Some(avg) => avg + (1.0 - (-dt / SPEED_SMOOTHING_SECS).exp()) * (sample - avg),
+        None => sample,
+    }
+}
+
+/// Progress, speed and time left in one `pipeline_status` event.
+#[derive(Clone, PartialEq, serde::Serialize)]
+struct PipelineStatus {
+    progress: f64,
+    /// Frames per second over the last reading, as measured.
+    fps: Option<f64>,
+    /// The same, smoothed over the stage (see `SPEED_SMOOTHING_SECS`).
+    fps_smoothed: Option<f64>,
+    /// From the smoothed progress rate of the stage.
+    eta_secs: Option<f64>,
+    /// Decimal places `progress` is given to.
+    precision: u32,
+}
+
+/// Events of one job (or of no job) waiting for the next flush.
+#[derive(Default)]
+struct PendingEvents {
+    progress: Option<f64>,
+    logs: Vec<String>,
+    last_flush: Option<std::time::Instant>,
+    /// First progress of the current stage and when it arrived; the ETA extrapolates from it.
+    stage_start: Option<(std::time::Instant, f64)>,
+    /// Last frame count and when it was seen.
+    last_frames: Option<(std::time::Instant, u64)>,
+    fps: Option<f64>,
+    fps_smoothed: Option<f64>,
+    /// Last progress folded into `progress_rate`, and when.
+    last_rate_sample: Option<(std::time::Instant, f64)>,
+    /// Smoothed percent per second of the current stage.
+    progress_rate: Option<f64>,
+    resolution: Option<ProgressResolution>,
+    /// Status last sent; an unchanged one (progress as rounded, speed and ETA) isn't sent again.
+    last_status: Option<PipelineStatus>,
+}
+
+impl PendingEvents {
+    /// Stages progress at very different rates, so speed and ETA start over.
+    fn new_stage(&mut self) {
+        self.stage_start = None;
+        self.last_frames = None;
+        self.fps = None;
+        self.fps_smoothed = None;
+        self.last_rate_sample = None;
+        self.progress_rate = None;
+    }
+
+    fn note_frames(&mut self, frames: u64) {
+        let now = std::time::Instant::now();
+        match self.last_frames {
+            Some((_, before)) if frames < before => self.last_frames = Some((now, frames)),
+            Some((at, before)) => {
+                let secs = now.duration_since(at).as_secs_f64();
+                if secs >= 0.5 {
+                    let fps = (frames - before) as f64 / secs;
+                    self.fps = Some(fps);
+                    self.fps_smoothed = Some(ema(self.fps_smoothed, fps, secs));
+                    self.last_frames = Some((now, frames));
+                }
+            }
+            None => self.last_frames = Some((now, frames)),
+        }
+    }
+
+    fn note_progress(&mut self, progress: f64) {
+        let now = std::time::Instant::now();
+        match self.last_rate_sample {
+            Some((_, before)) if progress < before => self.last_rate_sample = Some((now, progress)),
+            Some((at, before)) => {
+                let secs = now.duration_since(at).as_secs_f64();
+                if secs >= 0.5 {
+                    self.progress_rate = Some(ema(self.progress_rate, (progress - before) / secs, secs));
+                    self.last_rate_sample = Some((now, progress));
+                }
+            }
+            None => self.last_rate_sample = Some((now, progress)),
+        }
+    }
+
+    fn status(&mut self, progress: f64) -> PipelineStatus {
+        let (started, first) = *self.stage_start.get_or_insert((std::time::Instant::now(), progress));
+        self.note_progress(progress);
+        // Until the stage has a smoothed rate, extrapolate from where it started.
+        let eta_secs = (progress > first && progress < 100.0).then(|| match self.progress_rate.filter(|r| *r > 0.0) {
+            Some(rate) => (100.0 - progress) / rate,
+            None => started.elapsed().as_secs_f64() * (100.0 - progress) / (progress - first),
+        });
+        let resolution = self.resolution.unwrap_or_default();
+        PipelineStatus {
+            progress: resolution.round(progress),
+            fps: self.fps,
+            fps_smoothed: self.fps_smoothed,
+            eta_secs,
+            precision: resolution.decimals,
+        }
+    }
+
+    /// The job's own interval when its resolution asks for fewer events than `interval`.
+    fn interval(&self, interval: std::time::Duration) -> std::time::Duration {
+        self.resolution.map_or(interval, |r| interval.max(std::time::Duration::from_millis(r.interval_ms)))
+    }
+}
+
+/// Collects high-frequency events per job and emits them at most every `EVENT_INTERVAL_MS`:
+/// the latest progress as `pipeline_status` (plus `pipeline_progress` for older listeners) and
+/// log lines batched into `pipeline_log_batch`. A background thread flushes quiet jobs.
+struct EventCoalescer {
+    app: AppHandle,
+    pending: HashMap<Option<String>, PendingEvents>,
+}
+
+static EVENT_COALESCER: Mutex<Option<EventCoalescer>> = Mutex::new(None);
+
+fn coalesce_event(app: &AppHandle, job_id: Option<String>, update: impl FnOnce(&mut PendingEvents)) {
+    let mut guard = lock(&EVENT_COALESCER);
+    let coalescer = guard.get_or_insert_with(|| {
+        std::thread::spawn(|| loop {
+            let ms = EVENT_INTERVAL_MS.load(std::sync::atomic::Ordering::Relaxed);
+            std::thread::sleep(std::time::Duration::from_millis((ms / 4).max(10)));
+            flush_events(|_| false);
+        });
+        EventCoalescer { app: app.clone(), pending: HashMap::new() }
+    });
+    update(coalescer.pending.entry(job_id).or_default());
+}
+
+/// Emit now whatever `job_id` has queued; `finished` also forgets the job.
+fn flush_job_events(job_id: &Option<String>, finished: bool) {
+    flush_events(|id| id == job_id);
+    if finished {
+        if let Some(c) = lock(&EVENT_COALESCER).as_mut() {
+            c.pending.remove(job_id);
+        }
+    }
+}
+
+/// Emit queued events of jobs whose interval has passed, and of any job `force` selects.
+fn flush_events(force: impl Fn(&Option<String>) -> bool) {
+    let interval = std::time::Duration::from_millis(EVENT_INTERVAL_MS.load(std::sync::atomic::Ordering::Relaxed));
+    let mut ready = Vec::new();
+    let app = {
+        let mut guard = lock(&EVENT_COALESCER);
+        let Some(c) = guard.as_mut() else { return };
+        for (job_id, p) in c.pending.iter_mut() {
+            if p.progress.is_none() && p.logs.is_empty() {
+                continue;
+            }
+            if !force(job_id) && p.last_flush.is_some_and(|t| t.elapsed() < p.interval(interval)) {
+                continue;
+            }
+            p.last_flush = Some(std::time::Instant::now());
+            let status = p.progress.take().map(|pct| p.status(pct)).filter(|s| p.last_status.as_ref() != Some(s));
+            if let Some(s) = &status {
+                p.last_status = Some(s.clone());
+            }
+            if status.is_none() && p.logs.is_empty() {
+                continue;
+            }
+            ready.push((job_id.clone(), status, std::mem::take(&mut p.logs)));
+        }
+        c.app.clone()
+    };
+    // Emitted outside the lock: emitting can block on the webview.
+    for (job_id, status, logs) in ready {
+        if !logs.is_empty() {
+            emit_tagged(&app, job_id.clone(), "pipeline_log_batch", logs);
+        }
+        if let Some(status) = status {
+            emit_tagged(&app, job_id.clone(), "pipeline_progress", status.progress);
+            emit_tagged(&app, job_id, "pipeline_status", status);
+        }
+    }
+}
+
+// -------------------- App state --------------------
+
+/// Tool locations resolved from `bin/`, cached in `AppState` until an install or repair
+/// changes them so a running job keeps seeing the same binaries.
+#[derive(Clone, Debug, Default)]
+struct ResolvedTools {
+    ffmpeg: Option<PathBuf>,
+    rife: Option<PathBuf>,
+    rife_models: Option<PathBuf>,
+    /// Optional tools, from `bin/<tool>/<version>/` or a system install.
+    vspipe: Option<PathBuf>,
+    av1an: Option<PathBuf>,
+}
+
+impl ResolvedTools {
+    fn scan(root: &Path) -> Self {
+        let (ffmpeg, rife, rife_models) = find_installed_tool_paths(root);
+        Self { ffmpeg, rife, rife_models, vspipe: find_vspipe(root), av1an: find_managed_tool(root, "av1an", "av1an") }
+    }
+
+    fn paths(&self) -> (Option<PathBuf>, Option<PathBuf>, Option<PathBuf>) {
+        (self.ffmpeg.clone(), self.rife.clone(), self.rife_models.clone())
+    }
+
+    /// Name of the `bin/<tool>/<version>` folder the active binary lives in.
+    fn active_version(&self, tool: &str) -> Option<String> {
+        let bin = match tool {
+            "rife" => &self.rife,
+            "ffmpeg" => &self.ffmpeg,
+            _ => return None,
+        };
+        bin.as_deref()
+            .and_then(Path::parent)
+            .and_then(Path::file_name)
+            .map(|v| v.to_string_lossy().to_string())
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct RunningJob {
+    job_id: String,
+    kind: String,
+    input: String,
+    output: String,
+    started_at: String,
+    stage: Option<JobStage>,
+    /// External process the current stage is running, killed by `cancel_stage`.
+    #[serde(skip)]
+    child_pid: Option<u32>,
+    #[serde(skip)]
+    cancel_requested: bool,
+    /// Output of the stage process that failed last, for the job's `pipeline_done` event.
+    #[serde(skip)]
+    failure_tail: Option<ProcessTail>,
+}
+
+/// Pipeline stages of a job, in run order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum JobStage {
+    #[default]
+    Extract,
+    Interpolate,
+    Encode,
+}
+
+impl JobStage {
+    fn label(self) -> &'static str {
+        match self {
+            JobStage::Extract => "extract",
+            JobStage::Interpolate => "interpolate",
+            JobStage::Encode => "encode",
+        }
+    }
+}
+
+/// Process-wide state shared by all commands and job threads.
+#[derive(Default)]
+struct AppState {
+    tools: Mutex<Option<ResolvedTools>>,
+    settings: Mutex<Option<AppSettings>>,
+    jobs: Mutex<HashMap<String, RunningJob>>,
+    /// Jobs currently holding a run slot (see `acquire_job_slot`).
+    active_jobs: Mutex<usize>,
+    slot_freed: std::sync::Condvar,
+    /// Running jobs per tool binary, so installs and repairs don't replace a binary in use.
+    tool_users: Mutex<HashMap<PathBuf, usize>>,
+    /// Parsed `ffmpeg -encoders/-decoders/-hwaccels/-filters`, per ffmpeg binary.
+    ffmpeg_caps: Mutex<HashMap<PathBuf, FfmpegCapabilities>>,
+    /// Last preflight of the temp folder and when it ran (see `check_storage`).
+    temp_storage: Mutex<Option<(std::time::Instant, StorageCheck)>>,
+}
+
+fn lock<T>(m: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl AppState {
+    fn tools(&self, root: &Path) -> ResolvedTools {
+        lock(&self.tools).get_or_insert_with(|| ResolvedTools::scan(root)).clone()
+    }
+
+    fn refresh_tools(&self, root: &Path) -> ResolvedTools {
+        let tools = ResolvedTools::scan(root);
+        *lock(&self.tools) = Some(tools.clone());
+        tools
+    }
+
+    /// Capabilities of `ffmpeg`, parsed once per binary and again after it is replaced.
+    fn ffmpeg_capabilities(&self, ffmpeg: &Path) -> Result<FfmpegCapabilities, String> {
+        let identity = file_identity(ffmpeg);
+        if let Some(caps) = lock(&self.ffmpeg_caps).get(ffmpeg).filter(|c| c.identity == identity) {
+            return Ok(caps.clone());
+        }
+        let caps = probe_ffmpeg_capabilities(ffmpeg)?;
+        lock(&self.ffmpeg_caps).insert(ffmpeg.to_path_buf(), caps.clone());
+        Ok(caps)
+    }
+
+    /// Preflight of `temp/`, measured again once `STORAGE_CHECK_TTL_SECS` have passed.
+    fn temp_storage_check(&self, root: &Path, refresh: bool) -> StorageCheck {
+        if let Some((at, check)) = lock(&self.temp_storage).as_ref() {
+            if !refresh && at.elapsed().as_secs() < STORAGE_CHECK_TTL_SECS {
+                return check.clone();
+            }
+        }
+        let check = check_storage(&temp_root(root));
+        *lock(&self.temp_storage) = Some((std::time::Instant::now(), check.clone()));
+        check
+    }
+
+    fn settings(&self, root: &Path) -> AppSettings {
+        lock(&self.settings).get_or_insert_with(|| load_settings(root)).clone()
+    }
+
+    fn set_settings(&self, settings: AppSettings) {
+        *lock(&self.settings) = Some(settings);
+    }
+
+    fn job_started(&self, job_id: &str, kind: &str, input: &Path, output: &Path) {
+        lock(&self.jobs).insert(
+            job_id.to_string(),
+            RunningJob {
+                job_id: job_id.to_string(),
+                kind: kind.to_string(),
+                input: input.to_string_lossy().to_string(),
+                output: output.to_string_lossy().to_string(),
+                started_at: chrono::Local::now().to_rfc3339(),
+                stage: None,
+                child_pid: None,
+                cancel_requested: false,
+                failure_tail: None,
+            },
+        );
+    }
+
+    fn set_job_stage(&self, job_id: &str, stage: JobStage) {
+        if let Some(job) = lock(&self.jobs).get_mut(job_id) {
+            job.stage = Some(stage);
+            job.failure_tail = None;
+        }
+    }
+
+    fn set_failure_tail(&self, job_id: &str, tail: ProcessTail) {
+        if let Some(job) = lock(&self.jobs).get_mut(job_id) {
+            job.failure_tail = Some(tail);
+        }
+    }
+
+    fn take_failure_tail(&self, job_id: &str) -> Option<ProcessTail> {
+        lock(&self.jobs).get_mut(job_id).and_then(|j| j.failure_tail.take())
+    }
+
+    /// Record the process the job's current stage runs; a cancel that arrived before the
+    /// process existed is applied right away.
+    fn set_job_child(&self, job_id: &str, pid: u32) {
+        if let Some(job) = lock(&self.jobs).get_mut(job_id) {
+            job.child_pid = Some(pid);
+            if job.cancel_requested {
+                kill_process(pid);
+            }
+        }
+    }
+
+    fn job_child(&self, job_id: &str) -> Option<u32> {
+        lock(&self.jobs).get(job_id).and_then(|j| j.child_pid)
+    }
+
+    /// Clear the stage's process and report whether it was cancelled.
+    fn finish_job_child(&self, job_id: &str) -> bool {
+        match lock(&self.jobs).get_mut(job_id) {
+            Some(job) => {
+                job.child_pid = None;
+                std::mem::take(&mut job.cancel_requested)
+            }
+            None => false,
+        }
+    }
+
+    /// Number of running jobs using a binary at or below `path`.
+    fn tool_users_under(&self, path: &Path) -> usize {
+        lock(&self.tool_users)
+            .iter()
+            .filter(|(bin, _)| bin.starts_with(path))
+            .map(|(_, n)| *n)
+            .sum()
+    }
+
+    fn is_job_running(&self, job_id: &str) -> bool {
+        lock(&self.jobs).contains_key(job_id)
+    }
+
+    fn running_jobs(&self) -> Vec<RunningJob> {
+        let mut jobs: Vec<RunningJob> = lock(&self.jobs).values().cloned().collect();
+        jobs.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+        jobs
+    }
+}
+
+/// Removes a job from `AppState` when its worker thread ends, whichever way it ends.
+struct RunningJobGuard {
+    app: AppHandle,
+    job_id: String,
+}
+
+impl Drop for RunningJobGuard {
+    fn drop(&mut self) {
+        lock(&self.app.state::<AppState>().jobs).remove(&self.job_id);
+    }
+}
+
+/// Held by a job for as long as it may run its tools; see `AppState::tool_users`.
+struct ToolLease {
+    app: AppHandle,
+    tools: Vec<PathBuf>,
+}
+
+fn lease_tools(app: &AppHandle, tools: &[&Path]) -> ToolLease {
+    let state = app.state::<AppState>();
+    let mut users = lock(&state.tool_users);
+    for t in tools {
+        *users.entry(t.to_path_buf()).or_insert(0) += 1;
+    }
+    ToolLease { app: app.clone(), tools: tools.iter().map(|t| t.to_path_buf()).collect() }
+}
+
+impl Drop for ToolLease {
+    fn drop(&mut self) {
+        let state = self.app.state::<AppState>();
+        let mut users = lock(&state.tool_users);
+        for t in &self.tools {
+            if let Some(n) = users.get_mut(t) {
+                *n -= 1;
+                if *n == 0 {
+                    users.remove(t);
+                }
+            }
+        }
+    }
+}
+
+fn ensure_tool_not_in_use(app: &AppHandle, tool: &str, version_dir: &Path) -> Result<(), String> {
+    match app.state::<AppState>().tool_users_under(version_dir) {
+        0 => Ok(()),
+        n => Err(format!("{tool} is in use by {n} running job(s); wait for them to finish first")),
+    }
+}
+
+/// A run slot; at most `AppSettings::max_concurrent_jobs` are held at once.
+struct JobSlot {
+    app: AppHandle,
+}
+
+impl Drop for JobSlot {
+    fn drop(&mut self) {
+        let state = self.app.state::<AppState>();
+        *lock(&state.active_jobs) -= 1;
+        state.slot_freed.notify_all();
+    }
+}
+
+/// Block the job's worker thread until fewer than `max_concurrent_jobs` jobs are running.
+fn acquire_job_slot(app: &AppHandle, settings: &AppSettings) -> JobSlot {
+    let state = app.state::<AppState>();
+    let max = settings.max_concurrent_jobs.max(1) as usize;
+    let mut active = lock(&state.active_jobs);
+    if *active >= max {
+        emit_stage(app, StageId::WaitingForSlot, message("stage.waiting_for_slot", &[]));
+        emit_log_limited(app, &format!("{} job(s) already running; waiting for one to finish", *active));
+    }
+    while *active >= max {
+        active = state.slot_freed.wait(active).unwrap_or_else(|e| e.into_inner());
+    }
+    *active += 1;
+    JobSlot { app: app.clone() }
+}
+
+/// Error for a stage whose process was stopped through `cancel_stage`.
+fn check_stage_cancelled(app: &AppHandle, job_id: &str, stage: JobStage) -> Result<(), String> {
+    if app.state::<AppState>().finish_job_child(job_id) {
+        return Err(format!(
+            "Cancelled during the {} stage. Intermediate files were kept; retry the stage to continue.",
+            stage.label()
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn cancel_stage(app: AppHandle, state: State<'_, AppState>, job_id: String) -> Result<String, String> {
+    // Taken first, so a due job can't start between the two checks.
+    let schedule = lock_schedule();
+    let mut jobs = lock(&state.jobs);
+    let Some(job) = jobs.get_mut(&job_id) else {
+        drop(jobs);
+        return cancel_scheduled_start(&app, &schedule, &job_id);
+    };
+    job.cancel_requested = true;
+    if let Some(pid) = job.child_pid {
+        kill_process(pid);
+    }
+    Ok(match job.stage {
+        Some(stage) => format!("Cancelling the {} stage", stage.label()),
+        None => "Cancelling before the first stage starts".to_string(),
+    })
+}
+
+#[tauri::command]
+fn list_running_jobs(state: State<'_, AppState>) -> Vec<RunningJob> {
+    state.running_jobs()
+}
+
+// -------------------- Settings --------------------
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct AppSettings {
+    /// When overwriting an existing output, move the old file to the OS trash first.
+    /// Trashing can be very slow (or unsupported) on network volumes, so it can be turned off.
+    trash_replaced_outputs: bool,
+    /// Post-job hooks run arbitrary programs, so they only run after an explicit opt-in.
+    allow_post_job_hooks: bool,
+    /// Stage plugins (see `list_plugins`) and VapourSynth scripts likewise need an explicit opt-in.
+    allow_stage_plugins: bool,
+    /// Local-time window during which no job starts (e.g. peak electricity hours).
+    quiet_hours: Option<QuietHours>,
+    /// Hold (or pause) GPU-heavy work while running on battery at or below this charge.
+    battery_min_percent: Option<u8>,
+    /// Jobs allowed to run at the same time; further jobs wait for a free slot.
+    max_concurrent_jobs: u32,
+    /// Decode the source on the GPU during frame extraction when a hardware decoder passes a
+    /// one-frame test decode of it; a failed hardware decode falls back to software. Off by
+    /// default: it isn't faster on every machine.
+    hw_decode: bool,
+    /// Image format of the extracted and interpolated frames.
+    frame_format: FrameFormat,
+    /// Keep the frames of jobs estimated to need at most this many MB in RAM-backed storage
+    /// (see `ram_temp_dir`). Off when unset; larger jobs, or a full RAM disk, use `temp/`.
+    ram_temp_max_mb: Option<u64>,
+    /// RAM disk to use for `ram_temp_max_mb`; defaults to /dev/shm on Linux. Other platforms
+    /// need a RAM disk created and set here.
+    ram_temp_dir: Option<String>,
+    event_mode: EventMode,
+    /// Overrides the interval `event_mode` implies, in milliseconds.
+    event_interval_ms: Option<u64>,
+    /// How interpolation runs. `builtin` needs a build with the `ncnn` feature.
+    rife_backend: RifeBackend,
+    /// With the built-in backend, pipe frames from the decoder through RIFE into the encoder
+    /// instead of writing them to disk, for jobs that don't need them there (see
+    /// `streamed_frame_size`).
+    stream_frames: bool,
+    /// Program that converts PyTorch RIFE checkpoints, called like the bundled script:
+    /// `<converter> --input <checkpoint> --output <folder>`. Unset uses the bundled script.
+    model_converter: Option<String>,
+    /// Allow `check_for_tool_updates` to contact GitHub and the update catalog.
+    check_tool_updates: bool,
+    /// JSON release catalog (a list of `CatalogEntry`) for ffmpeg, models and other tools;
+    /// its entries replace the built-in rife-ncnn-vulkan check for the same tool.
+    update_catalog_url: Option<String>,
+    /// Proxy for downloads. Unset, curl uses the `HTTPS_PROXY` / `NO_PROXY` environment.
+    proxy: Option<ProxySettings>,
+    /// Fixed share of the progress bar per stage. Unset derives the shares from the stage
+    /// speeds of recent jobs, so the bar moves evenly with wall time.
+    stage_weights: Option<StageWeights>,
+    /// Size cap for `cache/` in MiB; least recently used entries are evicted past it. 0 = no cap.
+    cache_limit_mb: u64,
+    /// CA bundle (PEM) for networks that intercept TLS. It replaces curl's default bundle, so
+    /// it should hold the public roots as well as the proxy's.
+    ca_bundle: Option<String>,
+    /// Folder for suggested output paths; unset puts them next to the input.
+    output_dir: Option<String>,
+    /// File name (without extension) of suggested outputs. `{name}` is the input's name;
+    /// `{fps}`, `{model}`, `{preset}` and `{date}` describe the job.
+    output_name_template: String,
+    /// Inputs of recent jobs, newest first (see `remember_input`). Owned by the recent-input
+    /// commands; `update_settings` keeps the stored list whatever the form sends.
+    recent_inputs: Vec<String>,
+    /// Folders pinned with `pin_favorite`; kept by `update_settings` like `recent_inputs`.
+    favorite_folders: Vec<String>,
+    /// What jobs do with HDR, VFR, interlaced, rotated and very high frame rate sources.
+    source_policy: SourcePolicy,
+    gpu_sharing: GpuSharing,
+    /// Lines of output kept per stage process and sent with a failed job's `pipeline_done`
+    /// event (8 to 1000).
+    output_tail_lines: usize,
+    /// Skip RIFE inside static runs (credits, title cards) and repeat their frames instead.
+    /// Off interpolates every frame.
+    skip_frame_holds: bool,
+    /// Never write in the source's folder, or anywhere on its volume when that isn't the drive
+    /// the app lives on (camera cards, USB drives). Jobs fail preflight instead.
+    protect_sources: bool,
+    /// Suggest outputs inside the library (`<year>/<month>/<job name>/` under `library_dir`)
+    /// instead of `output_dir`.
+    library_mode: bool,
+    /// Library folder; unset uses `Output/` in the app folder.
+    library_dir: Option<String>,
+    /// What `smooth_video` does when the same input with the same settings is already queued,
+    /// running or done. A job's `on_duplicate` wins.
+    duplicate_jobs: DuplicatePolicy,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct ProxySettings {
+    /// `host`, or `scheme://host` for socks5 and https proxies.
+    host: String,
+    port: Option<u16>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            trash_replaced_outputs: true,
+            allow_post_job_hooks: false,
+            allow_stage_plugins: false,
+            quiet_hours: None,
+            battery_min_percent: None,
+            max_concurrent_jobs: 2,
+            hw_decode: false,
+            frame_format: FrameFormat::default(),
+            ram_temp_max_mb: None,
+            ram_temp_dir: None,
+            event_mode: EventMode::default(),
+            event_interval_ms: None,
+            rife_backend: RifeBackend::default(),
+            stream_frames: true,
+            model_converter: None,
+            check_tool_updates: false,
+            update_catalog_url: None,
+            proxy: None,
+            ca_bundle: None,
+            cache_limit_mb: 5 * 1024,
+            stage_weights: None,
+            output_dir: None,
+            output_name_template: "{name}_{fps}fps".into(),
+            recent_inputs: Vec::new(),
+            favorite_folders: Vec::new(),
+            source_policy: SourcePolicy::default(),
+            gpu_sharing: GpuSharing::default(),
+            output_tail_lines: DEFAULT_OUTPUT_TAIL_LINES,
+            skip_frame_holds: true,
+            protect_sources: false,
+            library_mode: false,
+            library_dir: None,
+            duplicate_jobs: DuplicatePolicy::default(),
+        }
+    }
+}
+
+/// `[start_hour, end_hour)` in local time; wraps past midnight when start > end.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+struct QuietHours {
+    start_hour: u32,
+    end_hour: u32,
+}
+
+impl QuietHours {
+    fn contains(&self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+/// Unix millis as a local "YYYY-MM-DD HH:MM".
+fn local_time_label(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|d| d.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// Block the job's worker thread until `not_before` (unix millis) has passed and the clock is
+/// outside the configured quiet hours. Quiet hours only hold back job *starts*; a job that is
+/// already running is never paused.
+fn wait_for_start_window(app: &AppHandle, settings: &AppSettings, not_before: Option<i64>) {
+    use chrono::Timelike;
+
+    let mut last_note = String::new();
+    loop {
+        let now = chrono::Local::now();
+        let (stage, note) = match (not_before, settings.quiet_hours) {
+            (Some(t), _) if now.timestamp_millis() < t => {
+                (StageId::Scheduled, message("stage.scheduled", &[("at", local_time_label(t))]))
+            }
+            (_, Some(q)) if q.contains(now.hour()) => {
+                (StageId::QuietHours, message("stage.quiet_hours", &[("end_hour", format!("{:02}", q.end_hour))]))
+            }
+            _ => return,
+        };
+        if note.text != last_note {
+            emit_log_limited(app, &note.text);
+            last_note = note.text.clone();
+            emit_stage(app, stage, note);
+        }
+        std::thread::sleep(std::time::Duration::from_secs(5));
+    }
+}
+
+// -------------------- Scheduled starts --------------------
+
+/// A Smooth Video job waiting for its `not_before` time. The job itself is its saved spec
+/// (see `save_job_spec`); the scheduler starts it from there, so it survives a restart.
+///
+/// Like the history its jobs are recorded in, the schedule belongs to the profile it was made
+/// in: jobs scheduled under another profile wait until that profile is active again.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct ScheduledStart {
+    job_id: String,
+    /// Unix millis.
+    not_before: i64,
+}
+
+/// Held across each change to scheduled_jobs.json, and by the scheduler until the jobs it took
+/// are running, so a cancel finds a job either still scheduled or running (see `cancel_stage`).
+static SCHEDULE_LOCK: Mutex<()> = Mutex::new(());
+
+/// How often the scheduler looks for jobs that are due.
+const SCHEDULER_POLL_SECS: u64 = 5;
+
+fn schedule_path(root: &Path) -> PathBuf {
+    profile_root(root).join("scheduled_jobs.json")
+}
+
+fn lock_schedule() -> std::sync::MutexGuard<'static, ()> {
+    lock(&SCHEDULE_LOCK)
+}
+
+fn load_schedule(root: &Path) -> Vec<ScheduledStart> {
+    fs::read_to_string(schedule_path(root))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Change the schedule; `_held` is the caller's `lock_schedule` guard.
+fn update_schedule<T>(
+    _held: &std::sync::MutexGuard<'_, ()>,
+    root: &Path,
+    f: impl FnOnce(&mut Vec<ScheduledStart>) -> T,
+) -> Result<T, String> {
+    let mut schedule = load_schedule(root);
+    let out = f(&mut schedule);
+    let json = serde_json::to_string_pretty(&schedule).map_err(|e| e.to_string())?;
+    fs::write(schedule_path(root), json).map_err(|e| format!("Failed to write the job schedule: {e}"))?;
+    Ok(out)
+}
+
+/// Remove and return the entries due at `now` (unix millis).
+fn take_due_starts(held: &std::sync::MutexGuard<'_, ()>, root: &Path, now: i64) -> Vec<ScheduledStart> {
+    update_schedule(held, root, |s| {
+        let (due, waiting): (Vec<_>, Vec<_>) = s.drain(..).partition(|e| e.not_before <= now);
+        *s = waiting;
+        due
+    })
+    .unwrap_or_default()
+}
+
+/// Start scheduled jobs once their time has come. Runs for the life of the app; quiet hours
+/// are still honoured by the job itself (see `wait_for_start_window`).
+fn run_start_scheduler(app: AppHandle) {
+    let Ok(root) = app_root(&app) else { return };
+    loop {
+        let held = lock_schedule();
+        for entry in take_due_starts(&held, &root, chrono::Local::now().timestamp_millis()) {
+            match load_job_spec(&root, &entry.job_id) {
+                Ok(job) => {
+                    emit_log_limited(&app, &format!("Starting scheduled job {}", entry.job_id));
+                    spawn_smooth_job(&app, &app.state::<AppState>(), job);
+                }
+                Err(e) => {
+                    emit_log_limited(&app, &format!("Scheduled job {} can't start: {e}", entry.job_id));
+                    record_job_finish(&root, &entry.job_id, false, &e);
+                }
+            }
+        }
+        drop(held);
+        std::thread::sleep(std::time::Duration::from_secs(SCHEDULER_POLL_SECS));
+    }
+}
+
+/// Drop a job from the schedule before it starts, along with its empty job folders.
+fn cancel_scheduled_start(app: &AppHandle, held: &std::sync::MutexGuard<'_, ()>, job_id: &str) -> Result<String, String> {
+    let root = app_root(app)?;
+    let removed = update_schedule(held, &root, |s| {
+        let before = s.len();
+        s.retain(|e| e.job_id != job_id);
+        s.len() != before
+    })?;
+    if !removed {
+        return Err("Job is not running".into());
+    }
+    if let Ok(job) = load_job_spec(&root, job_id) {
+        remove_job_intermediates(app, &job);
+        let _ = fs::remove_file(job_spec_path(&root, job_id));
+    }
+    record_job_finish(&root, job_id, false, "Scheduled start cancelled");
+    Ok("Scheduled start cancelled".into())
+}
+
+/// Jobs waiting for their scheduled start, soonest first.
+#[tauri::command]
+fn list_scheduled_jobs(app: AppHandle) -> Result<Vec<ScheduledStart>, String> {
+    let mut schedule = load_schedule(&app_root(&app)?);
+    schedule.sort_by_key(|e| e.not_before);
+    Ok(schedule)
+}
+
+// -------------------- Profiles --------------------
+//
+// Settings, presets, model profiles and history belong to the active profile; tools, models,
+// the cache and temp are shared. The `default` profile keeps its files directly in the app
+// root, other profiles in `profiles/<name>/`.
+
+const DEFAULT_PROFILE: &str = "default";
+
+/// Active profile name, read from `profiles/active` on first use.
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+fn active_profile(root: &Path) -> String {
+    lock(&ACTIVE_PROFILE)
+        .get_or_insert_with(|| {
+            fs::read_to_string(root.join("profiles").join("active"))
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|n| is_plain_name(n) && (n == DEFAULT_PROFILE || root.join("profiles").join(n).is_dir()))
+                .unwrap_or_else(|| DEFAULT_PROFILE.into())
+        })
+        .clone()
+}
+
+/// Folder holding the active profile's settings, presets and history.
+fn profile_root(root: &Path) -> PathBuf {
+    match active_profile(root).as_str() {
+        DEFAULT_PROFILE => root.to_path_buf(),
+        name => root.join("profiles").join(name),
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct ProfileInfo {
+    name: String,
+    active: bool,
+}
+
+#[tauri::command]
+fn list_profiles(app: AppHandle) -> Result<Vec<ProfileInfo>, String> {
+    let root = app_root(&app)?;
+    let active = active_profile(&root);
+    let mut names: Vec<String> = fs::read_dir(root.join("profiles"))
+        .map(|rd| {
+            rd.flatten()
+                .filter(|e| e.path().is_dir())
+                .map(|e| e.file_name().to_string_lossy().to_string())
+                .filter(|n| n != DEFAULT_PROFILE)
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.into());
+    Ok(names.into_iter().map(|name| ProfileInfo { active: name == active, name }).collect())
+}
+
+/// Make `name` the active profile, creating it when `create` is set. Refused while jobs run,
+/// since they record their history into the profile they started in.
+#[tauri::command]
+fn switch_profile(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    name: String,
+    create: Option<bool>,
+) -> Result<AppSettings, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let name = name.trim().to_string();
+    if !is_plain_name(&name) || name == "active" {
+        return Err(format!("Invalid profile name: {name:?}"));
+    }
+    if !lock(&state.jobs).is_empty() {
+        return Err("Wait for running jobs to finish before switching profiles".into());
+    }
+    let dir = root.join("profiles").join(&name);
+    if name != DEFAULT_PROFILE && !dir.is_dir() {
+        if !create.unwrap_or(false) {
+            return Err(format!("No profile named {name}"));
+        }
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create profile {name}: {e}"))?;
+    }
+    fs::create_dir_all(root.join("profiles")).map_err(|e| format!("Failed to create profiles folder: {e}"))?;
+    fs::write(root.join("profiles").join("active"), &name).map_err(|e| format!("Failed to save the active profile: {e}"))?;
+    *lock(&ACTIVE_PROFILE) = Some(name.clone());
+
+    let settings = load_settings(&root);
+    apply_event_settings(&settings);
+    state.set_settings(settings.clone());
+    emit_log_limited(&app, &format!("Switched to profile {name}"));
+    Ok(settings)
+}
+
+fn settings_path(root: &Path) -> PathBuf {
+    profile_root(root).join("settings.json")
+}
+
+fn load_settings(root: &Path) -> AppSettings {
+    fs::read_to_string(settings_path(root))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(root: &Path, settings: &AppSettings) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(settings_path(root), json).map_err(|e| format!("Failed to write settings: {e}"))
+}
+
+#[tauri::command]
+fn get_settings(app: AppHandle, state: State<'_, AppState>) -> Result<AppSettings, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    Ok(state.settings(&root))
+}
+
+#[tauri::command]
+fn update_settings(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    mut settings: AppSettings,
+) -> Result<AppSettings, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    // A settings form holds a copy from when it opened; jobs and pins since then win.
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let current = state.settings(&root);
+    settings.recent_inputs = current.recent_inputs;
+    settings.favorite_folders = current.favorite_folders;
+    save_settings(&root, &settings)?;
+    apply_event_settings(&settings);
+    enforce_cache_limit(&root, &settings);
+    state.set_settings(settings.clone());
+    Ok(settings)
+}
+
+// -------------------- Recent inputs --------------------
+
+const MAX_RECENT_INPUTS: usize = 20;
+
+/// Serializes read-modify-write cycles on settings.json between `update_settings` and the
+/// recent-input commands.
+static SETTINGS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct RecentPath {
+    path: String,
+    exists: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct RecentInputs {
+    recent: Vec<RecentPath>,
+    favorites: Vec<RecentPath>,
+}
+
+/// Move `input` to the front of the recent inputs and persist them.
+fn remember_input(state: &AppState, root: &Path, input: &Path) {
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut settings = state.settings(root);
+    let path = input.to_string_lossy().to_string();
+    settings.recent_inputs.retain(|p| *p != path);
+    settings.recent_inputs.insert(0, path);
+    settings.recent_inputs.truncate(MAX_RECENT_INPUTS);
+    if save_settings(root, &settings).is_ok() {
+        state.set_settings(settings);
+    }
+}
+
+/// Recent inputs and favorite folders. Recent files that are gone are dropped for good;
+/// favorites are kept (a network share or external drive may just be offline) and marked.
+#[tauri::command]
+fn get_recent_inputs(app: AppHandle, state: State<'_, AppState>) -> Result<RecentInputs, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut settings = state.settings(&root);
+    let before = settings.recent_inputs.len();
+    settings.recent_inputs.retain(|p| Path::new(p).is_file());
+    if settings.recent_inputs.len() != before {
+        save_settings(&root, &settings)?;
+        state.set_settings(settings.clone());
+    }
+    Ok(RecentInputs {
+        recent: settings.recent_inputs.iter().map(|p| RecentPath { path: p.clone(), exists: true }).collect(),
+        favorites: settings
+            .favorite_folders
+            .iter()
+            .map(|p| RecentPath { path: p.clone(), exists: Path::new(p).is_dir() })
+            .collect(),
+    })
+}
+
+/// Pin (or with `pinned: false`, unpin) a favorite folder. A file path pins its folder.
+#[tauri::command]
+fn pin_favorite(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    pinned: Option<bool>,
+) -> Result<RecentInputs, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let mut folder = PathBuf::from(path.trim());
+    if folder.is_file() {
+        folder = folder.parent().map(Path::to_path_buf).unwrap_or_default();
+    }
+    let folder = folder.to_string_lossy().to_string();
+    {
+        let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut settings = state.settings(&root);
+        settings.favorite_folders.retain(|p| *p != folder);
+        if pinned.unwrap_or(true) {
+            if !Path::new(&folder).is_dir() {
+                return Err(format!("Folder does not exist: {folder}"));
+            }
+            settings.favorite_folders.push(folder);
+        }
+        save_settings(&root, &settings)?;
+        state.set_settings(settings);
+    }
+    get_recent_inputs(app, state)
+}
+
+// -------------------- Power --------------------
+
+#[derive(Clone, Copy, Debug)]
+struct BatteryStatus {
+    on_battery: bool,
+    percent: f64,
+}
+
+/// Best-effort battery reading; None on desktops or when the platform query fails.
+#[cfg(target_os = "linux")]
+fn read_battery_status() -> Option<BatteryStatus> {
+    let mut percent = None;
+    let mut on_ac = false;
+    for e in fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let p = e.path();
+        let kind = fs::read_to_string(p.join("type")).unwrap_or_default();
+        match kind.trim() {
+            "Battery" => {
+                if percent.is_none() {
+                    percent = fs::read_to_string(p.join("capacity"))
+                        .ok()
+                        .and_then(|c| c.trim().parse::<f64>().ok());
+                }
+            }
+            "Mains" | "USB" => {
+                if fs::read_to_string(p.join("online")).map(|o| o.trim() == "1").unwrap_or(false) {
+                    on_ac = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(BatteryStatus { on_battery: !on_ac, percent: percent? })
+}
+
+#[cfg(target_os = "macos")]
+fn read_battery_status() -> Option<BatteryStatus> {
+    // "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=…)\t85%; discharging; …"
+    let out = run_tool(tool_command("pmset").arg("-g").arg("batt"), QUERY_TIMEOUT_SECS).ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let percent = text
+        .split('%')
+        .next()?
+        .rsplit(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse::<f64>()
+        .ok()?;
+    Some(BatteryStatus { on_battery: text.contains("'Battery Power'"), percent })
+}
+
+#[cfg(target_os = "windows")]
+fn read_battery_status() -> Option<BatteryStatus> {
+    // Win32_Battery.BatteryStatus 1 = discharging.
+    let out = run_tool(
+        tool_command("powershell")
+            .arg("-NoProfile")
+            .arg("-Command")
+            .arg("Get-CimInstance Win32_Battery | Select-Object -First 1 | ForEach-Object { \"$($_.BatteryStatus) $($_.EstimatedChargeRemaining)\" }"),
+        QUERY_TIMEOUT_SECS,
+    )
+    .ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let mut it = text.split_whitespace();
+    let status = it.next()?.parse::<u32>().ok()?;
+    let percent = it.next()?.parse::<f64>().ok()?;
+    Some(BatteryStatus { on_battery: status == 1, percent })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read_battery_status() -> Option<BatteryStatus> {
+    None
+}
+
+/// Why GPU work should be held right now, if the battery policy says so.
+fn battery_hold_reason(settings: &AppSettings) -> Option<Message> {
+    let min = settings.battery_min_percent?;
+    let b = read_battery_status()?;
+    if b.on_battery && b.percent <= min as f64 {
+        Some(message("power.on_battery", &[("percent", format!("{:.0}", b.percent)), ("limit", min.to_string())]))
+    } else {
+        None
+    }
+}
+
+/// Hold the calling worker until the battery policy allows GPU work again.
+fn wait_for_power(app: &AppHandle, settings: &AppSettings) {
+    let mut announced = false;
+    while let Some(reason) = battery_hold_reason(settings) {
+        if !announced {
+            emit_log_limited(app, &reason.text);
+            emit_stage(app, StageId::PowerHold, reason);
+            announced = true;
+        }
+        std::thread::sleep(std::time::Duration::from_secs(15));
+    }
+    if announced {
+        emit_log_limited(app, "Power restored: resuming");
+    }
+}
+
+/// Suspend/resume a running child (SIGSTOP/SIGCONT). Not available on Windows.
+fn set_process_suspended(pid: u32, suspended: bool) -> bool {
+    if cfg!(unix) {
+        run_tool(tool_command("kill").arg(if suspended { "-STOP" } else { "-CONT" }).arg(pid.to_string()), QUERY_TIMEOUT_SECS)
+            .is_ok_and(|o| o.status.success())
+    } else {
+        false
+    }
+}
+
+/// Start `cmd` as the leader of a process group of its own, so `kill_process` reaches the
+/// processes it starts as well. Windows needs nothing: `taskkill /T` stops the whole tree.
+fn new_process_group(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(not(unix))]
+    let _ = cmd;
+}
+
+/// Start `cmd` in the process group `leader` leads (see `new_process_group`), so stopping the
+/// leader stops it too. On Windows it stays on its own; a process that reads the leader's
+/// output still ends once the leader is gone.
+fn join_process_group(cmd: &mut Command, leader: u32) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(leader as i32);
+    }
+    #[cfg(not(unix))]
+    let _ = (cmd, leader);
+}
+
+/// Send `signal` to `pid` and, when it leads a process group (see `new_process_group`), to
+/// the rest of the group.
+fn signal_process(pid: u32, signal: &str) {
+    for args in [vec![signal.to_string(), "--".into(), format!("-{pid}")], vec![signal.to_string(), pid.to_string()]] {
+        // Not `run_tool`: its timeout signals through here.
+        let Ok(mut kill) = tool_command("kill").args(&args).stdout(Stdio::null()).stderr(Stdio::null()).spawn() else { continue };
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(QUERY_TIMEOUT_SECS);
+        while matches!(kill.try_wait(), Ok(None)) && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        let _ = kill.kill();
+        let _ = kill.wait();
+    }
+}
+
+/// Stop an external tool. A process paused by `set_process_suspended` is resumed so it can
+/// act on the signal.
+///
+/// A watchdog then checks the process actually went away: one that outlives
+/// `KILL_GRACE_SECS` is force-killed (SIGKILL; on Windows `taskkill /F` again, which is
+/// TerminateProcess), and one that outlives that too is reported through `check_unkillable`.
+fn kill_process(pid: u32) {
+    let identity = process_identity(pid);
+    if cfg!(windows) {
+        force_kill(pid);
+    } else {
+        signal_process(pid, "-TERM");
+        signal_process(pid, "-CONT");
+    }
+    let Some(identity) = identity else { return };
+    std::thread::spawn(move || {
+        if exited_within(pid, &identity, KILL_GRACE_SECS) {
+            return;
+        }
+        force_kill(pid);
+        if exited_within(pid, &identity, KILL_GRACE_SECS) {
+            return;
+        }
+        let state = process_state(pid).unwrap_or_else(|| "unknown".into());
+        lock(&UNKILLABLE).push((pid, format!(
+            "The stage's process (pid {pid}) is still there after being force-killed; the OS reports it as \"{state}\". \
+             It's most likely stuck in the GPU driver and may only go away after a driver reset or a reboot."
+        )));
+    });
+}
+
+/// How long a stopped process gets to exit before the watchdog's next step.
+const KILL_GRACE_SECS: u64 = 5;
+
+/// Processes that outlived a force-kill, with the watchdog's diagnostics, until the stage
+/// waiting on them picks them up.
+static UNKILLABLE: Mutex<Vec<(u32, String)>> = Mutex::new(Vec::new());
+
+fn force_kill(pid: u32) {
+    if cfg!(windows) {
+        let _ = run_tool(tool_command("taskkill").arg("/PID").arg(pid.to_string()).arg("/T").arg("/F"), QUERY_TIMEOUT_SECS);
+    } else {
+        signal_process(pid, "-KILL");
+    }
+}
+
+/// Something that tells this process apart from a later one given the same pid (its start
+/// time and name), or None once it's gone. A zombie counts as gone: it has exited, and the
+/// stage's wait reaps it.
+fn process_identity(pid: u32) -> Option<String> {
+    if cfg!(windows) {
+        let out = run_tool(tool_command("tasklist").args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH"]), QUERY_TIMEOUT_SECS).ok()?;
+        let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        if !text.contains(&format!("\"{pid}\"")) {
+            return None;
+        }
+        text.split(',').next().map(str::to_string)
+    } else {
+        let out = run_tool(tool_command("ps").args(["-o", "stat=", "-o", "lstart=", "-o", "comm=", "-p", &pid.to_string()]), QUERY_TIMEOUT_SECS).ok()?;
+        let text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+        let (stat, rest) = text.split_once(char::is_whitespace)?;
+        (!stat.starts_with('Z')).then(|| rest.trim().to_string())
+    }
+}
+
+/// Whether the process `identity` names is gone (or its pid was reused) within `secs`.
+fn exited_within(pid: u32, identity: &str, secs: u64) -> bool {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(secs);
+    loop {
+        if process_identity(pid).as_deref() != Some(identity) {
+            return true;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(250));
+    }
+}
+
+/// What the OS says a stuck process is doing, for the failure report: state and wait channel
+/// (`D` plus a driver function is the usual Vulkan hang), or the task list entry on Windows.
+fn process_state(pid: u32) -> Option<String> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = tool_command("tasklist");
+        cmd.args(["/FI", &format!("PID eq {pid}"), "/FO", "CSV", "/NH", "/V"]);
+        cmd
+    } else {
+        let mut cmd = tool_command("ps");
+        cmd.args(["-o", "stat=", "-o", "wchan=", "-p", &pid.to_string()]);
+        cmd
+    };
+    let out = run_tool(&mut cmd, QUERY_TIMEOUT_SECS).ok()?;
+    let text = String::from_utf8_lossy(&out.stdout).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Fail a stage whose process outlived the watchdog's force-kill, instead of waiting on it
+/// forever. Its output readers are left to end with the process.
+fn check_unkillable(app: &AppHandle, job_id: &str, pid: u32) -> Result<(), String> {
+    let report = {
+        let mut stuck = lock(&UNKILLABLE);
+        stuck.iter().position(|(p, _)| *p == pid).map(|i| stuck.remove(i).1)
+    };
+    let Some(report) = report else { return Ok(()) };
+    app.state::<AppState>().finish_job_child(job_id);
+    emit_log_limited(app, &report);
+    Err(report)
+}
+
+/// `Child::wait` for a stage's process, checking `check_unkillable` while it waits.
+fn wait_stage_child(app: &AppHandle, job_id: &str, child: &mut std::process::Child) -> Result<Option<std::process::ExitStatus>, String> {
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(Some(status)),
+            Ok(None) => {}
+            Err(_) => return Ok(None),
+        }
+        check_unkillable(app, job_id, child.id())?;
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+}
+
+/// How long output readers get to drain once their process has exited.
+const READER_JOIN_SECS: u64 = 5;
+
+/// Join a finished process's output readers. One still blocked after `READER_JOIN_SECS` (a
+/// grandchild kept the pipe open) is left to end with the pipe rather than hang the job.
+fn join_readers(app: &AppHandle, readers: impl IntoIterator<Item = std::thread::JoinHandle<()>>) {
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(READER_JOIN_SECS);
+    let mut left = 0;
+    for reader in readers {
+        while !reader.is_finished() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        if reader.is_finished() {
+            let _ = reader.join();
+        } else {
+            left += 1;
+        }
+    }
+    if left > 0 {
+        emit_log_limited(app, &format!("{left} output reader(s) still waiting on a pipe held by a child process; continuing without them"));
+    }
+}
+
+// -------------------- Sleep/resume --------------------
+
+/// A wall-clock jump this much longer than the watch's 1 s tick means the machine slept.
+const RESUME_GAP_SECS: u64 = 30;
+/// After a resume, a stage whose output doesn't grow for this long is treated as hung.
+const RESUME_STALL_SECS: u64 = 120;
+/// Stage restarts a job gets after sleeps.
+const MAX_RESUME_RESTARTS: u32 = 2;
+
+/// Watches a running stage for system sleep. After a resume it checks that the stage's output
+/// keeps growing and kills a child that stays stuck, so the stage fails and can be restarted.
+struct ResumeWatch {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    resumed: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl ResumeWatch {
+    fn start<P>(app: &AppHandle, job: &SmoothJob, stage: JobStage, progress: P) -> Self
+    where
+        P: Fn() -> u64 + Send + 'static,
+    {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        let stop = std::sync::Arc::new(AtomicBool::new(false));
+        let resumed = std::sync::Arc::new(AtomicBool::new(false));
+        let (app, job_id, settings) = (app.clone(), job.job_id.clone(), job.settings.clone());
+        let (stop_flag, resumed_flag) = (stop.clone(), resumed.clone());
+        let handle = spawn_in_job(move || {
+            let mut last_tick = std::time::SystemTime::now();
+            // Set after a resume until the stage shows progress: (checking since, output then).
+            let mut checking: Option<(std::time::Instant, u64)> = None;
+            while !stop_flag.load(Ordering::Relaxed) {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                let now = std::time::SystemTime::now();
+                let gap = now.duration_since(last_tick).map(|d| d.as_secs()).unwrap_or(0);
+                last_tick = now;
+                if gap >= RESUME_GAP_SECS {
+                    resumed_flag.store(true, Ordering::Relaxed);
+                    emit_log_limited(&app, &format!(
+                        "System resumed after ~{gap}s asleep; checking the {} stage is still running",
+                        stage.label()
+                    ));
+                    checking = Some((std::time::Instant::now(), progress()));
+                    continue;
+                }
+                let Some((since, before)) = checking else { continue };
+                if progress() > before {
+                    emit_log_limited(&app, &format!("The {} stage is making progress again", stage.label()));
+                    checking = None;
+                } else if battery_hold_reason(&settings).is_some() {
+                    // Paused on purpose for low battery; that isn't a hang.
+                    checking = Some((std::time::Instant::now(), before));
+                } else if since.elapsed().as_secs() >= RESUME_STALL_SECS {
+                    emit_log_limited(&app, &format!(
+                        "The {} stage made no progress for {RESUME_STALL_SECS}s after resume; stopping it",
+                        stage.label()
+                    ));
+                    if let Some(pid) = app.state::<AppState>().job_child(&job_id) {
+                        kill_process(pid);
+                    }
+                    checking = None;
+                }
+            }
+        });
+        ResumeWatch { stop, resumed, handle: Some(handle) }
+    }
+
+    /// Stop watching; reports whether the system resumed from sleep while the stage ran.
+    fn finish(mut self) -> bool {
+        self.stop();
+        self.resumed.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn stop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+    }
+}
+
+impl Drop for ResumeWatch {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Run one stage of a job under a `ResumeWatch`. A stage that fails after the system resumed
+/// (a child killed by the sleep, or stopped as hung) is restarted from the stage's start, at
+/// most `restarts` times per job; earlier stages' output is kept.
+fn run_with_resume_watch<T, P>(
+    app: &AppHandle,
+    job: &SmoothJob,
+    stage: JobStage,
+    restarts: &mut u32,
+    progress: P,
+    mut run: impl FnMut() -> Result<T, String>,
+) -> Result<T, String>
+where
+    P: Fn() -> u64 + Send + Clone + 'static,
+{
+    loop {
+        let watch = ResumeWatch::start(app, job, stage, progress.clone());
+        let result = run();
+        let resumed = watch.finish();
+        match result {
+            Err(e) if resumed && *restarts > 0 && !is_cancelled_error(&e) => {
+                *restarts -= 1;
+                emit_log_limited(app, &format!(
+                    "The {} stage failed after the system slept; restarting it",
+                    stage.label()
+                ));
+                if stage == JobStage::Encode {
+                    let _ = fs::remove_file(&job.output);
+                }
+            }
+            other => return other,
+        }
+    }
+}
+
+/// Whether a stage error came from `check_stage_cancelled`.
+fn is_cancelled_error(err: &str) -> bool {
+    err.starts_with("Cancelled during")
+}
+
+// -------------------- GPU sharing --------------------
+
+/// How RIFE and a GPU encoder (NVENC, VideoToolbox) share the GPU when they would run at the
+/// same time: streamed jobs do both at once, and concurrent jobs can overlap one's RIFE with
+/// another's encode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+enum GpuSharing {
+    /// Let them run together and the driver share the GPU.
+    #[default]
+    Share,
+    /// One at a time, across all jobs; streamed jobs with a GPU encoder run in stages instead.
+    Serialize,
+    /// RIFE on one device (`-g`), NVENC on another (`-gpu`). VideoToolbox can't be pinned.
+    Pin { rife_gpu: u32, encode_gpu: u32 },
+}
+
+impl GpuSharing {
+    fn label(self) -> &'static str {
+        match self {
+            GpuSharing::Share => "share",
+            GpuSharing::Serialize => "serialize",
+            GpuSharing::Pin { .. } => "pin",
+        }
+    }
+}
+
+/// Held by whichever GPU stage has its turn under `GpuSharing::Serialize`.
+static GPU_TURN: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Wait for the GPU under `GpuSharing::Serialize`; None (no waiting) in the other modes.
+fn gpu_turn(app: &AppHandle, settings: &AppSettings, what: &str) -> Option<std::sync::MutexGuard<'static, ()>> {
+    if settings.gpu_sharing != GpuSharing::Serialize {
+        return None;
+    }
+    if let Ok(guard) = GPU_TURN.try_lock() {
+        return Some(guard);
+    }
+    emit_log_limited(app, &format!("{what}: waiting for the GPU (GPU sharing is set to one stage at a time)"));
+    Some(GPU_TURN.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Vulkan device for rife-ncnn-vulkan: `RIFE_INTERP_GPU`, else the pinned RIFE device.
+fn rife_gpu(settings: &AppSettings) -> Option<u32> {
+    env_overrides().gpu.or(match settings.gpu_sharing {
+        GpuSharing::Pin { rife_gpu, .. } => Some(rife_gpu),
+        _ => None,
+    })
+}
+
+/// Log a stage's frames per second, so the sharing modes can be compared.
+fn log_throughput(app: &AppHandle, stage: &str, frames: u64, secs: f64) {
+    if frames > 0 && secs > 0.0 {
+        emit_log_limited(app, &format!("{stage}: {frames} frames in {secs:.1}s ({:.1} fps)", frames as f64 / secs));
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct GpuSharingStats {
+    mode: &'static str,
+    jobs: usize,
+    /// Mean output frames per second of the RIFE stage, over jobs that had one.
+    interpolate_fps: Option<f64>,
+    /// Mean output frames per second of the encode (streamed jobs: RIFE and encode together).
+    encode_fps: Option<f64>,
+}
+
+/// Stage speeds of past jobs per `GpuSharing` mode.
+#[tauri::command]
+fn gpu_sharing_stats(app: AppHandle) -> Result<Vec<GpuSharingStats>, String> {
+    let root = app_root(&app)?;
+    let history = load_history(&root);
+    let mean = |v: Vec<f64>| (!v.is_empty()).then(|| v.iter().sum::<f64>() / v.len() as f64);
+    Ok(["share", "serialize", "pin"]
+        .into_iter()
+        .map(|mode| {
+            let jobs: Vec<&JobRecord> = history
+                .iter()
+                .filter(|r| r.ok == Some(true) && r.gpu_sharing.is_some_and(|g| g.label() == mode))
+                .collect();
+            let rate = |secs: fn(&JobRecord) -> f64| {
+                mean(jobs.iter().filter(|r| secs(r) > 0.0).map(|r| r.frames_out as f64 / secs(r)).collect())
+            };
+            GpuSharingStats { mode, jobs: jobs.len(), interpolate_fps: rate(|r| r.interpolate_secs), encode_fps: rate(|r| r.encode_secs) }
+        })
+        .collect())
+}
+
+// -------------------- GPU memory --------------------
+
+#[derive(Clone, Debug)]
+struct GpuMemory {
+    name: String,
+    free_bytes: u64,
+}
+
+/// Free video memory of the GPU RIFE runs on (`gpu` is its `-g` device, None for the
+/// default first one). NVIDIA is queried through nvidia-smi; on Apple Silicon memory is
+/// unified, so roughly two thirds of system RAM is treated as usable by the GPU. Other setups
+/// return None and the requested settings are used unchanged.
+fn query_gpu_memory(gpu: Option<u32>) -> Option<GpuMemory> {
+    if let Ok(out) = run_tool(
+        tool_command("nvidia-smi").arg("--query-gpu=index,name,memory.free").arg("--format=csv,noheader,nounits"),
+        QUERY_TIMEOUT_SECS,
+    ) {
+        if out.status.success() {
+            if let Some(mem) = parse_nvidia_smi_memory(&String::from_utf8_lossy(&out.stdout), gpu) {
+                return Some(mem);
+            }
+        }
+    }
+
+    if cfg!(target_os = "macos") {
+        let out = run_tool(tool_command("sysctl").arg("-n").arg("hw.memsize"), QUERY_TIMEOUT_SECS).ok()?;
+        let total = String::from_utf8_lossy(&out.stdout).trim().parse::<u64>().ok()?;
+        return Some(GpuMemory { name: "Unified memory".into(), free_bytes: total / 3 * 2 });
+    }
+
+    None
+}
+
+/// The `index, name, memory.free` row of nvidia-smi for `gpu`, or the first row when `gpu`
+/// isn't set or isn't listed. On NVIDIA-only systems the Vulkan device numbers RIFE's `-g`
+/// takes follow nvidia-smi's order.
+fn parse_nvidia_smi_memory(text: &str, gpu: Option<u32>) -> Option<GpuMemory> {
+    let rows: Vec<(u32, GpuMemory)> = text
+        .lines()
+        .filter_map(|l| {
+            let (index, rest) = l.split_once(',')?;
+            let (name, free_mib) = rest.rsplit_once(',')?;
+            let mib = free_mib.trim().parse::<u64>().ok()?;
+            Some((index.trim().parse().ok()?, GpuMemory { name: name.trim().to_string(), free_bytes: mib * 1024 * 1024 }))
+        })
+        .collect();
+    let pick = gpu.and_then(|g| rows.iter().position(|(i, _)| *i == g)).unwrap_or(0);
+    rows.into_iter().nth(pick).map(|(_, mem)| mem)
+}
+
+/// Rough per-proc-thread VRAM need of rife-ncnn-vulkan for one frame pair of this size.
+/// Calibrated so a 3840x2160 v4 model lands around 5 GB, matching OOMs seen on 4 GB cards.
+fn estimate_rife_vram_bytes(width: u32, height: u32, model: &str) -> u64 {
+    let model = model.to_ascii_lowercase();
+    let factor = if model.contains("uhd") {
+        0.8
+    } else if model.contains("v4") {
+        1.0
+    } else {
+        0.9
+    };
+    (width as f64 * height as f64 * 600.0 * factor) as u64
+}
+
+const RIFE_VRAM_OVERHEAD_BYTES: u64 = 300 * 1024 * 1024;
+
+/// Fit the RIFE `-j load:proc:save` proc count to the free VRAM, falling back to RIFE's UHD
+/// mode (`-u`, internally downscaled flow) when even a single proc thread would not fit.
+/// Returns the (possibly adjusted) thread string and whether UHD mode is needed, plus a
+/// human-readable note describing the decision.
+fn fit_rife_to_vram(threads: RifeThreads, width: u32, height: u32, model: &str, gpu: &GpuMemory) -> (RifeThreads, bool, String) {
+    let per_proc = estimate_rife_vram_bytes(width, height, model);
+    let available = gpu.free_bytes.saturating_sub(RIFE_VRAM_OVERHEAD_BYTES);
+
+    let requested_proc = threads.proc as u64;
+    let fits = if per_proc == 0 { requested_proc } else { available / per_proc };
+
+    let gib = |b: u64| b as f64 / (1024.0 * 1024.0 * 1024.0);
+    if fits >= requested_proc {
+        let note = format!(
+            "VRAM: {} has {:.1} GiB free, ~{:.1} GiB per proc thread at {width}x{height}: keeping -j {threads}",
+            gpu.name, gib(gpu.free_bytes), gib(per_proc)
+        );
+        return (threads, false, note);
+    }
+
+    let adjusted = threads.with_proc(fits.max(1) as u32);
+    let uhd = fits == 0;
+    let note = format!(
+        "VRAM: {} has {:.1} GiB free, ~{:.1} GiB per proc thread at {width}x{height}: using -j {adjusted}{}",
+        gpu.name,
+        gib(gpu.free_bytes),
+        gib(per_proc),
+        if uhd { " and UHD mode (-u)" } else { "" }
+    );
+    (adjusted, uhd, note)
+}
+
+// -------------------- Temp storage --------------------
+
+/// Temp folders writing slower than this get a warning; a local SSD manages several hundred MB/s.
+const SLOW_STORAGE_MB_PER_SEC: f64 = 60.0;
+/// Size of the file written to measure throughput.
+const STORAGE_PROBE_BYTES: usize = 64 * 1024 * 1024;
+const STORAGE_CHECK_TTL_SECS: u64 = 600;
+/// Rough size of one extracted/interpolated PNG frame per pixel.
+const FRAME_PNG_BYTES_PER_PIXEL: f64 = 2.0;
+/// Rough size per pixel of a frame of av1an's lossless FFV1 intermediate.
+const FFV1_BYTES_PER_PIXEL: f64 = 1.5;
+
+/// Filesystem types (as /proc/mounts and `mount` name them) that live on another machine.
+const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs", "nfs4", "cifs", "smb3", "smbfs", "afpfs", "webdav", "davfs", "fuse.davfs2",
+    "sshfs", "fuse.sshfs", "fuse.rclone", "fuse.s3fs", "9p", "glusterfs", "ceph", "afs",
+];
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct StorageCheck {
+    path: String,
+    /// Filesystem type, or "unc"/"network drive" on Windows, when it could be determined.
+    filesystem: Option<String>,
+    network: bool,
+    write_mb_per_sec: Option<f64>,
+    slow: bool,
+    warnings: Vec<String>,
+}
+
+impl StorageCheck {
+    /// Time to write `bytes` at the measured speed.
+    fn write_secs(&self, bytes: u64) -> Option<f64> {
+        self.write_mb_per_sec.filter(|s| *s > 0.0).map(|s| bytes as f64 / (s * 1024.0 * 1024.0))
+    }
+}
+
+/// A mounted volume as listed by the OS.
+struct Mount {
+    point: PathBuf,
+    kind: String,
+    read_only: bool,
+}
+
+/// The mount holding `path`: the one with the longest matching mount point.
+fn mount_for<'a>(path: &Path, mounts: &'a [Mount]) -> Option<&'a Mount> {
+    mounts
+        .iter()
+        .filter(|m| path.starts_with(&m.point))
+        .max_by_key(|m| m.point.as_os_str().len())
+}
+
+/// Mounted volumes on Linux and macOS; empty on Windows, where callers look at the path itself.
+fn list_mounts() -> Vec<Mount> {
+    if cfg!(target_os = "linux") {
+        // "<device> <mount point> <type> <options> …", with spaces in paths escaped as \040.
+        let text = fs::read_to_string("/proc/mounts").unwrap_or_default();
+        text.lines()
+            .filter_map(|l| {
+                let mut parts = l.split_whitespace();
+                let mount = parts.nth(1)?.replace("\\040", " ");
+                let kind = parts.next()?.to_string();
+                let read_only = parts.next().is_some_and(|o| o.split(',').any(|o| o == "ro"));
+                Some(Mount { point: PathBuf::from(mount), kind, read_only })
+            })
+            .collect()
+    } else if cfg!(target_os = "macos") {
+        // "<device> on <mount point> (<type>, <options>)"
+        let out = run_tool(&mut tool_command("mount"), QUERY_TIMEOUT_SECS).map(|o| String::from_utf8_lossy(&o.stdout).to_string());
+        out.unwrap_or_default()
+            .lines()
+            .filter_map(|l| {
+                let (_, rest) = l.split_once(" on ")?;
+                let (mount, opts) = rest.rsplit_once(" (")?;
+                let kind = opts.split([',', ')']).next()?.trim().to_string();
+                let read_only = opts.split([',', ')']).any(|o| o.trim() == "read-only");
+                Some(Mount { point: PathBuf::from(mount), kind, read_only })
+            })
+            .collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Filesystem type of the volume holding `path` and whether it is a network volume.
+fn filesystem_of(path: &Path) -> (Option<String>, bool) {
+    let path = canonicalize_lenient(path);
+    if cfg!(windows) {
+        let s = path.to_string_lossy().to_string();
+        let s = s.strip_prefix(r"\\?\").unwrap_or(&s);
+        if s.starts_with(r"\\") || s.starts_with("UNC\\") {
+            return (Some("unc".into()), true);
+        }
+        // Mapped drive letters show up in `net use` next to their \\server\share.
+        let drive = s.get(..2).filter(|d| d.ends_with(':')).map(|d| d.to_ascii_uppercase());
+        if let Some(drive) = drive {
+            let out = run_tool(tool_command("net").arg("use"), QUERY_TIMEOUT_SECS).map(|o| String::from_utf8_lossy(&o.stdout).to_string());
+            let mapped = out.unwrap_or_default().lines().any(|l| {
+                l.split_whitespace().any(|t| t.eq_ignore_ascii_case(&drive)) && l.contains(r"\\")
+            });
+            if mapped {
+                return (Some("network drive".into()), true);
+            }
+        }
+        return (None, false);
+    }
+    let mounts = list_mounts();
+    match mount_for(&path, &mounts) {
+        Some(m) => (Some(m.kind.clone()), NETWORK_FILESYSTEMS.contains(&m.kind.as_str())),
+        None => (None, false),
+    }
+}
+
+/// Write and flush a `STORAGE_PROBE_BYTES` file in `dir`; returns MB/s.
+fn measure_write_speed(dir: &Path) -> Result<f64, String> {
+    use std::io::Write;
+    fs::create_dir_all(dir).map_err(|e| fs_error("create", dir, &e))?;
+    let probe = dir.join(".write-speed-probe");
+    let chunk = vec![0x5Au8; 4 * 1024 * 1024];
+    let started = std::time::Instant::now();
+    let result = fs::File::create(&probe).and_then(|mut f| {
+        for _ in 0..STORAGE_PROBE_BYTES / chunk.len() {
+            f.write_all(&chunk)?;
+        }
+        f.sync_all()
+    });
+    let secs = started.elapsed().as_secs_f64();
+    let _ = fs::remove_file(&probe);
+    result.map_err(|e| fs_error("write", &probe, &e))?;
+    Ok(STORAGE_PROBE_BYTES as f64 / (1024.0 * 1024.0) / secs.max(1e-6))
+}
+
+/// Check whether `dir` is on a network volume and how fast it takes writes.
+fn check_storage(dir: &Path) -> StorageCheck {
+    let (filesystem, network) = filesystem_of(dir);
+    let mut warnings = Vec::new();
+    if network {
+        warnings.push(format!(
+            "{} is on a network volume ({}); jobs write thousands of frame files there and will run much slower than on a local disk",
+            dir.to_string_lossy(),
+            filesystem.as_deref().unwrap_or("remote")
+        ));
+    }
+    let write_mb_per_sec = match measure_write_speed(dir) {
+        Ok(speed) => Some(speed),
+        Err(e) => {
+            warnings.push(format!("Could not measure write speed: {e}"));
+            None
+        }
+    };
+    let slow = write_mb_per_sec.is_some_and(|s| s < SLOW_STORAGE_MB_PER_SEC);
+    if let (true, Some(speed)) = (slow, write_mb_per_sec) {
+        warnings.push(format!(
+            "{} writes at {speed:.0} MB/s (below {SLOW_STORAGE_MB_PER_SEC:.0} MB/s); frame extraction and interpolation will be held up by storage",
+            dir.to_string_lossy()
+        ));
+    }
+    StorageCheck {
+        path: dir.to_string_lossy().to_string(),
+        filesystem,
+        network,
+        write_mb_per_sec,
+        slow,
+        warnings,
+    }
+}
+
+/// Preflight of the temp folder jobs write their frames to. Results are reused for a few
+/// minutes unless `refresh` is set.
+#[tauri::command]
+fn check_temp_storage(app: AppHandle, state: State<'_, AppState>, refresh: Option<bool>) -> Result<StorageCheck, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    Ok(state.temp_storage_check(&root, refresh.unwrap_or(false)))
+}
+
+/// Rough disk space a job's frame folders take; see `frame_folder_bytes`.
+fn estimate_frame_bytes(ffmpeg: &Path, input: &Path, frames_in: f64, multiplier: f64, chained: bool, av1an: bool) -> Option<u64> {
+    let (w, h) = probe_video_size(ffmpeg, input)?;
+    Some(frame_folder_bytes(w as f64 * h as f64, frames_in, multiplier, chained, av1an))
+}
+
+/// Peak bytes of `frames_in` extracted frames and the `multiplier` times as many interpolated
+/// ones. Chained passes also hold the previous pass's frames while the last one runs, and
+/// `av1an` adds the lossless intermediate it encodes from.
+fn frame_folder_bytes(pixels: f64, frames_in: f64, multiplier: f64, chained: bool, av1an: bool) -> u64 {
+    let frames_out = frames_in * multiplier;
+    let previous_pass = if chained { frames_out / 2.0 } else { 0.0 };
+    let frames = frames_in + frames_out + previous_pass;
+    let intermediate = if av1an { frames_out * pixels * FFV1_BYTES_PER_PIXEL } else { 0.0 };
+    (frames * pixels * FRAME_PNG_BYTES_PER_PIXEL + intermediate) as u64
+}
+
+/// Log storage warnings for a new job and, on slow or network storage, how long writing its
+/// frames alone will take.
+fn report_temp_storage(app: &AppHandle, storage: &StorageCheck, frame_bytes: Option<u64>) {
+    for w in &storage.warnings {
+        emit_log_limited(app, &format!("Temp storage: {w}"));
+    }
+    if !(storage.slow || storage.network) {
+        return;
+    }
+    let Some(bytes) = frame_bytes else { return };
+    if let Some(secs) = storage.write_secs(bytes) {
+        emit_log_limited(app, &format!(
+            "Temp storage: ~{:.1} GB of frames to write, ~{:.0} min at the measured speed",
+            bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+            secs / 60.0
+        ));
+    }
+}
+
+/// Free space on the volume holding `dir`.
+fn free_bytes(dir: &Path) -> Option<u64> {
+    if cfg!(windows) {
+        let drive = dir.to_string_lossy().chars().next().filter(|c| c.is_ascii_alphabetic())?;
+        let out = run_tool(
+            tool_command("powershell").arg("-NoProfile").arg("-Command").arg(format!("(Get-PSDrive {drive}).Free")),
+            QUERY_TIMEOUT_SECS,
+        )
+        .ok()?;
+        return String::from_utf8_lossy(&out.stdout).trim().parse().ok();
+    }
+    // POSIX df: "Filesystem 1024-blocks Used Available Capacity Mounted on"
+    let out = run_tool(tool_command("df").arg("-Pk").arg(dir), QUERY_TIMEOUT_SECS).ok()?;
+    let text = String::from_utf8_lossy(&out.stdout);
+    let kb: u64 = text.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// The RAM disk `ram_temp_max_mb` uses: the configured one, else /dev/shm when it is tmpfs.
+fn ram_temp_dir(settings: &AppSettings) -> Option<PathBuf> {
+    if let Some(dir) = settings.ram_temp_dir.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    let shm = Path::new("/dev/shm");
+    let mounts = list_mounts();
+    match mount_for(shm, &mounts) {
+        Some(m) if m.kind == "tmpfs" => Some(shm.to_path_buf()),
+        _ => None,
+    }
+}
+
+/// Where a new job keeps its frame folders: a RAM disk when the settings allow it and the
+/// frames fit, else `temp/`. Logs why a RAM disk wasn't used.
+fn frame_store_for_job(app: &AppHandle, root: &Path, settings: &AppSettings, frame_bytes: Option<u64>) -> PathBuf {
+    let disk = temp_root(root);
+    let Some(cap_mb) = settings.ram_temp_max_mb else { return disk };
+    let mb = |b: u64| b / (1024 * 1024);
+    let fallback = |reason: String| {
+        emit_log_limited(app, &format!("RAM temp: {reason}; frames go to {}", disk.to_string_lossy()));
+        disk.clone()
+    };
+    let Some(needed) = frame_bytes else { return fallback("frame size unknown".into()) };
+    if mb(needed) > cap_mb {
+        return fallback(format!("job needs ~{} MB, over the {cap_mb} MB cap", mb(needed)));
+    }
+    let Some(dir) = ram_temp_dir(settings) else {
+        return fallback("no RAM disk found (set one in settings)".into());
+    };
+    // Headroom for the estimate being low; running out mid-job fails the stage.
+    match free_bytes(&dir) {
+        Some(free) if free >= needed + needed / 4 => {
+            let store = dir.join("RIFE-Interpolator");
+            emit_log_limited(app, &format!(
+                "RAM temp: ~{} MB of frames in {}",
+                mb(needed),
+                store.to_string_lossy()
+            ));
+            store
+        }
+        Some(free) => fallback(format!("{} has only {} MB free", dir.to_string_lossy(), mb(free))),
+        None => fallback(format!("could not read free space of {}", dir.to_string_lossy())),
+    }
+}
+
+// -------------------- Presets --------------------
+
+/// A program run after a successful job. Arguments may contain `{output}`, `{output_dir}`,
+/// `{input}` and `{job_id}` placeholders; no shell is involved, each arg is passed as-is.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct PostJobHook {
+    program: String,
+    args: Vec<String>,
+    /// Seconds the hook may run before it is killed; None (or 0) uses `POST_HOOK_TIMEOUT_SECS`.
+    timeout_secs: Option<u64>,
+}
+
+/// Named set of job options. Any option passed explicitly to a command wins over the preset.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct JobPreset {
+    name: String,
+    max_threads: Option<i32>,
+    on_conflict: Option<OnConflict>,
+    dedup: Option<bool>,
+    cut_handling: Option<CutHandling>,
+    keep_intermediates: Option<bool>,
+    filters: Option<FilterOptions>,
+    encode: Option<EncodeSettings>,
+    post_job_hook: Option<PostJobHook>,
+    /// Model folder name (see `list_models`).
+    model: Option<String>,
+    corrupt_input: Option<CorruptInputOptions>,
+}
+
+fn presets_path(root: &Path) -> PathBuf {
+    profile_root(root).join("presets.json")
+}
+
+fn load_presets(root: &Path) -> Vec<JobPreset> {
+    fs::read_to_string(presets_path(root))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_presets(root: &Path, presets: &[JobPreset]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(presets).map_err(|e| e.to_string())?;
+    fs::write(presets_path(root), json).map_err(|e| format!("Failed to write presets: {e}"))
+}
+
+/// The preset named by a command argument, or an empty preset when none was given.
+fn resolve_preset(root: &Path, name: Option<&str>) -> Result<JobPreset, String> {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        None => Ok(JobPreset::default()),
+        Some(n) => load_presets(root)
+            .into_iter()
+            .find(|p| p.name == n)
+            .ok_or_else(|| format!("Unknown preset: {n}")),
+    }
+}
+
+#[tauri::command]
+fn list_presets(app: AppHandle) -> Result<Vec<JobPreset>, String> {
+    let root = app_root(&app)?;
+    Ok(load_presets(&root))
+}
+
+/// Create or replace (by name) a preset.
+#[tauri::command]
+fn save_preset(app: AppHandle, preset: JobPreset) -> Result<Vec<JobPreset>, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let mut preset = preset;
+    preset.name = preset.name.trim().to_string();
+    if preset.name.is_empty() {
+        return Err("Preset name is required".into());
+    }
+    let mut presets = load_presets(&root);
+    match presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset,
+        None => presets.push(preset),
+    }
+    save_presets(&root, &presets)?;
+    Ok(presets)
+}
+
+#[tauri::command]
+fn delete_preset(app: AppHandle, name: String) -> Result<Vec<JobPreset>, String> {
+    let root = app_root(&app)?;
+    let mut presets = load_presets(&root);
+    presets.retain(|p| p.name != name);
+    save_presets(&root, &presets)?;
+    Ok(presets)
+}
+
+// -------------------- Model profiles --------------------
+
+/// Job defaults that suit a model. They fill in whatever neither the command nor the preset
+/// set, so preset values always win.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct ModelProfile {
+    dedup: Option<bool>,
+    cut_handling: Option<CutHandling>,
+    max_threads: Option<i32>,
+    /// RIFE's UHD mode regardless of free VRAM.
+    uhd: Option<bool>,
+    /// Whether the model interpolates at any timestep (RIFE `-n`). None goes by the model
+    /// version (see `model_supports_timestep`).
+    timestep: Option<bool>,
+    /// Reach rates above 2x with repeated 2x passes when the model has no timestep mode.
+    /// None or false repeats frames of the 2x result instead.
+    chained_passes: Option<bool>,
+}
+
+impl ModelProfile {
+    /// Defaults known for a model family, from its folder name.
+    fn builtin(model: &str) -> ModelProfile {
+        let model = model.to_ascii_lowercase();
+        ModelProfile {
+            // Anime repeats drawings over several frames; interpolating those makes judder.
+            dedup: model.contains("anime").then_some(true),
+            uhd: model.contains("uhd").then_some(true),
+            ..Default::default()
+        }
+    }
+
+    fn supports_timestep(&self, model: &str) -> bool {
+        self.timestep.unwrap_or_else(|| model_supports_timestep(model))
+    }
+}
+
+/// Models from v4 on interpolate at any timestep; older ones only make the midpoint, so
+/// higher rates need chained 2x passes.
+fn model_supports_timestep(model: &str) -> bool {
+    model
+        .to_ascii_lowercase()
+        .strip_prefix("rife-v")
+        .and_then(|v| v.split(['.', '-', '_']).next())
+        .and_then(|major| major.parse::<u32>().ok())
+        .is_some_and(|major| major >= 4)
+}
+
+fn model_profiles_path(root: &Path) -> PathBuf {
+    profile_root(root).join("model_profiles.json")
+}
+
+/// User-edited profiles by model folder name.
+fn load_model_profiles(root: &Path) -> HashMap<String, ModelProfile> {
+    fs::read_to_string(model_profiles_path(root))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// The user's profile for `model`, or the built-in one.
+fn model_profile(root: &Path, model: &str) -> ModelProfile {
+    load_model_profiles(root).remove(model).unwrap_or_else(|| ModelProfile::builtin(model))
+}
+
+/// Folders that hold models: the shared `models/` and the default model's parent (a RIFE
+/// install that ships its own).
+fn model_roots(root: &Path, tools: &ResolvedTools) -> Vec<PathBuf> {
+    let mut roots = vec![root.join("models")];
+    if let Some(parent) = tools.rife_models.as_deref().and_then(Path::parent) {
+        if !roots.iter().any(|r| r == parent) {
+            roots.push(parent.to_path_buf());
+        }
+    }
+    roots
+}
+
+/// Model folder for a job: `model` looked up by name, or the default model.
+fn resolve_model_dir(root: &Path, tools: &ResolvedTools, default: PathBuf, model: Option<&str>) -> Result<PathBuf, String> {
+    let Some(name) = model.map(str::trim).filter(|m| !m.is_empty()) else {
+        return Ok(default);
+    };
+    if name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!("Invalid model name: {name}"));
+    }
+    let mut roots = default.parent().map(Path::to_path_buf).into_iter().collect::<Vec<_>>();
+    roots.extend(model_roots(root, tools));
+    roots
+        .into_iter()
+        .map(|r| r.join(name))
+        .find(|d| d.join("flownet.param").is_file())
+        .ok_or_else(|| format!("Unknown model: {name}"))
+}
+
+#[derive(serde::Serialize)]
+struct ModelEntry {
+    name: String,
+    path: String,
+    is_default: bool,
+    /// The profile jobs get: the user's, or the built-in one.
+    profile: ModelProfile,
+    builtin_profile: ModelProfile,
+    customized: bool,
+    supports_timestep: bool,
+}
+
+#[tauri::command]
+fn list_models(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<ModelEntry>, String> {
+    let root = app_root(&app)?;
+    let tools = state.tools(&root);
+    let mut custom = load_model_profiles(&root);
+    let mut models: Vec<ModelEntry> = Vec::new();
+    for dir in model_roots(&root, &tools) {
+        let Ok(rd) = fs::read_dir(&dir) else { continue };
+        for path in rd.flatten().map(|e| e.path()) {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            if !path.join("flownet.param").is_file() || models.iter().any(|m| m.name == name) {
+                continue;
+            }
+            let builtin_profile = ModelProfile::builtin(&name);
+            let user = custom.remove(&name);
+            let customized = user.is_some();
+            let profile = user.unwrap_or_else(|| builtin_profile.clone());
+            models.push(ModelEntry {
+                is_default: tools.rife_models.as_deref() == Some(path.as_path()),
+                path: path.to_string_lossy().to_string(),
+                customized,
+                supports_timestep: profile.supports_timestep(&name),
+                profile,
+                builtin_profile,
+                name,
+            });
+        }
+    }
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+/// Replace a model's profile; None goes back to the built-in one.
+#[tauri::command]
+fn save_model_profile(app: AppHandle, model: String, profile: Option<ModelProfile>) -> Result<ModelProfile, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let model = model.trim().to_string();
+    if model.is_empty() {
+        return Err("Model name is required".into());
+    }
+    let mut profiles = load_model_profiles(&root);
+    match profile {
+        Some(p) => profiles.insert(model.clone(), p),
+        None => profiles.remove(&model),
+    };
+    let json = serde_json::to_string_pretty(&profiles).map_err(|e| e.to_string())?;
+    fs::write(model_profiles_path(&root), json).map_err(|e| format!("Failed to write model profiles: {e}"))?;
+    Ok(model_profile(&root, &model))
+}
+
+// -------------------- Quality/speed --------------------
+
+/// What a `quality` level (1 = fastest, 10 = best) means on this machine, calibrated with the
+/// model and encode speeds of past jobs. Each choice only fills in what the job, its preset
+/// and its model profile leave unset.
+#[derive(Clone, Debug, serde::Serialize)]
+struct QualityPlan {
+    level: u8,
+    /// Model folder name (ncnn runtime only).
+    model: Option<String>,
+    /// Intermediate frame format; None keeps the setting.
+    frame_format: Option<FrameFormat>,
+    /// RIFE's TTA mode (`-x`): about twice the RIFE time for fewer artifacts. ncnn only.
+    tta: bool,
+    /// x264-style preset name (mapped for SVT-AV1 like any other preset).
+    encoder_preset: String,
+    crf: u32,
+    /// How each choice was made.
+    notes: Vec<String>,
+}
+
+impl QualityPlan {
+    fn encode_settings(&self) -> EncodeSettings {
+        EncodeSettings { crf: Some(self.crf), preset: Some(self.encoder_preset.clone()), ..Default::default() }
+    }
+}
+
+/// x264 presets from fastest to slowest.
+const X264_PRESETS: [&str; 9] = ["ultrafast", "superfast", "veryfast", "faster", "fast", "medium", "slow", "slower", "veryslow"];
+/// Index into `X264_PRESETS` per quality level.
+const QUALITY_PRESETS: [usize; 10] = [1, 2, 2, 3, 4, 5, 5, 6, 6, 7];
+/// How much slower than the fastest measured model each level up may be.
+const QUALITY_SLOWDOWN_PER_LEVEL: f64 = 1.35;
+
+/// Sort key for models, worst to best: RIFE version, then full before lite variants.
+fn model_quality_rank(model: &str) -> (u32, u32, bool) {
+    let model = model.to_ascii_lowercase();
+    let mut version = model
+        .strip_prefix("rife-v")
+        .unwrap_or_default()
+        .split(['.', '-', '_'])
+        .map(|part| part.parse::<u32>().unwrap_or(0));
+    (version.next().unwrap_or(0), version.next().unwrap_or(0), !model.contains("lite"))
+}
+
+/// Names of the models in `model_roots`.
+fn installed_model_names(root: &Path, tools: &ResolvedTools) -> Vec<String> {
+    let mut names: Vec<String> = model_roots(root, tools)
+        .iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|rd| rd.flatten().map(|e| e.path()))
+        .filter(|p| p.join("flownet.param").is_file())
+        .filter_map(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Map a quality level onto concrete settings. With speeds for at least two models in the
+/// history, the level sets how much slower than the fastest one the model may be; without,
+/// the installed models are spread over the levels by version. The encoder preset moves a
+/// step when past jobs show the encode far ahead of RIFE, or holding it back.
+fn plan_quality(root: &Path, tools: &ResolvedTools, level: u8) -> Result<QualityPlan, String> {
+    if !(1..=10).contains(&level) {
+        return Err("Quality must be between 1 (fastest) and 10 (best)".into());
+    }
+    let mut notes = Vec::new();
+    // (frames, secs) of RIFE per model, and of all encodes.
+    let mut rife: HashMap<String, (u64, f64)> = HashMap::new();
+    let mut encode = (0u64, 0.0);
+    for r in load_history(root).iter().filter(|r| r.ok == Some(true) && r.frames_out > 0) {
+        if !r.model.is_empty() && r.interpolate_secs > 0.0 {
+            let m = rife.entry(r.model.clone()).or_default();
+            m.0 += r.rife_frames();
+            m.1 += r.interpolate_secs;
+        }
+        if r.encode_secs > 0.0 {
+            encode.0 += r.frames_out;
+            encode.1 += r.encode_secs;
+        }
+    }
+    let rife_fps = |model: &str| rife.get(model).map(|(frames, secs)| *frames as f64 / secs);
+
+    let mut models = installed_model_names(root, tools);
+    models.sort_by_key(|m| model_quality_rank(m));
+    let budget = QUALITY_SLOWDOWN_PER_LEVEL.powi(i32::from(level) - 1);
+    let measured: Vec<(&String, f64)> = models.iter().filter_map(|m| Some((m, rife_fps(m)?))).collect();
+    let fastest = measured.iter().map(|(_, fps)| *fps).fold(0.0, f64::max);
+    let model = if measured.len() >= 2 {
+        let (model, fps) = measured.iter().rev().find(|(_, fps)| fps * budget >= fastest).copied().unwrap_or(measured[0]);
+        notes.push(format!(
+            "Model {model}: {fps:.1} fps in past jobs, the best within {budget:.1}x of the fastest ({fastest:.1} fps)"
+        ));
+        Some(model.clone())
+    } else if models.is_empty() {
+        None
+    } else {
+        let i = (f64::from(level - 1) / 9.0 * (models.len() - 1) as f64).round() as usize;
+        notes.push(format!("Model {}: too few past jobs to compare model speeds, picked by version", models[i]));
+        Some(models[i].clone())
+    };
+    let model_fps = model.as_deref().and_then(rife_fps);
+
+    let tta = match model_fps {
+        Some(fps) => level >= 9 && fps / 2.0 * budget >= fastest,
+        None => level == 10,
+    };
+    if tta {
+        notes.push("TTA on: about twice the RIFE time for fewer artifacts".into());
+    }
+
+    let mut preset = QUALITY_PRESETS[usize::from(level - 1)];
+    if let (Some(rife), true) = (model_fps, encode.1 > 0.0) {
+        let encode_fps = encode.0 as f64 / encode.1;
+        if encode_fps >= rife * 3.0 && preset + 1 < X264_PRESETS.len() {
+            preset += 1;
+            notes.push(format!("Encoder preset a step slower: encoding ({encode_fps:.0} fps) easily keeps up with RIFE ({rife:.0} fps) here"));
+        } else if encode_fps < rife && preset > 0 {
+            preset -= 1;
+            notes.push(format!("Encoder preset a step faster: encoding ({encode_fps:.0} fps) is slower than RIFE ({rife:.0} fps) here"));
+        }
+    }
+    let crf = 26 - (u32::from(level) - 1) * 10 / 9;
+
+    Ok(QualityPlan {
+        level,
+        model,
+        // PNG is the cheaper of the two lossless formats to write and read.
+        frame_format: (level <= 3).then_some(FrameFormat::Png),
+        tta,
+        encoder_preset: X264_PRESETS[preset].to_string(),
+        crf,
+        notes,
+    })
+}
+
+/// What `smooth_video`'s `quality` level would pick right now.
+#[tauri::command]
+fn get_quality_plan(app: AppHandle, state: State<'_, AppState>, level: u8) -> Result<QualityPlan, String> {
+    let root = app_root(&app)?;
+    plan_quality(&root, &state.tools(&root), level)
+}
+
+// -------------------- Config export --------------------
+
+const CONFIG_FORMAT: u32 = 1;
+
+/// Settings, presets and model profiles in one file, to set up another machine the same way.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct ConfigExport {
+    format: u32,
+    exported_at: String,
+    settings: AppSettings,
+    #[serde(default)]
+    presets: Vec<JobPreset>,
+    #[serde(default)]
+    model_profiles: HashMap<String, ModelProfile>,
+}
+
+#[tauri::command]
+fn export_config(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<String, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let dest = PathBuf::from(path.trim());
+    if dest.as_os_str().is_empty() || dest.is_dir() {
+        return Err("Pick a file name for the exported configuration".into());
+    }
+    let mut settings = state.settings(&root);
+    // Recent files only mean something on this machine, and the password stays here too.
+    settings.recent_inputs.clear();
+    settings.favorite_folders.clear();
+    if let Some(proxy) = settings.proxy.as_mut() {
+        proxy.password = None;
+    }
+    let export = ConfigExport {
+        format: CONFIG_FORMAT,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        settings,
+        presets: load_presets(&root),
+        model_profiles: load_model_profiles(&root),
+    };
+    let json = serde_json::to_string_pretty(&export).map_err(|e| e.to_string())?;
+    fs::write(&dest, json).map_err(|e| format!("Failed to write {}: {e}", dest.to_string_lossy()))?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Part of an imported configuration that runs commands or changes what downloads are trusted.
+/// It is left out unless the import lists its `key` in `confirmed`.
+#[derive(Clone, serde::Serialize)]
+struct HeldBackImport {
+    key: String,
+    description: String,
+}
+
+#[derive(serde::Serialize)]
+struct ConfigImportResult {
+    settings: AppSettings,
+    /// Imported items that weren't applied; confirm them one by one and import again.
+    held_back: Vec<HeldBackImport>,
+}
+
+/// Apply an exported configuration. Settings are replaced, keeping this machine's recent
+/// files, favorites, proxy password and trust opt-ins (hooks, plugins, source protection).
+/// The model converter, update catalog, CA bundle and preset post-job hooks are only taken
+/// when listed in `confirmed`; otherwise the local values stay and they are reported in
+/// `held_back`. Presets and model profiles are merged, imported ones winning on equal names.
+#[tauri::command]
+fn import_config(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    confirmed: Option<Vec<String>>,
+) -> Result<ConfigImportResult, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let src = PathBuf::from(path.trim());
+    let text = fs::read_to_string(&src).map_err(|e| format!("Failed to read {}: {e}", src.to_string_lossy()))?;
+    let import: ConfigExport = serde_json::from_str(&text).map_err(|e| format!("Not a configuration export: {e}"))?;
+    if import.format > CONFIG_FORMAT {
+        return Err(format!("Configuration format {} is newer than this version supports", import.format));
+    }
+
+    let _guard = SETTINGS_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let current = state.settings(&root);
+    let mut settings = import.settings;
+    settings.recent_inputs = current.recent_inputs.clone();
+    settings.favorite_folders = current.favorite_folders.clone();
+    if let (Some(proxy), Some(old)) = (settings.proxy.as_mut(), current.proxy.as_ref()) {
+        if proxy.password.is_none() && proxy.host == old.host && proxy.username == old.username {
+            proxy.password = old.password.clone();
+        }
+    }
+    // Trust opt-ins are this machine's decision, never a shared file's.
+    settings.allow_post_job_hooks = current.allow_post_job_hooks;
+    settings.allow_stage_plugins = current.allow_stage_plugins;
+    settings.protect_sources = current.protect_sources;
+
+    let confirmed = confirmed.unwrap_or_default();
+    let mut held_back = Vec::new();
+    let mut accept = |key: String, description: String| {
+        let ok = confirmed.contains(&key);
+        if !ok {
+            held_back.push(HeldBackImport { key, description });
+        }
+        ok
+    };
+    let shown = |v: &Option<String>| v.as_deref().unwrap_or("(none)").to_string();
+    if settings.model_converter != current.model_converter
+        && !accept("setting:model_converter".into(), format!("Model converter command: {}", shown(&settings.model_converter)))
+    {
+        settings.model_converter = current.model_converter.clone();
+    }
+    if settings.update_catalog_url != current.update_catalog_url
+        && !accept("setting:update_catalog_url".into(), format!("Tool update catalog: {}", shown(&settings.update_catalog_url)))
+    {
+        settings.update_catalog_url = current.update_catalog_url.clone();
+    }
+    if settings.ca_bundle != current.ca_bundle
+        && !accept("setting:ca_bundle".into(), format!("CA bundle for downloads: {}", shown(&settings.ca_bundle)))
+    {
+        settings.ca_bundle = current.ca_bundle.clone();
+    }
+
+    let mut presets = load_presets(&root);
+    let mut imported = import.presets;
+    for preset in &mut imported {
+        let Some(hook) = &preset.post_job_hook else { continue };
+        let key = format!("preset:{}:post_job_hook", preset.name);
+        let description = format!("Post-job hook of preset {}: {} {}", preset.name, hook.program, hook.args.join(" "));
+        if !accept(key, description) {
+            // Keep the local preset's own hook, if it had one.
+            preset.post_job_hook = presets.iter().find(|p| p.name == preset.name).and_then(|p| p.post_job_hook.clone());
+        }
+    }
+    presets.retain(|p| !imported.iter().any(|i| i.name == p.name));
+    presets.extend(imported);
+    save_presets(&root, &presets)?;
+
+    let mut profiles = load_model_profiles(&root);
+    profiles.extend(import.model_profiles);
+    let json = serde_json::to_string_pretty(&profiles).map_err(|e| e.to_string())?;
+    fs::write(model_profiles_path(&root), json).map_err(|e| format!("Failed to write model profiles: {e}"))?;
+
+    save_settings(&root, &settings)?;
+    apply_event_settings(&settings);
+    enforce_cache_limit(&root, &settings);
+    state.set_settings(settings.clone());
+    Ok(ConfigImportResult { settings, held_back })
+}
+
+/// How long a post-job hook may run before it is killed, unless the hook sets its own limit;
+/// the job holds its run slot until then.
+const POST_HOOK_TIMEOUT_SECS: u64 = 600;
+
+/// Run the post-job hook (if any, and if allowed), streaming its output to the job log.
+/// Returns the captured output for the job history.
+fn run_post_job_hook(
+    app: &AppHandle,
+    settings: &AppSettings,
+    hook: Option<&PostJobHook>,
+    job_id: &str,
+    input: &Path,
+    output: &Path,
+) -> Option<String> {
+    let hook = hook.filter(|h| !h.program.trim().is_empty())?;
+    if !settings.allow_post_job_hooks {
+        emit_log_limited(app, "Post-job hook skipped: hooks are disabled in settings");
+        return None;
+    }
+
+    let output_dir = output.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+    let vars = [
+        ("{output}", output.to_string_lossy().to_string()),
+        ("{output_dir}", output_dir),
+        ("{input}", input.to_string_lossy().to_string()),
+        ("{job_id}", job_id.to_string()),
+    ];
+    let args: Vec<String> = hook
+        .args
+        .iter()
+        .map(|a| vars.iter().fold(a.clone(), |acc, (k, v)| acc.replace(k, v)))
+        .collect();
+
+    emit_stage(app, StageId::PostHook, message("stage.post_hook", &[]));
+    emit_log_limited(app, &format!("Hook: {} {}", hook.program, args.join(" ")));
+
+    let mut cmd = tool_command(hook.program.trim());
+    cmd.args(&args);
+    // Scripts start programs of their own; a timeout has to stop those too.
+    new_process_group(&mut cmd);
+    let timeout = hook.timeout_secs.filter(|s| *s > 0).unwrap_or(POST_HOOK_TIMEOUT_SECS);
+    let captured = match run_tool(log_stage_command(app, "post_hook", &mut cmd), timeout) {
+        Ok(out) => {
+            let mut text = String::from_utf8_lossy(&out.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&out.stderr));
+            for line in text.lines() {
+                emit_log_limited(app, line);
+            }
+            let status = format!("Hook exited with {}", out.status);
+            emit_log_limited(app, &status);
+            format!("{}\n{status}", text.trim())
+        }
+        Err(e) => {
+            let msg = format!("Hook failed: {e}");
+            emit_log_limited(app, &msg);
+            msg
+        }
+    };
+    Some(captured.trim().to_string())
+}
+
+// -------------------- VapourSynth --------------------
+
+/// A user VapourSynth script run over the job's frames with vspipe. The script gets the
+/// globals `frames` (printf pattern of the frames, e.g. for `core.imwri.Read(frames, firstnum=1)`),
+/// `first` and `fps` as strings and must set its output clip; vspipe's Y4M is piped into
+/// ffmpeg, which writes the new frames.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct VapourSynthStage {
+    script: String,
+    #[serde(default)]
+    position: PluginPosition,
+}
+
+/// `program` from `bin/<tool>/<version>/`, else a system install.
+fn find_managed_tool(root: &Path, tool: &str, program: &str) -> Option<PathBuf> {
+    let exe = if cfg!(windows) { format!("{program}.exe") } else { program.to_string() };
+    let managed = fs::read_dir(root.join("bin").join(tool))
+        .ok()
+        .into_iter()
+        .flat_map(|d| d.flatten())
+        .map(|v| v.path().join(&exe))
+        .find(|p| p.is_file());
+    managed.or_else(|| {
+        ["/opt/homebrew/bin", "/usr/local/bin", "/usr/bin"]
+            .into_iter()
+            .map(|d| Path::new(d).join(&exe))
+            .find(|p| p.is_file())
+    })
+}
+
+fn find_vspipe(root: &Path) -> Option<PathBuf> {
+    find_managed_tool(root, "vapoursynth", "vspipe")
+}
+
+/// Pipe `frames_dir` through the job's VapourSynth script when it runs at `position`, then
+/// swap in the result the way `run_stage_plugins` does. The script reads one image pattern,
+/// so a sharded folder goes through it a shard at a time, `first` set to the shard's first
+/// frame number. Returns whether the script changed the number of frames.
+fn run_vapoursynth_stage(app: &AppHandle, job: &SmoothJob, position: PluginPosition, frames_dir: &Path, fps: f64) -> Result<bool, String> {
+    let Some(vs) = job.vapoursynth.as_ref().filter(|v| v.position == position) else { return Ok(false) };
+    let stage = match position {
+        PluginPosition::BeforeInterpolation => JobStage::Extract,
+        PluginPosition::AfterInterpolation => JobStage::Interpolate,
+    };
+    // A script runs arbitrary code, so it needs the same opt-in as plugins.
+    if !job.settings.allow_stage_plugins {
+        return Err("Stage plugins (including VapourSynth scripts) are disabled in settings".into());
+    }
+    let vspipe = app.state::<AppState>().tools(&job.root).vspipe.ok_or("vspipe not found (install VapourSynth)")?;
+    emit_stage(app, StageId::Plugin, message("stage.plugin", &[("name", "VapourSynth".into())]));
+    let sharded = is_sharded(frames_dir);
+    let work = frames_dir.with_file_name(format!("{}.vapoursynth", job.job_id));
+    let _ = fs::remove_dir_all(&work);
+    let new_frames = work.join("frames");
+    fs::create_dir_all(&new_frames).map_err(|e| format!("Failed to create VapourSynth folder: {e}"))?;
+    if sharded {
+        make_sharded(&new_frames)?;
+    }
+    let new_files = FrameFiles::new(&new_frames, job.frame_format);
+    let parts = if sharded { shard_dirs(frames_dir) } else { vec![(frames_dir.to_path_buf(), 1)] };
+
+    let started = std::time::Instant::now();
+    let mut next = 1;
+    for (k, (dir, first)) in parts.iter().enumerate() {
+        let out_frames = work.join(format!("part{k}"));
+        fs::create_dir_all(&out_frames).map_err(|e| format!("Failed to create VapourSynth folder: {e}"))?;
+        run_vspipe(app, job, stage, (&vspipe, &vs.script), (&job.frame_format.pattern(dir), *first), fps, &out_frames)?;
+        next = append_frames(&out_frames, &new_files, next)?;
+    }
+
+    let count = next - 1;
+    if count == 0 {
+        return Err("The VapourSynth script produced no frames".into());
+    }
+    let before = count_files_in_dir(frames_dir);
+    fs::remove_dir_all(frames_dir).map_err(|e| format!("Failed to replace frames with VapourSynth's: {e}"))?;
+    fs::rename(&new_frames, frames_dir).map_err(|e| format!("Failed to replace frames with VapourSynth's: {e}"))?;
+    let _ = fs::remove_dir_all(&work);
+    emit_log_limited(app, &format!(
+        "VapourSynth: {before} → {count} frames in {:.1}s",
+        started.elapsed().as_secs_f64()
+    ));
+    Ok(count != before)
+}
+
+/// One vspipe run of `script` over the frames `pattern` names from number `first` on, piped
+/// into ffmpeg writing `out_frames`.
+fn run_vspipe(
+    app: &AppHandle,
+    job: &SmoothJob,
+    stage: JobStage,
+    (vspipe, script): (&Path, &str),
+    (pattern, first): (&Path, usize),
+    fps: f64,
+    out_frames: &Path,
+) -> Result<(), String> {
+    let mut pipe = tool_command(vspipe);
+    pipe.arg("--arg").arg(format!("frames={}", pattern.to_string_lossy()))
+        .arg("--arg").arg(format!("first={first}"))
+        .arg("--arg").arg(format!("fps={fps:.6}"))
+        .arg("--progress")
+        .arg(script)
+        .arg("-c").arg("y4m")
+        .arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    log_stage_command(app, "vapoursynth", &mut pipe);
+    let mut vspipe = spawn_stage_process(app, &job.job_id, "vspipe", &mut pipe)?;
+    let y4m = vspipe.child.stdout.take().ok_or("vspipe has no output pipe")?;
+
+    let mut enc = job.ffmpeg_command();
+    enc.arg("-hide_banner").arg("-loglevel").arg("error").arg("-y")
+        .arg("-f").arg("yuv4mpegpipe").arg("-i").arg("-")
+        .arg("-start_number").arg("1")
+        .arg(job.frame_format.pattern(out_frames))
+        .stdin(Stdio::from(y4m))
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    log_stage_command(app, "vapoursynth", &mut enc);
+    // The job only records vspipe; in its group, cancelling stops the reader as well.
+    join_process_group(&mut enc, vspipe.child.id());
+    let enc_child = enc.spawn();
+
+    // vspipe reports `Frame: n/total` on stderr; a failing script prints its traceback there.
+    let app_log = app.clone();
+    vspipe.relay(Pipe::Stderr, move |line| {
+        let progress = line
+            .strip_prefix("Frame: ")
+            .and_then(|v| v.split_whitespace().next())
+            .and_then(|v| v.split_once('/'))
+            .and_then(|(n, t)| Some((n.parse::<f64>().ok()?, t.parse::<f64>().ok()?)));
+        match progress {
+            Some((n, t)) => {
+                let _ = app_log.emit("plugin_progress", PluginProgress { name: "VapourSynth".into(), fraction: (n / t.max(1.0)).min(1.0) });
+                false
+            }
+            None => {
+                emit_pipeline(&app_log, "pipeline_log", format!("[vspipe] {line}"));
+                true
+            }
+        }
+    });
+    let enc_out = enc_child.and_then(|c| c.wait_with_output());
+    let status = vspipe.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, stage)?;
+    vspipe.check(app, "The VapourSynth script failed", status)?;
+    let enc_out = enc_out.map_err(|e| format!("ffmpeg failed to read vspipe's output: {e}"))?;
+    if !enc_out.status.success() {
+        let stderr = String::from_utf8_lossy(&enc_out.stderr);
+        let lines: Vec<String> = stderr.lines().map(str::to_string).collect();
+        let start = lines.len().saturating_sub(vspipe.tail.max);
+        return Err(stage_failure(app, &job.job_id, "FFmpeg", "ffmpeg failed to read vspipe's output", Some(enc_out.status), &lines[start..]));
+    }
+    Ok(())
+}
+
+// -------------------- Job history --------------------
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct JobRecord {
+    job_id: String,
+    /// Command that created the job ("smooth_video", "reencode_only").
+    kind: String,
+    input: String,
+    output: String,
+    started_at: String,
+    finished_at: Option<String>,
+    /// None while the job is still running.
+    ok: Option<bool>,
+    message: String,
+    note: String,
+    tags: Vec<String>,
+    /// Wall time from start to finish.
+    elapsed_secs: f64,
+    /// Model folder name used for interpolation (empty for encode-only jobs).
+    model: String,
+    source_duration_secs: f64,
+    frames_in: u64,
+    frames_out: u64,
+    /// Time spent in the RIFE stage, for per-model speed statistics.
+    interpolate_secs: f64,
+    /// Time spent extracting and encoding, for `estimate_stage_weights`.
+    extract_secs: f64,
+    encode_secs: f64,
+    /// Bytes written to disk: intermediate frames plus the final output.
+    bytes_written: u64,
+    /// Captured output of the post-job hook, if one ran.
+    hook_output: String,
+    /// Requested deferred start time (RFC 3339), if the job was scheduled.
+    scheduled_for: Option<String>,
+    /// Contact sheet of the output (see `make_contact_sheet`), shown as the job's thumbnail.
+    thumbnail: Option<String>,
+    /// Output rate chosen by the job's `FpsPolicy`, if it had one.
+    fps_decision: Option<FpsDecision>,
+    /// Source conditions the job's `SourcePolicy` acted on.
+    source_decisions: Vec<SourceDecision>,
+    /// GPU sharing mode the job ran under (see `gpu_sharing_stats`).
+    gpu_sharing: Option<GpuSharing>,
+    /// Output frames repeated instead of interpolated inside static runs (see `find_frame_holds`).
+    held_frames: u64,
+    /// Estimated RIFE time those frames would have taken.
+    hold_saved_secs: f64,
+    /// Hash of the input and the output-shaping settings (see `job_fingerprint`).
+    fingerprint: String,
+}
+
+impl JobRecord {
+    /// Output frames RIFE actually produced, for speed statistics.
+    fn rife_frames(&self) -> u64 {
+        self.frames_out.saturating_sub(self.held_frames)
+    }
+}
+
+/// Serializes read-modify-write cycles on history.json across worker threads.
+static HISTORY_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+fn history_path(root: &Path) -> PathBuf {
+    profile_root(root).join("history.json")
+}
+
+fn load_history(root: &Path) -> Vec<JobRecord> {
+    fs::read_to_string(history_path(root))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(root: &Path, history: &[JobRecord]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(history).map_err(|e| e.to_string())?;
+    fs::write(history_path(root), json).map_err(|e| format!("Failed to write job history: {e}"))
+}
+
+fn update_history<T>(root: &Path, f: impl FnOnce(&mut Vec<JobRecord>) -> T) -> Result<T, String> {
+    let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut history = load_history(root);
+    let out = f(&mut history);
+    save_history(root, &history)?;
+    Ok(out)
+}
+
+fn record_job_start(root: &Path, job_id: &str, kind: &str, input: &Path, output: &Path) {
+    let record = JobRecord {
+        job_id: job_id.to_string(),
+        kind: kind.to_string(),
+        input: input.to_string_lossy().to_string(),
+        output: output.to_string_lossy().to_string(),
+        started_at: chrono::Local::now().to_rfc3339(),
+        ..Default::default()
+    };
+    let _ = update_history(root, |h| h.push(record));
+}
+
+fn update_job_record(root: &Path, job_id: &str, f: impl FnOnce(&mut JobRecord)) {
+    let _ = update_history(root, |h| {
+        if let Some(r) = h.iter_mut().find(|r| r.job_id == job_id) {
+            f(r);
+        }
+    });
+}
+
+fn record_job_schedule(root: &Path, job_id: &str, not_before: Option<i64>) {
+    let Some(at) = not_before.and_then(chrono::DateTime::from_timestamp_millis) else { return };
+    update_job_record(root, job_id, |r| {
+        r.scheduled_for = Some(at.with_timezone(&chrono::Local).to_rfc3339());
+    });
+}
+
+fn record_job_finish(root: &Path, job_id: &str, ok: bool, message: &str) {
+    update_job_record(root, job_id, |r| {
+        let now = chrono::Local::now();
+        if let Ok(start) = chrono::DateTime::parse_from_rfc3339(&r.started_at) {
+            r.elapsed_secs = (now.fixed_offset() - start).num_milliseconds().max(0) as f64 / 1000.0;
+        }
+        r.finished_at = Some(now.to_rfc3339());
+        r.ok = Some(ok);
+        r.message = message.to_string();
+    });
+    let settings = load_settings(root);
+    if ok {
+        write_library_manifest(root, &settings, job_id);
+    }
+    // Jobs are what fill the cache (probes, engines, contact sheets).
+    enforce_cache_limit(root, &settings);
+}
+
+// -------------------- Duplicate jobs --------------------
+
+/// What to do with a job whose input and settings match an earlier one that still stands.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum DuplicatePolicy {
+    Allow,
+    /// Log which job it repeats and run it anyway.
+    #[default]
+    Warn,
+    /// Don't queue it; `smooth_video` returns `skipped` instead of a job.
+    Skip,
+}
+
+/// Identifies the work a job does: the input as the probe cache keys it (path, size, mtime)
+/// plus `settings`, everything that shapes the output.
+fn job_fingerprint(input: &Path, settings: &impl serde::Serialize) -> Option<String> {
+    use sha2::{Digest, Sha256};
+    let (size, mtime_ms) = file_identity(input)?;
+    let settings = serde_json::to_string(settings).ok()?;
+    let mut hasher = Sha256::new();
+    hasher.update(canonicalize_lenient(input).to_string_lossy().as_bytes());
+    hasher.update(format!("\0{size}\0{mtime_ms}\0").as_bytes());
+    hasher.update(settings.as_bytes());
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// The newest earlier job with this fingerprint whose result still stands: queued or running,
+/// or succeeded with its output still on disk.
+fn find_duplicate_job(state: &AppState, root: &Path, fingerprint: &str) -> Option<JobRecord> {
+    load_history(root).into_iter().rev().find(|r| {
+        r.fingerprint == fingerprint
+            && match r.ok {
+                None => state.is_job_running(&r.job_id),
+                Some(ok) => ok && Path::new(&r.output).is_file(),
+            }
+    })
+}
+
+fn dir_size_bytes(dir: &Path) -> u64 {
+    let mut total = 0;
+    if let Ok(rd) = fs::read_dir(dir) {
+        for e in rd.flatten() {
+            let p = e.path();
+            if p.is_dir() {
+                total += dir_size_bytes(&p);
+            } else if let Ok(m) = e.metadata() {
+                total += m.len();
+            }
+        }
+    }
+    total
+}
+
+#[derive(serde::Serialize)]
+struct ModelStats {
+    model: String,
+    jobs: u64,
+    frames_out: u64,
+    /// Interpolated frames per second of RIFE stage time.
+    avg_fps: f64,
+}
+
+#[derive(serde::Serialize)]
+struct JobStats {
+    total_jobs: u64,
+    succeeded: u64,
+    failed: u64,
+    running: u64,
+    /// failed / finished, 0 when nothing has finished yet.
+    failure_rate: f64,
+    frames_interpolated: u64,
+    hours_processed: f64,
+    hours_elapsed: f64,
+    bytes_written: u64,
+    by_model: Vec<ModelStats>,
+}
+
+/// Totals derived from the job history, for a stats panel and for tuning defaults.
+#[tauri::command]
+fn get_stats(app: AppHandle) -> Result<JobStats, String> {
+    let root = app_root(&app)?;
+    let history = load_history(&root);
+
+    let mut stats = JobStats {
+        total_jobs: history.len() as u64,
+        succeeded: 0,
+        failed: 0,
+        running: 0,
+        failure_rate: 0.0,
+        frames_interpolated: 0,
+        hours_processed: 0.0,
+        hours_elapsed: 0.0,
+        bytes_written: 0,
+        by_model: Vec::new(),
+    };
+    // (jobs, frames_out, interpolate_secs) per model
+    let mut models: std::collections::BTreeMap<String, (u64, u64, f64)> = Default::default();
+
+    for r in &history {
+        match r.ok {
+            Some(true) => stats.succeeded += 1,
+            Some(false) => stats.failed += 1,
+            None => stats.running += 1,
+        }
+        stats.bytes_written += r.bytes_written;
+        stats.hours_elapsed += r.elapsed_secs / 3600.0;
+        if r.ok == Some(true) {
+            stats.frames_interpolated += r.frames_out;
+            stats.hours_processed += r.source_duration_secs / 3600.0;
+            if !r.model.is_empty() {
+                let m = models.entry(r.model.clone()).or_default();
+                m.0 += 1;
+                m.1 += r.rife_frames();
+                m.2 += r.interpolate_secs;
+            }
+        }
+    }
+
+    let finished = stats.succeeded + stats.failed;
+    if finished > 0 {
+        stats.failure_rate = stats.failed as f64 / finished as f64;
+    }
+    stats.by_model = models
+        .into_iter()
+        .map(|(model, (jobs, frames_out, secs))| ModelStats {
+            model,
+            jobs,
+            frames_out,
+            avg_fps: if secs > 0.0 { frames_out as f64 / secs } else { 0.0 },
+        })
+        .collect();
+
+    Ok(stats)
+}
+
+// -------------------- Progress weighting --------------------
+
+/// Share of the overall progress bar for each stage of a Smooth Video job. Any scale works;
+/// the shares are normalised.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct StageWeights {
+    extract: f64,
+    interpolate: f64,
+    encode: f64,
+}
+
+impl Default for StageWeights {
+    /// Before there is any history: RIFE dominates.
+    fn default() -> Self {
+        Self { extract: 10.0, interpolate: 75.0, encode: 15.0 }
+    }
+}
+
+impl StageWeights {
+    /// Overall percent for `fraction` (0-1) of the way through `stage`.
+    fn overall(&self, stage: JobStage, fraction: f64) -> f64 {
+        let valid = [self.extract, self.interpolate, self.encode].iter().all(|w| w.is_finite() && *w >= 0.0);
+        let w = if valid && self.extract + self.interpolate + self.encode > 0.0 { *self } else { Self::default() };
+        let total = w.extract + w.interpolate + w.encode;
+        let (before, width) = match stage {
+            JobStage::Extract => (0.0, w.extract),
+            JobStage::Interpolate => (w.extract, w.interpolate),
+            JobStage::Encode => (w.extract + w.interpolate, w.encode),
+        };
+        (before + width * fraction.clamp(0.0, 1.0)) / total * 100.0
+    }
+}
+
+/// Jobs looked at by `estimate_stage_weights`, newest first.
+const STAGE_WEIGHT_HISTORY: usize = 20;
+
+/// Stage shares from the speeds of recent successful jobs: each stage's time per source frame
+/// (extraction reads one frame per source frame, RIFE and the encode handle the ~2x output).
+/// Jobs with the same model are preferred when there are a few of them.
+fn estimate_stage_weights(root: &Path, model: &str) -> StageWeights {
+    let timed: Vec<JobRecord> = load_history(root)
+        .into_iter()
+        .rev()
+        .filter(|r| r.ok == Some(true) && r.frames_in > 0 && r.frames_out > 0)
+        .filter(|r| r.extract_secs > 0.0 && r.interpolate_secs > 0.0 && r.encode_secs > 0.0)
+        .take(STAGE_WEIGHT_HISTORY * 5)
+        .collect();
+    let same_model: Vec<&JobRecord> = timed.iter().filter(|r| r.model == model).take(STAGE_WEIGHT_HISTORY).collect();
+    let sample: Vec<&JobRecord> = if same_model.len() >= 3 {
+        same_model
+    } else {
+        timed.iter().take(STAGE_WEIGHT_HISTORY).collect()
+    };
+    if sample.is_empty() {
+        return StageWeights::default();
+    }
+    let n = sample.len() as f64;
+    let mean_fps = |f: &dyn Fn(&JobRecord) -> f64| sample.iter().map(|r| f(r)).sum::<f64>() / n;
+    let extract_fps = mean_fps(&|r| r.frames_in as f64 / r.extract_secs);
+    let interpolate_fps = mean_fps(&|r| r.rife_frames() as f64 / r.interpolate_secs);
+    let encode_fps = mean_fps(&|r| r.frames_out as f64 / r.encode_secs);
+    StageWeights { extract: 1.0 / extract_fps, interpolate: 2.0 / interpolate_fps, encode: 2.0 / encode_fps }
+}
+
+/// Source frames per second a whole job is assumed to manage before there is any history.
+const ASSUMED_JOB_FPS: f64 = 10.0;
+
+/// Expected wall time of a job over `frames` source frames, from the time per source frame of
+/// recent successful jobs (preferring the same model, as `estimate_stage_weights` does).
+fn estimate_job_secs(root: &Path, model: &str, frames: f64) -> f64 {
+    let timed: Vec<JobRecord> = load_history(root)
+        .into_iter()
+        .rev()
+        .filter(|r| r.ok == Some(true) && r.frames_in > 0 && r.elapsed_secs > 0.0 && !r.model.is_empty())
+        .take(STAGE_WEIGHT_HISTORY * 5)
+        .collect();
+    let same_model: Vec<&JobRecord> = timed.iter().filter(|r| r.model == model).take(STAGE_WEIGHT_HISTORY).collect();
+    let sample: Vec<&JobRecord> = if same_model.len() >= 3 {
+        same_model
+    } else {
+        timed.iter().take(STAGE_WEIGHT_HISTORY).collect()
+    };
+    let secs_per_frame = if sample.is_empty() {
+        1.0 / ASSUMED_JOB_FPS
+    } else {
+        sample.iter().map(|r| r.elapsed_secs / r.frames_in as f64).sum::<f64>() / sample.len() as f64
+    };
+    frames.max(0.0) * secs_per_frame
+}
+
+/// Emit `fraction` of `stage` as overall progress with the job's stage weights.
+fn emit_stage_progress(app: &AppHandle, job: &SmoothJob, stage: JobStage, fraction: f64, frames: u64) {
+    emit_progress_frames(app, job.stage_weights.overall(stage, fraction), frames);
+}
+
+/// Read `-progress` output from `out`, reporting `frame=` against `expected` frames as
+/// `span` (start, width, as fractions) of `stage`.
+fn follow_ffmpeg_progress(
+    app: &AppHandle,
+    job: &SmoothJob,
+    stage: JobStage,
+    out: std::process::ChildStdout,
+    expected: f64,
+    span: (f64, f64),
+) -> std::thread::JoinHandle<()> {
+    let (app, weights) = (app.clone(), job.stage_weights);
+    spawn_in_job(move || {
+        for line in BufReader::new(out).lines().map_while(Result::ok) {
+            if let Some(("frame", v)) = parse_ffmpeg_progress_line(&line) {
+                let frame = v.parse::<u64>().unwrap_or(0);
+                let fraction = span.0 + span.1 * (frame as f64 / expected.max(1.0)).min(1.0);
+                emit_progress_frames(&app, weights.overall(stage, fraction), frame);
+            }
+        }
+    })
+}
+
+fn job_matches(record: &JobRecord, query: &str) -> bool {
+    let q = query.to_lowercase();
+    [&record.job_id, &record.input, &record.output, &record.note, &record.message]
+        .iter()
+        .any(|f| f.to_lowercase().contains(&q))
+        || record.tags.iter().any(|t| t.to_lowercase().contains(&q))
+}
+
+/// Job history, newest first. `query` matches ids, paths, notes, messages and tags;
+/// `tag` additionally requires an exact (case-insensitive) tag match.
+#[tauri::command]
+fn list_jobs(app: AppHandle, query: Option<String>, tag: Option<String>) -> Result<Vec<JobRecord>, String> {
+    let root = app_root(&app)?;
+    let query = query.unwrap_or_default();
+    let query = query.trim();
+    let tag = tag.unwrap_or_default();
+    let tag = tag.trim();
+
+    let mut jobs: Vec<JobRecord> = load_history(&root)
+        .into_iter()
+        .filter(|r| query.is_empty() || job_matches(r, query))
+        .filter(|r| tag.is_empty() || r.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+        .collect();
+    jobs.reverse();
+    Ok(jobs)
+}
+
+/// Attach a freeform note and/or replace the tag list of a job. Omitted fields are left as-is.
+#[tauri::command]
+fn set_job_metadata(
+    app: AppHandle,
+    job_id: String,
+    note: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<JobRecord, String> {
+    let root = app_root(&app)?;
+    update_history(&root, |h| {
+        let r = h
+            .iter_mut()
+            .find(|r| r.job_id == job_id)
+            .ok_or_else(|| format!("Unknown job: {job_id}"))?;
+        if let Some(note) = note {
+            r.note = note.trim().to_string();
+        }
+        if let Some(tags) = tags {
+            let mut cleaned: Vec<String> = Vec::new();
+            for t in tags {
+                let t = t.trim().to_string();
+                if !t.is_empty() && !cleaned.iter().any(|c| c.eq_ignore_ascii_case(&t)) {
+                    cleaned.push(t);
+                }
+            }
+            r.tags = cleaned;
+        }
+        Ok(r.clone())
+    })?
+}
+
+fn has_rife_executable(dir: &Path) -> bool {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for e in entries.flatten() {
+            let p = e.path();
+            if p.is_file() {
+                if let Some(name) = p.file_name().and_then(|n| n.to_str()) {
+                    if name.to_ascii_lowercase().starts_with("rife") {
+                        return true;
+                    }
+                }
+            }
+        }
+    }
+    false
+}
+
+#[tauri::command]
+fn tool_status(app: AppHandle, tool: String) -> Result<String, String> {
+    let root = app_root(&app)?;
+    let tool_dir = root.join("bin").join(&tool);
+
+    if !tool_dir.exists() {
+        return Ok("missing".into());
+    }
+
+    if let Ok(versions) = fs::read_dir(&tool_dir) {
+        for v in versions.flatten() {
+            let p = v.path();
+            if !p.is_dir() {
+                continue;
+            }
+
+            if RifeRuntime::of_tool(&tool).is_some() {
+                if has_rife_executable(&p) {
+                    return Ok("installed".into());
+                }
+            } else {
+                // ffmpeg: any file in any version folder counts as installed
+                if let Ok(files) = fs::read_dir(&p) {
+                    for f in files.flatten() {
+                        if f.path().is_file() {
+                            return Ok("installed".into());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok("missing".into())
+}
+
+#[tauri::command]
+fn install_tool(
+    app: AppHandle,
+    source_path: String,
+    tool: String,
+    version: String,
+) -> Result<String, String> {
+    let src = Path::new(&source_path);
+    if !src.exists() {
+        return Err("Source path does not exist".into());
+    }
+
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    ensure_tool_not_in_use(&app, &tool, &root.join("bin").join(&tool).join(&version))?;
+    let installed = install_tool_files(&root, src, &tool, &version)?;
+    write_install_manifest(&root, &tool, &version, src);
+    app.state::<AppState>().refresh_tools(&root);
+    Ok(installed.to_string_lossy().to_string())
+}
+
+/// Where installs are assembled before `replace_dir` moves them into place. Under `bin/`, so
+/// on the same volume as `bin/` and `models/`, but outside the folders tools are looked up in.
+fn install_staging_dir(root: &Path, name: &str) -> PathBuf {
+    root.join("bin").join(".staging").join(name)
+}
+
+/// Build the new contents of `dest` in `staging` with `fill`, then swap it in by renaming, so
+/// a failed or interrupted copy leaves the previous install working.
+fn replace_dir(staging: &Path, dest: &Path, fill: impl FnOnce(&Path) -> Result<(), String>) -> Result<(), String> {
+    let name = dest.file_name().ok_or("Invalid install folder")?.to_string_lossy().to_string();
+    let staging_name = staging.file_name().ok_or("Invalid staging folder")?.to_string_lossy().to_string();
+    let old = staging.with_file_name(format!("{staging_name}.old"));
+    let _ = fs::remove_dir_all(staging);
+    let _ = fs::remove_dir_all(&old);
+    fs::create_dir_all(staging).map_err(|e| fs_error("create dir", staging, &e))?;
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| fs_error("create dir", parent, &e))?;
+    }
+    if let Err(e) = fill(staging) {
+        let _ = fs::remove_dir_all(staging);
+        return Err(e);
+    }
+    if dest.exists() {
+        if let Err(e) = fs::rename(dest, &old) {
+            let _ = fs::remove_dir_all(staging);
+            return Err(format!("Failed to move the old {name} aside: {e}"));
+        }
+    }
+    if let Err(e) = fs::rename(staging, dest) {
+        let _ = fs::rename(&old, dest);
+        let _ = fs::remove_dir_all(staging);
+        return Err(format!("Failed to move {name} into place: {e}"));
+    }
+    let _ = fs::remove_dir_all(&old);
+    Ok(())
+}
+
+fn install_tool_files(root: &Path, src: &Path, tool: &str, version: &str) -> Result<PathBuf, String> {
+    let dest_dir = root.join("bin").join(tool).join(version);
+    let staging = install_staging_dir(root, &format!("{tool}-{version}"));
+
+    if src.is_dir() {
+        // RIFE-style folder install (binary + models)
+        replace_dir(&staging, &dest_dir, |dir| copy_dir_recursive(src, dir))?;
+        Ok(dest_dir)
+    } else {
+        // ffmpeg-style single binary
+        let filename = src.file_name().ok_or("Invalid file")?;
+        replace_dir(&staging, &dest_dir, |dir| {
+            let dest = dir.join(filename);
+            fs::copy(src, &dest).map_err(|e| e.to_string())?;
+
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mut perms =
+                    fs::metadata(&dest).map_err(|e| e.to_string())?.permissions();
+                perms.set_mode(0o755);
+                fs::set_permissions(&dest, perms).map_err(|e| e.to_string())?;
+            }
+            Ok(())
+        })?;
+        Ok(dest_dir.join(filename))
+    }
+}
+
+/// Copy the model folder `src` to `models/<name>` through `replace_dir`.
+fn install_model_files(root: &Path, src: &Path, name: &str) -> Result<PathBuf, String> {
+    let dest = root.join("models").join(name);
+    replace_dir(&install_staging_dir(root, &format!("model-{name}")), &dest, |dir| copy_dir_recursive(src, dir))?;
+    Ok(dest)
+}
+
+/// Where a tool version was installed from, so it can be repaired later. Stored next to the
+/// version folder (`bin/<tool>/<version>.install.json`) because ffmpeg version folders must only
+/// contain the binary.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct InstallManifest {
+    tool: String,
+    version: String,
+    source_path: String,
+    installed_at: String,
+    /// SHA-256 of every installed file, relative to the install folder. Empty for installs
+    /// made before checksums were recorded.
+    checksums: std::collections::BTreeMap<String, String>,
+}
+
+fn install_manifest_path(root: &Path, tool: &str, version: &str) -> PathBuf {
+    root.join("bin").join(tool).join(format!("{version}.install.json"))
+}
+
+/// Models get the same manifest, with tool "model" and the folder name as version, stored
+/// next to the folder as `models/<name>.install.json`.
+fn model_manifest_path(root: &Path, name: &str) -> PathBuf {
+    root.join("models").join(format!("{name}.install.json"))
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    use sha2::{Digest, Sha256};
+    let mut file = fs::File::open(path).map_err(|e| format!("{}: {e}", path.to_string_lossy()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("{}: {e}", path.to_string_lossy()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Every file under `dir`, as paths relative to `base` joined with '/'.
+fn relative_files(base: &Path, dir: &Path, out: &mut Vec<String>) {
+    for e in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let p = e.path();
+        if p.is_dir() {
+            relative_files(base, &p, out);
+        } else if let Ok(rel) = p.strip_prefix(base) {
+            let parts: Vec<String> = rel.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+            out.push(parts.join("/"));
+        }
+    }
+}
+
+fn checksum_dir(dir: &Path) -> Result<std::collections::BTreeMap<String, String>, String> {
+    let mut files = Vec::new();
+    relative_files(dir, dir, &mut files);
+    files.into_iter().map(|rel| sha256_file(&dir.join(&rel)).map(|sum| (rel, sum))).collect()
+}
+
+fn save_manifest(path: &Path, tool: &str, version: &str, src: &Path, installed: &Path) {
+    let manifest = InstallManifest {
+        tool: tool.to_string(),
+        version: version.to_string(),
+        source_path: src.to_string_lossy().to_string(),
+        installed_at: chrono::Local::now().to_rfc3339(),
+        checksums: checksum_dir(installed).unwrap_or_default(),
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&manifest) {
+        let _ = fs::write(path, json);
+    }
+}
+
+fn write_install_manifest(root: &Path, tool: &str, version: &str, src: &Path) {
+    let installed = root.join("bin").join(tool).join(version);
+    save_manifest(&install_manifest_path(root, tool, version), tool, version, src, &installed);
+}
+
+fn write_model_manifest(root: &Path, name: &str, src: &Path) {
+    let installed = root.join("models").join(name);
+    save_manifest(&model_manifest_path(root, name), "model", name, src, &installed);
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct IntegrityReport {
+    /// "ffmpeg", a RIFE runtime tool, or "model".
+    tool: String,
+    /// Version folder, or the model's name.
+    version: String,
+    path: String,
+    ok: bool,
+    /// No checksums were recorded, so nothing could be checked.
+    unrecorded: bool,
+    modified: Vec<String>,
+    missing: Vec<String>,
+}
+
+impl IntegrityReport {
+    fn summary(&self) -> String {
+        let mut files: Vec<&str> = self.modified.iter().chain(&self.missing).map(String::as_str).take(5).collect();
+        let more = self.modified.len() + self.missing.len() - files.len();
+        if more > 0 {
+            files.push("…");
+        }
+        format!("{} modified, {} missing: {}", self.modified.len(), self.missing.len(), files.join(", "))
+    }
+}
+
+fn verify_install_dir(manifest: &InstallManifest, dir: &Path) -> IntegrityReport {
+    let mut report = IntegrityReport {
+        tool: manifest.tool.clone(),
+        version: manifest.version.clone(),
+        path: dir.to_string_lossy().to_string(),
+        ok: true,
+        unrecorded: manifest.checksums.is_empty(),
+        modified: Vec::new(),
+        missing: Vec::new(),
+    };
+    for (rel, expected) in &manifest.checksums {
+        match sha256_file(&dir.join(rel)) {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => {}
+            Ok(_) => report.modified.push(rel.clone()),
+            Err(_) => report.missing.push(rel.clone()),
+        }
+    }
+    report.ok = report.modified.is_empty() && report.missing.is_empty();
+    report
+}
+
+/// Manifest and install folder for a path inside `bin/<tool>/<version>` or `models/<name>`.
+fn manifest_for_installed_path(root: &Path, path: &Path) -> Option<(InstallManifest, PathBuf)> {
+    let (manifest, dir) = if let Ok(rel) = path.strip_prefix(root.join("bin")) {
+        let mut parts = rel.components().map(|c| c.as_os_str().to_string_lossy().to_string());
+        let (tool, version) = (parts.next()?, parts.next()?);
+        (install_manifest_path(root, &tool, &version), root.join("bin").join(&tool).join(&version))
+    } else {
+        let name = path.strip_prefix(root.join("models")).ok()?.components().next()?;
+        let name = name.as_os_str().to_string_lossy().to_string();
+        (model_manifest_path(root, &name), root.join("models").join(&name))
+    };
+    let manifest = serde_json::from_str(&fs::read_to_string(manifest).ok()?).ok()?;
+    Some((manifest, dir))
+}
+
+/// Re-hash the installs `paths` live in; an error for the first one that changed since it was
+/// installed. Used to explain failures the error patterns don't recognise.
+fn integrity_error(root: &Path, paths: &[&Path]) -> Option<ActionableError> {
+    let report = paths
+        .iter()
+        .filter_map(|p| manifest_for_installed_path(root, p))
+        .map(|(manifest, dir)| verify_install_dir(&manifest, &dir))
+        .find(|r| !r.ok)?;
+    let what = if report.tool == "model" { format!("Model {}", report.version) } else { format!("{} {}", report.tool, report.version) };
+    Some(ActionableError {
+        code: "install_damaged".into(),
+        title: format!("{what} has changed since it was installed"),
+        suggestion: if report.tool == "model" {
+            "Re-import the model; if this keeps happening, check the disk and your antivirus quarantine.".into()
+        } else {
+            format!("Repair {} from the Tools panel; if this keeps happening, check the disk and your antivirus quarantine.", report.tool)
+        },
+        detail: report.summary(),
+    })
+}
+
+/// Re-hash every installed tool version and model against the checksums recorded when it
+/// was installed, to catch tampering and bitrot.
+#[tauri::command]
+fn verify_installation(app: AppHandle) -> Result<Vec<IntegrityReport>, String> {
+    let root = app_root(&app)?;
+    let manifests = |dir: PathBuf| -> Vec<InstallManifest> {
+        fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".install.json"))
+            .filter_map(|e| serde_json::from_str(&fs::read_to_string(e.path()).ok()?).ok())
+            .collect()
+    };
+    let mut reports = Vec::new();
+    for tool in std::iter::once("ffmpeg").chain(RifeRuntime::ALL.iter().map(|r| r.tool())) {
+        for m in manifests(root.join("bin").join(tool)) {
+            let dir = root.join("bin").join(tool).join(&m.version);
+            if dir.is_dir() {
+                reports.push(verify_install_dir(&m, &dir));
+            }
+        }
+    }
+    for m in manifests(root.join("models")) {
+        let dir = root.join("models").join(&m.version);
+        if dir.is_dir() {
+            reports.push(verify_install_dir(&m, &dir));
+        }
+    }
+    Ok(reports)
+}
+
+fn read_install_manifest(root: &Path, tool: &str, version: &str) -> Option<InstallManifest> {
+    let text = fs::read_to_string(install_manifest_path(root, tool, version)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+/// Version folder of the install currently in use, or failing that the most recently
+/// installed version that has a manifest.
+fn active_tool_version(root: &Path, tools: &ResolvedTools, tool: &str) -> Option<String> {
+    if let Some(v) = tools.active_version(tool) {
+        return Some(v);
+    }
+
+    let mut manifests: Vec<InstallManifest> = fs::read_dir(root.join("bin").join(tool))
+        .ok()?
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().ends_with(".install.json"))
+        .filter_map(|e| fs::read_to_string(e.path()).ok())
+        .filter_map(|t| serde_json::from_str(&t).ok())
+        .collect();
+    manifests.sort_by(|a, b| a.installed_at.cmp(&b.installed_at));
+    manifests.pop().map(|m| m.version)
+}
+
+/// Re-copy the active version of a tool from its original install source and re-validate it.
+#[tauri::command]
+fn repair_tool(app: AppHandle, state: State<'_, AppState>, tool: String) -> Result<ToolValidation, String> {
+    if tool != "ffmpeg" && RifeRuntime::of_tool(&tool).is_none() {
+        return Err(format!("Unknown tool: {tool}"));
+    }
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    let version = active_tool_version(&root, &state.refresh_tools(&root), &tool)
+        .ok_or_else(|| format!("No {tool} install found to repair; install it first"))?;
+    let manifest = read_install_manifest(&root, &tool, &version).ok_or_else(|| {
+        format!("{tool} {version} was installed before repair support; reinstall it from its original files")
+    })?;
+    let src = PathBuf::from(&manifest.source_path);
+    if !src.exists() {
+        return Err(format!(
+            "Original install source no longer exists: {}; reinstall {tool} from a new source",
+            src.to_string_lossy()
+        ));
+    }
+
+    let version_dir = root.join("bin").join(&tool).join(&version);
+    ensure_tool_not_in_use(&app, &tool, &version_dir)?;
+    // Replaces the broken folder only once the fresh copy is complete.
+    install_tool_files(&root, &src, &tool, &version)?;
+
+    let tools = state.refresh_tools(&root);
+    let (ffmpeg_path, rife_path, rife_models) = tools.paths();
+    Ok(match RifeRuntime::of_tool(&tool) {
+        Some(RifeRuntime::NcnnVulkan) => validate_rife_install(rife_path, rife_models),
+        Some(runtime) => {
+            let (bin, models) = runtime.installed(&root, &tools).unzip();
+            validate_rife_install(bin, models)
+        }
+        None => validate_ffmpeg_install(ffmpeg_path),
+    })
+}
+
+// -------------------- Tool updates --------------------
+
+/// Latest rife-ncnn-vulkan release; its archives include the models.
+const RIFE_RELEASES_API: &str = "https://api.github.com/repos/nihui/rife-ncnn-vulkan/releases/latest";
+
+/// One entry of the release catalog: a tool version or a model.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct CatalogEntry {
+    /// "ffmpeg", a RIFE runtime tool ("rife", "rife-trt", ...) or "model".
+    tool: String,
+    /// Folder name, for models.
+    name: Option<String>,
+    version: String,
+    changelog: String,
+    /// Download per platform ("windows", "macos", "linux"): an archive, or a bare binary.
+    assets: HashMap<String, String>,
+    /// Lowercase hex SHA-256 of each download in `assets`, by platform. Downloads without one
+    /// aren't installed.
+    sha256: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct ToolUpdate {
+    tool: String,
+    name: Option<String>,
+    /// Installed version; for models, the folder they are installed in.
+    installed: Option<String>,
+    latest: String,
+    changelog: String,
+    download_url: Option<String>,
+    /// Expected SHA-256 of the download.
+    #[serde(default)]
+    sha256: Option<String>,
+    update_available: bool,
+}
+
+fn platform_key() -> &'static str {
+    if cfg!(windows) {
+        "windows"
+    } else if cfg!(target_os = "macos") {
+        "macos"
+    } else {
+        "linux"
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// The proxy URL for `proxy`, credentials included; None when no host is set.
+fn proxy_url(proxy: &ProxySettings) -> Option<String> {
+    let host = proxy.host.trim();
+    if host.is_empty() {
+        return None;
+    }
+    let (scheme, host) = host.split_once("://").unwrap_or(("http", host));
+    let auth = match (proxy.username.as_deref().filter(|u| !u.is_empty()), proxy.password.as_deref()) {
+        (Some(user), Some(pass)) => format!("{}:{}@", percent_encode(user), percent_encode(pass)),
+        (Some(user), None) => format!("{}@", percent_encode(user)),
+        (None, _) => String::new(),
+    };
+    let port = proxy.port.map(|p| format!(":{p}")).unwrap_or_default();
+    Some(format!("{scheme}://{auth}{host}{port}"))
+}
+
+/// curl set up with the proxy and CA settings; every download goes through this. The proxy is
+/// passed in the environment rather than on the command line, keeping credentials out of the
+/// process list.
+fn curl_command(settings: &AppSettings) -> Result<Command, String> {
+    let mut cmd = tool_command("curl");
+    cmd.arg("-fL").arg("--silent").arg("--show-error");
+    cmd.arg("-H").arg("User-Agent: rife-interpolator");
+    if let Some(url) = settings.proxy.as_ref().and_then(proxy_url) {
+        for var in ["http_proxy", "https_proxy", "HTTPS_PROXY"] {
+            cmd.env(var, &url);
+        }
+    }
+    if let Some(ca) = settings.ca_bundle.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        if !Path::new(ca).is_file() {
+            return Err(format!("CA bundle not found: {ca}"));
+        }
+        cmd.arg("--cacert").arg(ca);
+    }
+    Ok(cmd)
+}
+
+fn fetch_url(settings: &AppSettings, url: &str) -> Result<String, String> {
+    let out = run_tool(curl_command(settings)?.arg("--max-time").arg("30").arg(url), PROBE_TIMEOUT_SECS)?;
+    if !out.status.success() {
+        return Err(format!("Couldn't fetch {url}: {}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+fn rife_release_entry(settings: &AppSettings) -> Result<CatalogEntry, String> {
+    let release: serde_json::Value =
+        serde_json::from_str(&fetch_url(settings, RIFE_RELEASES_API)?).map_err(|e| format!("Unreadable release info: {e}"))?;
+    let version = release["tag_name"].as_str().ok_or("Release info has no tag")?.to_string();
+    let suffix = match platform_key() {
+        "windows" => "-windows.zip",
+        "macos" => "-macos.zip",
+        _ => "-ubuntu.zip",
+    };
+    let asset = release["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find(|a| a["name"].as_str().is_some_and(|n| n.ends_with(suffix)));
+    let for_platform = |v: String| -> HashMap<String, String> { [(platform_key().to_string(), v)].into_iter().collect() };
+    let assets = asset
+        .and_then(|a| a["browser_download_url"].as_str())
+        .map(|url| for_platform(url.to_string()))
+        .unwrap_or_default();
+    // GitHub lists each asset's digest as "sha256:<hex>".
+    let sha256 = asset
+        .and_then(|a| a["digest"].as_str()?.strip_prefix("sha256:"))
+        .map(|hex| for_platform(hex.to_ascii_lowercase()))
+        .unwrap_or_default();
+    let changelog = release["body"].as_str().unwrap_or_default().to_string();
+    Ok(CatalogEntry { tool: "rife".into(), name: None, version, changelog, assets, sha256 })
+}
+
+/// Whether `latest` is newer than `installed`, comparing the numbers in each ("20221029" >
+/// "20220728", "7.1" > "7.0.2"). Versions without numbers only compare as different.
+fn version_newer(latest: &str, installed: &str) -> bool {
+    let nums = |v: &str| -> Vec<u64> {
+        v.split(|c: char| !c.is_ascii_digit()).filter_map(|p| p.parse().ok()).collect()
+    };
+    let (l, i) = (nums(latest), nums(installed));
+    if l.is_empty() || i.is_empty() {
+        return latest != installed;
+    }
+    l > i
+}
+
+/// Compare installed tools and models against the latest releases. Opt-in: nothing is
+/// fetched unless `check_tool_updates` is on.
+#[tauri::command]
+fn check_for_tool_updates(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<ToolUpdate>, String> {
+    let root = app_root(&app)?;
+    let settings = state.settings(&root);
+    if !settings.check_tool_updates {
+        return Err("Update checks are off; turn them on in settings first".into());
+    }
+    let mut catalog = Vec::new();
+    let mut problems = Vec::new();
+    match rife_release_entry(&settings) {
+        Ok(e) => catalog.push(e),
+        Err(e) => problems.push(e),
+    }
+    if let Some(url) = settings.update_catalog_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        let entries = fetch_url(&settings, url)
+            .and_then(|t| serde_json::from_str::<Vec<CatalogEntry>>(&t).map_err(|e| format!("Unreadable update catalog: {e}")));
+        match entries {
+            Ok(entries) => {
+                catalog.retain(|c| !entries.iter().any(|e| e.tool == c.tool && e.name == c.name));
+                catalog.extend(entries);
+            }
+            Err(e) => problems.push(e),
+        }
+    }
+    if catalog.is_empty() {
+        return Err(problems.join("; "));
+    }
+
+    let tools = state.refresh_tools(&root);
+    let roots = model_roots(&root, &tools);
+    Ok(catalog
+        .into_iter()
+        .map(|e| {
+            let installed = match (e.tool.as_str(), e.name.as_deref()) {
+                ("model", Some(name)) => roots
+                    .iter()
+                    .map(|r| r.join(name))
+                    .find(|d| d.join("flownet.param").is_file())
+                    .map(|d| d.to_string_lossy().to_string()),
+                ("model", None) => None,
+                (tool, _) => active_tool_version(&root, &tools, tool),
+            };
+            let update_available = match (&installed, e.tool.as_str()) {
+                (None, _) => true,
+                (Some(_), "model") => false,
+                (Some(v), _) => version_newer(&e.version, v),
+            };
+            ToolUpdate {
+                download_url: e.assets.get(platform_key()).cloned(),
+                sha256: e.sha256.get(platform_key()).cloned(),
+                tool: e.tool,
+                name: e.name,
+                installed,
+                latest: e.version,
+                changelog: e.changelog,
+                update_available,
+            }
+        })
+        .collect())
+}
+
+/// Unpack `archive` into `dest` with the system tools; false when it isn't an archive.
+fn unpack_archive(archive: &Path, dest: &Path) -> Result<bool, String> {
+    let name = archive.file_name().map(|n| n.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    let is_tar = [".tar", ".tar.gz", ".tgz", ".tar.xz", ".tar.bz2"].iter().any(|e| name.ends_with(e));
+    if !is_tar && !name.ends_with(".zip") {
+        return Ok(false);
+    }
+    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+    // Windows 10+ and macOS ship bsdtar, which reads zip; GNU tar doesn't.
+    let mut cmd = if name.ends_with(".zip") && cfg!(target_os = "linux") {
+        let mut c = tool_command("unzip");
+        c.arg("-q").arg(archive).arg("-d").arg(dest);
+        c
+    } else {
+        let mut c = tool_command("tar");
+        c.arg("-xf").arg(archive).arg("-C").arg(dest);
+        c
+    };
+    let out = run_tool(log_command("unpack", &mut cmd), INSTALL_TIMEOUT_SECS)?;
+    if !out.status.success() {
+        return Err(format!("Unpacking {name} failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(true)
+}
+
+/// A tool, version or model name that is safe to use as a single path component.
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..")
+}
+
+/// `dir`, or the folder it wraps when it only holds one (archives usually have a top folder).
+fn unwrap_single_dir(dir: &Path) -> PathBuf {
+    let entries: Vec<PathBuf> = fs::read_dir(dir).map(|rd| rd.flatten().map(|e| e.path()).collect()).unwrap_or_default();
+    match entries.as_slice() {
+        [only] if only.is_dir() => unwrap_single_dir(only),
+        _ => dir.to_path_buf(),
+    }
+}
+
+fn find_file_named(dir: &Path, names: &[&str]) -> Option<PathBuf> {
+    let entries: Vec<PathBuf> = fs::read_dir(dir).ok()?.flatten().map(|e| e.path()).collect();
+    entries
+        .iter()
+        .find(|p| p.is_file() && p.file_name().and_then(|n| n.to_str()).is_some_and(|n| names.contains(&n)))
+        .cloned()
+        .or_else(|| entries.iter().filter(|p| p.is_dir()).find_map(|p| find_file_named(p, names)))
+}
+
+fn find_model_folder(dir: &Path) -> Option<PathBuf> {
+    find_file_named(dir, &["flownet.param"]).and_then(|p| p.parent().map(Path::to_path_buf))
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ToolUpdateDone {
+    ok: bool,
+    tool: String,
+    version: String,
+    message: String,
+}
+
+/// Download and install an update from `check_for_tool_updates`. Returns right away; progress
+/// arrives as `tool_update_log` lines and the outcome as `tool_update_done`. The unpacked
+/// download stays in `cache/downloads` as the install source `repair_tool` copies from.
+#[tauri::command]
+fn install_tool_update(app: AppHandle, state: State<'_, AppState>, update: ToolUpdate) -> Result<(), String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let mut curl = curl_command(&state.settings(&root))?;
+    let url = update.download_url.clone().ok_or("This update has no download for this platform")?;
+    if !url.starts_with("https://") {
+        return Err("Updates are only downloaded over https".into());
+    }
+    let is_model = update.tool == "model";
+    if !is_model && update.tool != "ffmpeg" && RifeRuntime::of_tool(&update.tool).is_none() {
+        return Err(format!("Unknown tool: {}", update.tool));
+    }
+    let label = if is_model { update.name.clone().unwrap_or_default() } else { update.tool.clone() };
+    if !is_plain_name(&label) || !is_plain_name(&update.latest) {
+        return Err(format!("Invalid update entry: {label} {}", update.latest));
+    }
+    let version = update.latest.clone();
+    let expected_sha256 = update
+        .sha256
+        .clone()
+        .filter(|h| h.len() == 64 && h.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or("This update lists no SHA-256 checksum for its download, so it can't be verified")?;
+    if !is_model {
+        ensure_tool_not_in_use(&app, &update.tool, &root.join("bin").join(&update.tool).join(&version))?;
+    }
+
+    std::thread::spawn(move || {
+        let log = |line: String| {
+            let _ = app.emit("tool_update_log", line);
+        };
+        let result = (|| -> Result<String, String> {
+            let source = root.join("cache").join("downloads").join(format!("{label}-{version}"));
+            let _ = fs::remove_dir_all(&source);
+            fs::create_dir_all(&source).map_err(|e| e.to_string())?;
+            let file_name = url.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("download");
+            let archive = temp_root(&root).join(file_name);
+            log(format!("Downloading {url}"));
+            curl.arg("-o").arg(&archive).arg(&url);
+            let out = run_tool(log_command("download", &mut curl), INSTALL_TIMEOUT_SECS)?;
+            if !out.status.success() {
+                return Err(format!("Download failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+            }
+            log("Verifying checksum…".into());
+            if !sha256_file(&archive)?.eq_ignore_ascii_case(&expected_sha256) {
+                let _ = fs::remove_file(&archive);
+                return Err(format!("Checksum mismatch for {file_name}; the download is damaged or was tampered with"));
+            }
+            log("Unpacking…".into());
+            let unpacked = unpack_archive(&archive, &source)?;
+            if unpacked {
+                let _ = fs::remove_file(&archive);
+            } else {
+                fs::rename(&archive, source.join(file_name)).map_err(|e| e.to_string())?;
+            }
+            let src = unwrap_single_dir(&source);
+
+            if is_model {
+                let folder = find_model_folder(&src).ok_or("The download has no model (flownet.param)")?;
+                let dest = install_model_files(&root, &folder, &label)?;
+                write_model_manifest(&root, &label, &folder);
+                return Ok(dest.to_string_lossy().to_string());
+            }
+            let install_src = if update.tool == "ffmpeg" {
+                find_file_named(&src, &["ffmpeg", "ffmpeg.exe"]).ok_or("The download has no ffmpeg binary")?
+            } else {
+                src
+            };
+            let installed = install_tool_files(&root, &install_src, &update.tool, &version)?;
+            write_install_manifest(&root, &update.tool, &version, &install_src);
+            Ok(installed.to_string_lossy().to_string())
+        })();
+        app.state::<AppState>().refresh_tools(&root);
+        let done = match result {
+            Ok(path) => ToolUpdateDone { ok: true, tool: label.clone(), version: version.clone(), message: format!("Installed to {path}") },
+            Err(message) => ToolUpdateDone { ok: false, tool: label.clone(), version: version.clone(), message },
+        };
+        let _ = app.emit("tool_update_done", done);
+    });
+    Ok(())
+}
+
+// -------------------- Offline bundles --------------------
+//
+// A bundle is a .tar.gz of `bundle.json` plus `tools/<tool>/<version>/…` and `models/<name>/…`,
+// made by `export_bundle` on a connected machine and installed by `import_bundle` on an
+// air-gapped one. The manifest carries a SHA-256 for every file, and a `<bundle>.sha256` file
+// next to the archive (sha256sum format) carries the archive's, checked before unpacking.
+
+const BUNDLE_FORMAT: u32 = 1;
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct BundleManifest {
+    format: u32,
+    created_at: String,
+    tools: Vec<BundleTool>,
+    models: Vec<String>,
+    /// Path relative to the bundle root ('/'-separated) to lowercase hex SHA-256.
+    checksums: std::collections::BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+struct BundleTool {
+    tool: String,
+    version: String,
+}
+
+#[derive(Clone, serde::Serialize)]
+struct BundleDone {
+    ok: bool,
+    path: String,
+    message: String,
+}
+
+fn emit_bundle_done(app: &AppHandle, path: &Path, result: Result<String, String>) {
+    let path = path.to_string_lossy().to_string();
+    let done = match result {
+        Ok(message) => BundleDone { ok: true, path, message },
+        Err(message) => BundleDone { ok: false, path, message },
+    };
+    let _ = app.emit("bundle_done", done);
+}
+
+/// Pack the active ffmpeg and RIFE runtimes and every model in `models/` into a bundle at
+/// `path` (".tar.gz" is appended when missing). Returns the bundle path right away; progress
+/// arrives as `bundle_log` lines and the outcome as `bundle_done`.
+#[tauri::command]
+fn export_bundle(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<String, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let mut dest = PathBuf::from(path.trim());
+    let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).ok_or("Pick a file name for the bundle")?;
+    if !name.ends_with(".tar.gz") && !name.ends_with(".tgz") {
+        dest.set_file_name(format!("{name}.tar.gz"));
+    }
+    let tools = state.refresh_tools(&root);
+
+    let mut manifest = BundleManifest { format: BUNDLE_FORMAT, created_at: chrono::Local::now().to_rfc3339(), ..Default::default() };
+    let mut sources = Vec::new();
+    for tool in std::iter::once("ffmpeg").chain(RifeRuntime::ALL.iter().map(|r| r.tool())) {
+        let Some(version) = active_tool_version(&root, &tools, tool) else { continue };
+        let dir = root.join("bin").join(tool).join(&version);
+        if dir.is_dir() {
+            sources.push((dir, PathBuf::from("tools").join(tool).join(&version)));
+            manifest.tools.push(BundleTool { tool: tool.to_string(), version });
+        }
+    }
+    for e in fs::read_dir(root.join("models")).into_iter().flatten().flatten() {
+        let name = e.file_name().to_string_lossy().to_string();
+        if e.path().join("flownet.param").is_file() {
+            sources.push((e.path(), PathBuf::from("models").join(&name)));
+            manifest.models.push(name);
+        }
+    }
+    if sources.is_empty() {
+        return Err("Nothing to export: no tools or models are installed".into());
+    }
+
+    let dest_str = dest.to_string_lossy().to_string();
+    std::thread::spawn(move || {
+        let work = temp_root(&root).join("bundle").join(make_job_id());
+        let result = (|| -> Result<String, String> {
+            for (src, rel) in &sources {
+                let _ = app.emit("bundle_log", format!("Adding {}", rel.to_string_lossy()));
+                copy_dir_recursive(src, &work.join(rel))?;
+            }
+            let _ = app.emit("bundle_log", "Computing checksums…".to_string());
+            manifest.checksums = checksum_dir(&work)?;
+            let json = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+            fs::write(work.join("bundle.json"), json).map_err(|e| e.to_string())?;
+
+            let _ = app.emit("bundle_log", "Packing…".to_string());
+            let mut tar = tool_command("tar");
+            tar.arg("-czf").arg(&dest).arg("-C").arg(&work).arg(".");
+            let out = run_tool(log_command("bundle", &mut tar), INSTALL_TIMEOUT_SECS)?;
+            if !out.status.success() {
+                return Err(format!("Packing the bundle failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+            }
+            let sidecar = bundle_checksum_path(&dest);
+            let file_name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            fs::write(&sidecar, format!("{}  {file_name}\n", sha256_file(&dest)?)).map_err(|e| fs_error("write", &sidecar, &e))?;
+            Ok(format!(
+                "Exported {} tools and {} models; copy {} along with the bundle",
+                manifest.tools.len(),
+                manifest.models.len(),
+                sidecar.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+            ))
+        })();
+        let _ = fs::remove_dir_all(&work);
+        emit_bundle_done(&app, &dest, result);
+    });
+    Ok(dest_str)
+}
+
+fn bundle_checksum_path(bundle: &Path) -> PathBuf {
+    let name = bundle.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    bundle.with_file_name(format!("{name}.sha256"))
+}
+
+/// Check the bundle archive against the checksum file exported with it.
+fn verify_bundle_archive(archive: &Path) -> Result<(), String> {
+    let sidecar = bundle_checksum_path(archive);
+    let text = fs::read_to_string(&sidecar).map_err(|_| {
+        format!(
+            "The bundle's checksum file {} is missing; copy it along with the bundle",
+            sidecar.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+        )
+    })?;
+    let expected = text.split_whitespace().next().unwrap_or_default();
+    if !sha256_file(archive)?.eq_ignore_ascii_case(expected) {
+        return Err("Checksum mismatch for the bundle archive; it is damaged or was tampered with".into());
+    }
+    Ok(())
+}
+
+/// Check `dir` against its `bundle.json`: known format, safe names, and every file present,
+/// listed and matching its checksum.
+fn verify_bundle(dir: &Path) -> Result<BundleManifest, String> {
+    let text = fs::read_to_string(dir.join("bundle.json")).map_err(|_| "Not a bundle: bundle.json is missing")?;
+    let manifest: BundleManifest = serde_json::from_str(&text).map_err(|e| format!("Unreadable bundle.json: {e}"))?;
+    if manifest.format != BUNDLE_FORMAT {
+        return Err(format!("Unsupported bundle format {}; update the app to import it", manifest.format));
+    }
+    for t in &manifest.tools {
+        if t.tool != "ffmpeg" && RifeRuntime::of_tool(&t.tool).is_none() {
+            return Err(format!("Bundle contains an unknown tool: {}", t.tool));
+        }
+        if !is_plain_name(&t.version) {
+            return Err(format!("Invalid version in bundle: {}", t.version));
+        }
+    }
+    if let Some(bad) = manifest.models.iter().find(|m| !is_plain_name(m)) {
+        return Err(format!("Invalid model name in bundle: {bad}"));
+    }
+
+    let mut files = Vec::new();
+    relative_files(dir, dir, &mut files);
+    files.retain(|f| f != "bundle.json");
+    if let Some(extra) = files.iter().find(|f| !manifest.checksums.contains_key(*f)) {
+        return Err(format!("{extra} is not listed in the bundle manifest"));
+    }
+    for (rel, expected) in &manifest.checksums {
+        if rel.split('/').any(|part| part.is_empty() || part == "..") {
+            return Err(format!("Invalid path in bundle manifest: {rel}"));
+        }
+        let actual = sha256_file(&dir.join(rel)).map_err(|_| format!("{rel} is missing from the bundle"))?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("Checksum mismatch for {rel}; the bundle is damaged"));
+        }
+    }
+    Ok(manifest)
+}
+
+/// Install every tool and model in the bundle at `path`, after verifying its checksums.
+/// Nothing is installed if verification fails. Returns right away; progress arrives as
+/// `bundle_log` lines and the outcome as `bundle_done`. The unpacked bundle is kept in
+/// `cache/bundles` as the install source `repair_tool` copies from.
+#[tauri::command]
+fn import_bundle(app: AppHandle, path: String) -> Result<(), String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let archive = PathBuf::from(path.trim());
+    if !archive.is_file() {
+        return Err("Bundle file does not exist".into());
+    }
+
+    std::thread::spawn(move || {
+        let stem = archive.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let stem = stem.trim_end_matches(".tar.gz").trim_end_matches(".tgz").to_string();
+        let source = root.join("cache").join("bundles").join(if is_plain_name(&stem) { stem } else { make_job_id() });
+        let result = (|| -> Result<String, String> {
+            let _ = fs::remove_dir_all(&source);
+            let _ = app.emit("bundle_log", "Verifying checksums…".to_string());
+            verify_bundle_archive(&archive)?;
+            let _ = app.emit("bundle_log", "Unpacking…".to_string());
+            if !unpack_archive(&archive, &source)? {
+                return Err("Bundles are .tar.gz or .zip archives".into());
+            }
+            let dir = unwrap_single_dir(&source);
+            let manifest = verify_bundle(&dir)?;
+            for t in &manifest.tools {
+                ensure_tool_not_in_use(&app, &t.tool, &root.join("bin").join(&t.tool).join(&t.version))?;
+            }
+
+            for t in &manifest.tools {
+                let _ = app.emit("bundle_log", format!("Installing {} {}", t.tool, t.version));
+                let src = dir.join("tools").join(&t.tool).join(&t.version);
+                install_tool_files(&root, &src, &t.tool, &t.version)?;
+                write_install_manifest(&root, &t.tool, &t.version, &src);
+            }
+            for name in &manifest.models {
+                let _ = app.emit("bundle_log", format!("Installing model {name}"));
+                install_model_files(&root, &dir.join("models").join(name), name)?;
+                write_model_manifest(&root, name, &dir.join("models").join(name));
+            }
+            Ok(format!("Installed {} tools and {} models", manifest.tools.len(), manifest.models.len()))
+        })();
+        if result.is_err() {
+            let _ = fs::remove_dir_all(&source);
+        }
+        app.state::<AppState>().refresh_tools(&root);
+        emit_bundle_done(&app, &archive, result);
+    });
+    Ok(())
+}
+
+// -------------------- Model import --------------------
+
+/// Bundled with the app under `scripts/`; see the script for what it needs.
+const MODEL_CONVERT_SCRIPT: &str = "convert_rife_model.py";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ModelFormat {
+    /// `flownet.param` + `flownet.bin`, ready to use.
+    Ncnn,
+    /// A `.pkl`/`.pth` checkpoint, or a folder holding one (Practical-RIFE's `train_log`).
+    PyTorch,
+    Onnx,
+    Unknown,
+}
+
+fn detect_model_format(path: &Path) -> ModelFormat {
+    let is_checkpoint = |p: &Path| matches!(output_extension(p).as_str(), "pkl" | "pth" | "pt");
+    if path.is_dir() {
+        if path.join("flownet.param").is_file() && path.join("flownet.bin").is_file() {
+            return ModelFormat::Ncnn;
+        }
+        let has_checkpoint = |d: &Path| {
+            fs::read_dir(d).is_ok_and(|rd| rd.flatten().any(|e| e.path().is_file() && is_checkpoint(&e.path())))
+        };
+        if has_checkpoint(path) || has_checkpoint(&path.join("train_log")) {
+            return ModelFormat::PyTorch;
+        }
+        return ModelFormat::Unknown;
+    }
+    if is_checkpoint(path) {
+        return ModelFormat::PyTorch;
+    }
+    match output_extension(path).as_str() {
+        "onnx" => ModelFormat::Onnx,
+        _ => ModelFormat::Unknown,
+    }
+}
+
+/// The model folder a picked path stands for: a picked `flownet.param` means its folder.
+fn model_source(path: &Path) -> PathBuf {
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some("flownet.param" | "flownet.bin") => path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// `models/` folder name for an import: `name`, or the source's name, starting with `rife-`
+/// like the folders RIFE builds ship.
+fn model_folder_name(src: &Path, name: Option<&str>) -> Result<String, String> {
+    let name = match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(n) => n.to_string(),
+        None => {
+            // Practical-RIFE checkpoints live in `<release>/train_log`.
+            let named = if src.file_name().is_some_and(|n| n == "train_log") { src.parent().unwrap_or(src) } else { src };
+            named.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default()
+        }
+    };
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+        return Err(format!("Invalid model name: {name:?}"));
+    }
+    Ok(if name.starts_with("rife-") { name } else { format!("rife-{name}") })
+}
+
+/// The checkpoint converter: the `model_converter` setting, or the bundled script on Python.
+fn model_converter(app: &AppHandle, settings: &AppSettings) -> Result<Command, String> {
+    if let Some(custom) = settings.model_converter.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        return Ok(tool_command(custom));
+    }
+    let script = app
+        .path()
+        .resource_dir()
+        .ok()
+        .map(|d| d.join("scripts").join(MODEL_CONVERT_SCRIPT))
+        .filter(|p| p.is_file())
+        .ok_or("The model conversion script is missing from this install; set a model converter in settings")?;
+    let python = ["python3", "python"]
+        .into_iter()
+        .find(|p| run_tool(tool_command(p).arg("--version"), QUERY_TIMEOUT_SECS).is_ok_and(|o| o.status.success()))
+        .ok_or("Converting PyTorch checkpoints needs Python 3 with torch, pnnx and ncnn (pip install torch pnnx ncnn)")?;
+    let mut cmd = tool_command(python);
+    cmd.arg(script);
+    Ok(cmd)
+}
+
+#[derive(serde::Serialize)]
+struct ModelInspection {
+    format: ModelFormat,
+    /// Folder the model would get in `models/`.
+    name: String,
+    importable: bool,
+    /// What importing does, or why it can't.
+    message: String,
+}
+
+/// What `import_model` would do with `path`, without doing it.
+#[tauri::command]
+fn inspect_model(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<ModelInspection, String> {
+    let root = app_root(&app)?;
+    let src = model_source(Path::new(path.trim()));
+    if !src.exists() {
+        return Err("Model path does not exist".into());
+    }
+    let format = detect_model_format(&src);
+    let name = model_folder_name(&src, None).unwrap_or_default();
+    let (importable, message) = match format {
+        ModelFormat::Ncnn => (true, format!("ncnn model; copied to models/{name}")),
+        ModelFormat::PyTorch => match model_converter(&app, &state.settings(&root)) {
+            Ok(_) => (true, format!(
+                "PyTorch checkpoint; converted to ncnn in models/{name}. Converted models run on the built-in backend"
+            )),
+            Err(e) => (false, e),
+        },
+        ModelFormat::Onnx => (false, "ONNX models have no conversion path here; convert them with pnnx to flownet.param/flownet.bin and import that folder".into()),
+        ModelFormat::Unknown => (false, "Not a RIFE model: expected an ncnn folder (flownet.param/bin) or a .pkl/.pth checkpoint".into()),
+    };
+    Ok(ModelInspection { format, name, importable, message })
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ModelImportDone {
+    ok: bool,
+    path: String,
+    message: String,
+}
+
+/// Import a model into `models/`: ncnn folders are copied, PyTorch checkpoints converted.
+/// Returns the destination right away; progress arrives as `model_import_log` lines and the
+/// outcome as `model_import_done`.
+#[tauri::command]
+fn import_model(app: AppHandle, state: State<'_, AppState>, path: String, name: Option<String>) -> Result<String, String> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let src = model_source(Path::new(path.trim()));
+    if !src.exists() {
+        return Err("Model path does not exist".into());
+    }
+    let format = detect_model_format(&src);
+    let dest = root.join("models").join(model_folder_name(&src, name.as_deref())?);
+    if dest.exists() {
+        return Err(format!("{} already exists; pick another name", dest.to_string_lossy()));
+    }
+    let converter = match format {
+        ModelFormat::Ncnn => None,
+        ModelFormat::PyTorch => Some(model_converter(&app, &state.settings(&root))?),
+        ModelFormat::Onnx | ModelFormat::Unknown => return Err(inspect_model(app, state, path)?.message),
+    };
+
+    let dest_str = dest.to_string_lossy().to_string();
+    std::thread::spawn(move || {
+        // Built under temp/ and renamed into place at the end, so a failed import leaves nothing
+        // in models/. The converter only writes its output after a test inference passed.
+        let work = temp_root(&root).join("model_import").join(make_job_id());
+        let result = match converter {
+            None => copy_dir_recursive(&src, &work),
+            Some(cmd) => convert_checkpoint(&app, cmd, &src, &work),
+        }
+        .and_then(|_| fs::rename(&work, &dest).map_err(|e| format!("Failed to move the model into place: {e}")))
+        .map(|_| write_model_manifest(&root, &dest.file_name().unwrap_or_default().to_string_lossy(), &src));
+        let _ = fs::remove_dir_all(&work);
+        let done = match result {
+            Ok(()) => ModelImportDone { ok: true, path: dest.to_string_lossy().to_string(), message: "Model imported".into() },
+            Err(message) => ModelImportDone { ok: false, path: dest.to_string_lossy().to_string(), message },
+        };
+        let _ = app.emit("model_import_done", done);
+    });
+    Ok(dest_str)
+}
+
+fn convert_checkpoint(app: &AppHandle, mut cmd: Command, src: &Path, out: &Path) -> Result<(), String> {
+    cmd.arg("--input").arg(src)
+        .arg("--output").arg(out)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = cmd.spawn().map_err(|e| format!("Model converter failed to start: {e}"))?;
+    let deadline = ToolDeadline::start(child.id(), INSTALL_TIMEOUT_SECS);
+    let stdout = child.stdout.take().map(|o| {
+        let app = app.clone();
+        std::thread::spawn(move || {
+            for line in BufReader::new(o).lines().map_while(Result::ok) {
+                let _ = app.emit("model_import_log", line);
+            }
+        })
+    });
+    let mut tail = Vec::new();
+    if let Some(err) = child.stderr.take() {
+        for line in BufReader::new(err).lines().map_while(Result::ok) {
+            let line = line.trim().to_string();
+            if !line.is_empty() {
+                push_tail(&mut tail, &line, 32);
+                let _ = app.emit("model_import_log", line);
+            }
+        }
+    }
+    if let Some(h) = stdout {
+        let _ = h.join();
+    }
+    let status = child.wait().ok();
+    if deadline.finish() {
+        return Err(format!("Model conversion did not finish within {INSTALL_TIMEOUT_SECS}s"));
+    }
+    if !status.map(|s| s.success()).unwrap_or(false) {
+        return Err(failure_message("Model conversion failed", status, &tail));
+    }
+    // Every ncnn param file starts with this magic number.
+    let param = fs::read_to_string(out.join("flownet.param")).unwrap_or_default();
+    if !param.starts_with("7767517") || !out.join("flownet.bin").is_file() {
+        return Err("The converter finished but didn't write an ncnn model (flownet.param/flownet.bin)".into());
+    }
+    Ok(())
+}
+
+// -------------------- Validation helpers --------------------
+
+fn find_ffmpeg_in_version_dir(dir: &Path) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir).ok()?;
+    for e in entries.flatten() {
+        let p = e.path();
+        if p.is_file() {
+            return Some(p);
+        }
+    }
+    None
+}
+
+fn find_rife_in_version_dir(dir: &Path) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir).ok()?;
+    for e in entries.flatten() {
+        let p = e.path();
+        if !p.is_file() {
+            continue;
+        }
+        let name = p.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.starts_with("rife") {
+            return Some(p);
+        }
+    }
+    None
+}
+
+/// Canonicalize a path that may not exist yet by resolving its parent directory instead.
+fn canonicalize_lenient(p: &Path) -> PathBuf {
+    if let Ok(c) = fs::canonicalize(p) {
+        return c;
+    }
+    match (p.parent(), p.file_name()) {
+        (Some(parent), Some(name)) => canonicalize_lenient(parent).join(name),
+        _ => p.to_path_buf(),
+    }
+}
+
+/// Preflight checks for a user-chosen output video path.
+///
+/// The pipeline creates and later deletes/iterates folders under `temp/`, so an output placed
+/// there (or inside the frames folder being encoded) can be clobbered or picked up as a frame.
+fn validate_output_path(
+    root: &Path,
+    input: &Path,
+    output: &Path,
+    frames_dirs: &[&Path],
+) -> Result<(), String> {
+    if output.is_dir() {
+        return Err(format!("Output path is a folder, expected a file: {}", output.to_string_lossy()));
+    }
+
+    let out_c = canonicalize_lenient(output);
+
+    let temp_c = canonicalize_lenient(&temp_root(root));
+    if out_c.starts_with(&temp_c) {
+        return Err(format!(
+            "Output path must not be inside the app temp folder ({}); pick a different location",
+            temp_c.to_string_lossy()
+        ));
+    }
+
+    for d in frames_dirs {
+        let d_c = canonicalize_lenient(d);
+        if out_c.starts_with(&d_c) {
+            return Err(format!(
+                "Output path must not be inside the frames folder ({})",
+                d_c.to_string_lossy()
+            ));
+        }
+    }
+
+    if out_c == canonicalize_lenient(input) {
+        return Err("Output path is the same file as the input video".into());
+    }
+
+    let parent = match output.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    if !parent.is_dir() {
+        return Err(format!("Output folder does not exist: {}", parent.to_string_lossy()));
+    }
+
+    probe_write(&parent).map_err(|e| fs_error("write to the output folder", &parent, &e))
+}
+
+// -------------------- Permission errors --------------------
+
+/// System folders only administrators can write, matched as path prefixes.
+const PROTECTED_DIRS_WINDOWS: &[&str] = &[r"C:\Windows", r"C:\Program Files", r"C:\Program Files (x86)", r"C:\ProgramData"];
+const PROTECTED_DIRS_UNIX: &[&str] = &["/System", "/Applications", "/Library", "/usr", "/bin", "/sbin", "/etc", "/opt", "/boot", "/lib", "/var/lib"];
+
+/// Create and remove a tiny file in `dir`: the cheapest reliable writability check.
+fn probe_write(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".rife-write-test-{}", std::process::id()));
+    fs::File::create(&probe)?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// Whether `path` is an OS location ordinary accounts can't write: a system folder, the
+/// filesystem root or the root of the Windows system drive.
+fn is_protected_location(path: &Path) -> bool {
+    let path = canonicalize_lenient(path);
+    let s = path.to_string_lossy().to_string();
+    let s = s.strip_prefix(r"\\?\").unwrap_or(&s);
+    if cfg!(windows) {
+        let lower = s.to_lowercase();
+        let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".into()).to_lowercase();
+        let in_dir = |d: &str| lower == d || lower.starts_with(&format!("{d}\\"));
+        return PROTECTED_DIRS_WINDOWS.iter().any(|d| in_dir(&d.to_lowercase()))
+            || lower.trim_end_matches('\\') == system_drive
+            || Path::new(&lower).parent().is_some_and(|p| p.to_string_lossy().trim_end_matches('\\') == system_drive);
+    }
+    path.parent().is_none()
+        || path.parent() == Some(Path::new("/"))
+        || PROTECTED_DIRS_UNIX.iter().any(|d| path.starts_with(d))
+}
+
+/// First writable folder a job could use instead of one it was refused: the user's Videos
+/// (Movies on macOS) folder, their home folder, then the system temp folder.
+fn writable_alternative() -> Option<PathBuf> {
+    let home = std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(PathBuf::from);
+    let videos = if cfg!(target_os = "macos") { "Movies" } else { "Videos" };
+    home.iter()
+        .flat_map(|h| [h.join(videos), h.clone()])
+        .chain([std::env::temp_dir()])
+        .find(|d| d.is_dir() && probe_write(d).is_ok())
+}
+
+/// Why the OS refused a write, when it was about permissions rather than a missing path or a
+/// full disk.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum WriteDenial {
+    ReadOnlyVolume,
+    InUse,
+    /// macOS privacy protection (Desktop, Documents, removable volumes) without consent.
+    PrivacyBlocked,
+    ProtectedLocation,
+    NoAccess,
+}
+
+impl WriteDenial {
+    fn classify(path: &Path, e: &std::io::Error) -> Option<Self> {
+        let code = e.raw_os_error();
+        // EROFS / ERROR_WRITE_PROTECT
+        if code == Some(if cfg!(windows) { 19 } else { 30 }) {
+            return Some(Self::ReadOnlyVolume);
+        }
+        // ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION
+        if cfg!(windows) && matches!(code, Some(32 | 33)) {
+            return Some(Self::InUse);
+        }
+        // EPERM rather than EACCES is what the privacy layer returns.
+        if cfg!(target_os = "macos") && code == Some(1) && !is_protected_location(path) {
+            return Some(Self::PrivacyBlocked);
+        }
+        if e.kind() != std::io::ErrorKind::PermissionDenied {
+            return None;
+        }
+        Some(if is_protected_location(path) { Self::ProtectedLocation } else { Self::NoAccess })
+    }
+
+    fn guidance(self) -> &'static str {
+        match self {
+            Self::ReadOnlyVolume => "The drive is read-only (a locked SD card, a disk image or a read-only share).",
+            Self::InUse => "The file is open in another program; close the player, editor or sync client using it and retry.",
+            Self::PrivacyBlocked => {
+                "macOS is blocking the app from this folder. Allow it under System Settings > Privacy & Security > \
+                 Files and Folders, or use another folder."
+            }
+            Self::ProtectedLocation => {
+                "This is a system folder only administrators can write. There's no need to run the app as \
+                 administrator; use a folder in your user account instead."
+            }
+            Self::NoAccess => "Your account can't write there; the folder may belong to another user or be locked by security software.",
+        }
+    }
+}
+
+/// A filesystem error, explained and with a writable folder to use instead when the OS refused
+/// it on permission grounds.
+fn fs_error(action: &str, path: &Path, e: &std::io::Error) -> String {
+    let base = format!("Failed to {action} {}: {e}", path.to_string_lossy());
+    let Some(denial) = WriteDenial::classify(path, e) else { return base };
+    let mut msg = format!("{base}\n\n{}", denial.guidance());
+    if denial != WriteDenial::InUse {
+        if let Some(alt) = writable_alternative() {
+            msg.push_str(&format!(" Try {}.", alt.to_string_lossy()));
+        }
+    }
+    msg
+}
+
+// -------------------- Source protection --------------------
+
+/// The volume holding `path`: its drive or share on Windows, its mount point elsewhere.
+fn volume_of(path: &Path, mounts: &[Mount]) -> Option<PathBuf> {
+    let path = canonicalize_lenient(path);
+    if cfg!(windows) {
+        return match path.components().next() {
+            Some(std::path::Component::Prefix(p)) => Some(PathBuf::from(p.as_os_str())),
+            _ => None,
+        };
+    }
+    mount_for(&path, mounts).map(|m| m.point.clone())
+}
+
+/// Preflight for sources on camera cards and other read-only media, run before anything is
+/// written. The output, the temp folder and `work_dirs` must not be on the source's read-only
+/// volume; with `protect_sources` they also stay out of the source's folder, and off its
+/// volume when that is removable media (any volume other than the app's own).
+fn check_source_protection(
+    root: &Path,
+    settings: &AppSettings,
+    input: &Path,
+    output: &Path,
+    work_dirs: &[&Path],
+) -> Result<(), String> {
+    let mounts = list_mounts();
+    let input_c = canonicalize_lenient(input);
+    let source_dir = input_c.parent().map(Path::to_path_buf);
+    let source_volume = volume_of(&input_c, &mounts);
+    let read_only = match mount_for(&input_c, &mounts) {
+        Some(m) => m.read_only,
+        // No mount table to ask (Windows): a write-protected volume refuses a test file.
+        None => source_dir.as_deref().is_some_and(|d| {
+            probe_write(d).is_err_and(|e| WriteDenial::classify(d, &e) == Some(WriteDenial::ReadOnlyVolume))
+        }),
+    };
+    let external = source_volume.is_some() && source_volume != volume_of(root, &mounts);
+    let volume_str = source_volume.as_deref().map(|v| v.to_string_lossy().to_string()).unwrap_or_default();
+
+    let temp = temp_root(root);
+    let places = std::iter::once(("Output", output))
+        .chain(std::iter::once(("Temp folder", temp.as_path())))
+        .chain(work_dirs.iter().map(|d| ("Frames folder", *d)));
+    for (what, place) in places {
+        let place_c = canonicalize_lenient(place);
+        let place_str = place_c.to_string_lossy();
+        let same_volume = source_volume.is_some() && volume_of(&place_c, &mounts) == source_volume;
+        if same_volume && read_only {
+            return Err(format!(
+                "{what} ({place_str}) is on the source's read-only volume ({volume_str}). \
+                 Pick an output folder on a writable drive, or copy the video off the card first"
+            ));
+        }
+        if !settings.protect_sources {
+            continue;
+        }
+        if source_dir.as_ref().is_some_and(|d| place_c.starts_with(d)) {
+            return Err(format!(
+                "{what} ({place_str}) is in the source's folder, which source protection keeps \
+                 untouched. Set an output folder in Settings, or turn source protection off"
+            ));
+        }
+        if same_volume && external {
+            return Err(format!(
+                "{what} ({place_str}) is on the source's volume ({volume_str}), which source \
+                 protection keeps untouched. Pick a folder on another drive, or turn source protection off"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// What to do when the output file already exists.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OnConflict {
+    #[default]
+    Overwrite,
+    Rename,
+    Fail,
+}
+
+/// Apply the conflict policy to `output`, returning the path the encode should write to.
+/// Rename picks the first free `name (2).ext`, `name (3).ext`, … in the same folder.
+fn resolve_output_conflict(output: &Path, policy: OnConflict) -> Result<PathBuf, CommandError> {
+    if !output.exists() {
+        return Ok(output.to_path_buf());
+    }
+    match policy {
+        OnConflict::Overwrite => Ok(output.to_path_buf()),
+        OnConflict::Fail => Err(CommandError::new("output_exists", format!("Output file already exists: {}", output.to_string_lossy()))),
+        OnConflict::Rename => {
+            let parent = output.parent().unwrap_or(Path::new(""));
+            let stem = output.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            let ext = output.extension().map(|s| s.to_string_lossy().to_string());
+            for n in 2..10_000 {
+                let name = match &ext {
+                    Some(e) => format!("{stem} ({n}).{e}"),
+                    None => format!("{stem} ({n})"),
+                };
+                let candidate = parent.join(name);
+                if !candidate.exists() {
+                    return Ok(candidate);
+                }
+            }
+            Err(format!("Could not find a free output name next to {}", output.to_string_lossy()).into())
+        }
+    }
+}
+
+/// Right before the final encode, move an existing output to the OS trash (Overwrite policy only),
+/// so a replaced render can still be recovered. Falls back to a plain overwrite if trashing fails.
+fn trash_existing_output(app: &AppHandle, settings: &AppSettings, output: &Path, policy: OnConflict) {
+    if policy != OnConflict::Overwrite || !settings.trash_replaced_outputs || !output.is_file() {
+        return;
+    }
+    match trash::delete(output) {
+        Ok(()) => emit_log_limited(app, &format!("Moved existing output to trash: {}", output.to_string_lossy())),
+        Err(e) => emit_log_limited(app, &format!("Could not move existing output to trash ({e}); it will be overwritten")),
+    }
+}
+
+/// ffmpeg flag matching the conflict policy: only Overwrite may clobber at encode time.
+fn ffmpeg_overwrite_flag(policy: OnConflict) -> &'static str {
+    if policy == OnConflict::Overwrite { "-y" } else { "-n" }
+}
+
+/// A default output path, and whether it can be used as is.
+#[derive(Clone, Debug, serde::Serialize)]
+struct SuggestedOutput {
+    path: String,
+    /// Set when the path can't be written (missing or read-only folder, …).
+    problem: Option<String>,
+    /// The same file name in a folder that can be written, when the OS refused the folder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    alternative: Option<String>,
+}
+
+/// Expand the output name template; characters that aren't allowed in file names become `_`.
+fn render_output_name(template: &str, vars: &[(&str, String)]) -> String {
+    let mut name = template.to_string();
+    for (key, value) in vars {
+        name = name.replace(key, value);
+    }
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() || matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') { '_' } else { c })
+        .collect();
+    name.trim().trim_end_matches('.').to_string()
+}
+
+/// Default output path for `input`: the naming template applied in the output folder (or next
+/// to the input), moved to a free `name (2).ext` if taken, then checked like a picked path.
+#[tauri::command]
+fn suggest_output_path(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    input: String,
+    preset: Option<String>,
+    model: Option<String>,
+) -> Result<SuggestedOutput, String> {
+    let root = app_root(&app)?;
+    let settings = state.settings(&root);
+    let input = PathBuf::from(input.trim());
+    if !input.is_file() {
+        return Err("Input video does not exist".into());
+    }
+    let preset_name = preset.as_deref().map(str::trim).filter(|p| !p.is_empty());
+    let preset_def = resolve_preset(&root, preset_name)?;
+    let model = model.or(preset_def.model).unwrap_or_else(|| "rife".into());
+    let fps_in = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .and_then(|ffmpeg| probe_duration_and_fps(&ffmpeg, &input))
+        .map(|(_, fps)| fps)
+        .unwrap_or(30.0);
+    let fps_out = fps_in * 2.0;
+    let fps = if (fps_out - fps_out.round()).abs() < 0.01 { format!("{}", fps_out.round()) } else { format!("{fps_out:.2}") };
+
+    let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let template = match settings.output_name_template.trim() {
+        "" => "{name}_{fps}fps",
+        t => t,
+    };
+    let mut name = render_output_name(template, &[
+        ("{name}", stem.clone()),
+        ("{fps}", fps),
+        ("{model}", model.trim_start_matches("rife-").to_string()),
+        ("{preset}", preset_name.unwrap_or("default").to_string()),
+        ("{date}", chrono::Local::now().format("%Y%m%d").to_string()),
+    ]);
+    if name.is_empty() {
+        name = format!("{stem}_smooth");
+    }
+    // Keep containers the encoder writes; anything else becomes MP4.
+    let ext = match output_extension(&input).as_str() {
+        e @ ("mp4" | "mov" | "mkv" | "webm") => e.to_string(),
+        _ => "mp4".into(),
+    };
+    let library = settings.library_mode.then(|| library_root(&root, &settings));
+    let dir = match (&library, settings.output_dir.as_deref().map(str::trim).filter(|d| !d.is_empty())) {
+        (Some(library), _) => library_item_dir(library, &name),
+        (None, Some(d)) => PathBuf::from(d),
+        (None, None) => input.parent().map(Path::to_path_buf).unwrap_or_default(),
+    };
+    let mut output = dir.join(format!("{name}.{ext}"));
+    // A template without `{name}` could land on the input itself.
+    if canonicalize_lenient(&output) == canonicalize_lenient(&input) {
+        output = dir.join(format!("{name}_smooth.{ext}"));
+    }
+    let output = resolve_output_conflict(&output, OnConflict::Rename)?;
+    // The item folder is only created when the job starts; check the library itself.
+    let problem = check_source_protection(&root, &settings, &input, &output, &[])
+        .and_then(|_| match &library {
+            Some(library) => fs::create_dir_all(library)
+                .and_then(|_| probe_write(library))
+                .map_err(|e| fs_error("write to the library", library, &e)),
+            None => validate_output_path(&root, &input, &output, &[]),
+        })
+        .err();
+    let denied = problem.is_some()
+        && probe_write(&dir).is_err_and(|e| WriteDenial::classify(&dir, &e).is_some_and(|d| d != WriteDenial::InUse));
+    let alternative = denied
+        .then(writable_alternative)
+        .flatten()
+        .and_then(|alt| resolve_output_conflict(&alt.join(format!("{name}.{ext}")), OnConflict::Rename).ok())
+        .map(|p| p.to_string_lossy().to_string());
+    Ok(SuggestedOutput { path: output.to_string_lossy().to_string(), problem, alternative })
+}
+
+fn resolve_rife_model_path(models_root_or_model: &str) -> PathBuf {
+    let p = PathBuf::from(models_root_or_model);
+    if p.join("flownet.bin").exists() || p.join("model.param").exists() {
+        return p;
+    }
+    // Prefer rife-v2.3 if present
+    let preferred = p.join("rife-v2.3");
+    if preferred.exists() {
+        return preferred;
+    }
+    // Otherwise pick the first subdirectory
+    if let Ok(rd) = std::fs::read_dir(&p) {
+        for e in rd.flatten() {
+            let path = e.path();
+            if path.is_dir() {
+                return path;
+            }
+        }
+    }
+    p
+}
+
+/// rife-ncnn-vulkan mishandles absolute model paths on Windows: drive-letter paths are taken
+/// as relative, and long (`\\?\`) or non-ASCII ones fail with `_wfopen .../flownet.param
+/// failed`. So `-m` is always relative: RIFE runs in the folder holding the model (the shared
+/// `models/` root, or the binary's folder in older layouts) and gets the model folder's name.
+fn compute_rife_cwd_and_model_arg(model_path: &Path) -> (Option<PathBuf>, std::ffi::OsString) {
+    match (model_path.parent().filter(|p| !p.as_os_str().is_empty()), model_path.file_name()) {
+        (Some(dir), Some(name)) => {
+            // Nor does a verbatim prefix belong in the working directory.
+            let dir_str = dir.to_string_lossy();
+            let dir = match dir_str.strip_prefix(r"\\?\") {
+                Some(plain) if !plain.starts_with("UNC\\") => PathBuf::from(plain),
+                _ => dir.to_path_buf(),
+            };
+            (Some(dir), name.to_os_string())
+        }
+        _ => (None, model_path.as_os_str().to_os_string()),
+    }
+}
+
+fn find_models_dir(dir: &Path) -> Option<PathBuf> {
+    // Accept common RIFE layouts:
+    // - folders like 'rife-v2.3', 'rife-v4', 'rife-anime', 'rife-UHD', etc.
+    // - models/ (some older builds)
+
+    let direct = dir.join("models");
+    if direct.is_dir() {
+        return Some(direct);
+    }
+
+    // Prefer the default model folder if present
+    let preferred = dir.join("rife-v2.3");
+    if preferred.is_dir() {
+        return Some(preferred);
+    }
+
+    // Otherwise pick the first folder starting with "rife-"
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = fs::read_dir(dir) {
+        for e in entries.flatten() {
+            let p = e.path();
+            if p.is_dir() {
+                if let Some(name) = p.file_name().and_then(|n| n.to_str()) {
+                    if name.starts_with("rife-") {
+                        candidates.push(p);
+                    }
+                }
+            }
+        }
+    }
+    candidates.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    candidates.into_iter().next()
+}
+
+fn find_installed_tool_paths(root: &Path) -> (Option<PathBuf>, Option<PathBuf>, Option<PathBuf>) {
+    let ffmpeg_root = root.join("bin/ffmpeg");
+    let rife_root = root.join("bin/rife");
+
+    let mut ffmpeg_path: Option<PathBuf> = None;
+    let mut rife_path: Option<PathBuf> = None;
+    let mut rife_models: Option<PathBuf> = None;
+
+    if let Ok(versions) = fs::read_dir(&ffmpeg_root) {
+        for v in versions.flatten() {
+            let p = v.path();
+            if p.is_dir() {
+                if let Some(bin) = find_ffmpeg_in_version_dir(&p) {
+                    ffmpeg_path = Some(bin);
+                    break;
+                }
+            }
+        }
+    }
+
+    if let Ok(versions) = fs::read_dir(&rife_root) {
+        for v in versions.flatten() {
+            let p = v.path();
+            if p.is_dir() {
+                if let Some(bin) = find_rife_in_version_dir(&p) {
+                    rife_path = Some(bin);
+                    rife_models = find_models_dir(&p).or_else(|| find_models_dir(&root.join("models")));
+                    break;
+                }
+            }
+        }
+    }
+
+    (ffmpeg_path, rife_path, rife_models)
+}
+
+#[derive(Clone, serde::Serialize)]
+struct ToolValidation {
+    ok: bool,
+    path: Option<String>,
+    output: String,
+}
+
+#[derive(serde::Serialize)]
+struct ValidateToolsResult {
+    ffmpeg: ToolValidation,
+    rife: ToolValidation,
+    /// None when VapourSynth isn't installed.
+    vspipe: Option<ToolValidation>,
+    /// None when av1an isn't installed.
+    av1an: Option<ToolValidation>,
+}
+
+#[derive(serde::Serialize)]
+struct ExtractFramesResult {
+    ok: bool,
+    job_id: String,
+    frames_dir: String,
+    frame_pattern: String,
+    output: String,
+    /// Why the source or duplicate policy skipped the file; no job was started.
+    skipped: Option<String>,
+    /// Earlier job with the same input and settings, when there was one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duplicate_of: Option<String>,
+}
+
+fn make_job_id() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("job-{}-{}", now.as_secs(), now.subsec_nanos())
+}
+
+/// Files in `dir`, including those in frame shard folders (see `shard_name`).
+fn count_files_in_dir(dir: &Path) -> usize {
+    match fs::read_dir(dir) {
+        Ok(rd) => rd
+            .flatten()
+            .map(|e| {
+                let path = e.path();
+                if path.is_file() {
+                    1
+                } else if is_shard_name(&e.file_name().to_string_lossy()) && path.is_dir() {
+                    count_files_in_dir(&path)
+                } else {
+                    0
+                }
+            })
+            .sum(),
+        Err(_) => 0,
+    }
+}
+
+/// Numbering of an image sequence: `<prefix><number>.<ext>`, numbers zero-padded to `padding`
+/// digits (0 = unpadded) and counting up from `start`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct FrameNaming {
+    prefix: String,
+    padding: u32,
+    start: u32,
+    ext: String,
+}
+
+impl Default for FrameNaming {
+    fn default() -> Self {
+        FrameNaming { prefix: String::new(), padding: 8, start: 1, ext: "png".into() }
+    }
+}
+
+impl FrameNaming {
+    /// rife-ncnn-vulkan's `-f` pattern; its output numbering always starts at 1.
+    fn pattern(&self) -> String {
+        let prefix = self.prefix.replace('%', "%%");
+        match self.padding {
+            0 => format!("{prefix}%d.{}", self.ext),
+            n => format!("{prefix}%0{n}d.{}", self.ext),
+        }
+    }
+
+    fn file_name(&self, n: u32) -> String {
+        format!("{}{n:0width$}.{}", self.prefix, self.ext, width = self.padding as usize)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if !matches!(self.ext.as_str(), "png" | "jpg" | "webp") {
+            return Err(format!("RIFE writes png, jpg or webp frames, not .{}", self.ext));
+        }
+        if self.padding > 12 {
+            return Err("Frame numbers can be padded to at most 12 digits".into());
+        }
+        if self.prefix.contains(['/', '\\']) {
+            return Err("The frame name prefix can't contain a path separator".into());
+        }
+        Ok(())
+    }
+}
+
+/// Numbering of the image sequence in `dir`, from the files whose names end in a number.
+/// Padding is only reported when every number has the same width.
+fn detect_frame_naming(dir: &Path) -> Option<FrameNaming> {
+    let mut found: Option<FrameNaming> = None;
+    let mut widths = std::collections::BTreeSet::new();
+    for path in sorted_files(dir) {
+        let (Some(stem), ext) = (path.file_stem().and_then(|s| s.to_str()), output_extension(&path)) else { continue };
+        let digits = stem.len() - stem.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+        let Ok(n) = stem[stem.len() - digits..].parse::<u32>() else { continue };
+        let prefix = &stem[..stem.len() - digits];
+        match &mut found {
+            Some(f) if f.prefix == prefix && f.ext == ext => f.start = f.start.min(n),
+            Some(_) => continue,
+            None => found = Some(FrameNaming { prefix: prefix.to_string(), padding: 0, start: n, ext }),
+        }
+        widths.insert(digits as u32);
+    }
+    let mut naming = found?;
+    if let (1, Some(w)) = (widths.len(), widths.first()) {
+        naming.padding = *w;
+    }
+    Some(naming)
+}
+
+/// Numbering detected in a frames folder, for prefilling `run_rife_pipeline`'s output naming.
+#[tauri::command]
+fn get_frame_naming(frames_dir: String) -> Result<Option<FrameNaming>, String> {
+    let dir = PathBuf::from(frames_dir.trim());
+    if !dir.is_dir() {
+        return Err(format!("Frames dir does not exist: {}", dir.to_string_lossy()));
+    }
+    Ok(detect_frame_naming(&dir))
+}
+
+/// Shift RIFE's output (numbered from 1) to start at `naming.start`.
+fn renumber_frames(dir: &Path, naming: &FrameNaming) -> Result<(), String> {
+    let count = (1..).take_while(|i| dir.join(naming.file_name(*i)).is_file()).count() as u32;
+    let rename = |i: u32| {
+        let from = dir.join(naming.file_name(i));
+        let to = dir.join(naming.file_name(i - 1 + naming.start));
+        fs::rename(&from, &to).map_err(|e| format!("Failed to renumber {}: {e}", from.to_string_lossy()))
+    };
+    // Renaming towards the free end never overwrites a frame that hasn't moved yet.
+    if naming.start < 1 {
+        (1..=count).try_for_each(rename)
+    } else {
+        (1..=count).rev().try_for_each(rename)
+    }
+}
+
+#[tauri::command]
+fn run_rife_pipeline(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    input_frames: String,
+    output_frames: String,
+    model_dir: String,
+    threads: RifeThreads,
+    output_naming: Option<FrameNaming>,
+) -> Result<ExtractFramesResult, String> {
+    threads.validate()?;
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    let rife_path = state.tools(&root).rife;
+    let rife_bin = rife_path.ok_or("rife not installed (install rife first)")?;
+
+    let model_path = resolve_rife_model_path(model_dir.trim());
+    if !model_path.exists() {
+        return Err(format!("Model path does not exist: {}", model_path.to_string_lossy()));
+    }
+
+    let in_dir = PathBuf::from(input_frames.trim());
+    if !in_dir.exists() {
+        return Err(format!("Input frames dir does not exist: {}", in_dir.to_string_lossy()));
+    }
+    // Unless told otherwise, the output is numbered like the input (in a format RIFE writes).
+    let input_naming = detect_frame_naming(&in_dir);
+    let naming = output_naming.or_else(|| input_naming.clone()).map_or_else(FrameNaming::default, |mut n| {
+        if !matches!(n.ext.as_str(), "png" | "jpg" | "webp") {
+            n.ext = "png".into();
+        }
+        n
+    });
+    naming.validate()?;
+
+    let out_dir = PathBuf::from(output_frames.trim());
+    std::fs::create_dir_all(&out_dir)
+        .map_err(|e| format!("Failed to create output frames dir: {e}"))?;
+
+    let job_id = make_job_id();
+    let settings = state.settings(&root);
+    state.job_started(&job_id, "run_rife_pipeline", &in_dir, &out_dir);
+    state.set_job_stage(&job_id, JobStage::Interpolate);
+    let running = RunningJobGuard { app: app.clone(), job_id: job_id.clone() };
+    let lease = lease_tools(&app, &[&rife_bin]);
+    let out_pattern = out_dir.join(naming.pattern()).to_string_lossy().to_string();
+    let result = ExtractFramesResult {
+        ok: true,
+        job_id: job_id.clone(),
+        frames_dir: out_dir.to_string_lossy().to_string(),
+        frame_pattern: out_pattern.clone(),
+        output: out_dir.to_string_lossy().to_string(),
+        skipped: None,
+        duplicate_of: None,
+    };
+
+    let app_for_task = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _running = running;
+        let _lease = lease;
+        let _scope = JobScope::enter(Some(job_id.clone()));
+        let _slot = acquire_job_slot(&app_for_task, &settings);
+        let app = &app_for_task;
+        emit_stage(app, StageId::Interpolate, message("stage.interpolate", &[]));
+        emit_pipeline(app, "pipeline_progress", 0.0_f64);
+        emit_pipeline(app, "pipeline_log", "Starting RIFE (GPU/Vulkan)…");
+        emit_pipeline(app, "pipeline_log", format!("RIFE: {}", rife_bin.to_string_lossy()));
+        emit_pipeline(app, "pipeline_log", format!("Model: {}", model_path.to_string_lossy()));
+        emit_pipeline(app, "pipeline_log", format!("Threads (-j): {}", threads));
+        if let Some(n) = &input_naming {
+            emit_pipeline(app, "pipeline_log", format!("Input frames: {} from {}", n.pattern(), n.start));
+        }
+        emit_pipeline(app, "pipeline_log", format!("Output frames: {} from {}", naming.pattern(), naming.start));
+
+        let (cwd, model_arg) = compute_rife_cwd_and_model_arg(&model_path);
+        if let Some(ref d) = cwd {
+            emit_pipeline(app, "pipeline_log", format!("Working dir: {}", d.to_string_lossy()));
+        }
+        emit_pipeline(app, "pipeline_log", format!("Model arg (-m): {}", model_arg.to_string_lossy()));
+
+        let mut cmd = tool_command(&rife_bin);
+        if let Some(d) = cwd {
+            cmd.current_dir(d);
+        }
+        cmd.arg("-v")
+            .arg("-i").arg(&in_dir)
+            .arg("-o").arg(&out_dir)
+            .arg("-m").arg(model_arg)
+            .arg("-f").arg(naming.pattern())
+            .arg("-j").arg(threads.to_string())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(gpu) = rife_gpu(&settings) {
+            cmd.arg("-g").arg(gpu.to_string());
+        }
+        log_stage_command(app, "interpolate", &mut cmd);
+
+        // 2x mode writes two frames per input frame.
+        let expected = count_files_in_dir(&in_dir) as u64 * 2;
+        let result = run_rife_frames(app, &job_id, cmd, expected).and_then(|written| {
+            if naming.start != 1 {
+                renumber_frames(&out_dir, &naming)?;
+            }
+            Ok(format!("RIFE wrote {written} frames to {}", out_dir.to_string_lossy()))
+        });
+        let done = PipelineDoneEvent {
+            ok: result.is_ok(),
+            error: result.as_ref().err().and_then(|e| classify_process_error(e)),
+            message: result.unwrap_or_else(|e| e),
+            frames_dir: out_dir.to_string_lossy().to_string(),
+            frame_pattern: out_pattern,
+            verification: None,
+            output_tail: app.state::<AppState>().take_failure_tail(&job_id),
+        };
+        emit_pipeline(app, "pipeline_done", done);
+    });
+
+    Ok(result)
+}
+
+/// `pipeline_progress` percentage for `done` of `expected` frames; 100 is left for the end of
+/// the run.
+fn frames_percent(done: u64, expected: u64) -> f64 {
+    (done as f64 / expected.max(1) as f64 * 100.0).min(99.0)
+}
+
+/// Run a standalone RIFE command to completion under `job_id`, so `cancel_stage` can stop it.
+/// Progress comes from the `... done` line `-v` prints per written frame, against `expected`
+/// frames. Returns the number of frames written.
+fn run_rife_frames(app: &AppHandle, job_id: &str, mut cmd: Command, expected: u64) -> Result<u64, String> {
+    let mut rife = spawn_stage_process(app, job_id, "RIFE", &mut cmd)?;
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    // rife-ncnn-vulkan logs to stderr, but some builds use stdout; both are read the same way.
+    for pipe in [Pipe::Stdout, Pipe::Stderr] {
+        let (app, done) = (app.clone(), done.clone());
+        rife.relay(pipe, move |line| {
+            if line.ends_with(" done") {
+                let n = done.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+                emit_progress_frames(&app, frames_percent(n, expected), n);
+                return false;
+            }
+            emit_pipeline(&app, "pipeline_log", line.to_string());
+            true
+        });
+    }
+    let status = rife.wait(app)?;
+    check_stage_cancelled(app, job_id, JobStage::Interpolate)?;
+    rife.check(app, "RIFE failed", status)?;
+    Ok(done.load(std::sync::atomic::Ordering::Relaxed))
+}
+
+
+
+#[tauri::command]
+fn extract_frames(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    video_path: String,
+) -> Result<ExtractFramesResult, String> {
+    // IMPORTANT: non-blocking. We return immediately and run ffmpeg in a background thread.
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    let ffmpeg_path = state.tools(&root).ffmpeg;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(ffmpeg_path)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+
+    let input = PathBuf::from(video_path.trim());
+    if !input.exists() {
+        return Err("Input video does not exist".into());
+    }
+
+    let job_id = make_job_id();
+    let frames_dir = temp_root(&root).join("frames_in").join(&job_id);
+    fs::create_dir_all(&frames_dir).map_err(|e| e.to_string())?;
+
+    let ext = "jpg";
+    let pattern = frames_dir.join(format!("%08d.{ext}"));
+
+    // Tiny, safe UI notes (no streaming logs).
+    emit_log_limited(&app, &format!("Frames folder: {}", frames_dir.to_string_lossy()));
+    emit_log_limited(&app, &format!("Extract format: {}", ext));
+
+    // Spawn background worker so the UI stays responsive.
+    let app_clone = app.clone();
+    let ffmpeg_clone = ffmpeg.clone();
+    let input_clone = input.clone();
+    let frames_dir_clone = frames_dir.clone();
+    let pattern_clone = pattern.clone();
+    let settings = state.settings(&root);
+    state.job_started(&job_id, "extract_frames", &input, &frames_dir);
+    let running = RunningJobGuard { app: app.clone(), job_id: job_id.clone() };
+    let lease = lease_tools(&app, &[&ffmpeg]);
+    let job_id_for_task = job_id.clone();
+
+    std::thread::spawn(move || {
+        let _running = running;
+        let _lease = lease;
+        let _scope = JobScope::enter(Some(job_id_for_task.clone()));
+        let _slot = acquire_job_slot(&app_clone, &settings);
+        let run = |hwaccel: bool| {
+            extract_frames_worker(&app_clone, &job_id_for_task, &ffmpeg_clone, &input_clone, &frames_dir_clone, &pattern_clone, hwaccel)
+        };
+        let result = match run(true) {
+            Err(err) if resource_failure(&err).is_some_and(|e| e.code == "hwaccel_failed") => {
+                emit_log_limited(&app_clone, &format!(
+                    "Auto-retry 1/{MAX_AUTO_RETRIES}: hardware decoding failed; retrying with software decode"
+                ));
+                run(false)
+            }
+            other => other,
+        };
+        let done = match result {
+            Ok(msg) => PipelineDoneEvent {
+                ok: true,
+                message: msg,
+                frames_dir: frames_dir_clone.to_string_lossy().to_string(),
+                frame_pattern: pattern_clone.to_string_lossy().to_string(),
+                verification: None,
+                output_tail: None,
+                error: None,
+            },
+            Err(err) => PipelineDoneEvent {
+                ok: false,
+                error: classify_process_error(&err),
+                message: err,
+                frames_dir: frames_dir_clone.to_string_lossy().to_string(),
+                frame_pattern: pattern_clone.to_string_lossy().to_string(),
+                verification: None,
+                output_tail: app_clone.state::<AppState>().take_failure_tail(&job_id_for_task),
+            },
+        };
+
+        emit_pipeline(&app_clone, "pipeline_done", done);
+    });
+
+    // Return immediately.
+    Ok(ExtractFramesResult {
+        ok: true, // accepted / started
+        job_id,
+        frames_dir: frames_dir.to_string_lossy().to_string(),
+        frame_pattern: pattern.to_string_lossy().to_string(),
+        output: "Started frame extraction in background".to_string(),
+        skipped: None,
+        duplicate_of: None,
+    })
+}
+
+
+
+
+
+
+#[derive(Clone, serde::Serialize)]
+struct PipelineDoneEvent {
+    ok: bool,
+    message: String,
+    frames_dir: String,
+    frame_pattern: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification: Option<OutputVerification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ActionableError>,
+    /// Output of the process that failed the job.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output_tail: Option<ProcessTail>,
+}
+
+// -------------------- Stage processes --------------------
+
+/// Default for `AppSettings.output_tail_lines`.
+const DEFAULT_OUTPUT_TAIL_LINES: usize = 64;
+
+/// The last lines a failed stage process printed.
+#[derive(Clone, Debug, serde::Serialize)]
+struct ProcessTail {
+    /// What ran, e.g. "FFmpeg" or "Plugin denoise".
+    process: String,
+    lines: Vec<String>,
+}
+
+/// Rolling tail of a process's output, shared with the threads reading it.
+#[derive(Clone)]
+struct OutputTail {
+    lines: std::sync::Arc<Mutex<std::collections::VecDeque<String>>>,
+    max: usize,
+}
+
+impl OutputTail {
+    /// Sized from `AppSettings.output_tail_lines`.
+    fn new(app: &AppHandle) -> Self {
+        let max = app_root(app)
+            .map(|root| app.state::<AppState>().settings(&root).output_tail_lines)
+            .unwrap_or(DEFAULT_OUTPUT_TAIL_LINES);
+        OutputTail { lines: Default::default(), max: max.clamp(8, 1000) }
+    }
+
+    fn push(&self, line: &str) {
+        let mut lines = lock(&self.lines);
+        if lines.len() == self.max {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+
+    fn lines(&self) -> Vec<String> {
+        lock(&self.lines).iter().cloned().collect()
+    }
+}
+
+/// `failure_message` for a stage process, keeping its output for the job's `pipeline_done`
+/// event (see `AppState::take_failure_tail`).
+fn stage_failure(app: &AppHandle, job_id: &str, process: &str, what: &str, status: Option<std::process::ExitStatus>, tail: &[String]) -> String {
+    app.state::<AppState>().set_failure_tail(job_id, ProcessTail { process: process.to_string(), lines: tail.to_vec() });
+    failure_message(what, status, tail)
+}
+
+#[derive(Clone, Copy)]
+enum Pipe {
+    Stdout,
+    Stderr,
+}
+
+/// An external process run by a job stage. It is the job's child (so `cancel_stage` and the
+/// kill watchdog reach it), and what `relay` reads from it is kept in a rolling tail.
+struct StageProcess {
+    child: std::process::Child,
+    job_id: String,
+    what: String,
+    tail: OutputTail,
+    readers: Vec<std::thread::JoinHandle<()>>,
+}
+
+/// Start `cmd` for the job's current stage; `what` names it in errors ("RIFE", "FFmpeg").
+fn spawn_stage_process(app: &AppHandle, job_id: &str, what: &str, cmd: &mut Command) -> Result<StageProcess, String> {
+    new_process_group(cmd);
+    let child = cmd.spawn().map_err(|e| format!("{what} failed to start: {e}"))?;
+    app.state::<AppState>().set_job_child(job_id, child.id());
+    Ok(StageProcess {
+        child,
+        job_id: job_id.to_string(),
+        what: what.to_string(),
+        tail: OutputTail::new(app),
+        readers: Vec::new(),
+    })
+}
+
+impl StageProcess {
+    /// Read one of the process's pipes on a job thread, if it was piped. Each non-empty line
+    /// goes to `on_line`, which returns whether to keep it in the tail; progress lines usually
+    /// aren't worth keeping. Lines may end in \r as well as \n.
+    fn relay<F>(&mut self, pipe: Pipe, on_line: F)
+    where
+        F: Fn(&str) -> bool + Send + 'static,
+    {
+        let out: Option<Box<dyn Read + Send>> = match pipe {
+            Pipe::Stdout => self.child.stdout.take().map(|o| Box::new(o) as _),
+            Pipe::Stderr => self.child.stderr.take().map(|e| Box::new(e) as _),
+        };
+        let Some(out) = out else { return };
+        let tail = self.tail.clone();
+        self.readers.push(spawn_in_job(move || {
+            for chunk in BufReader::new(out).split(b'\r').map_while(Result::ok) {
+                for line in String::from_utf8_lossy(&chunk).lines() {
+                    let line = line.trim();
+                    if !line.is_empty() && on_line(line) {
+                        tail.push(line);
+                    }
+                }
+            }
+        }));
+    }
+
+    /// `relay` every line of `pipe` to the job log.
+    fn relay_to_log(&mut self, app: &AppHandle, pipe: Pipe) {
+        let app = app.clone();
+        self.relay(pipe, move |line| {
+            emit_pipeline(&app, "pipeline_log", line.to_string());
+            true
+        });
+    }
+
+    /// Wait for the process (see `wait_stage_child`), then for its readers.
+    fn wait(&mut self, app: &AppHandle) -> Result<Option<std::process::ExitStatus>, String> {
+        let status = wait_stage_child(app, &self.job_id, &mut self.child).inspect_err(|e| {
+            let mut lines = self.tail.lines();
+            lines.push(e.clone());
+            app.state::<AppState>().set_failure_tail(&self.job_id, ProcessTail { process: self.what.clone(), lines });
+        })?;
+        join_readers(app, std::mem::take(&mut self.readers));
+        Ok(status)
+    }
+
+    /// Err with `stage_failure` unless the process succeeded.
+    fn check(&self, app: &AppHandle, what: &str, status: Option<std::process::ExitStatus>) -> Result<(), String> {
+        if status.is_some_and(|s| s.success()) {
+            return Ok(());
+        }
+        Err(stage_failure(app, &self.job_id, &self.what, what, status, &self.tail.lines()))
+    }
+}
+
+// -------------------- Auto-retry --------------------
+
+/// Retries with stepped-down settings one job gets after resource failures, across all stages.
+const MAX_AUTO_RETRIES: u32 = 2;
+
+/// Error codes (see `ERROR_PATTERNS`) that a smaller or software configuration can get past.
+const RESOURCE_ERROR_CODES: &[&str] = &[
+    "gpu_out_of_memory",
+    "gpu_device_lost",
+    "host_out_of_memory",
+    "too_many_threads",
+    "hwaccel_failed",
+    "hw_encoder_unavailable",
+];
+
+/// The classified error when `err` is a resource failure worth retrying with degraded settings.
+fn resource_failure(err: &str) -> Option<ActionableError> {
+    classify_process_error(err).filter(|e| RESOURCE_ERROR_CODES.contains(&e.code.as_str()))
+}
+
+/// RIFE settings forced after resource failures, on top of what `fit_rife_to_vram` picks.
+#[derive(Clone, Copy, Debug, Default)]
+struct RifeDegrade {
+    proc_cap: Option<u32>,
+    uhd: bool,
+    /// TTA (`-x`) turned off; the caller clears the job's `tta`.
+    tta_off: bool,
+}
+
+impl RifeDegrade {
+    /// One step down from the settings that just failed: turn TTA off (it doubles the
+    /// per-frame VRAM), halve the proc threads, then turn on UHD mode. None when all are
+    /// already at their lowest.
+    fn step_down(self, threads: RifeThreads, uhd: bool, tta: bool) -> Option<(RifeDegrade, String)> {
+        if tta && !self.tta_off {
+            return Some((RifeDegrade { tta_off: true, ..self }, "TTA (-x) off".into()));
+        }
+        if threads.proc > 1 {
+            let cap = threads.proc / 2;
+            let next = RifeDegrade { proc_cap: Some(cap), ..self };
+            return Some((next, format!("-j {threads} → {}", threads.with_proc(cap))));
+        }
+        if !uhd {
+            return Some((RifeDegrade { uhd: true, ..self }, "UHD mode (-u) on".into()));
+        }
+        None
+    }
+}
+
+/// Most threads of each kind a job may ask rife-ncnn-vulkan for.
+const RIFE_MAX_THREADS: u32 = 16;
+
+/// rife-ncnn-vulkan's `-j load:proc:save`: frame-decoding threads, GPU threads per device and
+/// frame-saving threads. Old job specs stored the `-j` string itself, which still loads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "RifeThreadsRepr")]
+struct RifeThreads {
+    load: u32,
+    proc: u32,
+    save: u32,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RifeThreadsRepr {
+    Fields { load: u32, proc: u32, save: u32 },
+    Arg(String),
+}
+
+impl TryFrom<RifeThreadsRepr> for RifeThreads {
+    type Error = String;
+
+    fn try_from(repr: RifeThreadsRepr) -> Result<Self, String> {
+        match repr {
+            RifeThreadsRepr::Fields { load, proc, save } => Ok(RifeThreads { load, proc, save }),
+            RifeThreadsRepr::Arg(arg) => {
+                let parts: Vec<u32> = arg.split(':').map(|p| p.trim().parse().map_err(|_| format!("Bad RIFE thread count in {arg:?}"))).collect::<Result<_, _>>()?;
+                match parts.as_slice() {
+                    &[load, proc, save] => Ok(RifeThreads { load, proc, save }),
+                    _ => Err(format!("RIFE threads must be load:proc:save, got {arg:?}")),
+                }
+            }
+        }
+    }
+}
+
+impl Default for RifeThreads {
+    fn default() -> Self {
+        RifeThreads::uniform(2)
+    }
+}
+
+impl std::fmt::Display for RifeThreads {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.load, self.proc, self.save)
+    }
+}
+
+impl RifeThreads {
+    fn uniform(n: u32) -> Self {
+        RifeThreads { load: n, proc: n, save: n }
+    }
+
+    /// From a job's single `max_threads`; unset or <= 0 keeps the default.
+    fn from_max_threads(max_threads: Option<i32>) -> Self {
+        match max_threads.unwrap_or(0) {
+            t if t <= 0 => RifeThreads::default(),
+            // Clamp to sane range
+            t => RifeThreads::uniform(t.clamp(1, 12) as u32),
+        }
+    }
+
+    fn with_proc(self, proc: u32) -> Self {
+        RifeThreads { proc, ..self }
+    }
+
+    /// rife-ncnn-vulkan needs at least one thread of each kind; zero makes it exit at once.
+    fn validate(&self) -> Result<(), String> {
+        for (name, n) in [("Load", self.load), ("GPU", self.proc), ("Save", self.save)] {
+            if !(1..=RIFE_MAX_THREADS).contains(&n) {
+                return Err(format!("{name} threads must be between 1 and {RIFE_MAX_THREADS}"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `-j` and UHD mode for a job's RIFE run: fitted to free VRAM, then capped by `degrade`.
+/// Also returns the VRAM note, when free VRAM could be read.
+fn planned_rife_settings(job: &SmoothJob, model_name: &str, degrade: RifeDegrade) -> (RifeThreads, bool, Option<String>) {
+    let mut threads = job.threads;
+    let mut uhd = false;
+    let mut note = None;
+    // Drafts interpolate smaller frames, which need less VRAM.
+    let size = draft_plan(job, None).map(|d| d.scaled).or_else(|| upright_video_size(&job.ffmpeg, &job.input));
+    if let (Some((w, h)), Some(gpu)) = (size, job.limits.gpu_memory(query_gpu_memory(rife_gpu(&job.settings)))) {
+        let (t, u, n) = fit_rife_to_vram(threads, w, h, model_name, &gpu);
+        threads = t;
+        uhd = u;
+        note = Some(n);
+    }
+    if let Some(cap) = degrade.proc_cap.filter(|c| *c < threads.proc) {
+        threads = threads.with_proc(cap);
+    }
+    (job.limits.cap_rife(threads), uhd || degrade.uhd || job.force_uhd, note)
+}
+
+impl EncodeSettings {
+    /// The same settings on the software encoder of the same codec, for when a GPU encoder
+    /// can't start. Encoder-specific options that don't carry over are dropped.
+    fn software_fallback(&self) -> Option<EncodeSettings> {
+        let encoder = match self.encoder() {
+            VideoEncoder::H264Nvenc => VideoEncoder::Libx264,
+            VideoEncoder::HevcNvenc | VideoEncoder::HevcVideotoolbox => VideoEncoder::Libx265,
+            _ => return None,
+        };
+        let keep = |v: &Option<String>, allowed: &[&str]| v.clone().filter(|v| allowed.contains(&v.as_str()));
+        let fallback = EncodeSettings {
+            encoder: Some(encoder),
+            preset: None,
+            tune: None,
+            profile: keep(&self.profile, encoder.profiles()),
+            level: keep(&self.level, encoder.levels()),
+            ..self.clone()
+        };
+        fallback.validate().ok().map(|_| fallback)
+    }
+}
+
+// -------------------- Output verification --------------------
+
+/// Result of the post-encode integrity check attached to `pipeline_done`.
+#[derive(Clone, serde::Serialize)]
+struct OutputVerification {
+    ok: bool,
+    duration_secs: f64,
+    expected_duration_secs: f64,
+    has_video: bool,
+    has_audio: bool,
+    decodable: bool,
+    /// |video duration - audio duration| when both streams are present.
+    av_drift_secs: Option<f64>,
+    /// First/last packet times of both streams, when both are present.
+    av_sync: Option<AvSync>,
+    issues: Vec<String>,
+}
+
+/// Packet timestamps of the first video and audio streams: where each starts, and where its
+/// last packet ends.
+#[derive(Clone, Copy, Debug, serde::Serialize)]
+struct AvSync {
+    video_start: f64,
+    audio_start: f64,
+    video_end: f64,
+    audio_end: f64,
+    /// How much later the audio starts than the video; what `fix_av_offset` shifts away.
+    start_offset_secs: f64,
+    /// How much later the audio ends than the video.
+    end_offset_secs: f64,
+}
+
+/// First packet time and last packet end of `stream` ("v:0", "a:0"). Only the head and the
+/// last few seconds are read, so it stays quick on long files.
+fn probe_packet_span(ffprobe: &Path, input: &Path, stream: &str, duration: f64) -> Option<(f64, f64)> {
+    let entries = ["-select_streams", stream, "-show_entries", "packet=pts_time,duration_time", "-of", "csv=p=0"];
+    let parse = |out: &str| -> Vec<(f64, f64)> {
+        out.lines()
+            .filter_map(|l| {
+                let mut parts = l.trim().split(',');
+                let pts = parts.next()?.parse::<f64>().ok()?;
+                Some((pts, parts.next().and_then(|d| d.parse().ok()).unwrap_or(0.0)))
+            })
+            .collect()
+    };
+    let head = run_ffprobe(ffprobe, &[&["-read_intervals", "%+#8"], &entries[..]].concat(), input)?;
+    // Packets come in decode order: the smallest pts of the first few is the start.
+    let start = parse(&head).iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let from = format!("{:.3}%", (duration - 5.0).max(0.0));
+    let tail = run_ffprobe(ffprobe, &[&["-read_intervals", from.as_str()], &entries[..]].concat(), input)?;
+    let end = parse(&tail).iter().map(|p| p.0 + p.1).fold(f64::NEG_INFINITY, f64::max);
+    (start.is_finite() && end.is_finite()).then_some((start, end))
+}
+
+fn probe_av_sync(ffmpeg: &Path, input: &Path, duration: f64) -> Option<AvSync> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let (video_start, video_end) = probe_packet_span(&ffprobe, input, "v:0", duration)?;
+    let (audio_start, audio_end) = probe_packet_span(&ffprobe, input, "a:0", duration)?;
+    Some(AvSync {
+        video_start,
+        audio_start,
+        video_end,
+        audio_end,
+        start_offset_secs: audio_start - video_start,
+        end_offset_secs: audio_end - video_end,
+    })
+}
+
+/// A/V drift beyond this is audible as lip-sync error.
+const MAX_AV_DRIFT_SECS: f64 = 0.1;
+
+impl OutputVerification {
+    /// Append a short note to the done message when something looked wrong.
+    fn annotate(&self, msg: String) -> String {
+        if self.ok {
+            msg
+        } else {
+            format!("{msg} (verification: {} issue(s))", self.issues.len())
+        }
+    }
+}
+
+/// Decode a couple of seconds with the null muxer; any error output means the segment is damaged.
+fn decodes_cleanly(ffmpeg: &Path, output: &Path, from_end: bool) -> bool {
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin").arg("-v").arg("error");
+    if from_end {
+        cmd.arg("-sseof").arg("-2");
+    }
+    cmd.arg("-i").arg(output)
+        .arg("-t").arg("2")
+        .arg("-f").arg("null").arg("-");
+    match run_tool(log_command("verify", &mut cmd), PROBE_TIMEOUT_SECS) {
+        Ok(out) => out.status.success() && String::from_utf8_lossy(&out.stderr).trim().is_empty(),
+        Err(_) => false,
+    }
+}
+
+/// Quick ffprobe/ffmpeg pass over a finished output: duration close to expected, the expected
+/// streams are present, and the first/last GOPs decode without errors.
+fn verify_output(ffmpeg: &Path, output: &Path, expected_duration: f64, expect_audio: bool) -> OutputVerification {
+    let mut issues = Vec::new();
+
+    let duration = probe_duration_and_fps(ffmpeg, output).map(|(d, _)| d).unwrap_or(0.0);
+    let streams = probe_stream_types(ffmpeg, output).unwrap_or_default();
+    let has_video = streams.iter().any(|c| c == "video");
+    let has_audio = streams.iter().any(|c| c == "audio");
+
+    if !output.is_file() {
+        issues.push("Output file is missing".to_string());
+    }
+    if !has_video {
+        issues.push("Output has no video stream".to_string());
+    }
+    if expect_audio && !has_audio {
+        issues.push("Source has audio but the output does not".to_string());
+    }
+    if expected_duration > 0.0 {
+        let tolerance = (expected_duration * 0.02).max(0.5);
+        if (duration - expected_duration).abs() > tolerance {
+            issues.push(format!(
+                "Duration {duration:.2}s differs from expected {expected_duration:.2}s"
+            ));
+        }
+    }
+
+    let av_drift = match probe_stream_durations(ffmpeg, output) {
+        (Some(v), Some(a)) => Some((v - a).abs()),
+        _ => None,
+    };
+    if let Some(d) = av_drift {
+        if d > MAX_AV_DRIFT_SECS {
+            issues.push(format!("Audio/video durations differ by {d:.3}s"));
+        }
+    }
+    let av_sync = if has_audio && has_video { probe_av_sync(ffmpeg, output, duration) } else { None };
+    if let Some(sync) = av_sync {
+        if sync.start_offset_secs.abs() > MAX_AV_DRIFT_SECS {
+            issues.push(format!(
+                "Audio starts {:+.3}s from the video (fix_av_offset can re-mux it shifted back)",
+                sync.start_offset_secs
+            ));
+        }
+        // A constant offset is fixable; a change between start and end is the audio running at
+        // a different speed.
+        let drift = sync.end_offset_secs - sync.start_offset_secs;
+        if drift.abs() > MAX_AV_DRIFT_SECS {
+            issues.push(format!("Audio drifts {drift:+.3}s against the video from start to end"));
+        }
+    }
+
+    let decodable = has_video
+        && decodes_cleanly(ffmpeg, output, false)
+        && decodes_cleanly(ffmpeg, output, true);
+    if has_video && !decodable {
+        issues.push("First/last GOP failed to decode cleanly".to_string());
+    }
+
+    OutputVerification {
+        ok: issues.is_empty(),
+        duration_secs: duration,
+        expected_duration_secs: expected_duration,
+        has_video,
+        has_audio,
+        decodable,
+        av_drift_secs: av_drift,
+        av_sync,
+        issues,
+    }
+}
+
+#[derive(Clone, serde::Serialize)]
+struct AvFixDone {
+    ok: bool,
+    path: String,
+    message: String,
+}
+
+/// Re-mux `path` with its audio shifted by `-offset_secs` (`-itsoffset`), in place. Without
+/// an offset the measured start offset is used. Returns right away; the outcome arrives as
+/// `av_fix_done`.
+#[tauri::command]
+fn fix_av_offset(app: AppHandle, state: State<'_, AppState>, path: String, offset_secs: Option<f64>) -> Result<f64, String> {
+    let root = app_root(&app)?;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    let output = PathBuf::from(path.trim());
+    if !output.is_file() {
+        return Err("Output file does not exist".into());
+    }
+    let duration = probe_duration_and_fps(&ffmpeg, &output).map(|(d, _)| d).unwrap_or(0.0);
+    let offset = match offset_secs {
+        Some(o) => o,
+        None => {
+            probe_av_sync(&ffmpeg, &output, duration)
+                .ok_or("Couldn't read the audio and video timestamps of this file")?
+                .start_offset_secs
+        }
+    };
+    if !offset.is_finite() || offset.abs() > 60.0 {
+        return Err(format!("Offset {offset:.3}s is out of range"));
+    }
+
+    let ext = output_extension(&output);
+    let temp = output.with_extension(format!("avfix.{ext}"));
+    let mut cmd = tool_command(&ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin").arg("-y")
+        .arg("-i").arg(&output)
+        .arg("-itsoffset").arg(format!("{:.6}", -offset))
+        .arg("-i").arg(&output)
+        .arg("-map").arg("0")
+        .arg("-map").arg("-0:a")
+        .arg("-map").arg("1:a")
+        .arg("-c").arg("copy");
+    if matches!(ext.as_str(), "mp4" | "mov" | "m4v") {
+        cmd.arg("-movflags").arg("+faststart");
+    }
+    cmd.arg(&temp).stdout(Stdio::null()).stderr(Stdio::piped());
+
+    std::thread::spawn(move || {
+        let result = (|| -> Result<String, String> {
+            // A stream copy runs far faster than real time.
+            let out = run_tool(log_command("av_fix", &mut cmd), PROBE_TIMEOUT_SECS + duration as u64)?;
+            if !out.status.success() {
+                let log = String::from_utf8_lossy(&out.stderr).to_string();
+                let tail: Vec<String> = log.lines().rev().take(8).map(str::to_string).collect();
+                return Err(failure_message("Re-mux failed", Some(out.status), &tail.into_iter().rev().collect::<Vec<_>>()));
+            }
+            fs::rename(&temp, &output).map_err(|e| format!("Failed to replace the output: {e}"))?;
+            Ok(format!("Audio shifted by {:+.3}s", -offset))
+        })();
+        let _ = fs::remove_file(&temp);
+        let path = output.to_string_lossy().to_string();
+        let done = match result {
+            Ok(message) => AvFixDone { ok: true, path, message },
+            Err(message) => AvFixDone { ok: false, path, message },
+        };
+        let _ = app.emit("av_fix_done", done);
+    });
+    Ok(offset)
+}
+
+fn report_verification(app: &AppHandle, v: &OutputVerification) {
+    if v.ok {
+        emit_log_limited(app, &format!("Output verified: {:.2}s", v.duration_secs));
+    } else {
+        for issue in &v.issues {
+            emit_log_limited(app, &format!("Output verification warning: {issue}"));
+        }
+    }
+}
+
+fn extract_frames_worker(
+    app: &tauri::AppHandle,
+    job_id: &str,
+    ffmpeg: &PathBuf,
+    input: &PathBuf,
+    frames_dir: &PathBuf,
+    pattern: &PathBuf,
+    hwaccel: bool,
+) -> Result<String, String> {
+    let (duration_secs, fps) = probe_duration_and_fps(ffmpeg, input).unwrap_or((0.0, 0.0));
+    let total_frames_est = if duration_secs > 0.0 && fps > 0.0 {
+        (duration_secs * fps).round() as i64
+    } else {
+        0
+    };
+
+    if total_frames_est > 0 {
+        emit_log_limited(app, &format!("Estimated frames: {}", total_frames_est));
+    }
+    // Extraction runs at roughly the speed of playback or faster.
+    let progress = ProgressResolution::for_estimate(duration_secs);
+    set_progress_resolution(app, progress);
+
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner")
+        .arg("-y")
+        .arg("-nostdin")
+        .arg("-loglevel").arg("error");
+    progress.push_args(&mut cmd);
+    cmd.arg("-i").arg(input)
+        .arg("-fps_mode").arg("passthrough")
+        .arg("-progress").arg("pipe:1");
+
+// Hardware decode when available (platform default).
+let jpg_quality = 2;
+
+// hwaccel name (ffmpeg): macOS=videotoolbox, Windows=d3d11va (fallback).
+let hwaccel = if !hwaccel {
+    None
+} else if cfg!(target_os = "macos") {
+    Some("videotoolbox")
+} else if cfg!(target_os = "windows") {
+    Some("d3d11va")
+} else {
+    None
+};
+
+if let Some(hw) = hwaccel {
+    cmd.arg("-hwaccel").arg(hw);
+}
+
+// Fast path for interpolation: decode frames as JPG (much faster IO than PNG/WebP lossless).
+cmd.arg("-threads").arg("0")
+    .arg("-c:v").arg("mjpeg")
+    .arg("-q:v").arg(jpg_quality.to_string());
+    cmd.arg(pattern.as_os_str())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    log_stage_command(app, "extract", &mut cmd);
+    let mut ffmpeg = spawn_stage_process(app, job_id, "FFmpeg", &mut cmd)?;
+
+    // Keep stderr for errors.
+    ffmpeg.relay(Pipe::Stderr, |_| true);
+
+    // Drain progress output so ffmpeg can't block on full buffers.
+    if let Some(out) = ffmpeg.child.stdout.take() {
+        let reader = BufReader::new(out);
+        let mut last_emit = std::time::Instant::now();
+        let mut frame = 0i64;
+
+        for line in reader.lines().flatten() {
+            if let Some((k, v)) = parse_ffmpeg_progress_line(&line) {
+                if k == "frame" {
+                    frame = v.parse::<i64>().unwrap_or(frame);
+                }
+                // throttle UI events
+                if last_emit.elapsed().as_millis() >= 250 {
+                    if total_frames_est > 0 && frame > 0 {
+                        let pct = ((frame as f64 / total_frames_est as f64) * 100.0).min(100.0);
+                        emit_pipeline(app, "pipeline_progress", pct);
+                    }
+                    last_emit = std::time::Instant::now();
+                }
+            }
+        }
+    }
+
+    let status = ffmpeg.wait(app)?;
+    let frame_count = count_files_in_dir(frames_dir);
+
+    if frame_count > 0 {
+        emit_pipeline(app, "pipeline_progress", 100.0f64);
+    }
+
+    ffmpeg.check(app, "ffmpeg failed", status).inspect_err(|e| emit_log_limited(app, e))?;
+
+    if frame_count <= 0 {
+        return Err("No frames were extracted".to_string());
+    }
+
+    Ok(format!("Frames extracted: {frame_count}"))
+}
+
+
+
+fn output_extension(output: &Path) -> String {
+    output
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+// -------------------- Audio --------------------
+
+/// Bitrate of a transcoded stereo track; surround tracks scale it per channel pair.
+const AUDIO_BITRATE_KBPS: u32 = 192;
+
+/// Whether `container` (an output extension) can hold a `codec` (ffprobe codec_name) track.
+/// Unknown containers are assumed to take anything, like mkv.
+fn container_accepts_audio(container: &str, codec: &str) -> bool {
+    let pcm = codec.starts_with("pcm_");
+    match container {
+        // Opus/FLAC-in-MP4 are valid but many players still reject them.
+        "mp4" | "m4v" => matches!(codec, "aac" | "mp3" | "ac3" | "eac3" | "alac"),
+        "mov" => pcm || matches!(codec, "aac" | "mp3" | "ac3" | "eac3" | "alac"),
+        "webm" => matches!(codec, "opus" | "vorbis"),
+        "avi" => pcm || matches!(codec, "mp3" | "ac3" | "mp2"),
+        "ts" | "m2ts" => matches!(codec, "aac" | "mp3" | "mp2" | "ac3" | "eac3" | "opus"),
+        _ => true,
+    }
+}
+
+/// Encoder used when the source track can't be copied into `container`.
+fn fallback_audio_encoder(container: &str, channels: u32) -> &'static str {
+    match container {
+        "webm" => "libopus",
+        // MP3 is stereo-only; AC-3 keeps 5.1 in AVI.
+        "avi" if channels > 2 => "ac3",
+        "avi" => "libmp3lame",
+        _ => "aac",
+    }
+}
+
+/// Channel layouts an encoder takes, best first; the source layout is converted to the
+/// nearest one (e.g. 5.1(side) to 5.1).
+fn encoder_channel_layouts(encoder: &str) -> &'static str {
+    match encoder {
+        "libmp3lame" => "stereo|mono",
+        "ac3" => "5.1|stereo|mono",
+        _ => "7.1|5.1|stereo|mono",
+    }
+}
+
+/// First audio stream of a file.
+#[derive(Clone, Debug)]
+struct AudioStreamInfo {
+    codec: String,
+    channels: u32,
+    /// ffprobe's layout name (stereo, 5.1(side), …), when the file declares one.
+    layout: Option<String>,
+}
+
+impl AudioStreamInfo {
+    fn layout_name(&self) -> String {
+        self.layout.clone().unwrap_or_else(|| format!("{} ch", self.channels))
+    }
+}
+
+/// Audio options for the encode stage.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct AudioOptions {
+    /// Add a silent track when the source has none; some platforms reject video-only files.
+    add_silence: bool,
+    /// Downmix surround tracks to stereo. Otherwise the channel layout is kept.
+    downmix_stereo: bool,
+}
+
+/// How the source audio track gets into the output.
+#[derive(Clone, Debug)]
+struct AudioPlan {
+    /// None when the source has no audio.
+    source: Option<AudioStreamInfo>,
+    /// Encoder to transcode with; None copies the track.
+    encoder: Option<&'static str>,
+    /// The source has no audio; a silent track is generated as input 2.
+    silence: bool,
+    /// Fold a surround source down to stereo.
+    downmix: bool,
+}
+
+impl AudioPlan {
+    fn new(ffmpeg: &Path, input: &Path, container: &str, opts: &AudioOptions) -> Self {
+        let source = probe_audio_stream(ffmpeg, input);
+        let silence = source.is_none() && opts.add_silence;
+        let downmix = opts.downmix_stereo && source.as_ref().is_some_and(|s| s.channels > 2);
+        let encoder = match &source {
+            // Copying can't change the channel count.
+            Some(_) if downmix => Some(fallback_audio_encoder(container, 2)),
+            Some(s) if !container_accepts_audio(container, &s.codec) => {
+                Some(fallback_audio_encoder(container, s.channels))
+            }
+            None if silence => Some(fallback_audio_encoder(container, 2)),
+            _ => None,
+        };
+        Self { source, encoder, silence, downmix }
+    }
+
+    /// Channels of the written track.
+    fn output_channels(&self) -> u32 {
+        let channels = self.source.as_ref().map(|s| s.channels).unwrap_or(2);
+        match self.encoder {
+            _ if self.downmix => 2,
+            Some("libmp3lame") => channels.min(2),
+            Some("ac3") => channels.min(6),
+            _ => channels.min(8),
+        }
+    }
+
+    /// Transcode bitrate: 192k per stereo pair, within what the encoders accept.
+    fn transcode_kbps(&self) -> u32 {
+        let pairs = self.output_channels().div_ceil(2).max(1);
+        (AUDIO_BITRATE_KBPS * pairs).min(if self.encoder == Some("ac3") { 640 } else { 512 })
+    }
+
+    /// Extra inputs after the frames (0) and the source (1).
+    fn push_inputs(&self, cmd: &mut Command, duration_secs: f64) {
+        if self.silence {
+            cmd.arg("-f").arg("lavfi")
+                .arg("-t").arg(format!("{:.3}", duration_secs.max(0.1)))
+                .arg("-i").arg("anullsrc=channel_layout=stereo:sample_rate=48000");
+        }
+    }
+
+    fn push_map(&self, cmd: &mut Command) {
+        cmd.arg("-map").arg(if self.silence { "2:a:0" } else { "1:a:0?" });
+    }
+
+    /// Audio bitrate the output will carry, for sizing a target-size encode.
+    fn bitrate_kbps(&self, ffmpeg: &Path, input: &Path) -> u32 {
+        match (&self.source, self.encoder) {
+            (_, Some(_)) => self.transcode_kbps(),
+            (None, None) => 0,
+            (Some(_), None) => probe_audio_bitrate_kbps(ffmpeg, input).unwrap_or(AUDIO_BITRATE_KBPS),
+        }
+    }
+
+    fn describe(&self, container: &str) -> String {
+        match (&self.source, self.encoder) {
+            (None, Some(e)) if self.silence => format!("Audio: source has no audio track; adding a silent {e} track"),
+            (None, _) => "Audio: source has no audio track".to_string(),
+            (Some(s), None) => format!("Audio: copying {} ({}) into .{container}", s.codec, s.layout_name()),
+            (Some(s), Some(e)) if self.downmix => {
+                format!("Audio: downmixing {} ({}) to stereo with {e}", s.codec, s.layout_name())
+            }
+            (Some(s), Some(e)) => format!(
+                "Audio: .{container} can't hold {}; transcoding with {e} ({} → {} ch)",
+                s.codec,
+                s.layout_name(),
+                self.output_channels()
+            ),
+        }
+    }
+
+    fn push_args(&self, cmd: &mut Command) {
+        let Some(e) = self.encoder else {
+            cmd.arg("-c:a").arg("copy");
+            return;
+        };
+        cmd.arg("-c:a").arg(e).arg("-b:a").arg(format!("{}k", self.transcode_kbps()));
+        if self.downmix {
+            // ITU-R BS.775 stereo downmix: centre and surrounds at -3 dB, LFE dropped.
+            cmd.arg("-af").arg("aresample=out_chlayout=stereo:clev=0.707:slev=0.707:lfe_mix_level=0");
+        } else if self.source.as_ref().is_some_and(|s| s.channels > 2) {
+            cmd.arg("-af").arg(format!("aformat=channel_layouts={}", encoder_channel_layouts(e)));
+            if e == "libopus" {
+                // Surround Opus needs the Vorbis channel mapping.
+                cmd.arg("-mapping_family").arg("1");
+            }
+        }
+    }
+}
+
+// -------------------- Interpolation sanity --------------------
+
+/// Interpolated frames decoded after RIFE runs.
+const SANITY_SAMPLES: usize = 8;
+/// Mean luma (0-255) below which a frame counts as black.
+const BLANK_MAX_MEAN: f64 = 12.0;
+/// Luma standard deviation below which a frame counts as a flat fill (gray or solid colour).
+const FLAT_MAX_STDDEV: f64 = 2.0;
+
+#[derive(Clone, Copy, Debug)]
+struct LumaStats {
+    mean: f64,
+    stddev: f64,
+}
+
+impl LumaStats {
+    fn looks_blank(&self) -> bool {
+        self.mean < BLANK_MAX_MEAN || self.stddev < FLAT_MAX_STDDEV
+    }
+}
+
+/// Decode an image to a 64x64 grayscale thumbnail (one byte per pixel).
+fn gray_thumbnail(ffmpeg: &Path, frame: &Path, stage: &str) -> Option<Vec<u8>> {
+    let out = run_tool(log_command(stage, tool_command(ffmpeg)
+        .arg("-hide_banner").arg("-nostdin")
+        .arg("-v").arg("error")
+        .arg("-i").arg(frame)
+        .arg("-frames:v").arg("1")
+        .arg("-s").arg("64x64")
+        .arg("-pix_fmt").arg("gray")
+        .arg("-f").arg("rawvideo").arg("-")), PROBE_TIMEOUT_SECS)
+        .ok()?;
+    if !out.status.success() || out.stdout.is_empty() {
+        return None;
+    }
+    Some(out.stdout)
+}
+
+/// Bytes in one `gray_thumbnail`.
+const GRAY_THUMBNAIL_BYTES: usize = 64 * 64;
+
+/// `gray_thumbnail` of each of `frames`, in order, from a single ffmpeg run over a concat
+/// list of just those files. Selecting them out of the frame folders instead would decode
+/// every frame of the clip.
+fn gray_thumbnails(ffmpeg: &Path, frames: &[&Path], list_path: &Path, stage: &str) -> Option<Vec<Vec<u8>>> {
+    if frames.is_empty() {
+        return None;
+    }
+    let mut list = String::from("ffconcat version 1.0\n");
+    for f in frames {
+        list.push_str(&format!("file {}\n", ffconcat_quote(f)));
+    }
+    fs::write(list_path, list).ok()?;
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin").arg("-v").arg("error")
+        .arg("-f").arg("concat").arg("-safe").arg("0").arg("-i").arg(list_path)
+        .arg("-vf").arg("scale=64:64,format=gray")
+        .arg("-fps_mode").arg("passthrough")
+        .arg("-f").arg("rawvideo").arg("-");
+    let out = run_tool(log_command(stage, &mut cmd), analysis_timeout_secs(frames.len() as f64));
+    let _ = fs::remove_file(list_path);
+    let out = out.ok()?;
+    if !out.status.success() || out.stdout.len() != frames.len() * GRAY_THUMBNAIL_BYTES {
+        return None;
+    }
+    Some(out.stdout.chunks(GRAY_THUMBNAIL_BYTES).map(<[u8]>::to_vec).collect())
+}
+
+impl LumaStats {
+    fn of(pixels: &[u8]) -> LumaStats {
+        let n = pixels.len().max(1) as f64;
+        let mean = pixels.iter().map(|&v| v as f64).sum::<f64>() / n;
+        let var = pixels.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n;
+        LumaStats { mean, stddev: var.sqrt() }
+    }
+}
+
+/// Decode a random handful of interpolated frames and look for black or flat output, the
+/// usual symptom of a broken model or GPU driver. A frame only counts when the source frames
+/// around it have picture content, so fades and title cards don't trip the check.
+///
+/// Returns a description of the problem when most samples are bad.
+fn check_interpolated_frames(ffmpeg: &Path, frames_in_dir: &Path, frames_out_dir: &Path, factor: f64) -> Option<String> {
+    let inputs = sorted_files(frames_in_dir);
+    let outputs = sorted_files(frames_out_dir);
+    // Output j sits at source position j / factor; the ones between two inputs are synthesized
+    // (with 2x output, the odd positions).
+    let position = |j: usize| j as f64 / factor;
+    let synthesized: Vec<usize> = (1..outputs.len()).filter(|&j| position(j).fract() > 1e-6).collect();
+    if inputs.len() < 2 || synthesized.is_empty() {
+        return None;
+    }
+
+    let mut seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64 | 1)
+        .unwrap_or(1);
+    let mut picks: Vec<usize> = (0..SANITY_SAMPLES * 3)
+        .map(|_| {
+            // xorshift64; good enough to spread samples across the clip
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            synthesized[(seed % synthesized.len() as u64) as usize]
+        })
+        .collect();
+    picks.sort_unstable();
+    picks.dedup();
+
+    let sources: Vec<usize> = picks
+        .iter()
+        .flat_map(|&j| {
+            let k0 = (position(j).floor() as usize).min(inputs.len() - 1);
+            [k0, (k0 + 1).min(inputs.len() - 1)]
+        })
+        .collect();
+    let files: Vec<&Path> =
+        sources.iter().map(|&k| inputs[k].as_path()).chain(picks.iter().map(|&j| outputs[j].as_path())).collect();
+    let thumbnails = gray_thumbnails(ffmpeg, &files, &frames_out_dir.with_extension("samples.ffconcat"), "sanity_check")?;
+    let (source_stats, output_stats) = thumbnails.split_at(sources.len());
+
+    let mut checked = 0;
+    let mut bad = Vec::new();
+    for (i, &j) in picks.iter().enumerate() {
+        if checked == SANITY_SAMPLES {
+            break;
+        }
+        let sources_have_content = source_stats[2 * i..2 * i + 2].iter().all(|p| !LumaStats::of(p).looks_blank());
+        if !sources_have_content {
+            continue;
+        }
+        let stats = LumaStats::of(&output_stats[i]);
+        checked += 1;
+        if stats.looks_blank() {
+            bad.push(format!(
+                "{} (mean {:.1}, stddev {:.1})",
+                outputs[j].file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                stats.mean,
+                stats.stddev
+            ));
+        }
+    }
+
+    if checked > 0 && bad.len() * 2 > checked {
+        Some(format!(
+            "Interpolated frames look blank: {} of {checked} sampled frames are black or flat while their source frames are not: {}",
+            bad.len(),
+            bad.join(", ")
+        ))
+    } else {
+        None
+    }
+}
+
+// -------------------- Built-in RIFE (ncnn) --------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum RifeBackend {
+    /// The rife-ncnn-vulkan binary.
+    #[default]
+    External,
+    /// RIFE run in-process through ncnn; falls back to the binary when it can't run.
+    Builtin,
+}
+
+/// Whether this build has the built-in backend, for the settings UI.
+#[tauri::command]
+fn builtin_rife_available() -> bool {
+    cfg!(feature = "ncnn")
+}
+
+/// RIFE's input sizes must be multiples of this; frames are padded and the output cropped.
+#[cfg(feature = "ncnn")]
+const RIFE_PAD: u32 = 32;
+
+/// Interpolate `frames_in` into `frames_out` in-process. Output numbering matches
+/// rife-ncnn-vulkan's 2x mode (source frame, in-between frame, ...; the last frame is
+/// repeated), so the later stages can't tell the backends apart. Each frame is decoded once:
+/// the previous one stays in memory for the next pair.
+#[cfg(feature = "ncnn")]
+fn interpolate_builtin(app: &AppHandle, job: &SmoothJob, in_count: f64, threads: RifeThreads) -> Result<(), String> {
+    let model_name = job.model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (net, takes_timestep) = load_rife_net(job, &model_profile(&job.root, &model_name), threads, true)?;
+    let inputs: Vec<PathBuf> = sorted_files(&job.frames_in_dir)
+        .into_iter()
+        .filter(|p| output_extension(p) == job.frame_format.ext())
+        .collect();
+    let load = |p: &Path| image::open(p).map(|i| i.to_rgb8()).map_err(|e| format!("Can't read {}: {e}", p.display()));
+    let out = FrameFiles::new(&job.frames_out_dir, job.frame_format);
+    let Some(first) = inputs.first() else {
+        return Err("No extracted frames to interpolate".into());
+    };
+    let mut prev = load(first)?;
+    for i in 1..=inputs.len() {
+        check_stage_cancelled(app, &job.job_id, JobStage::Interpolate)?;
+        let next = inputs.get(i).map(|p| load(p)).transpose()?;
+        let middle = match &next {
+            Some(next) => rife_pair(&net, &prev, next, takes_timestep)?,
+            None => prev.clone(),
+        };
+        save_frame(&prev, &out.create(2 * i - 1)?)?;
+        save_frame(&middle, &out.create(2 * i)?)?;
+        emit_stage_progress(app, job, JobStage::Interpolate, (2 * i) as f64 / (in_count * 2.0), (2 * i) as u64);
+        if let Some(next) = next {
+            prev = next;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "ncnn"))]
+fn interpolate_builtin(_app: &AppHandle, _job: &SmoothJob, _in_count: f64, _threads: RifeThreads) -> Result<(), String> {
+    Err(NO_BUILTIN_RIFE.into())
+}
+
+#[cfg(not(feature = "ncnn"))]
+const NO_BUILTIN_RIFE: &str = "This build has no built-in RIFE backend (it was compiled without the `ncnn` feature)";
+
+/// rife-ncnn-vulkan's `rife.Warp` layer, which every RIFE `flownet.param` uses and plain ncnn
+/// doesn't ship: backward-warps the image (first input) by the flow (second input, x and y
+/// offsets in pixels), sampling bilinearly with the edges clamped. Runs on the CPU; ncnn moves
+/// the blobs off and back onto the GPU around it.
+#[cfg(feature = "ncnn")]
+unsafe extern "C" fn rife_warp_forward(
+    _layer: ncnn_bind::ncnn_layer_t,
+    bottom_blobs: *const ncnn_bind::ncnn_mat_t,
+    n: i32,
+    top_blobs: *mut ncnn_bind::ncnn_mat_t,
+    n2: i32,
+    _opt: ncnn_bind::ncnn_option_t,
+) -> i32 {
+    use ncnn_bind::*;
+    if n < 2 || n2 < 1 {
+        return -1;
+    }
+    let (image, flow) = (*bottom_blobs, *bottom_blobs.add(1));
+    let (w, h, channels) = (ncnn_mat_get_w(image), ncnn_mat_get_h(image), ncnn_mat_get_c(image));
+    if ncnn_mat_get_w(flow) != w || ncnn_mat_get_h(flow) != h || ncnn_mat_get_c(flow) < 2 {
+        return -1;
+    }
+    let out = ncnn_mat_create_3d(w, h, channels, std::ptr::null_mut());
+    if out.is_null() {
+        return -100;
+    }
+    let (wu, len) = (w as usize, (w * h) as usize);
+    let fx = std::slice::from_raw_parts(ncnn_mat_get_channel_data(flow, 0) as *const f32, len);
+    let fy = std::slice::from_raw_parts(ncnn_mat_get_channel_data(flow, 1) as *const f32, len);
+    let clamp = |v: f32, max: i32| (v as i32).clamp(0, max - 1) as usize;
+    for q in 0..channels {
+        let src = std::slice::from_raw_parts(ncnn_mat_get_channel_data(image, q) as *const f32, len);
+        let dst = std::slice::from_raw_parts_mut(ncnn_mat_get_channel_data(out, q) as *mut f32, len);
+        for (i, px) in dst.iter_mut().enumerate() {
+            let (sx, sy) = ((i % wu) as f32 + fx[i], (i / wu) as f32 + fy[i]);
+            let (alpha, beta) = (sx - sx.floor(), sy - sy.floor());
+            let (x0, x1) = (clamp(sx.floor(), w), clamp(sx.floor() + 1.0, w));
+            let (row0, row1) = (clamp(sy.floor(), h) * wu, clamp(sy.floor() + 1.0, h) * wu);
+            let upper = src[row0 + x0] * (1.0 - alpha) + src[row0 + x1] * alpha;
+            let lower = src[row1 + x0] * (1.0 - alpha) + src[row1 + x1] * alpha;
+            *px = upper * (1.0 - beta) + lower * beta;
+        }
+    }
+    *top_blobs = out;
+    0
+}
+
+#[cfg(feature = "ncnn")]
+unsafe extern "C" fn rife_warp_create(_userdata: *mut std::ffi::c_void) -> ncnn_bind::ncnn_layer_t {
+    let layer = ncnn_bind::ncnn_layer_create();
+    ncnn_bind::ncnn_layer_set_one_blob_only(layer, 0);
+    (*layer).forward_n = Some(rife_warp_forward);
+    layer
+}
+
+#[cfg(feature = "ncnn")]
+unsafe extern "C" fn rife_warp_destroy(layer: ncnn_bind::ncnn_layer_t, _userdata: *mut std::ffi::c_void) {
+    ncnn_bind::ncnn_layer_destroy(layer);
+}
+
+/// Load the job's model into ncnn; with `weights` false only the graph is read, which is
+/// enough to tell whether ncnn can run it. Also returns whether the model takes a timestep
+/// input instead of always producing the midpoint, as `profile` says.
+#[cfg(feature = "ncnn")]
+fn load_rife_net(job: &SmoothJob, profile: &ModelProfile, threads: RifeThreads, weights: bool) -> Result<(ncnn_rs::Net, bool), String> {
+    let model_name = job.model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut opt = ncnn_rs::Option::new();
+    opt.set_vulkan_compute(true);
+    opt.set_num_threads(threads.proc);
+    let mut net = ncnn_rs::Net::new();
+    net.set_option(&opt);
+    // Must be known before the param file names it.
+    unsafe {
+        net.register_custom_layer_by_type("rife.Warp", Some(rife_warp_create), Some(rife_warp_destroy), std::ptr::null_mut());
+    }
+    // Model files are opened by absolute path; no working-directory tricks needed.
+    let files: &[(&str, bool)] = if weights { &[("flownet.param", true), ("flownet.bin", false)] } else { &[("flownet.param", true)] };
+    for &(file, is_param) in files {
+        let path = job.model_dir.join(file);
+        let path_str = path.to_string_lossy();
+        let loaded = if is_param { net.load_param(&path_str) } else { net.load_model(&path_str) };
+        loaded.map_err(|e| format!("Built-in RIFE can't load {}: {e}", path.display()))?;
+    }
+    Ok((net, profile.supports_timestep(&model_name)))
+}
+
+#[cfg(feature = "ncnn")]
+fn check_builtin_model(job: &SmoothJob) -> Result<(), String> {
+    let model_name = job.model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    load_rife_net(job, &model_profile(&job.root, &model_name), job.threads, true).map(|_| ())
+}
+
+#[cfg(not(feature = "ncnn"))]
+fn check_builtin_model(_job: &SmoothJob) -> Result<(), String> {
+    Err(NO_BUILTIN_RIFE.into())
+}
+
+/// Size of the rgb24 frames when `job` can stream through the built-in backend, or None when
+/// its frames have to go through the job folders: dedup timestamps, scene cuts and chapters,
+/// two-pass sizing, alpha and black-bar detection all revisit frames, and stage retries and
+/// kept intermediates need them on disk.
+fn streamed_frame_size(job: &SmoothJob) -> Option<(u32, u32)> {
+    let streamable = cfg!(feature = "ncnn")
+        && job.settings.rife_backend == RifeBackend::Builtin
+        && job.rife_runtime == RifeRuntime::NcnnVulkan
+        && job.settings.stream_frames
+        && job.start_at == JobStage::Extract
+        && !job.keep_intermediates
+        && !job.dedup
+        && !job.alpha
+        && job.cut_handling == CutHandling::Off
+        && !job.encode.chapters_at_cuts
+        && job.encode.target_size_mb.is_none()
+        && job.encode.backend == EncodeBackend::Ffmpeg
+        && !(job.settings.gpu_sharing == GpuSharing::Serialize && job.encode.encoder().uses_gpu())
+        && job.filters.bars == BarHandling::Off
+        && job.plugins.is_empty()
+        && job.vapoursynth.is_none()
+        && !job.skip_interpolation
+        && job.rife_factor.is_none()
+        && job.extra_passes == 0;
+    if !streamable {
+        return None;
+    }
+    // rawvideo has no header: the size has to be known up front, so follow the extraction
+    // filters that change it.
+    let (mut w, mut h) = upright_video_size(&job.ffmpeg, &job.input)?;
+    if let Some(c) = job.filters.crop {
+        (w, h) = (c.w, c.h);
+    }
+    if let Some(d) = draft_plan(job, None) {
+        (w, h) = d.scaled;
+    }
+    if let Some(s) = job.filters.scale {
+        if s.w <= 0 || s.h <= 0 {
+            return None;
+        }
+        (w, h) = (s.w as u32, s.h as u32);
+    }
+    Some((w, h))
+}
+
+/// Decode, interpolate and encode in one go: ffmpeg decodes rgb24 frames to a pipe, RIFE runs
+/// on them in memory, and the results (in the 2x order of `interpolate_builtin`) are written
+/// to the encoder's stdin. `enc` must read rawvideo of `size` from stdin.
+#[cfg(feature = "ncnn")]
+fn stream_encode(app: &AppHandle, job: &SmoothJob, mut enc: Command, (w, h): (u32, u32)) -> Result<(), String> {
+    use std::io::Write;
+    let state = app.state::<AppState>();
+    let model_name = job.model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (threads, _, _) = planned_rife_settings(job, &model_name, RifeDegrade::default());
+    let (net, takes_timestep) = load_rife_net(job, &model_profile(&job.root, &model_name), threads, true)?;
+
+    let mut dec = job.ffmpeg_command();
+    dec.arg("-hide_banner").arg("-loglevel").arg("error");
+    job.push_source_input_args(&mut dec);
+    if let Some(r) = job.region {
+        r.push_input_args(&mut dec);
+    }
+    dec.arg("-i").arg(&job.input);
+    job.corrupt_input.push_output_args(&mut dec);
+    job_extraction_chain(job, None).apply(&mut dec);
+    dec.arg("-vsync").arg("0")
+        .arg("-f").arg("rawvideo").arg("-pix_fmt").arg("rgb24").arg("-")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    log_stage_command(app, "extract", &mut dec);
+    new_process_group(&mut dec);
+    let mut decoder = dec.spawn().map_err(|e| format!("FFmpeg failed to start: {e}"))?;
+    enc.stdin(Stdio::piped());
+    log_stage_command(app, "encode", &mut enc);
+    new_process_group(&mut enc);
+    let mut encoder = match enc.spawn() {
+        Ok(c) => c,
+        Err(e) => {
+            kill_process(decoder.id());
+            let _ = decoder.wait();
+            return Err(format!("Encode failed to start: {e}"));
+        }
+    };
+    // Cancelling kills the encoder; the next write then fails and the decoder is stopped below.
+    state.set_job_child(&job.job_id, encoder.id());
+
+    let drain = |stderr: Option<std::process::ChildStderr>| {
+        stderr.map(|st| {
+            let app = app.clone();
+            spawn_in_job(move || {
+                let tail = OutputTail::new(&app);
+                for line in BufReader::new(st).lines().map_while(Result::ok) {
+                    let line = line.trim().to_string();
+                    if !line.is_empty() {
+                        tail.push(&line);
+                        emit_pipeline(&app, "pipeline_log", line);
+                    }
+                }
+                tail.lines()
+            })
+        })
+    };
+    let (dec_log, enc_log) = (drain(decoder.stderr.take()), drain(encoder.stderr.take()));
+
+    let frame_len = (w * h * 3) as usize;
+    let mut input = BufReader::new(decoder.stdout.take().ok_or("Decoder output unavailable")?);
+    let mut output = std::io::BufWriter::new(encoder.stdin.take().ok_or("Encoder input unavailable")?);
+    let mut read_frame = || {
+        let mut buf = vec![0u8; frame_len];
+        input.read_exact(&mut buf).ok()?;
+        image::RgbImage::from_raw(w, h, buf)
+    };
+    let expected = (job.duration_secs * job.fps_in * 2.0).max(1.0);
+    let mut written = 0u64;
+    let fed = (|| -> Result<(), String> {
+        let Some(mut prev) = read_frame() else { return Ok(()) };
+        loop {
+            check_stage_cancelled(app, &job.job_id, JobStage::Encode)?;
+            let next = read_frame();
+            let middle = match &next {
+                Some(next) => rife_pair(&net, &prev, next, takes_timestep)?,
+                None => prev.clone(),
+            };
+            output.write_all(prev.as_raw())
+                .and_then(|_| output.write_all(middle.as_raw()))
+                .map_err(|e| format!("The encoder stopped taking frames: {e}"))?;
+            written += 2;
+            emit_progress_frames(app, (written as f64 / expected * 100.0).min(99.0), written);
+            match next {
+                Some(next) => prev = next,
+                None => return output.flush().map_err(|e| format!("The encoder stopped taking frames: {e}")),
+            }
+        }
+    })();
+    // Closing stdin lets the encoder finish; on failure neither process has anything left to do.
+    drop(output);
+    if fed.is_err() {
+        kill_process(decoder.id());
+        kill_process(encoder.id());
+    }
+    // Both waits go through the kill watchdog, so a process stuck in the driver fails the
+    // stage instead of hanging it.
+    let dec_status = wait_stage_child(app, &job.job_id, &mut decoder)?;
+    let enc_status = wait_stage_child(app, &job.job_id, &mut encoder)?;
+    // A reader still blocked after `READER_JOIN_SECS` is left to end with its pipe.
+    let join_tail = |log: Option<std::thread::JoinHandle<Vec<String>>>| {
+        let log = log?;
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(READER_JOIN_SECS);
+        while !log.is_finished() && std::time::Instant::now() < deadline {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        log.is_finished().then(|| log.join().ok()).flatten()
+    };
+    let dec_tail = join_tail(dec_log).unwrap_or_default();
+    let enc_tail = join_tail(enc_log).unwrap_or_default();
+    check_stage_cancelled(app, &job.job_id, JobStage::Encode)?;
+    fed?;
+    if !dec_status.map(|s| s.success()).unwrap_or(false) {
+        return Err(stage_failure(app, &job.job_id, "FFmpeg (decode)", "Decoding failed", dec_status, &dec_tail));
+    }
+    if written == 0 {
+        return Err("The decoder produced no frames".into());
+    }
+    if !enc_status.map(|s| s.success()).unwrap_or(false) {
+        return Err(stage_failure(app, &job.job_id, "FFmpeg (encode)", "Encoding failed", enc_status, &enc_tail));
+    }
+    emit_log_limited(app, &format!("Streamed {written} frames through RIFE"));
+    Ok(())
+}
+
+#[cfg(not(feature = "ncnn"))]
+fn stream_encode(_app: &AppHandle, _job: &SmoothJob, _enc: Command, _size: (u32, u32)) -> Result<(), String> {
+    Err(NO_BUILTIN_RIFE.into())
+}
+
+/// The frame halfway between `a` and `b`.
+#[cfg(feature = "ncnn")]
+fn rife_pair(net: &ncnn_rs::Net, a: &image::RgbImage, b: &image::RgbImage, takes_timestep: bool) -> Result<image::RgbImage, String> {
+    let (w, h) = a.dimensions();
+    let (pw, ph) = (w.div_ceil(RIFE_PAD) * RIFE_PAD, h.div_ceil(RIFE_PAD) * RIFE_PAD);
+    let to_mat = |img: &image::RgbImage| -> Result<ncnn_rs::Mat, String> {
+        let mut padded = image::RgbImage::new(pw, ph);
+        image::imageops::replace(&mut padded, img, 0, 0);
+        let mut mat = ncnn_rs::Mat::from_pixels(padded.as_raw(), ncnn_rs::MatPixelType::RGB, pw as i32, ph as i32, None)
+            .map_err(|e| e.to_string())?;
+        mat.substract_mean_normalize(&[0.0; 3], &[1.0 / 255.0; 3]);
+        Ok(mat)
+    };
+    let (in0, in1) = (to_mat(a)?, to_mat(b)?);
+    let mut ex = net.create_extractor();
+    ex.input("in0", &in0).map_err(|e| e.to_string())?;
+    ex.input("in1", &in1).map_err(|e| e.to_string())?;
+    let mut timestep = ncnn_rs::Mat::new_3d(pw as i32, ph as i32, 1, None);
+    if takes_timestep {
+        timestep.fill(0.5);
+        ex.input("in2", &timestep).map_err(|e| e.to_string())?;
+    }
+    let mut out = ncnn_rs::Mat::new();
+    ex.extract("out0", &mut out).map_err(|e| format!("RIFE inference failed: {e}"))?;
+
+    // `out` is planar float RGB in 0..1 at the padded size; crop back to the frame.
+    let plane = out.cstep() as usize;
+    // SAFETY: the extracted blob holds 3 channels of `cstep` floats each.
+    let data = unsafe { std::slice::from_raw_parts(out.data() as *const f32, plane * 3) };
+    Ok(image::RgbImage::from_fn(w, h, |x, y| {
+        let at = (y * pw + x) as usize;
+        image::Rgb([0, 1, 2].map(|c| (data[c * plane + at] * 255.0).round().clamp(0.0, 255.0) as u8))
+    }))
+}
+
+#[cfg(feature = "ncnn")]
+fn save_frame(img: &image::RgbImage, path: &Path) -> Result<(), String> {
+    let file = fs::File::create(path).map_err(|e| format!("Can't write {}: {e}", path.display()))?;
+    let mut writer = std::io::BufWriter::new(file);
+    let written = match FrameFormat::of_path(path) {
+        FrameFormat::Webp => img.write_with_encoder(image::codecs::webp::WebPEncoder::new_lossless(&mut writer)),
+        FrameFormat::Png => img.write_with_encoder(image::codecs::png::PngEncoder::new_with_quality(
+            &mut writer,
+            image::codecs::png::CompressionType::Fast,
+            image::codecs::png::FilterType::Adaptive,
+        )),
+    };
+    written.map_err(|e| format!("Can't write {}: {e}", path.display()))
+}
+
+// -------------------- Accelerated RIFE runtimes --------------------
+
+/// External RIFE builds a job can run with. Each is its own tool (`bin/<tool>/<version>`, with
+/// its models next to the binary) installed through `install_tool`; all of them take
+/// rife-ncnn-vulkan's `-i/-o/-m/-f` arguments.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum RifeRuntime {
+    #[default]
+    #[serde(rename = "ncnn_vulkan")]
+    NcnnVulkan,
+    /// NVIDIA RTX (Turing or newer).
+    #[serde(rename = "tensorrt")]
+    TensorRt,
+    /// Apple Silicon.
+    #[serde(rename = "coreml")]
+    CoreMl,
+}
+
+/// TensorRT builds need tensor cores, i.e. compute capability 7.5 (Turing) or newer.
+const TENSORRT_MIN_COMPUTE_CAP: f64 = 7.5;
+
+impl RifeRuntime {
+    const ALL: [RifeRuntime; 3] = [RifeRuntime::NcnnVulkan, RifeRuntime::TensorRt, RifeRuntime::CoreMl];
+
+    fn tool(self) -> &'static str {
+        match self {
+            RifeRuntime::NcnnVulkan => "rife",
+            RifeRuntime::TensorRt => "rife-trt",
+            RifeRuntime::CoreMl => "rife-coreml",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            RifeRuntime::NcnnVulkan => "ncnn (Vulkan)",
+            RifeRuntime::TensorRt => "TensorRT",
+            RifeRuntime::CoreMl => "Core ML",
+        }
+    }
+
+    fn of_tool(tool: &str) -> Option<RifeRuntime> {
+        RifeRuntime::ALL.into_iter().find(|r| r.tool() == tool)
+    }
+
+    /// Whether this machine can run the runtime at all, with the reason when it can't.
+    fn hardware_support(self) -> Result<(), String> {
+        match self {
+            RifeRuntime::NcnnVulkan => Ok(()),
+            RifeRuntime::TensorRt => {
+                let out = run_tool(
+                    tool_command("nvidia-smi").arg("--query-gpu=name,compute_cap").arg("--format=csv,noheader"),
+                    QUERY_TIMEOUT_SECS,
+                )
+                .map_err(|_| "TensorRT needs an NVIDIA GPU (nvidia-smi not found)".to_string())?;
+                let text = String::from_utf8_lossy(&out.stdout);
+                let (name, cap) = text
+                    .lines()
+                    .next()
+                    .and_then(|l| l.rsplit_once(','))
+                    .ok_or("TensorRT needs an NVIDIA GPU (none reported by nvidia-smi)")?;
+                match cap.trim().parse::<f64>() {
+                    Ok(c) if c >= TENSORRT_MIN_COMPUTE_CAP => Ok(()),
+                    Ok(c) => Err(format!("{} (compute {c}) is too old for TensorRT; RTX/Turing or newer is needed", name.trim())),
+                    Err(_) => Err(format!("Couldn't read the compute capability of {}; the driver may be too old", name.trim())),
+                }
+            }
+            RifeRuntime::CoreMl if cfg!(all(target_os = "macos", target_arch = "aarch64")) => Ok(()),
+            RifeRuntime::CoreMl => Err("Core ML builds need a Mac with Apple Silicon".into()),
+        }
+    }
+
+    /// Binary and models folder for a job on this runtime.
+    fn resolve(self, root: &Path, tools: &ResolvedTools) -> Result<(PathBuf, PathBuf), String> {
+        if self == RifeRuntime::NcnnVulkan {
+            let rife_bin = tools.rife.clone().ok_or("rife not installed (install rife first)")?;
+            let model_dir = tools.rife_models.clone().ok_or("RIFE models folder not found (install rife first)")?;
+            return Ok((rife_bin, model_dir));
+        }
+        self.hardware_support()?;
+        self.installed(root, tools).ok_or_else(|| {
+            format!("{} RIFE isn't installed (install the {} tool with its models first)", self.label(), self.tool())
+        })
+    }
+
+    /// Binary and models folder of the installed runtime.
+    fn installed(self, root: &Path, tools: &ResolvedTools) -> Option<(PathBuf, PathBuf)> {
+        if self == RifeRuntime::NcnnVulkan {
+            return tools.rife.clone().zip(tools.rife_models.clone());
+        }
+        fs::read_dir(root.join("bin").join(self.tool()))
+            .ok()?
+            .flatten()
+            .map(|v| v.path())
+            .filter(|p| p.is_dir())
+            .find_map(|p| find_rife_in_version_dir(&p).zip(find_models_dir(&p)))
+    }
+
+    /// Everything after `-i/-o/-m/-f`. The TensorRT and Core ML builds take rife-ncnn-vulkan's
+    /// command line, but only its GPU choice: `-j` and `-u` tune ncnn, and Core ML picks its
+    /// compute units itself.
+    fn push_args(self, cmd: &mut Command, threads: RifeThreads, uhd: bool, gpu: Option<u32>) {
+        match self {
+            RifeRuntime::NcnnVulkan => {
+                cmd.arg("-j").arg(threads.to_string());
+                if uhd {
+                    cmd.arg("-u");
+                }
+                if let Some(gpu) = gpu {
+                    cmd.arg("-g").arg(gpu.to_string());
+                }
+            }
+            RifeRuntime::TensorRt => {
+                if let Some(gpu) = gpu {
+                    cmd.arg("-g").arg(gpu.to_string());
+                }
+            }
+            RifeRuntime::CoreMl => {}
+        }
+    }
+
+    /// `job` moved to the ncnn (Vulkan) runtime, for when its own runtime ran out of
+    /// resources: the same model when ncnn has one of that name, else ncnn's default.
+    fn fall_back_to_ncnn(job: &SmoothJob, tools: &ResolvedTools) -> Option<SmoothJob> {
+        let (rife_bin, default_model) = RifeRuntime::NcnnVulkan.resolve(&job.root, tools).ok()?;
+        let name = job.model_dir.file_name()?.to_string_lossy().to_string();
+        let model_dir = resolve_model_dir(&job.root, tools, default_model.clone(), Some(&name)).unwrap_or(default_model);
+        Some(SmoothJob { rife_runtime: RifeRuntime::NcnnVulkan, rife_bin, model_dir, ..job.clone() })
+    }
+}
+
+#[derive(serde::Serialize)]
+struct RifeRuntimeStatus {
+    runtime: RifeRuntime,
+    label: &'static str,
+    tool: &'static str,
+    installed: Option<String>,
+    supported: bool,
+    /// Why the runtime can't run here, when it can't.
+    reason: Option<String>,
+}
+
+/// RIFE runtimes, whether each is installed and whether this machine can run it.
+#[tauri::command]
+fn get_rife_runtimes(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<RifeRuntimeStatus>, String> {
+    let root = app_root(&app)?;
+    let tools = state.tools(&root);
+    Ok(RifeRuntime::ALL
+        .into_iter()
+        .map(|runtime| {
+            let support = runtime.hardware_support();
+            RifeRuntimeStatus {
+                runtime,
+                label: runtime.label(),
+                tool: runtime.tool(),
+                installed: runtime.installed(&root, &tools).map(|(bin, _)| bin.to_string_lossy().to_string()),
+                supported: support.is_ok(),
+                reason: support.err(),
+            }
+        })
+        .collect())
+}
+
+// -------------------- Corrupt input --------------------
+
+/// How extraction treats damaged input. Everything is off by default: a clean failure is
+/// better than silently glitched output unless the user asks for it.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct CorruptInputOptions {
+    /// `-err_detect ignore_err`: keep decoding past bitstream errors.
+    ignore_errors: bool,
+    /// `-fflags +genpts+discardcorrupt`: drop corrupt packets and regenerate missing timestamps.
+    discard_corrupt: bool,
+    /// Stop extraction once this many decode errors were logged; unset means no limit.
+    max_errors: Option<u32>,
+    /// When extraction fails, extract again segment by segment (see `extract_segmented`) so a
+    /// bad stretch costs a freeze-frame instead of the job.
+    isolate_segments: bool,
+}
+
+/// Decode errors this close together (in seconds) are reported as one damaged region.
+const DAMAGE_MERGE_SECS: f64 = 1.0;
+
+impl CorruptInputOptions {
+    fn tolerant(&self) -> bool {
+        self.ignore_errors || self.discard_corrupt
+    }
+
+    /// Input options; they go before `-i`.
+    fn push_input_args(&self, cmd: &mut Command) {
+        if self.ignore_errors {
+            cmd.arg("-err_detect").arg("ignore_err");
+        }
+        if self.discard_corrupt {
+            cmd.arg("-fflags").arg("+genpts+discardcorrupt");
+        }
+    }
+
+    /// Output options. ffmpeg gives up by itself once 2/3 of the frames had errors; with errors
+    /// ignored, `max_errors` decides instead.
+    fn push_output_args(&self, cmd: &mut Command) {
+        if self.ignore_errors {
+            cmd.arg("-max_error_rate").arg("1");
+        }
+    }
+}
+
+/// Whether an ffmpeg log line reports damaged input rather than progress or a setup problem.
+fn is_decode_error(line: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "error while decoding",
+        "concealing",
+        "corrupt",
+        "invalid nal unit",
+        "missing reference",
+        "decode_slice_header error",
+        "non-existing pps",
+        "no frame!",
+        "invalid data found",
+    ];
+    let lower = line.to_ascii_lowercase();
+    MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// Decode errors seen during extraction, grouped into time ranges of the source.
+#[derive(Default)]
+struct DamageReport {
+    errors: u32,
+    /// Start and end (seconds) and error count of each damaged region.
+    regions: Vec<(f64, f64, u32)>,
+}
+
+impl DamageReport {
+    fn note(&mut self, at_secs: f64) {
+        self.errors += 1;
+        match self.regions.last_mut() {
+            Some(r) if at_secs - r.1 <= DAMAGE_MERGE_SECS => {
+                r.1 = r.1.max(at_secs);
+                r.2 += 1;
+            }
+            _ => self.regions.push((at_secs, at_secs, 1)),
+        }
+    }
+
+    fn describe(&self, fps: f64) -> String {
+        let regions: Vec<String> = self
+            .regions
+            .iter()
+            .map(|(start, end, n)| format!("{}–{} ({n})", timecode(*start, fps), timecode(*end, fps)))
+            .collect();
+        format!("Damaged input: {} decode error(s) in {} region(s): {}", self.errors, regions.len(), regions.join(", "))
+    }
+}
+
+/// Segmented extraction cuts at keyframes at least this far apart.
+const SEGMENT_MIN_SECS: f64 = 10.0;
+
+/// Keyframe times of the first video stream, from the packet index (nothing is decoded).
+fn keyframe_times(ffmpeg: &Path, input: &Path) -> Vec<f64> {
+    let Some(out) = ffprobe_path(ffmpeg).and_then(|ffprobe| {
+        run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "packet=pts_time,flags", "-of", "csv=p=0"], input)
+    }) else {
+        return Vec::new();
+    };
+    out.lines()
+        .filter_map(|l| {
+            let (t, flags) = l.split_once(',')?;
+            flags.contains('K').then(|| t.trim().parse().ok()).flatten()
+        })
+        .collect()
+}
+
+/// Segments (start, end) in seconds from the start of the file, cut at keyframes.
+fn segment_bounds(keyframes: &[f64], duration: f64) -> Vec<(f64, f64)> {
+    // Packet times are absolute; -ss counts from the start of the file.
+    let base = keyframes.first().copied().unwrap_or(0.0);
+    let mut starts = vec![0.0];
+    for k in keyframes.iter().map(|k| k - base) {
+        if k < duration && k - starts[starts.len() - 1] >= SEGMENT_MIN_SECS {
+            starts.push(k);
+        }
+    }
+    starts.iter().enumerate().map(|(i, &s)| (s, starts.get(i + 1).copied().unwrap_or(duration))).collect()
+}
+
+/// Extract the job's frames one keyframe segment at a time, numbering them on into one
+/// sequence (see `ChunkSpan`). A segment that fails is replaced by a freeze of the nearest
+/// good frame for its length, so the later stages see a complete sequence. Dedup is skipped:
+/// its timestamps can't be stitched across segments.
+fn extract_segmented(app: &AppHandle, job: &SmoothJob, bars: Option<CropRect>) -> Result<Vec<f64>, String> {
+    clear_dir(&job.frames_in_dir)?;
+    shard_for_job(job, &job.frames_in_dir)?;
+    let segments = segment_bounds(&keyframe_times(&job.ffmpeg, &job.input), job.duration_secs);
+    let fps = job.fps_in.max(1.0);
+    let spans = chunk_spans(&segments, fps);
+    let files = FrameFiles::new(&job.frames_in_dir, job.frame_format);
+    let frame = |n: usize| files.path(n);
+    let staging = job.frames_in_dir.with_extension("chunk");
+    emit_log_limited(app, &format!("Segmented extraction: {} segment(s) of at least {SEGMENT_MIN_SECS}s", spans.len()));
+    if job.dedup {
+        emit_log_limited(app, "Dedup is off for segmented extraction");
+    }
+
+    let mut next = 1;
+    // Failed segments before the first good frame wait for it: (first number, frame count).
+    let mut unfilled: Vec<(usize, usize)> = Vec::new();
+    let mut frozen = Vec::new();
+    let mut seam: Option<Vec<u8>> = None;
+    let mut seams = SeamCheck::default();
+    // Decode `span` into staging. The decoder seeks to `seek_from` (at or before the span)
+    // and skips ahead to the span from there. Returns whether ffmpeg succeeded and the last
+    // line it logged.
+    let decode_chunk = |span: &ChunkSpan, last_chunk: bool, seek_from: f64| -> Result<(bool, Option<String>), String> {
+        let (start, end) = (span.start_secs, span.end_secs);
+        clear_dir(&staging)?;
+        fs::create_dir_all(&staging).map_err(|e| format!("Failed to create {}: {e}", staging.to_string_lossy()))?;
+        let mut cmd = job.ffmpeg_command();
+        cmd.arg("-hide_banner").arg("-y");
+        job.push_source_input_args(&mut cmd);
+        // Room for the overlap frame; -frames:v cuts at exactly the chunk plus overlap.
+        cmd.arg("-ss").arg(format!("{seek_from:.6}"))
+            .arg("-t").arg(format!("{:.6}", end - seek_from + if last_chunk { 0.0 } else { 2.0 / fps }))
+            .arg("-i").arg(&job.input);
+        if start > seek_from {
+            cmd.arg("-ss").arg(format!("{:.6}", start - seek_from));
+        }
+        job.corrupt_input.push_output_args(&mut cmd);
+        job_extraction_chain(job, bars).apply(&mut cmd);
+        if !last_chunk {
+            cmd.arg("-frames:v").arg((span.count + 1).to_string());
+        }
+        if job.alpha {
+            cmd.arg("-pix_fmt").arg("rgba");
+        }
+        job.frame_format.push_encoder_args(&mut cmd);
+        cmd.arg("-vsync").arg("0")
+            .arg("-start_number").arg("1")
+            .arg(job.frame_format.pattern(&staging))
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+        log_stage_command(app, "extract", &mut cmd);
+        let mut process = spawn_stage_process(app, &job.job_id, "FFmpeg", &mut cmd)?;
+        process.relay(Pipe::Stderr, |_| true);
+        let status = process.wait(app)?;
+        check_stage_cancelled(app, &job.job_id, JobStage::Extract)?;
+        Ok((status.is_some_and(|s| s.success()), process.tail.lines().pop()))
+    };
+    for (i, span) in spans.iter().enumerate() {
+        let (start, end) = (span.start_secs, span.end_secs);
+        let last_chunk = i + 1 == spans.len();
+        check_stage_cancelled(app, &job.job_id, JobStage::Extract)?;
+        let pct = job.stage_weights.overall(JobStage::Extract, i as f64 / spans.len() as f64);
+        emit_pipeline(app, "pipeline_progress", pct);
+        let (decoded, last_line) = decode_chunk(span, last_chunk, start)?;
+
+        let produced = count_files_in_dir(&staging);
+        if decoded && produced > 0 {
+            let mut placed = place_chunk(&staging, &job.frames_in_dir, job.frame_format, span, last_chunk)?;
+            if placed.padded > 0 {
+                emit_log_limited(app, &format!(
+                    "Segment {}/{} ended {} frame(s) early; repeating its last frame",
+                    i + 1,
+                    spans.len(),
+                    placed.padded
+                ));
+            }
+            if let Some(prev) = seam.take() {
+                if !seams.check(app, &prev, &frame(span.first), span.first) {
+                    // The seek landed on another frame than the previous segment's decode
+                    // reached. Decoding across the seam from the previous segment's start
+                    // reaches the same frames the way that decode did.
+                    let seek_from = spans[i - 1].start_secs;
+                    emit_log_limited(app, &format!("Re-extracting segment {}/{} from {}", i + 1, spans.len(), timecode(seek_from, fps)));
+                    let (decoded, _) = decode_chunk(span, last_chunk, seek_from)?;
+                    if !decoded || count_files_in_dir(&staging) == 0 {
+                        return Err(format!("Segment {}/{} failed to re-extract across its seam", i + 1, spans.len()));
+                    }
+                    placed = place_chunk(&staging, &job.frames_in_dir, job.frame_format, span, last_chunk)?;
+                    if fs::read(frame(span.first)).ok().as_deref() != Some(prev.as_slice()) {
+                        return Err(format!(
+                            "Segment seam at frame {} still differs after re-extracting; the source's timestamps don't line up \
+                             across segments (turn off segmented extraction)",
+                            span.first
+                        ));
+                    }
+                    seams.rerendered += 1;
+                }
+            }
+            seam = placed.overlap;
+            for (first, count) in unfilled.drain(..) {
+                fill_frames(&frame(span.first), &job.frames_in_dir, job.frame_format, first, count)?;
+            }
+            next = span.first + placed.frames;
+            continue;
+        }
+
+        seam = None;
+        let reason = last_line.map(|l| l.trim().to_string()).unwrap_or_else(|| "no frames decoded".into());
+        emit_log_limited(app, &format!(
+            "Segment {}/{} ({}–{}) failed ({reason}); using a {:.1}s freeze-frame",
+            i + 1,
+            spans.len(),
+            timecode(start, fps),
+            timecode(end, fps),
+            end - start
+        ));
+        frozen.push(format!("{}–{}", timecode(start, fps), timecode(end, fps)));
+        if span.first > 1 {
+            fill_frames(&frame(span.first - 1), &job.frames_in_dir, job.frame_format, span.first, span.count)?;
+        } else {
+            unfilled.push((span.first, span.count));
+        }
+        next = span.end();
+    }
+    let _ = fs::remove_dir_all(&staging);
+    if !unfilled.is_empty() {
+        return Err("No segment of the input could be decoded".into());
+    }
+    emit_log_limited(app, &format!("Extracted {} frames in {} segment(s)", next - 1, spans.len()));
+    if seams.checked > 0 {
+        emit_log_limited(app, &format!(
+            "Segment seams: {} checked, {} mismatched and re-extracted",
+            seams.checked, seams.rerendered
+        ));
+    }
+    if !frozen.is_empty() {
+        emit_log_limited(app, &format!("Frozen (undecodable) ranges: {}", frozen.join(", ")));
+    }
+    Ok(Vec::new())
+}
+
+// -------------------- Chunk boundaries --------------------
+
+/// One chunk of a segmented path, in the numbering of the whole sequence. Chunks are
+/// contiguous: chunk N+1 starts at `end()` of chunk N. Each chunk but the last is decoded with
+/// one frame of overlap, its `end()` frame, which must equal the next chunk's first frame; the
+/// overlap is only compared, never written, so a seam can neither repeat nor drop a frame.
+#[derive(Clone, Copy, Debug)]
+struct ChunkSpan {
+    /// 1-based number of the chunk's first frame.
+    first: usize,
+    /// Frames the chunk owns, not counting the overlap.
+    count: usize,
+    start_secs: f64,
+    end_secs: f64,
+}
+
+impl ChunkSpan {
+    /// Number of the next chunk's first frame.
+    fn end(&self) -> usize {
+        self.first + self.count
+    }
+}
+
+/// Chunks for contiguous `bounds` in seconds. Frame numbers are rounded from the absolute
+/// times rather than from each chunk's length, so rounding never adds up across chunks.
+fn chunk_spans(bounds: &[(f64, f64)], fps: f64) -> Vec<ChunkSpan> {
+    let frame_at = |t: f64| (t * fps).round() as usize + 1;
+    bounds
+        .iter()
+        .map(|&(start_secs, end_secs)| {
+            let first = frame_at(start_secs);
+            ChunkSpan { first, count: frame_at(end_secs).saturating_sub(first), start_secs, end_secs }
+        })
+        .filter(|c| c.count > 0)
+        .collect()
+}
+
+/// Frames of one chunk after `place_chunk`.
+struct PlacedChunk {
+    frames: usize,
+    /// Frames repeated because the chunk decoded fewer than it owns.
+    padded: usize,
+    /// The decoded overlap frame, for comparison with the next chunk.
+    overlap: Option<Vec<u8>>,
+}
+
+/// Move a chunk's frames from `staging` (numbered from 1) to their numbers in `dir`. A chunk
+/// that came up short is padded with its last frame and one that ran long is cut, so later
+/// chunks keep their numbers; the last chunk keeps whatever it decoded.
+fn place_chunk(staging: &Path, dir: &Path, format: FrameFormat, span: &ChunkSpan, last: bool) -> Result<PlacedChunk, String> {
+    let files = sorted_files(staging);
+    let frames = if last { files.len() } else { span.count };
+    let overlap = if last { None } else { files.get(span.count).and_then(|f| fs::read(f).ok()) };
+    let taken = files.len().min(frames);
+    let out = FrameFiles::new(dir, format);
+    for (k, src) in files.iter().take(taken).enumerate() {
+        fs::rename(src, out.create(span.first + k)?).map_err(|e| format!("Failed to move segment frames: {e}"))?;
+    }
+    let padded = frames - taken;
+    if padded > 0 && taken > 0 {
+        fill_frames(&out.path(span.first + taken - 1), dir, format, span.first + taken, padded)?;
+    }
+    Ok(PlacedChunk { frames, padded, overlap })
+}
+
+/// Running comparison of chunk overlaps with the frames that follow them.
+#[derive(Default)]
+struct SeamCheck {
+    checked: usize,
+    /// Seams that differed and were fixed by decoding the chunk again.
+    rerendered: usize,
+}
+
+impl SeamCheck {
+    /// Frames are written by a deterministic encoder, so a decoder that lands on the same
+    /// frame from either side of a seam produces the same bytes. False on a mismatch.
+    fn check(&mut self, app: &AppHandle, overlap: &[u8], first: &Path, n: usize) -> bool {
+        self.checked += 1;
+        let same = fs::read(first).ok().as_deref() == Some(overlap);
+        if !same {
+            emit_log_limited(app, &format!("Segment seam at frame {n} differs between the two segments"));
+        }
+        same
+    }
+}
+
+/// Write `count` copies of `src` as frames `first..` in `dir`. Hard links where the
+/// filesystem allows, since RIFE only reads them.
+fn fill_frames(src: &Path, dir: &Path, format: FrameFormat, first: usize, count: usize) -> Result<(), String> {
+    let files = FrameFiles::new(dir, format);
+    for n in first..first + count {
+        let dest = files.create(n)?;
+        if fs::hard_link(src, &dest).is_err() {
+            fs::copy(src, &dest).map_err(|e| format!("Failed to write freeze-frame {}: {e}", dest.to_string_lossy()))?;
+        }
+    }
+    Ok(())
+}
+
+// -------------------- Intermediate frames --------------------
+
+/// Image format of a job's frame folders. Both are lossless and read by rife-ncnn-vulkan;
+/// lossless WebP files are considerably smaller than PNG, which matters for long jobs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FrameFormat {
+    #[default]
+    Png,
+    Webp,
+}
+
+impl FrameFormat {
+    fn ext(self) -> &'static str {
+        match self {
+            FrameFormat::Png => "png",
+            FrameFormat::Webp => "webp",
+        }
+    }
+
+    /// image2 pattern for the numbered frames in `dir`.
+    fn pattern(self, dir: &Path) -> PathBuf {
+        dir.join(format!("%08d.{}", self.ext()))
+    }
+
+    /// Size of a frame relative to PNG, for estimates; lossless WebP is about a quarter smaller.
+    fn size_factor(self) -> f64 {
+        match self {
+            FrameFormat::Png => 1.0,
+            FrameFormat::Webp => 0.75,
+        }
+    }
+
+    fn of_path(path: &Path) -> FrameFormat {
+        match output_extension(path).as_str() {
+            "webp" => FrameFormat::Webp,
+            _ => FrameFormat::Png,
+        }
+    }
+
+    /// Format of the frames already in `dir`; PNG when it is empty.
+    fn in_dir(dir: &Path) -> FrameFormat {
+        let first_file = |dir: &Path| fs::read_dir(dir).ok()?.flatten().map(|e| e.path()).find(|p| p.is_file());
+        first_file(dir)
+            .or_else(|| first_file(&dir.join(shard_name(1))))
+            .map(|p| FrameFormat::of_path(&p))
+            .unwrap_or_default()
+    }
+
+    /// ffmpeg output options for writing frames. ffmpeg picks the encoder from the extension,
+    /// but libwebp defaults to lossy.
+    fn push_encoder_args(self, cmd: &mut Command) {
+        if self == FrameFormat::Webp {
+            cmd.arg("-c:v").arg("libwebp")
+                .arg("-lossless").arg("1")
+                // Fastest lossless effort; higher levels cost a lot of CPU for a few percent.
+                .arg("-compression_level").arg("0");
+        }
+    }
+}
+
+/// Counts numbered frames (`%08d.<ext>`, from 1) as a tool writes them by checking for the
+/// next expected names instead of listing the folder, so a poll costs a few `stat` calls
+/// however many frames are already there. Frames written out of order are counted once the
+/// gap before them fills.
+struct FrameCounter {
+    dir: PathBuf,
+    ext: &'static str,
+    seen: usize,
+}
+
+impl FrameCounter {
+    fn new(dir: &Path, format: FrameFormat) -> Self {
+        FrameCounter { dir: dir.to_path_buf(), ext: format.ext(), seen: 0 }
+    }
+
+    /// Frames may be in the folder itself or already moved on into their shard.
+    fn count(&mut self) -> usize {
+        loop {
+            let n = self.seen + 1;
+            let name = format!("{n:08}.{}", self.ext);
+            if !self.dir.join(&name).exists() && !self.dir.join(shard_name(n)).join(&name).exists() {
+                return self.seen;
+            }
+            self.seen = n;
+        }
+    }
+}
+
+// -------------------- Frame shards --------------------
+
+/// Frames per shard folder. Listing and lookups in one folder slow down badly on NTFS and
+/// APFS once it holds hundreds of thousands of files.
+const SHARD_FRAMES: usize = 10_000;
+/// Jobs with more source frames than this keep frames_in and frames_out in shard folders.
+const SHARD_MIN_FRAMES: f64 = 50_000.0;
+
+/// Shard folder of 1-based frame `n`: frames 1-10000 are in `0000`, and so on.
+fn shard_name(n: usize) -> String {
+    format!("{:04}", n.saturating_sub(1) / SHARD_FRAMES)
+}
+
+fn is_shard_name(name: &str) -> bool {
+    name.len() == 4 && name.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// A frame folder is sharded once its first shard exists. Everything that reads frames
+/// handles both layouts, so a stage that needs a flat folder can flatten it.
+fn is_sharded(dir: &Path) -> bool {
+    dir.join(shard_name(1)).is_dir()
+}
+
+fn make_sharded(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir.join(shard_name(1))).map_err(|e| format!("Failed to create {}: {e}", dir.to_string_lossy()))
+}
+
+/// Make `dir` (freshly cleared) sharded when the job has enough frames to need it.
+fn shard_for_job(job: &SmoothJob, dir: &Path) -> Result<bool, String> {
+    let sharded = job.duration_secs * job.fps_in > SHARD_MIN_FRAMES;
+    if sharded {
+        make_sharded(dir)?;
+    }
+    Ok(sharded)
+}
+
+/// Paths of the numbered frames of one folder, flat or sharded.
+struct FrameFiles {
+    dir: PathBuf,
+    ext: &'static str,
+    sharded: bool,
+}
+
+impl FrameFiles {
+    fn new(dir: &Path, format: FrameFormat) -> Self {
+        FrameFiles { dir: dir.to_path_buf(), ext: format.ext(), sharded: is_sharded(dir) }
+    }
+
+    fn path(&self, n: usize) -> PathBuf {
+        let name = format!("{n:08}.{}", self.ext);
+        if self.sharded {
+            self.dir.join(shard_name(n)).join(name)
+        } else {
+            self.dir.join(name)
+        }
+    }
+
+    /// `path`, creating its shard folder when it doesn't exist yet. Only the first frame of a
+    /// shard checks, so frames have to be written in order; see `create_shards` otherwise.
+    fn create(&self, n: usize) -> Result<PathBuf, String> {
+        let path = self.path(n);
+        if self.sharded && (n % SHARD_FRAMES == 1 || n == 1) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {e}", parent.to_string_lossy()))?;
+            }
+        }
+        Ok(path)
+    }
+
+    /// Create every shard folder frames 1 to `last` go in, for writes out of order.
+    fn create_shards(&self, last: usize) -> Result<(), String> {
+        if !self.sharded {
+            return Ok(());
+        }
+        for n in (1..=last).step_by(SHARD_FRAMES) {
+            let dir = self.dir.join(shard_name(n));
+            fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.to_string_lossy()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Move the numbered frames directly in `dir` into their shards.
+fn shard_frames(dir: &Path) -> Result<(), String> {
+    make_sharded(dir)?;
+    for path in fs::read_dir(dir).into_iter().flatten().flatten().map(|e| e.path()).filter(|p| p.is_file()) {
+        let Some(n) = path.file_stem().and_then(|s| s.to_str()).and_then(|s| s.parse::<usize>().ok()) else { continue };
+        let shard = dir.join(shard_name(n));
+        fs::create_dir_all(&shard).map_err(|e| format!("Failed to create {}: {e}", shard.to_string_lossy()))?;
+        if let Some(name) = path.file_name() {
+            fs::rename(&path, shard.join(name)).map_err(|e| format!("Failed to move frames into shards: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Shard folders of a sharded `dir` in frame order, each with the number of its first frame.
+fn shard_dirs(dir: &Path) -> Vec<(PathBuf, usize)> {
+    let mut shards: Vec<(PathBuf, usize)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let k = name.parse::<usize>().ok().filter(|_| is_shard_name(&name))?;
+            Some((e.path(), k * SHARD_FRAMES + 1))
+        })
+        .collect();
+    shards.sort_by_key(|(_, first)| *first);
+    shards
+}
+
+/// Move the frames of `from` (flat or sharded), in name order, on to `to` as frames `next`,
+/// `next + 1`, ... Returns the next free number.
+fn append_frames(from: &Path, to: &FrameFiles, mut next: usize) -> Result<usize, String> {
+    for f in sorted_files(from) {
+        fs::rename(&f, to.create(next)?).map_err(|e| format!("Failed to move {}: {e}", f.to_string_lossy()))?;
+        next += 1;
+    }
+    Ok(next)
+}
+
+/// Moves frames a tool writes into a flat folder on into their shards while it runs, so the
+/// folder itself never holds more than a few. Tools write frames in order, so every frame
+/// before the newest one present is complete.
+struct ShardMover {
+    dir: PathBuf,
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl ShardMover {
+    fn start(dir: &Path, format: FrameFormat) -> Self {
+        let stop = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let files = FrameFiles { dir: dir.to_path_buf(), ext: format.ext(), sharded: true };
+        let stopped = stop.clone();
+        let handle = std::thread::spawn(move || {
+            let flat = |n: usize| files.dir.join(format!("{n:08}.{}", files.ext));
+            let mut n = 1;
+            while !stopped.load(std::sync::atomic::Ordering::Relaxed) {
+                while flat(n + 1).exists() {
+                    let moved = files.create(n).and_then(|dest| fs::rename(flat(n), dest).map_err(|e| e.to_string()));
+                    if moved.is_err() {
+                        break;
+                    }
+                    n += 1;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+        });
+        ShardMover { dir: dir.to_path_buf(), stop, handle: Some(handle) }
+    }
+
+    /// Stop, then shard whatever the tool left in the folder.
+    fn finish(mut self) -> Result<(), String> {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+        if let Some(h) = self.handle.take() {
+            let _ = h.join();
+        }
+        shard_frames(&self.dir)
+    }
+}
+
+impl Drop for ShardMover {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// ffmpeg input options that read a folder of numbered frames as one stream at `rate` fps:
+/// the image2 pattern, or for a sharded folder a concat list next to it, since a pattern
+/// can't span folders.
+struct FramesInput {
+    args: Vec<std::ffi::OsString>,
+}
+
+impl FramesInput {
+    fn new(dir: &Path, rate: f64) -> Result<Self, String> {
+        let rate = format!("{rate:.6}");
+        if !is_sharded(dir) {
+            let pattern = FrameFormat::in_dir(dir).pattern(dir);
+            return Ok(FramesInput { args: vec!["-framerate".into(), rate.into(), "-i".into(), pattern.into()] });
+        }
+        let list_path = dir.with_extension("ffconcat");
+        let mut list = String::from("ffconcat version 1.0\n");
+        for f in sorted_files(dir) {
+            list.push_str(&format!("file {}\n", ffconcat_quote(&f)));
+        }
+        fs::write(&list_path, list).map_err(|e| format!("Failed to write the frame list: {e}"))?;
+        // As an input option -r replaces the concat timestamps with a constant rate.
+        Ok(FramesInput {
+            args: vec!["-r".into(), rate.into(), "-f".into(), "concat".into(), "-safe".into(), "0".into(), "-i".into(), list_path.into()],
+        })
+    }
+
+    fn push_args(&self, cmd: &mut Command) {
+        cmd.args(&self.args);
+    }
+}
+
+// -------------------- Alpha --------------------
+
+/// Background for sources with alpha when the RIFE build drops the alpha channel: frames are
+/// composited over it before interpolation and it is keyed back out at encode. Magenta rarely
+/// appears in real footage.
+const ALPHA_KEY_COLOR: u32 = 0xFF00FF;
+
+/// Pixel format of the first video stream (or of an image).
+fn probe_pix_fmt(ffmpeg: &Path, input: &Path) -> Option<String> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_entries", "stream=pix_fmt", "-of", "default=noprint_wrappers=1:nokey=1"], input)?;
+    let fmt = out.trim().to_string();
+    if fmt.is_empty() { None } else { Some(fmt) }
+}
+
+/// Chroma subsampling (420, 422 or 444) and bit depth of an ffmpeg pixel format name.
+/// RGB formats count as 4:4:4; anything unrecognised as 8-bit 4:2:0.
+fn pix_fmt_layout(fmt: &str) -> (u16, u8) {
+    let name = fmt.trim_end_matches("le").trim_end_matches("be");
+    // Semi-planar formats: nv12/nv16/nv24 are 8-bit, p010/p210/p416 give chroma then depth.
+    if let Some(n) = name.strip_prefix("nv") {
+        return (match n { "16" => 422, "24" | "42" => 444, _ => 420 }, 8);
+    }
+    if let [b'p', c, b'1', d] = name.as_bytes() {
+        return (match c { b'2' => 422, b'4' => 444, _ => 420 }, if *d == b'6' { 16 } else { 10 });
+    }
+    let chroma = if ["444", "gbr", "rgb", "bgr", "argb", "abgr"].iter().any(|c| name.contains(c)) {
+        444
+    } else if ["422", "yuyv", "uyvy", "y210"].iter().any(|c| name.contains(c)) {
+        422
+    } else {
+        420
+    };
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    let bits = match &name[name.len() - digits..] {
+        "9" => 9,
+        "10" | "210" => 10,
+        "12" => 12,
+        "14" => 14,
+        "16" | "48" | "64" => 16,
+        _ => 8,
+    };
+    (chroma, bits)
+}
+
+fn pix_fmt_has_alpha(fmt: &str) -> bool {
+    fmt.starts_with("yuva")
+        || fmt.starts_with("gbrap")
+        || fmt.starts_with("ya")
+        || fmt.starts_with("rgba")
+        || fmt.starts_with("bgra")
+        || fmt.starts_with("argb")
+        || fmt.starts_with("abgr")
+}
+
+fn has_alpha(ffmpeg: &Path, input: &Path) -> bool {
+    probe_pix_fmt(ffmpeg, input).is_some_and(|f| pix_fmt_has_alpha(&f))
+}
+
+/// Interpolate the first two extracted frames and check the result still has alpha. Some
+/// RIFE builds load images as RGB and silently drop it.
+fn rife_keeps_alpha(job: &SmoothJob) -> bool {
+    let frames = sorted_files(&job.frames_in_dir);
+    if frames.len() < 2 {
+        return true;
+    }
+    let probe = job_spec_path(&job.root, &job.job_id).with_extension("alpha-probe.png");
+    let (cwd, model_arg) = compute_rife_cwd_and_model_arg(&job.model_dir);
+    let mut cmd = tool_command(&job.rife_bin);
+    if let Some(d) = cwd {
+        cmd.current_dir(d);
+    }
+    cmd.arg("-0").arg(&frames[0])
+        .arg("-1").arg(&frames[1])
+        .arg("-o").arg(&probe)
+        .arg("-m").arg(model_arg)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let ok = run_tool(log_command("interpolate", &mut cmd), PROBE_TIMEOUT_SECS).is_ok_and(|o| o.status.success())
+        && has_alpha(&job.ffmpeg, &probe);
+    let _ = fs::remove_file(&probe);
+    ok
+}
+
+/// Flatten every frame in `dir` over `ALPHA_KEY_COLOR`, in place.
+fn composite_over_key(ffmpeg: &Path, dir: &Path) -> Result<(), String> {
+    let first = sorted_files(dir).into_iter().next().ok_or("No frames to composite")?;
+    let (w, h) = probe_video_size(ffmpeg, &first).ok_or("Could not read the frame size")?;
+    let keyed = dir.with_extension("keyed");
+    clear_dir(&keyed)?;
+    let sharded = is_sharded(dir);
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner").arg("-y");
+    FramesInput::new(dir, 25.0)?.push_args(&mut cmd);
+    let out = run_tool(log_command("interpolate", cmd
+        .arg("-filter_complex")
+        .arg(format!(
+            "color=c=0x{ALPHA_KEY_COLOR:06X}:s={w}x{h}:r=25[bg];[bg][0:v]overlay=shortest=1,format=rgb24"
+        ))
+        .arg("-fps_mode").arg("passthrough")
+        .arg(keyed.join("%08d.png"))), analysis_timeout_secs(count_files_in_dir(dir) as f64))?;
+    if !out.status.success() {
+        let _ = fs::remove_dir_all(&keyed);
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(format!("Compositing alpha frames failed: {}", err.lines().last().unwrap_or("").trim()));
+    }
+    fs::remove_dir_all(dir).map_err(|e| format!("Failed to replace frames: {e}"))?;
+    fs::rename(&keyed, dir).map_err(|e| format!("Failed to replace frames: {e}"))?;
+    if sharded {
+        shard_frames(dir)?;
+    }
+    Ok(())
+}
+
+// -------------------- Filter graph --------------------
+
+/// A single `-vf` filter. `FilterChain` sorts these into a fixed order no matter how they
+/// were added, so callers can't produce chains that are valid ffmpeg but wrong (e.g. cropping
+/// a scaled frame with source coordinates).
+#[derive(Clone, Debug, PartialEq)]
+enum VideoFilter {
+    /// Copy hardware-decoded frames to system memory; everything else works on those.
+    HwDownload,
+    Deinterlace,
+    /// Clockwise quarter turns, for sources decoded with `-noautorotate`.
+    Rotate(i32),
+    /// Reports the non-black area; only used on its own for bar detection.
+    CropDetect,
+    Crop { w: u32, h: u32, x: u32, y: u32 },
+    /// HDR (PQ/HLG) to SDR bt709.
+    Tonemap,
+    /// `-2` for one side keeps the aspect ratio at an even size.
+    Scale { w: i32, h: i32 },
+    Denoise { strength: f32 },
+    Lut3d(PathBuf),
+    /// Black padding back out to `w`x`h` with the picture at `x`,`y`.
+    Pad { w: u32, h: u32, x: u32, y: u32 },
+    /// Turn a key colour back into transparency (see `ALPHA_KEY_COLOR`).
+    ColorKey(u32),
+    /// RGB to YUV conversion with an explicit matrix/range, matching the colour tags written
+    /// at encode (swscale otherwise assumes bt601 limited).
+    ColorMatrix { matrix: &'static str, range: &'static str },
+    Fps(f64),
+    Decimate,
+    SelectScene(f64),
+    /// Logs runs of at least `frames` near-identical frames; times are in frames when the
+    /// input is read at 1 fps.
+    FreezeDetect { frames: usize },
+    /// Always last so it reports exactly the frames that leave the chain.
+    ShowInfo,
+}
+
+impl VideoFilter {
+    /// Position in the chain. Deinterlacing needs the untouched fields; crop coordinates are
+    /// in upright source pixels; tonemapping must precede LUTs, which expect SDR input; colour
+    /// conversion and timing filters come after all per-frame processing.
+    fn rank(&self) -> u8 {
+        match self {
+            VideoFilter::HwDownload => 0,
+            VideoFilter::Deinterlace => 1,
+            VideoFilter::Rotate(_) => 2,
+            VideoFilter::CropDetect | VideoFilter::Crop { .. } => 3,
+            VideoFilter::Tonemap => 4,
+            VideoFilter::Scale { .. } => 5,
+            VideoFilter::Denoise { .. } => 6,
+            VideoFilter::Lut3d(_) => 7,
+            VideoFilter::Pad { .. } => 8,
+            VideoFilter::ColorKey(_) => 9,
+            VideoFilter::ColorMatrix { .. } => 10,
+            VideoFilter::Fps(_) => 11,
+            VideoFilter::Decimate => 12,
+            VideoFilter::SelectScene(_) | VideoFilter::FreezeDetect { .. } => 13,
+            VideoFilter::ShowInfo => 14,
+        }
+    }
+
+    fn to_filter_string(&self) -> String {
+        match self {
+            // 8-bit sources come down as nv12, 10-bit ones as p010.
+            VideoFilter::HwDownload => "hwdownload,format=nv12|p010le".to_string(),
+            VideoFilter::Deinterlace => "bwdif=mode=send_frame".to_string(),
+            VideoFilter::Rotate(deg) => match deg.rem_euclid(360) {
+                90 => "transpose=clock".to_string(),
+                180 => "hflip,vflip".to_string(),
+                270 => "transpose=cclock".to_string(),
+                _ => "null".to_string(),
+            },
+            // reset=0 keeps the largest area seen, so dark scenes don't shrink the result.
+            VideoFilter::CropDetect => "cropdetect=limit=24:round=2:reset=0".to_string(),
+            VideoFilter::Crop { w, h, x, y } => format!("crop={w}:{h}:{x}:{y}"),
+            VideoFilter::Tonemap => "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+                tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p"
+                .to_string(),
+            VideoFilter::Scale { w, h } => format!("scale={w}:{h}:flags=lanczos"),
+            VideoFilter::Denoise { strength } => {
+                let s = strength.clamp(0.0, 10.0);
+                format!("hqdn3d={s:.2}:{:.2}:{:.2}:{:.2}", s * 0.75, s * 1.5, s * 1.125)
+            }
+            VideoFilter::Lut3d(path) => format!("lut3d=file={}", escape_filter_path(path)),
+            VideoFilter::Pad { w, h, x, y } => format!("pad={w}:{h}:{x}:{y}:black"),
+            VideoFilter::ColorKey(color) => format!("colorkey=0x{color:06X}:0.12:0.05"),
+            VideoFilter::ColorMatrix { matrix, range } => {
+                format!("scale=out_color_matrix={matrix}:out_range={range}")
+            }
+            VideoFilter::Fps(fps) => format!("fps={fps:.6}"),
+            VideoFilter::Decimate => "mpdecimate".to_string(),
+            VideoFilter::SelectScene(threshold) => format!("select='gt(scene,{threshold})'"),
+            // freezedetect's duration counts from the first frozen frame, so one less than the run.
+            VideoFilter::FreezeDetect { frames } => {
+                format!("freezedetect=n={HOLD_NOISE_DB}dB:d={}", frames.saturating_sub(1).max(1))
+            }
+            VideoFilter::ShowInfo => "showinfo".to_string(),
+        }
+    }
+}
+
+/// Quote a file path for use as a filter option value. ffmpeg unescapes twice (option value,
+/// then filtergraph), so special characters are escaped for both levels. Forward slashes work
+/// on every platform and avoid escaping Windows separators.
+fn escape_filter_path(path: &Path) -> String {
+    let mut value = path.to_string_lossy().replace('\\', "/");
+    for c in ['\'', ':'] {
+        value = value.replace(c, &format!("\\{c}"));
+    }
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Typed builder for a `-vf` chain. Each kind of filter appears at most once; setting it
+/// again replaces the earlier value.
+#[derive(Clone, Debug, Default)]
+struct FilterChain {
+    filters: Vec<VideoFilter>,
+}
+
+impl FilterChain {
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn with(mut self, filter: VideoFilter) -> Self {
+        self.filters.retain(|f| std::mem::discriminant(f) != std::mem::discriminant(&filter));
+        self.filters.push(filter);
+        self
+    }
+
+    fn hw_download(self) -> Self {
+        self.with(VideoFilter::HwDownload)
+    }
+
+    fn deinterlace(self) -> Self {
+        self.with(VideoFilter::Deinterlace)
+    }
+
+    fn rotate(self, deg: i32) -> Self {
+        self.with(VideoFilter::Rotate(deg))
+    }
+
+    fn crop_detect(self) -> Self {
+        self.with(VideoFilter::CropDetect)
+    }
+
+    fn crop(self, w: u32, h: u32, x: u32, y: u32) -> Self {
+        self.with(VideoFilter::Crop { w, h, x, y })
+    }
+
+    fn pad(self, w: u32, h: u32, x: u32, y: u32) -> Self {
+        self.with(VideoFilter::Pad { w, h, x, y })
+    }
+
+    fn tonemap(self) -> Self {
+        self.with(VideoFilter::Tonemap)
+    }
+
+    fn scale(self, w: i32, h: i32) -> Self {
+        self.with(VideoFilter::Scale { w, h })
+    }
+
+    fn denoise(self, strength: f32) -> Self {
+        self.with(VideoFilter::Denoise { strength })
+    }
+
+    fn lut(self, path: impl Into<PathBuf>) -> Self {
+        self.with(VideoFilter::Lut3d(path.into()))
+    }
+
+    fn color_key(self, color: u32) -> Self {
+        self.with(VideoFilter::ColorKey(color))
+    }
+
+    /// Convert to YUV with the matrix/range `tags` declare, when swscale knows the matrix.
+    fn color_matrix(self, tags: &ColorTags) -> Self {
+        match tags.scale_matrix() {
+            Some(matrix) => {
+                let range = if tags.range.as_deref() == Some("pc") { "pc" } else { "tv" };
+                self.with(VideoFilter::ColorMatrix { matrix, range })
+            }
+            None => self,
+        }
+    }
+
+    fn fps(self, fps: f64) -> Self {
+        self.with(VideoFilter::Fps(fps))
+    }
+
+    fn decimate(self) -> Self {
+        self.with(VideoFilter::Decimate)
+    }
+
+    fn select_scene(self, threshold: f64) -> Self {
+        self.with(VideoFilter::SelectScene(threshold))
+    }
+
+    fn freeze_detect(self, frames: usize) -> Self {
+        self.with(VideoFilter::FreezeDetect { frames })
+    }
+
+    fn show_info(self) -> Self {
+        self.with(VideoFilter::ShowInfo)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// The `-vf` value, or None for an empty chain.
+    fn to_arg(&self) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut filters: Vec<&VideoFilter> = self.filters.iter().collect();
+        filters.sort_by_key(|f| f.rank());
+        Some(filters.iter().map(|f| f.to_filter_string()).collect::<Vec<_>>().join(","))
+    }
+
+    fn apply(&self, cmd: &mut Command) {
+        if let Some(vf) = self.to_arg() {
+            cmd.arg("-vf").arg(vf);
+        }
+    }
+}
+
+/// User-facing clean-up filters for a job. Source filters run at extraction so RIFE sees the
+/// cleaned frames; `output_fps` resamples the interpolated result at encode.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct FilterOptions {
+    deinterlace: bool,
+    crop: Option<CropRect>,
+    tonemap: bool,
+    scale: Option<ScaleSize>,
+    denoise: Option<f32>,
+    lut_path: Option<String>,
+    output_fps: Option<f64>,
+    bars: BarHandling,
+    /// Interpolate smaller frames for a quick preview (several times faster).
+    draft: Option<DraftQuality>,
+    /// Clockwise degrees to turn the frames upright, set by the source policy's rotation fix.
+    /// The source is then decoded with `-noautorotate` so ffmpeg doesn't turn it as well.
+    rotate: Option<i32>,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+struct DraftQuality {
+    size: DraftSize,
+    /// Scale the result back to the full size at encode, so the draft drops in where the
+    /// final output will go.
+    #[serde(default)]
+    upscale: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum DraftSize {
+    #[serde(rename = "half")]
+    Half,
+    /// 720 lines high; sources already that small aren't scaled.
+    #[serde(rename = "720p")]
+    P720,
+}
+
+impl DraftSize {
+    /// Interpolation size for a `w`x`h` picture; None when it is already that small.
+    fn scaled(self, w: u32, h: u32) -> Option<(u32, u32)> {
+        let even = |v: f64| ((v / 2.0).round() as u32 * 2).max(2);
+        match self {
+            DraftSize::Half => Some((even(w as f64 / 2.0), even(h as f64 / 2.0))),
+            DraftSize::P720 if h > 720 => Some((even(w as f64 * 720.0 / h as f64), 720)),
+            DraftSize::P720 => None,
+        }
+    }
+}
+
+/// Sizes for a draft job: frames are extracted and interpolated at `scaled`, and encoded at
+/// `full` when the draft upscales.
+#[derive(Clone, Copy, Debug)]
+struct DraftPlan {
+    scaled: (u32, u32),
+    full: (u32, u32),
+    upscale: bool,
+}
+
+/// The draft sizes for `job`, from the picture RIFE would otherwise see: the detected bars,
+/// the crop, or the whole frame.
+fn draft_plan(job: &SmoothJob, bars: Option<CropRect>) -> Option<DraftPlan> {
+    let draft = job.filters.draft?;
+    let full = match bars.or(job.filters.crop) {
+        Some(c) => (c.w, c.h),
+        None => upright_video_size(&job.ffmpeg, &job.input)?,
+    };
+    let scaled = draft.size.scaled(full.0, full.1)?;
+    Some(DraftPlan { scaled, full, upscale: draft.upscale })
+}
+
+/// The extraction filters of `job` plus the per-run parts: the bar crop and the draft size.
+fn job_extraction_chain(job: &SmoothJob, bars: Option<CropRect>) -> FilterChain {
+    let mut chain = job.filters.extraction_chain();
+    if let Some(c) = bars {
+        chain = chain.crop(c.w, c.h, c.x, c.y);
+    }
+    if let Some(d) = draft_plan(job, bars) {
+        chain = chain.scale(d.scaled.0 as i32, d.scaled.1 as i32);
+    }
+    chain
+}
+
+/// What to do with letterbox/pillarbox bars. Bars confuse RIFE's motion estimation at the
+/// picture edges, so cropping them before interpolation gives cleaner results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BarHandling {
+    #[default]
+    Off,
+    /// Crop the bars; the output is the cropped size.
+    Crop,
+    /// Crop the bars for interpolation and pad them back, so the output matches the source size.
+    CropAndRestore,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+struct CropRect {
+    w: u32,
+    h: u32,
+    x: u32,
+    y: u32,
+}
+
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+struct ScaleSize {
+    w: i32,
+    h: i32,
+}
+
+impl FilterOptions {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(c) = self.crop {
+            if c.w == 0 || c.h == 0 {
+                return Err("Crop width and height must be greater than 0".into());
+            }
+        }
+        if let Some(s) = self.scale {
+            if s.w == 0 || s.h == 0 || s.w < -2 || s.h < -2 || (s.w < 0 && s.h < 0) {
+                return Err("Scale needs a positive size for at least one side (-2 keeps aspect)".into());
+            }
+        }
+        if let Some(lut) = self.lut_path.as_deref() {
+            if !Path::new(lut).is_file() {
+                return Err(format!("LUT file does not exist: {lut}"));
+            }
+        }
+        if let Some(fps) = self.output_fps {
+            if !(fps > 0.0 && fps <= 1000.0) {
+                return Err("Output fps must be between 0 and 1000".into());
+            }
+        }
+        if self.bars == BarHandling::CropAndRestore && self.scale.is_some() {
+            return Err("Restoring black bars needs the source size; turn off scaling or just crop the bars".into());
+        }
+        if let Some(d) = self.draft {
+            if self.scale.is_some() {
+                return Err("Draft quality sets the size itself; turn off scaling".into());
+            }
+            if self.bars == BarHandling::CropAndRestore && !d.upscale {
+                return Err("Restoring black bars needs the full size; let the draft upscale or just crop the bars".into());
+            }
+        }
+        Ok(())
+    }
+
+    fn extraction_chain(&self) -> FilterChain {
+        let mut chain = FilterChain::new();
+        if self.deinterlace {
+            chain = chain.deinterlace();
+        }
+        if let Some(deg) = self.rotate {
+            chain = chain.rotate(deg);
+        }
+        if let Some(c) = self.crop {
+            chain = chain.crop(c.w, c.h, c.x, c.y);
+        }
+        if self.tonemap {
+            chain = chain.tonemap();
+        }
+        if let Some(s) = self.scale {
+            chain = chain.scale(s.w, s.h);
+        }
+        if let Some(d) = self.denoise.filter(|d| *d > 0.0) {
+            chain = chain.denoise(d);
+        }
+        if let Some(lut) = self.lut_path.as_deref() {
+            chain = chain.lut(lut);
+        }
+        chain
+    }
+
+    fn encode_chain(&self) -> FilterChain {
+        match self.output_fps {
+            Some(fps) => FilterChain::new().fps(fps),
+            None => FilterChain::new(),
+        }
+    }
+}
+
+/// Letterbox/pillarbox bars found by running cropdetect over 30 s from a quarter into the
+/// clip. None when the picture fills the frame, or when the detected area is implausibly
+/// small (a long dark scene rather than bars).
+fn detect_black_bars(ffmpeg: &Path, input: &Path, duration_secs: f64) -> Option<CropRect> {
+    let (w, h) = upright_video_size(ffmpeg, input)?;
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin")
+        .arg("-ss").arg(format!("{:.3}", (duration_secs * 0.25).max(0.0)))
+        .arg("-i").arg(input)
+        .arg("-t").arg("30");
+    FilterChain::new().crop_detect().apply(&mut cmd);
+    cmd.arg("-an").arg("-f").arg("null").arg("-");
+    // 30 s at up to 60 fps.
+    let out = run_tool(log_command("bar_detect", &mut cmd), analysis_timeout_secs(30.0 * 60.0)).ok()?;
+
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    let last = stderr.lines().rev().find(|l| l.contains("Parsed_cropdetect"))?;
+    let values: Vec<u32> = last
+        .split("crop=")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .split(':')
+        .filter_map(|v| v.parse().ok())
+        .collect();
+    let [cw, ch, x, y] = values[..] else { return None };
+    if (cw == w && ch == h) || cw < w / 2 || ch < h / 2 {
+        return None;
+    }
+    Some(CropRect { w: cw, h: ch, x, y })
+}
+
+// -------------------- Encode settings --------------------
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum VideoEncoder {
+    #[default]
+    Libx264,
+    Libx265,
+    H264Nvenc,
+    HevcNvenc,
+    Libsvtav1,
+    /// ProRes (intra-only); the 4444 profiles carry alpha.
+    ProresKs,
+    /// Apple's hardware HEVC encoder, the only ffmpeg HEVC encoder that writes alpha.
+    HevcVideotoolbox,
+}
+
+/// x264-style preset names and the SVT-AV1 preset (0 = slowest, 13 = fastest) each maps to.
+const SVT_AV1_PRESETS: &[(&str, &str)] = &[
+    ("placebo", "2"),
+    ("veryslow", "3"),
+    ("slower", "4"),
+    ("slow", "5"),
+    ("medium", "6"),
+    ("fast", "8"),
+    ("faster", "9"),
+    ("veryfast", "10"),
+    ("superfast", "11"),
+    ("ultrafast", "12"),
+];
+
+const H264_LEVELS: &[&str] = &[
+    "1", "1b", "1.1", "1.2", "1.3", "2", "2.1", "2.2", "3", "3.1", "3.2", "4", "4.1", "4.2",
+    "5", "5.1", "5.2", "6", "6.1", "6.2",
+];
+const HEVC_LEVELS: &[&str] = &[
+    "1", "2", "2.1", "3", "3.1", "4", "4.1", "5", "5.1", "5.2", "6", "6.1", "6.2",
+];
+
+impl VideoEncoder {
+    const ALL: [VideoEncoder; 7] = [
+        VideoEncoder::Libx264,
+        VideoEncoder::Libx265,
+        VideoEncoder::H264Nvenc,
+        VideoEncoder::HevcNvenc,
+        VideoEncoder::Libsvtav1,
+        VideoEncoder::ProresKs,
+        VideoEncoder::HevcVideotoolbox,
+    ];
+
+    fn ffmpeg_name(self) -> &'static str {
+        match self {
+            VideoEncoder::Libx264 => "libx264",
+            VideoEncoder::Libx265 => "libx265",
+            VideoEncoder::H264Nvenc => "h264_nvenc",
+            VideoEncoder::HevcNvenc => "hevc_nvenc",
+            VideoEncoder::Libsvtav1 => "libsvtav1",
+            VideoEncoder::ProresKs => "prores_ks",
+            VideoEncoder::HevcVideotoolbox => "hevc_videotoolbox",
+        }
+    }
+
+    /// ffprobe's `codec_name` for what the encoder writes.
+    fn codec_name(self) -> &'static str {
+        match self {
+            VideoEncoder::Libx264 | VideoEncoder::H264Nvenc => "h264",
+            VideoEncoder::Libx265 | VideoEncoder::HevcNvenc | VideoEncoder::HevcVideotoolbox => "hevc",
+            VideoEncoder::Libsvtav1 => "av1",
+            VideoEncoder::ProresKs => "prores",
+        }
+    }
+
+    fn is_nvenc(self) -> bool {
+        matches!(self, VideoEncoder::H264Nvenc | VideoEncoder::HevcNvenc)
+    }
+
+    /// Encodes on the GPU RIFE uses (see `GpuSharing`).
+    fn uses_gpu(self) -> bool {
+        self.is_nvenc() || self == VideoEncoder::HevcVideotoolbox
+    }
+
+    /// Presets the encoder accepts. SVT-AV1 also takes the x264 names (see `SVT_AV1_PRESETS`).
+    fn presets(self) -> &'static [&'static str] {
+        match self {
+            VideoEncoder::H264Nvenc | VideoEncoder::HevcNvenc => &["p1", "p2", "p3", "p4", "p5", "p6", "p7"],
+            VideoEncoder::Libsvtav1 => &[
+                "0", "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13",
+            ],
+            VideoEncoder::Libx264 | VideoEncoder::Libx265 => &[
+                "ultrafast", "superfast", "veryfast", "faster", "fast",
+                "medium", "slow", "slower", "veryslow", "placebo",
+            ],
+            VideoEncoder::ProresKs | VideoEncoder::HevcVideotoolbox => &[],
+        }
+    }
+
+    fn tunes(self) -> &'static [&'static str] {
+        match self {
+            VideoEncoder::Libx264 => &[
+                "film", "animation", "grain", "stillimage", "fastdecode", "zerolatency", "psnr", "ssim",
+            ],
+            VideoEncoder::Libx265 => &["animation", "grain", "fastdecode", "zerolatency", "psnr", "ssim"],
+            VideoEncoder::H264Nvenc | VideoEncoder::HevcNvenc => &["hq", "ll", "ull", "lossless"],
+            VideoEncoder::Libsvtav1 | VideoEncoder::ProresKs | VideoEncoder::HevcVideotoolbox => &[],
+        }
+    }
+
+    fn profiles(self) -> &'static [&'static str] {
+        match self {
+            VideoEncoder::Libx264 => &["baseline", "main", "high", "high10", "high422", "high444"],
+            VideoEncoder::Libx265 => &["main", "main10", "main12", "main422-10", "main444-8", "main444-10"],
+            VideoEncoder::H264Nvenc => &["baseline", "main", "high", "high444p"],
+            VideoEncoder::HevcNvenc => &["main", "main10", "rext"],
+            VideoEncoder::Libsvtav1 => &["main", "high", "professional"],
+            VideoEncoder::ProresKs => &["proxy", "lt", "standard", "hq", "4444", "4444xq"],
+            VideoEncoder::HevcVideotoolbox => &["main", "main10"],
+        }
+    }
+
+    /// Pixel formats the encoder takes as (chroma, bit depth, name), smallest first. Chroma
+    /// is 420, 422 or 444.
+    fn pix_fmts(self) -> &'static [(u16, u8, &'static str)] {
+        match self {
+            VideoEncoder::Libx264 => &[
+                (420, 8, "yuv420p"), (420, 10, "yuv420p10le"), (422, 8, "yuv422p"),
+                (422, 10, "yuv422p10le"), (444, 8, "yuv444p"), (444, 10, "yuv444p10le"),
+            ],
+            VideoEncoder::Libx265 => &[
+                (420, 8, "yuv420p"), (420, 10, "yuv420p10le"), (420, 12, "yuv420p12le"),
+                (422, 8, "yuv422p"), (422, 10, "yuv422p10le"), (422, 12, "yuv422p12le"),
+                (444, 8, "yuv444p"), (444, 10, "yuv444p10le"), (444, 12, "yuv444p12le"),
+            ],
+            VideoEncoder::H264Nvenc => &[(420, 8, "yuv420p"), (444, 8, "yuv444p")],
+            VideoEncoder::HevcNvenc => &[(420, 8, "yuv420p"), (420, 10, "p010le"), (444, 8, "yuv444p"), (444, 16, "yuv444p16le")],
+            VideoEncoder::Libsvtav1 => &[(420, 8, "yuv420p"), (420, 10, "yuv420p10le")],
+            VideoEncoder::ProresKs => &[(422, 10, "yuv422p10le"), (444, 10, "yuv444p10le")],
+            VideoEncoder::HevcVideotoolbox => &[(420, 8, "nv12"), (420, 10, "p010le")],
+        }
+    }
+
+    /// The closest pixel format the encoder takes for `chroma`/`bits`: the richest one that
+    /// doesn't exceed either, or the encoder's smallest when none fits (ProRes is 4:2:2 10-bit
+    /// at least).
+    fn pix_fmt_for(self, chroma: u16, bits: u8) -> &'static str {
+        let fmts = self.pix_fmts();
+        fmts.iter()
+            .filter(|(c, b, _)| *c <= chroma && *b <= bits)
+            .max_by_key(|(c, b, _)| (*c, *b))
+            .unwrap_or(&fmts[0])
+            .2
+    }
+
+    fn levels(self) -> &'static [&'static str] {
+        match self {
+            VideoEncoder::Libx264 | VideoEncoder::H264Nvenc => H264_LEVELS,
+            VideoEncoder::Libx265 | VideoEncoder::HevcNvenc => HEVC_LEVELS,
+            VideoEncoder::Libsvtav1 | VideoEncoder::ProresKs | VideoEncoder::HevcVideotoolbox => &[],
+        }
+    }
+
+    /// Largest B-frame count the encoder supports. SVT-AV1 has no `-bf`; it picks its own
+    /// hierarchical structure.
+    fn max_bframes(self) -> Option<u32> {
+        match self {
+            VideoEncoder::H264Nvenc | VideoEncoder::HevcNvenc => Some(4),
+            VideoEncoder::Libx264 | VideoEncoder::Libx265 => Some(16),
+            VideoEncoder::Libsvtav1 | VideoEncoder::ProresKs | VideoEncoder::HevcVideotoolbox => None,
+        }
+    }
+
+    /// Every frame is a keyframe, so GOP settings don't apply.
+    fn is_intra_only(self) -> bool {
+        self == VideoEncoder::ProresKs
+    }
+}
+
+/// Output frame timing (`-fps_mode`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum FpsMode {
+    /// Constant frame rate: frames are duplicated/dropped onto a fixed grid. Some editors
+    /// require it.
+    Cfr,
+    /// Variable frame rate: keep each frame's timestamp, drop exact duplicates.
+    Vfr,
+    /// Keep every frame with its timestamp as-is.
+    Passthrough,
+}
+
+impl FpsMode {
+    fn as_arg(self) -> &'static str {
+        match self {
+            FpsMode::Cfr => "cfr",
+            FpsMode::Vfr => "vfr",
+            FpsMode::Passthrough => "passthrough",
+        }
+    }
+}
+
+/// How MP4/MOV outputs lay out their index (moov atom).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Mp4Layout {
+    /// Index moved to the front after encoding, so web players can start before the download ends.
+    #[default]
+    Faststart,
+    /// Fragmented MP4 for streaming and for files that stay playable if the encode is cut off.
+    Fragmented,
+    /// ffmpeg's default: index at the end.
+    Plain,
+}
+
+impl Mp4Layout {
+    fn push_args(self, cmd: &mut Command, output_ext: &str) {
+        if !matches!(output_ext, "mp4" | "mov" | "m4v") {
+            return;
+        }
+        match self {
+            Mp4Layout::Faststart => {
+                cmd.arg("-movflags").arg("+faststart");
+            }
+            Mp4Layout::Fragmented => {
+                cmd.arg("-movflags").arg("+frag_keyframe+empty_moov+default_base_moof");
+            }
+            Mp4Layout::Plain => {}
+        }
+    }
+}
+
+/// What runs the final video encode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum EncodeBackend {
+    #[default]
+    Ffmpeg,
+    /// av1an (`bin/av1an/<version>/` or a system install) splits a lossless intermediate at
+    /// scene changes and encodes the chunks with SVT-AV1 in parallel. AV1 only.
+    Av1an,
+}
+
+/// Video encoder options for the encode stage. Unset fields leave the encoder's defaults.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct EncodeSettings {
+    /// None leaves the choice to `match_source`, or libx264 (see `encoder()`).
+    encoder: Option<VideoEncoder>,
+    backend: EncodeBackend,
+    /// Constant quality on the x264 scale (0-51, lower is better). NVENC uses it as `-cq`;
+    /// SVT-AV1 maps it onto its 0-63 scale.
+    crf: Option<u32>,
+    /// Encoder preset; SVT-AV1 also accepts the x264 names.
+    preset: Option<String>,
+    /// `-tune`, e.g. film/animation/grain/fastdecode.
+    tune: Option<String>,
+    /// `-profile:v`, e.g. high or main10. 10-bit profiles switch the output to 10-bit.
+    profile: Option<String>,
+    /// `-level`, e.g. 4.1, for devices that cap the decodable level.
+    level: Option<String>,
+    /// Maximum keyframe interval in frames (`-g`).
+    gop: Option<u32>,
+    /// Consecutive B-frames (`-bf`).
+    bframes: Option<u32>,
+    /// Insert extra keyframes at scene cuts. Turning this off together with `gop` gives the
+    /// fixed keyframe cadence streaming/HLS segmenters want.
+    scene_cut_keyframes: bool,
+    /// Fit the output into this many MiB with a two-pass bitrate encode instead of constant
+    /// quality (libx264/libx265 only).
+    target_size_mb: Option<f64>,
+    /// Index placement for MP4/MOV outputs; ignored for other containers.
+    mp4_layout: Mp4Layout,
+    /// Frame timing of the output. None picks VFR for retimed output and leaves
+    /// ffmpeg's default otherwise.
+    fps_mode: Option<FpsMode>,
+    /// Colour tags to write instead of the ones probed from the source; unset fields keep
+    /// the probed value.
+    color: ColorTags,
+    /// Keep a source alpha channel when the encoder and container can store it (ProRes 4444
+    /// or HEVC VideoToolbox in .mov).
+    keep_alpha: bool,
+    audio: AudioOptions,
+    /// Average video bitrate (`-b:v`); set by `match_source`, exclusive with `crf`.
+    bitrate_kbps: Option<u32>,
+    /// Source pixel format, set by `match_source`. Its chroma subsampling and bit depth are
+    /// kept where the encoder and profile allow (see `pix_fmt`).
+    source_pix_fmt: Option<String>,
+    /// Pick the encoder, profile, bit depth and bitrate from the source (see `matched_to_source`).
+    /// Settings given explicitly alongside it win.
+    match_source: bool,
+    /// Write a chapter at each detected scene cut (mkv/webm/mp4/mov). Runs scene detection
+    /// even with cut protection off.
+    chapters_at_cuts: bool,
+}
+
+/// One pass of a two-pass bitrate encode.
+struct EncodePass {
+    /// 1 or 2.
+    pass: u8,
+    bitrate_kbps: u32,
+    /// Stats file prefix inside the job's `pass_log_dir`, so parallel jobs don't share one.
+    log_prefix: PathBuf,
+}
+
+impl Default for EncodeSettings {
+    fn default() -> Self {
+        Self {
+            encoder: None,
+            backend: EncodeBackend::default(),
+            crf: None,
+            preset: None,
+            tune: None,
+            profile: None,
+            level: None,
+            gop: None,
+            bframes: None,
+            scene_cut_keyframes: true,
+            target_size_mb: None,
+            mp4_layout: Mp4Layout::default(),
+            fps_mode: None,
+            color: ColorTags::default(),
+            keep_alpha: true,
+            audio: AudioOptions::default(),
+            bitrate_kbps: None,
+            source_pix_fmt: None,
+            match_source: false,
+            chapters_at_cuts: false,
+        }
+    }
+}
+
+impl EncodeSettings {
+    fn encoder(&self) -> VideoEncoder {
+        self.encoder.unwrap_or_default()
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let enc = self.encoder();
+        let name = enc.ffmpeg_name();
+        if let Some(crf) = self.crf {
+            if crf > 51 {
+                return Err(format!("{name}: quality must be 0-51"));
+            }
+            if enc == VideoEncoder::ProresKs {
+                return Err(format!("{name}: quality is set by the profile"));
+            }
+        }
+        if let Some(preset) = self.preset.as_deref() {
+            if enc.presets().is_empty() {
+                return Err(format!("{name}: preset is not supported"));
+            }
+            let svt_alias = enc == VideoEncoder::Libsvtav1 && SVT_AV1_PRESETS.iter().any(|(n, _)| *n == preset);
+            if !svt_alias && !enc.presets().contains(&preset) {
+                return Err(format!(
+                    "{name}: unknown preset '{preset}' (expected one of {})",
+                    enc.presets().join(", ")
+                ));
+            }
+        }
+        for (what, value, allowed) in [
+            ("tune", &self.tune, enc.tunes()),
+            ("profile", &self.profile, enc.profiles()),
+            ("level", &self.level, enc.levels()),
+        ] {
+            let Some(value) = value.as_deref() else { continue };
+            if allowed.is_empty() {
+                return Err(format!("{name}: {what} is not supported"));
+            }
+            if !allowed.contains(&value) {
+                return Err(format!("{name}: unknown {what} '{value}' (expected one of {})", allowed.join(", ")));
+            }
+        }
+        if let Some(gop) = self.gop {
+            if enc.is_intra_only() {
+                return Err(format!("{name}: every frame is a keyframe; keyframe interval can't be set"));
+            }
+            if gop == 0 || gop > 1000 {
+                return Err(format!("{name}: keyframe interval must be 1-1000 frames"));
+            }
+        }
+        if let Some(bf) = self.bframes {
+            let Some(max) = enc.max_bframes() else {
+                return Err(format!("{name}: B-frame count can't be set"));
+            };
+            if bf > max {
+                return Err(format!("{name}: at most {max} B-frames are supported"));
+            }
+            if let Some(gop) = self.gop {
+                if bf >= gop {
+                    return Err(format!("{name}: B-frames ({bf}) must be fewer than the keyframe interval ({gop})"));
+                }
+            }
+        }
+        self.color.validate()?;
+        if let Some(kbps) = self.bitrate_kbps {
+            if kbps == 0 {
+                return Err("Video bitrate must be greater than 0".into());
+            }
+            if self.crf.is_some() {
+                return Err("Set either a quality (CRF) or a bitrate, not both".into());
+            }
+            if enc == VideoEncoder::ProresKs {
+                return Err(format!("{name}: bitrate is set by the profile"));
+            }
+        }
+        if self.backend == EncodeBackend::Av1an {
+            if enc != VideoEncoder::Libsvtav1 {
+                return Err("av1an encodes AV1: choose the libsvtav1 encoder".into());
+            }
+            if self.bitrate_kbps.is_some() || self.target_size_mb.is_some() {
+                return Err("av1an encodes at a constant quality; set a CRF instead of a bitrate or size".into());
+            }
+            if self.chapters_at_cuts {
+                return Err("Chapters can't be written by av1an".into());
+            }
+        }
+        if let Some(mb) = self.target_size_mb {
+            if !matches!(enc, VideoEncoder::Libx264 | VideoEncoder::Libx265) {
+                return Err(format!("{name}: target file size needs libx264 or libx265"));
+            }
+            if !(mb.is_finite() && mb > 0.0) {
+                return Err("Target file size must be greater than 0 MB".into());
+            }
+            if self.crf.is_some() {
+                return Err("Set either a quality (CRF) or a target file size, not both".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Chroma subsampling and bit depth the profile fixes. A None depth is left to the source
+    /// (x264's 4:2:2/4:4:4 profiles take 8 and 10-bit); None overall means the profile allows
+    /// anything the plain 4:2:0 profiles do.
+    fn profile_layout(&self) -> Option<(u16, Option<u8>)> {
+        match (self.encoder(), self.profile.as_deref()) {
+            (VideoEncoder::ProresKs, Some("4444" | "4444xq")) => Some((444, Some(10))),
+            (VideoEncoder::ProresKs, _) => Some((422, Some(10))),
+            (_, Some("high10" | "main10")) => Some((420, Some(10))),
+            (_, Some("main12")) => Some((420, Some(12))),
+            (_, Some("high422")) => Some((422, None)),
+            (_, Some("main422-10")) => Some((422, Some(10))),
+            (_, Some("high444" | "high444p")) => Some((444, None)),
+            (_, Some("main444-8")) => Some((444, Some(8))),
+            (_, Some("main444-10")) => Some((444, Some(10))),
+            (_, Some("baseline" | "main" | "high")) => Some((420, Some(8))),
+            _ => None,
+        }
+    }
+
+    /// Pixel format for the encode: the profile's chroma and bit depth, filled in from the
+    /// matched source, then moved to the closest format the encoder takes. None (no profile
+    /// constraint and no source) keeps the caller's choice.
+    fn pix_fmt(&self) -> Option<&'static str> {
+        let source = self.source_pix_fmt.as_deref().map(pix_fmt_layout);
+        let (chroma, bits) = match (self.profile_layout(), source) {
+            (Some((chroma, Some(bits))), _) => (chroma, bits),
+            (Some((chroma, None)), source) => (chroma, source.map_or(8, |(_, b)| b)),
+            (None, Some(source)) => source,
+            (None, None) => return None,
+        };
+        Some(self.encoder().pix_fmt_for(chroma, bits))
+    }
+
+    /// Settings mirroring the source video: same codec family, profile and bit depth, and the
+    /// source bitrate (per second, so the doubled frame rate gets the same budget). Fields the
+    /// user set explicitly are kept. Unknown source codecs fall back to libx264.
+    fn matched_to_source(&self, src: &VideoStreamInfo) -> EncodeSettings {
+        let mut out = self.clone();
+        out.match_source = false;
+        out.source_pix_fmt = Some(src.pix_fmt.clone());
+        if self.encoder.is_none() {
+            out.encoder = Some(match src.codec.as_str() {
+                "hevc" => VideoEncoder::Libx265,
+                "av1" => VideoEncoder::Libsvtav1,
+                "prores" => VideoEncoder::ProresKs,
+                _ => VideoEncoder::Libx264,
+            });
+        }
+        if self.profile.is_none() {
+            let ten_bit = src.pix_fmt.contains("10");
+            let profile = src.profile.as_deref().unwrap_or("").to_lowercase();
+            out.profile = match out.encoder() {
+                VideoEncoder::Libx264 => match profile.as_str() {
+                    "high 10" | "high 10 intra" => Some("high10"),
+                    "high 4:2:2" | "high 4:2:2 intra" => Some("high422"),
+                    "high 4:4:4 predictive" | "high 4:4:4 intra" => Some("high444"),
+                    "main" => Some("main"),
+                    "baseline" | "constrained baseline" => Some("baseline"),
+                    _ if ten_bit => Some("high10"),
+                    "high" => Some("high"),
+                    _ => None,
+                },
+                VideoEncoder::Libx265 => match (profile.as_str(), pix_fmt_layout(&src.pix_fmt)) {
+                    (_, (444, 8)) => Some("main444-8"),
+                    (_, (444, _)) => Some("main444-10"),
+                    (_, (422, _)) => Some("main422-10"),
+                    ("main 10", _) => Some("main10"),
+                    ("main 12", _) => Some("main12"),
+                    _ if ten_bit => Some("main10"),
+                    ("main", _) => Some("main"),
+                    _ => None,
+                },
+                VideoEncoder::Libsvtav1 => match profile.as_str() {
+                    "main" | "high" | "professional" => Some(profile.as_str()),
+                    _ => None,
+                },
+                VideoEncoder::ProresKs => match profile.as_str() {
+                    "proxy" => Some("proxy"),
+                    "lt" => Some("lt"),
+                    "standard" => Some("standard"),
+                    "hq" => Some("hq"),
+                    "4444" => Some("4444"),
+                    "4444 xq" | "4444xq" => Some("4444xq"),
+                    _ => None,
+                },
+                _ => None,
+            }
+            .map(str::to_string);
+        }
+        let rate_set = self.crf.is_some() || self.bitrate_kbps.is_some() || self.target_size_mb.is_some();
+        if !rate_set && out.encoder() != VideoEncoder::ProresKs {
+            out.bitrate_kbps = src.bit_rate_kbps;
+        }
+        out
+    }
+
+    /// Whether this encode can carry an alpha channel into `output_ext`.
+    fn supports_alpha(&self, output_ext: &str) -> bool {
+        if output_ext != "mov" {
+            return false;
+        }
+        match self.encoder() {
+            VideoEncoder::ProresKs => matches!(self.profile.as_deref(), None | Some("4444" | "4444xq")),
+            VideoEncoder::HevcVideotoolbox => self.profile.as_deref() != Some("main10"),
+            _ => false,
+        }
+    }
+
+    /// Pixel format and encoder options for an alpha encode (see `supports_alpha`).
+    fn push_alpha_args(&self, cmd: &mut Command) {
+        match self.encoder() {
+            VideoEncoder::ProresKs => {
+                if self.profile.is_none() {
+                    cmd.arg("-profile:v").arg("4444");
+                }
+                cmd.arg("-pix_fmt").arg("yuva444p10le");
+            }
+            VideoEncoder::HevcVideotoolbox => {
+                cmd.arg("-alpha_quality").arg("0.75").arg("-pix_fmt").arg("bgra");
+            }
+            _ => {}
+        }
+    }
+
+    fn push_args(&self, cmd: &mut Command, pass: Option<&EncodePass>) {
+        if self.backend == EncodeBackend::Av1an {
+            // The lossless intermediate av1an reads; the real settings go to av1an.
+            cmd.arg("-c:v").arg("ffv1").arg("-level").arg("3");
+            return;
+        }
+        let enc = self.encoder();
+        let mut x265_params: Vec<String> = Vec::new();
+        cmd.arg("-c:v").arg(enc.ffmpeg_name());
+        if let Some(preset) = self.preset.as_deref() {
+            let preset = match enc {
+                VideoEncoder::Libsvtav1 => SVT_AV1_PRESETS
+                    .iter()
+                    .find(|(n, _)| *n == preset)
+                    .map(|(_, v)| *v)
+                    .unwrap_or(preset),
+                _ => preset,
+            };
+            cmd.arg("-preset").arg(preset);
+        }
+        if let Some(crf) = self.crf {
+            if enc.is_nvenc() {
+                cmd.arg("-cq").arg(crf.to_string());
+            } else if enc == VideoEncoder::HevcVideotoolbox {
+                // VideoToolbox quality runs 1-100, higher is better.
+                cmd.arg("-q:v").arg((100 - crf * 100 / 51).max(1).to_string());
+            } else if enc == VideoEncoder::Libsvtav1 {
+                // AV1 needs a higher number for similar quality: x264 23 is roughly SVT 35.
+                cmd.arg("-crf").arg((crf * 3).div_ceil(2).min(63).to_string());
+            } else {
+                cmd.arg("-crf").arg(crf.to_string());
+            }
+        }
+        if let (None, Some(kbps)) = (pass, self.bitrate_kbps) {
+            cmd.arg("-b:v").arg(format!("{kbps}k"));
+        }
+        if let Some(p) = pass {
+            cmd.arg("-b:v").arg(format!("{}k", p.bitrate_kbps));
+            if enc == VideoEncoder::Libx265 {
+                x265_params.push(format!("pass={}", p.pass));
+                x265_params.push(format!("stats={}", escape_x265_param(&format!("{}.log", p.log_prefix.to_string_lossy()))));
+            } else {
+                cmd.arg("-pass").arg(p.pass.to_string())
+                    .arg("-passlogfile").arg(&p.log_prefix);
+            }
+        }
+        if let Some(tune) = self.tune.as_deref() {
+            cmd.arg("-tune").arg(tune);
+        }
+        if let Some(profile) = self.profile.as_deref() {
+            cmd.arg("-profile:v").arg(profile);
+        }
+        if let Some(level) = self.level.as_deref() {
+            cmd.arg("-level").arg(level);
+        }
+        if let Some(gop) = self.gop {
+            cmd.arg("-g").arg(gop.to_string());
+            if !self.scene_cut_keyframes {
+                cmd.arg("-keyint_min").arg(gop.to_string());
+            }
+        }
+        if let Some(bf) = self.bframes {
+            cmd.arg("-bf").arg(bf.to_string());
+        }
+        if !self.scene_cut_keyframes {
+            match enc {
+                VideoEncoder::Libx264 => {
+                    cmd.arg("-sc_threshold").arg("0");
+                }
+                VideoEncoder::Libx265 => x265_params.push("scenecut=0".into()),
+                VideoEncoder::H264Nvenc | VideoEncoder::HevcNvenc => {
+                    cmd.arg("-no-scenecut").arg("1");
+                }
+                VideoEncoder::Libsvtav1 => {
+                    cmd.arg("-svtav1-params").arg("scd=0");
+                }
+                // ProRes is intra-only; VideoToolbox has no scene-cut switch.
+                VideoEncoder::ProresKs | VideoEncoder::HevcVideotoolbox => {}
+            }
+        }
+        if !x265_params.is_empty() {
+            cmd.arg("-x265-params").arg(x265_params.join(":"));
+        }
+    }
+
+    /// SvtAv1EncApp options for av1an's `-v`, matching what `push_args` gives libsvtav1.
+    fn av1an_video_params(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(preset) = self.preset.as_deref() {
+            let preset = SVT_AV1_PRESETS.iter().find(|(n, _)| *n == preset).map_or(preset, |(_, v)| *v);
+            params.push(format!("--preset {preset}"));
+        }
+        if let Some(crf) = self.crf {
+            params.push(format!("--crf {}", (crf * 3).div_ceil(2).min(63)));
+        }
+        if let Some(gop) = self.gop {
+            params.push(format!("--keyint {gop}"));
+        }
+        params.join(" ")
+    }
+}
+
+const COLOR_PRIMARIES: &[&str] = &[
+    "bt709", "bt470m", "bt470bg", "smpte170m", "smpte240m", "film", "bt2020", "smpte428",
+    "smpte431", "smpte432",
+];
+const COLOR_TRANSFERS: &[&str] = &[
+    "bt709", "gamma22", "gamma28", "smpte170m", "smpte240m", "linear", "iec61966-2-1",
+    "iec61966-2-4", "bt1361e", "bt2020-10", "bt2020-12", "smpte2084", "smpte428", "arib-std-b67",
+];
+const COLOR_MATRICES: &[&str] = &[
+    "rgb", "bt709", "fcc", "bt470bg", "smpte170m", "smpte240m", "ycgco", "bt2020nc", "bt2020c",
+];
+const COLOR_RANGES: &[&str] = &["tv", "pc"];
+
+/// Colorimetry written to the output (`-color_primaries/-color_trc/-colorspace/-color_range`),
+/// using ffprobe's names. Without them players guess, usually bt601 for SD and bt709 for HD.
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct ColorTags {
+    primaries: Option<String>,
+    transfer: Option<String>,
+    matrix: Option<String>,
+    range: Option<String>,
+}
+
+impl ColorTags {
+    /// What the tonemap filter produces.
+    fn bt709_sdr() -> Self {
+        Self {
+            primaries: Some("bt709".into()),
+            transfer: Some("bt709".into()),
+            matrix: Some("bt709".into()),
+            range: Some("tv".into()),
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        for (what, value, allowed) in [
+            ("colour primaries", &self.primaries, COLOR_PRIMARIES),
+            ("transfer", &self.transfer, COLOR_TRANSFERS),
+            ("colour matrix", &self.matrix, COLOR_MATRICES),
+            ("colour range", &self.range, COLOR_RANGES),
+        ] {
+            if let Some(v) = value.as_deref() {
+                if !allowed.contains(&v) {
+                    return Err(format!("Unknown {what} '{v}' (expected one of {})", allowed.join(", ")));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Fields set here win; the rest come from `fallback`.
+    fn or(&self, fallback: ColorTags) -> ColorTags {
+        ColorTags {
+            primaries: self.primaries.clone().or(fallback.primaries),
+            transfer: self.transfer.clone().or(fallback.transfer),
+            matrix: self.matrix.clone().or(fallback.matrix),
+            range: self.range.clone().or(fallback.range),
+        }
+    }
+
+    /// swscale's name for the matrix, when it has one.
+    fn scale_matrix(&self) -> Option<&'static str> {
+        match self.matrix.as_deref()? {
+            "bt709" => Some("bt709"),
+            "fcc" => Some("fcc"),
+            "bt470bg" => Some("bt470"),
+            "smpte170m" => Some("smpte170m"),
+            "smpte240m" => Some("smpte240m"),
+            "bt2020nc" | "bt2020c" => Some("bt2020"),
+            _ => None,
+        }
+    }
+
+    fn push_args(&self, cmd: &mut Command) {
+        for (flag, value) in [
+            ("-color_primaries", &self.primaries),
+            ("-color_trc", &self.transfer),
+            ("-colorspace", &self.matrix),
+            ("-color_range", &self.range),
+        ] {
+            if let Some(v) = value {
+                cmd.arg(flag).arg(v);
+            }
+        }
+    }
+}
+
+/// `-x265-params` is a `key=value:key=value` list; escape the separators (and the backslashes
+/// of Windows paths) in a value.
+fn escape_x265_param(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | ':' | '=') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Video bitrate that fits `target_mb` MiB over `duration_secs`, after the audio track and
+/// about 2% container overhead.
+fn target_video_kbps(target_mb: f64, duration_secs: f64, audio_kbps: u32) -> Result<u32, String> {
+    if duration_secs <= 0.0 {
+        return Err("Target file size needs the source duration, which could not be probed".into());
+    }
+    let total_kbps = target_mb * 1024.0 * 1024.0 * 8.0 / 1000.0 / duration_secs;
+    let video_kbps = total_kbps * 0.98 - audio_kbps as f64;
+    if video_kbps < 100.0 {
+        return Err(format!(
+            "{target_mb} MB is too small for {duration_secs:.0}s of video ({:.0} kbps left after audio)",
+            video_kbps.max(0.0)
+        ));
+    }
+    Ok(video_kbps as u32)
+}
+
+/// Work folder for a two-pass encode's stats files (`pass-0.log`, `.mbtree`, `.log.cutree`…),
+/// beside the job's frame folders so it goes with the rest of the job's temp files.
+fn pass_log_dir(job: &SmoothJob) -> PathBuf {
+    job.frames_out_dir.with_extension("passlog")
+}
+
+// -------------------- Dedup retiming --------------------
+
+/// `pts_time` of a frame reported by ffmpeg's `showinfo` filter, e.g.
+/// `[Parsed_showinfo_1 @ 0x…] n:   3 pts:  3003 pts_time:0.1001 duration:…`.
+fn parse_showinfo_pts_time(line: &str) -> Option<f64> {
+    let rest = line.split("pts_time:").nth(1)?;
+    rest.split_whitespace().next()?.parse::<f64>().ok()
+}
+
+/// Escape a path for a single-quoted entry in an ffconcat list.
+fn ffconcat_quote(p: &Path) -> String {
+    format!("'{}'", p.to_string_lossy().replace('\'', "'\\''"))
+}
+
+/// Source timestamps of a job's extracted frames, one per frame in `frames_in` order. Written
+/// next to the job spec at extraction, and read back at encode to give every output frame
+/// its exact source time.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct FrameTimestamps {
+    fps_in: f64,
+    dedup: bool,
+    pts: Vec<f64>,
+}
+
+fn frame_timestamps_path(root: &Path, job_id: &str) -> PathBuf {
+    job_spec_path(root, job_id).with_extension("pts.json")
+}
+
+fn save_frame_timestamps(job: &SmoothJob, pts: &[f64]) -> Result<(), String> {
+    let sidecar = FrameTimestamps { fps_in: job.fps_in, dedup: job.dedup, pts: pts.to_vec() };
+    let json = serde_json::to_string(&sidecar).map_err(|e| e.to_string())?;
+    fs::write(frame_timestamps_path(&job.root, &job.job_id), json).map_err(|e| format!("Failed to write frame timestamps: {e}"))
+}
+
+/// The job's frame timestamps; older job specs carried dedup timestamps themselves.
+fn load_frame_timestamps(job: &SmoothJob) -> Vec<f64> {
+    fs::read_to_string(frame_timestamps_path(&job.root, &job.job_id))
+        .ok()
+        .and_then(|t| serde_json::from_str::<FrameTimestamps>(&t).ok())
+        .map(|s| s.pts)
+        .unwrap_or_else(|| job.kept_pts.clone())
+}
+
+/// Whether frame intervals stray from `fps` by more than a quarter frame.
+fn is_variable_rate(pts: &[f64], fps: f64) -> bool {
+    let step = 1.0 / fps.max(1.0);
+    pts.windows(2).any(|w| ((w[1] - w[0]) - step).abs() > step / 4.0)
+}
+
+/// Write an ffconcat list giving every interpolated frame its own duration, so a deduplicated
+/// or variable-rate frame sequence keeps the exact timeline of the source.
+///
+/// `kept_pts` are the source timestamps of the extracted frames (after dedup); `end_time` is the
+/// source duration. Output frames are spread evenly over each kept frame's interval, which for
+/// a 2x RIFE run puts frame 2k at the source frame and 2k+1 halfway to the next one.
+fn write_retime_concat(frames_out_dir: &Path, kept_pts: &[f64], end_time: f64, list_path: &Path) -> Result<usize, String> {
+    let frames = sorted_files(frames_out_dir);
+    if frames.is_empty() || kept_pts.is_empty() {
+        return Err("Nothing to retime (no frames or timestamps)".into());
+    }
+
+    let base = kept_pts[0];
+    let mut times: Vec<f64> = kept_pts.iter().map(|t| t - base).collect();
+    let end = (end_time - base).max(*times.last().unwrap_or(&0.0) + 0.001);
+    times.push(end);
+
+    let n = kept_pts.len() as f64;
+    let m = frames.len() as f64;
+    let time_at = |j: usize| -> f64 {
+        let pos = j as f64 * n / m;
+        let k = (pos.floor() as usize).min(kept_pts.len() - 1);
+        let frac = pos - k as f64;
+        times[k] + frac * (times[k + 1] - times[k])
+    };
+
+    let mut list = String::from("ffconcat version 1.0\n");
+    for (j, f) in frames.iter().enumerate() {
+        let start = time_at(j);
+        let next = if j + 1 < frames.len() { time_at(j + 1) } else { end };
+        list.push_str(&format!("file {}\nduration {:.6}\n", ffconcat_quote(f), (next - start).max(0.000_001)));
+    }
+    // The concat demuxer ignores the duration of the final entry unless the file is repeated.
+    if let Some(last) = frames.last() {
+        list.push_str(&format!("file {}\n", ffconcat_quote(last)));
+    }
+
+    fs::write(list_path, list).map_err(|e| format!("Failed to write retime list: {e}"))?;
+    Ok(frames.len())
+}
+
+// -------------------- Scene cut protection --------------------
+
+/// How to treat the interpolated frame that straddles a scene cut. RIFE happily morphs the
+/// last shot into the next one, which shows up as a single ghosted frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum CutHandling {
+    /// Keep whatever RIFE produced.
+    #[default]
+    Off,
+    /// Repeat the last frame before the cut (no ghosting, but a visible 1-frame hold).
+    Duplicate,
+    /// 50/50 cross-blend of the frames on either side of the cut.
+    Blend,
+}
+
+/// ffmpeg `scene` score above which a frame is treated as the start of a new shot.
+const SCENE_CUT_THRESHOLD: f64 = 0.4;
+
+/// Indices (0-based, in sorted file order) of extracted frames that start a new scene.
+///
+/// Runs over the extracted frame sequence rather than the source so the indices line up with
+/// what RIFE sees, dedup included: the image2 demuxer numbers frames from pts 0.
+fn detect_scene_cuts(ffmpeg: &Path, frames_dir: &Path, threshold: f64) -> Result<Vec<usize>, String> {
+    let mut cmd = tool_command(ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin");
+    // At 25 fps (image2's default) showinfo's pts are frame numbers.
+    FramesInput::new(frames_dir, 25.0)?.push_args(&mut cmd);
+    FilterChain::new().select_scene(threshold).show_info().apply(&mut cmd);
+    cmd.arg("-f").arg("null").arg("-");
+    let frames = count_files_in_dir(frames_dir) as f64;
+    let out = run_tool(log_command("scene_detect", &mut cmd), analysis_timeout_secs(frames))
+        .map_err(|e| format!("Scene detection failed: {e}"))?;
+    if !out.status.success() {
+        return Err("Scene detection failed".into());
+    }
+
+    let mut cuts = Vec::new();
+    for line in String::from_utf8_lossy(&out.stderr).lines() {
+        if !line.contains("Parsed_showinfo") {
+            continue;
+        }
+        let pts = line
+            .split(" pts:")
+            .nth(1)
+            .and_then(|r| r.split_whitespace().next())
+            .and_then(|v| v.parse::<usize>().ok());
+        if let Some(k) = pts {
+            if k > 0 {
+                cuts.push(k);
+            }
+        }
+    }
+    Ok(cuts)
+}
+
+// -------------------- Frame holds --------------------
+
+/// Shortest static run worth skipping, in source frames.
+const MIN_HOLD_FRAMES: usize = 12;
+/// Noise floor below which freezedetect treats frames as identical; low enough that slow
+/// credit rolls and fades still count as motion.
+const HOLD_NOISE_DB: i32 = -60;
+
+/// A run of unchanging source frames, as 1-based indices of its first and last frame.
+#[derive(Clone, Copy, Debug)]
+struct FrameHold {
+    first: usize,
+    last: usize,
+}
+
+impl FrameHold {
+    /// Frames inside the run. Its first and last frame still go through RIFE so the motion
+    /// into and out of the hold is interpolated.
+    fn interior(&self) -> std::ops::Range<usize> {
+        self.first + 1..self.last
+    }
+}
+
+/// Static runs among the `count` extracted frames in `frames_dir`.
+fn find_frame_holds(ffmpeg: &Path, frames_dir: &Path, count: usize) -> Result<Vec<FrameHold>, String> {
+    let mut cmd = tool_command(ffmpeg);
+    // At 1 fps the reported times are 0-based frame numbers.
+    cmd.arg("-hide_banner").arg("-nostdin");
+    FramesInput::new(frames_dir, 1.0)?.push_args(&mut cmd);
+    FilterChain::new().freeze_detect(MIN_HOLD_FRAMES).apply(&mut cmd);
+    cmd.arg("-f").arg("null").arg("-");
+    let out = run_tool(log_command("hold_detect", &mut cmd), analysis_timeout_secs(count as f64))
+        .map_err(|e| format!("Static scene detection failed: {e}"))?;
+    if !out.status.success() {
+        return Err("Static scene detection failed".into());
+    }
+
+    let value = |line: &str, key: &str| {
+        line.split(key).nth(1).and_then(|r| r.split_whitespace().next()?.parse::<f64>().ok())
+    };
+    let mut holds = Vec::new();
+    let mut start = None;
+    for line in String::from_utf8_lossy(&out.stderr).lines() {
+        if let Some(t) = value(line, "freeze_start:") {
+            start = Some(t.round() as usize + 1);
+        } else if let Some(t) = value(line, "freeze_end:") {
+            // freeze_end is the first frame that moves again.
+            if let Some(first) = start.take() {
+                holds.push(FrameHold { first, last: (t.round() as usize).min(count) });
+            }
+        }
+    }
+    // A hold that runs to the end is never closed.
+    if let Some(first) = start {
+        holds.push(FrameHold { first, last: count });
+    }
+    holds.retain(|h| h.last > h.first + 1);
+    Ok(holds)
+}
+
+/// Link every frame outside the holds' interiors into `dir`, numbered without gaps, so RIFE
+/// only interpolates those. Hard links where the filesystem allows, since RIFE only reads them.
+fn link_unheld_frames(frames_in_dir: &Path, dir: &Path, holds: &[FrameHold]) -> Result<usize, String> {
+    clear_dir(dir)?;
+    if is_sharded(frames_in_dir) {
+        make_sharded(dir)?;
+    }
+    let out = FrameFiles::new(dir, FrameFormat::in_dir(frames_in_dir));
+    let held = held_mask(holds, count_files_in_dir(frames_in_dir));
+    let mut n = 0;
+    for (i, src) in sorted_files(frames_in_dir).iter().enumerate() {
+        if held.get(i + 1).copied().unwrap_or(false) {
+            continue;
+        }
+        n += 1;
+        let dest = out.create(n)?;
+        if fs::hard_link(src, &dest).is_err() {
+            fs::copy(src, &dest).map_err(|e| format!("Failed to write {}: {e}", dest.to_string_lossy()))?;
+        }
+    }
+    Ok(n)
+}
+
+/// `mask[i]` is true for 1-based source frame `i` inside a hold's interior.
+fn held_mask(holds: &[FrameHold], count: usize) -> Vec<bool> {
+    let mut mask = vec![false; count + 1];
+    for h in holds {
+        for i in h.interior().filter(|&i| i <= count) {
+            mask[i] = true;
+        }
+    }
+    mask
+}
+
+/// Turn RIFE's output for the frames `link_unheld_frames` kept back into a full 2x sequence:
+/// each kept frame's pair moves to its source position and every held frame becomes two
+/// copies of its hold's first frame. Returns how many output frames were copies.
+fn expand_held_output(frames_out_dir: &Path, format: FrameFormat, holds: &[FrameHold], count: usize) -> Result<u64, String> {
+    let held = held_mask(holds, count);
+    let files = FrameFiles::new(frames_out_dir, format);
+    let path = |n: usize| files.path(n);
+    // RIFE writes two frames per input; one missing would leave the output short.
+    let missing = |n: usize| format!("Interpolated frame {n} is missing from {}", frames_out_dir.to_string_lossy());
+    // Frames move to higher numbers, last first, so their shards have to exist up front.
+    files.create_shards(2 * count)?;
+    // Compact position of each kept frame; positions only grow, so moving from the end never
+    // overwrites a frame that hasn't moved yet.
+    let mut kept = Vec::new();
+    for (i, &is_held) in held.iter().enumerate().skip(1) {
+        if !is_held {
+            kept.push(i);
+        }
+    }
+    for (c, &i) in kept.iter().enumerate().rev() {
+        let c = c + 1;
+        for (from, to) in [(2 * c, 2 * i), (2 * c - 1, 2 * i - 1)] {
+            if !path(from).exists() {
+                return Err(missing(from));
+            }
+            if from != to {
+                fs::rename(path(from), path(to)).map_err(|e| format!("Failed to renumber interpolated frames: {e}"))?;
+            }
+        }
+    }
+    let mut copies = 0;
+    for h in holds {
+        let src = path(2 * h.first);
+        if !src.exists() {
+            return Err(missing(2 * h.first));
+        }
+        for i in h.interior().filter(|&i| i <= count) {
+            for n in [2 * i - 1, 2 * i] {
+                if fs::hard_link(&src, path(n)).is_err() {
+                    fs::copy(&src, path(n)).map_err(|e| format!("Failed to write held frame {n}: {e}"))?;
+                }
+                copies += 1;
+            }
+        }
+    }
+    Ok(copies)
+}
+
+/// One detected cut, in source and output terms.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct SceneCut {
+    /// 1-based scene number of the shot that starts here.
+    scene: usize,
+    /// Index of the first extracted frame of the new shot.
+    source_frame: usize,
+    /// Index of the same frame in the interpolated (2x) output.
+    output_frame: usize,
+    /// Position on the source timeline.
+    time_secs: f64,
+    /// `time_secs` as HH:MM:SS:FF at the output frame rate.
+    timecode: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+struct SceneList {
+    job_id: String,
+    source: String,
+    output_fps: f64,
+    duration_secs: f64,
+    cuts: Vec<SceneCut>,
+    json_path: String,
+    csv_path: String,
+    edl_path: String,
+}
+
+/// Non-drop-frame timecode at the nominal (rounded) rate; NLEs conform 59.94 as 60 NDF.
+fn timecode(secs: f64, fps: f64) -> String {
+    let rate = fps.round().max(1.0) as u64;
+    let frames = (secs.max(0.0) * fps).round() as u64;
+    let (h, m, s, f) = (frames / (rate * 3600), frames / (rate * 60) % 60, frames / rate % 60, frames % rate);
+    format!("{h:02}:{m:02}:{s:02}:{f:02}")
+}
+
+/// Write the job's scene cuts as JSON, CSV and a CMX3600 EDL (one event per shot) next to the
+/// job spec, for import into an editor.
+fn write_scene_list(job: &SmoothJob, cuts: &[usize], kept_pts: &[f64]) -> Result<SceneList, String> {
+    let output_fps = (job.fps_in * job.rate_multiplier()).max(1.0);
+    let time_of = |k: usize| match kept_pts.get(k) {
+        Some(&t) => t,
+        None => k as f64 / job.fps_in.max(1.0),
+    };
+    let cuts: Vec<SceneCut> = cuts
+        .iter()
+        .enumerate()
+        .map(|(i, &k)| SceneCut {
+            scene: i + 2,
+            source_frame: k,
+            output_frame: (k as f64 * job.rate_multiplier()).round() as usize,
+            time_secs: time_of(k),
+            timecode: timecode(time_of(k), output_fps),
+        })
+        .collect();
+
+    let base = job_spec_path(&job.root, &job.job_id);
+    let json_path = base.with_extension("scenes.json");
+    let csv_path = base.with_extension("scenes.csv");
+    let edl_path = base.with_extension("scenes.edl");
+
+    let mut csv = String::from("scene,source_frame,output_frame,time_secs,timecode\n");
+    csv.push_str(&format!("1,0,0,0.000000,{}\n", timecode(0.0, output_fps)));
+    for c in &cuts {
+        csv.push_str(&format!(
+            "{},{},{},{:.6},{}\n",
+            c.scene, c.source_frame, c.output_frame, c.time_secs, c.timecode
+        ));
+    }
+
+    let clip = job.output.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", job.job_id);
+    let mut bounds: Vec<f64> = std::iter::once(0.0).chain(cuts.iter().map(|c| c.time_secs)).collect();
+    bounds.push(job.duration_secs.max(*bounds.last().unwrap_or(&0.0)));
+    for (i, w) in bounds.windows(2).enumerate() {
+        let (a, b) = (timecode(w[0], output_fps), timecode(w[1], output_fps));
+        edl.push_str(&format!("{:03}  AX       V     C        {a} {b} {a} {b}\n", i + 1));
+        edl.push_str(&format!("* FROM CLIP NAME: {clip}\n\n"));
+    }
+
+    let list = SceneList {
+        job_id: job.job_id.clone(),
+        source: job.input.to_string_lossy().to_string(),
+        output_fps,
+        duration_secs: job.duration_secs,
+        cuts,
+        json_path: json_path.to_string_lossy().to_string(),
+        csv_path: csv_path.to_string_lossy().to_string(),
+        edl_path: edl_path.to_string_lossy().to_string(),
+    };
+    let json = serde_json::to_string_pretty(&list).map_err(|e| e.to_string())?;
+    for (path, body) in [(&json_path, json), (&csv_path, csv), (&edl_path, edl)] {
+        fs::write(path, body).map_err(|e| format!("Failed to write {}: {e}", path.to_string_lossy()))?;
+    }
+    Ok(list)
+}
+
+/// Chapters for every shot of a scene list, as an ffmetadata file for `-map_chapters`.
+fn write_chapter_metadata(list: &SceneList, path: &Path) -> Result<(), String> {
+    let mut meta = String::from(";FFMETADATA1\n");
+    let mut bounds: Vec<f64> = std::iter::once(0.0).chain(list.cuts.iter().map(|c| c.time_secs)).collect();
+    bounds.push(list.duration_secs.max(*bounds.last().unwrap_or(&0.0)));
+    for (i, w) in bounds.windows(2).enumerate() {
+        meta.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle=Scene {}\n",
+            (w[0] * 1000.0).round() as u64,
+            (w[1] * 1000.0).round() as u64,
+            i + 1
+        ));
+    }
+    fs::write(path, meta).map_err(|e| format!("Failed to write {}: {e}", path.to_string_lossy()))
+}
+
+/// Scene cuts exported by a job's scene-detection pass.
+#[tauri::command]
+fn get_scene_list(app: AppHandle, job_id: String) -> Result<SceneList, String> {
+    let root = app_root(&app)?;
+    validate_job_id(&job_id)?;
+    load_scene_list(&root, &job_id)
+}
+
+fn load_scene_list(root: &Path, job_id: &str) -> Result<SceneList, String> {
+    let path = job_spec_path(root, job_id).with_extension("scenes.json");
+    let text = fs::read_to_string(&path).map_err(|_| {
+        format!("No scene list for job {job_id}; scene detection runs when cut protection is on")
+    })?;
+    serde_json::from_str(&text).map_err(|e| format!("Scene list is unreadable: {e}"))
+}
+
+/// Files in `dir` and its frame shard folders, by file name.
+fn sorted_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for e in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = e.path();
+        if path.is_file() {
+            files.push(path);
+        } else if is_shard_name(&e.file_name().to_string_lossy()) && path.is_dir() {
+            files.extend(fs::read_dir(&path).into_iter().flatten().flatten().map(|e| e.path()).filter(|p| p.is_file()));
+        }
+    }
+    files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    files
+}
+
+/// Replace the interpolated frame at each cut according to `mode`. For a 2x run, source frame
+/// `k` becomes output `2k` and the in-between frame for a cut at `k` is output `2k - 1`.
+/// Returns how many frames were rewritten.
+fn apply_cut_handling(ffmpeg: &Path, frames_out_dir: &Path, cuts: &[usize], mode: CutHandling, factor: f64) -> usize {
+    if mode == CutHandling::Off {
+        return 0;
+    }
+    let frames = sorted_files(frames_out_dir);
+    let mut fixed = 0;
+    // Output frame i sits at source position i / factor; the ones strictly between source
+    // frames k - 1 and k are the in-betweens across the cut.
+    let targets = cuts.iter().flat_map(|&k| {
+        let first = ((k - 1) as f64 * factor).floor() as usize + 1;
+        let end = (k as f64 * factor).ceil() as usize;
+        (first..end).map(move |i| (first, i, end))
+    });
+    for (first, mid, end) in targets {
+        if end >= frames.len() {
+            continue;
+        }
+        let (before, target, after) = (&frames[first - 1], &frames[mid], &frames[end]);
+        let ok = match mode {
+            CutHandling::Off => false,
+            CutHandling::Duplicate => fs::copy(before, target).is_ok(),
+            CutHandling::Blend => {
+                let mut cmd = tool_command(ffmpeg);
+                cmd.arg("-hide_banner").arg("-nostdin").arg("-y")
+                    .arg("-v").arg("error")
+                    .arg("-i").arg(before)
+                    .arg("-i").arg(after)
+                    .arg("-filter_complex").arg("[0:v][1:v]blend=all_mode=average")
+                    .arg("-frames:v").arg("1");
+                FrameFormat::of_path(target).push_encoder_args(&mut cmd);
+                cmd.arg(target);
+                run_tool(log_command("cut_blend", &mut cmd), PROBE_TIMEOUT_SECS).is_ok_and(|o| o.status.success())
+            }
+        };
+        if ok {
+            fixed += 1;
+        }
+    }
+    fixed
+}
+
+fn run_and_capture(mut cmd: Command) -> ToolValidation {
+    match run_tool(&mut cmd, PROBE_TIMEOUT_SECS) {
+        Ok(out) => {
+            let mut text = String::new();
+            if !out.stdout.is_empty() {
+                text.push_str(&String::from_utf8_lossy(&out.stdout));
+            }
+            if !out.stderr.is_empty() {
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&String::from_utf8_lossy(&out.stderr));
+            }
+            ToolValidation {
+                ok: out.status.success(),
+                path: None,
+                output: text.trim().to_string(),
+            }
+        }
+        Err(e) => ToolValidation {
+            ok: false,
+            path: None,
+            output: e,
+        },
+    }
+}
+
+
+/// Arguments of `smooth_video`. Everything but the paths is optional, and new job options
+/// are added here rather than as command parameters.
+#[derive(serde::Deserialize)]
+struct SmoothVideoRequest {
+    video_path: String,
+    output_path: String,
+    max_threads: Option<i32>,
+    on_conflict: Option<OnConflict>,
+    dedup: Option<bool>,
+    cut_handling: Option<CutHandling>,
+    keep_intermediates: Option<bool>,
+    filters: Option<FilterOptions>,
+    encode: Option<EncodeSettings>,
+    preset: Option<String>,
+    not_before: Option<i64>,
+    rife_runtime: Option<RifeRuntime>,
+    model: Option<String>,
+    corrupt_input: Option<CorruptInputOptions>,
+    region: Option<TimeRegion>,
+    fps_policy: Option<FpsPolicy>,
+    source_policy: Option<SourcePolicy>,
+    rife_threads: Option<RifeThreads>,
+    plugins: Option<Vec<String>>,
+    vapoursynth: Option<VapourSynthStage>,
+    quality: Option<u8>,
+    on_duplicate: Option<DuplicatePolicy>,
+    limits: Option<ResourceLimits>,
+}
+
+#[tauri::command]
+fn smooth_video(app: AppHandle, state: State<'_, AppState>, request: SmoothVideoRequest) -> Result<ExtractFramesResult, CommandError> {
+    let SmoothVideoRequest {
+        video_path,
+        output_path,
+        max_threads,
+        on_conflict,
+        dedup,
+        cut_handling,
+        keep_intermediates,
+        filters,
+        encode,
+        preset,
+        not_before,
+        rife_runtime,
+        model,
+        corrupt_input,
+        region,
+        fps_policy,
+        source_policy,
+        rife_threads,
+        plugins,
+        vapoursynth,
+        quality,
+        on_duplicate,
+        limits,
+    } = request;
+    // Non-blocking: returns immediately; work is done on a background thread.
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    let preset = resolve_preset(&root, preset.as_deref())?;
+    let tools = state.tools(&root);
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(tools.ffmpeg.clone())
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    let rife_runtime = rife_runtime.unwrap_or_default();
+    let (rife_bin, default_model) = rife_runtime.resolve(&root, &tools)?;
+    // Other runtimes ship their own models, and only ncnn has TTA.
+    let quality = quality.map(|level| plan_quality(&root, &tools, level)).transpose()?.map(|mut q| {
+        if rife_runtime != RifeRuntime::NcnnVulkan {
+            q.model = None;
+            q.tta = false;
+        }
+        q
+    });
+    let quality_model = quality.as_ref().and_then(|q| q.model.as_deref());
+    let model_dir = resolve_model_dir(&root, &tools, default_model, model.as_deref().or(preset.model.as_deref()).or(quality_model))?;
+    let model_name = model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let profile = model_profile(&root, &model_name);
+    if let Some(q) = &quality {
+        for note in &q.notes {
+            emit_log_limited(&app, &format!("Quality {}: {note}", q.level));
+        }
+    }
+
+    let max_threads = max_threads.or(preset.max_threads).or(profile.max_threads);
+    // Separate load/proc/save counts win over the single `max_threads`.
+    let threads = rife_threads.unwrap_or_else(|| RifeThreads::from_max_threads(max_threads));
+    threads.validate()?;
+    let limits = limits.unwrap_or_default();
+    limits.validate()?;
+    let on_conflict = on_conflict.or(preset.on_conflict);
+    let dedup = dedup.or(preset.dedup).or(profile.dedup);
+    let cut_handling = cut_handling.or(preset.cut_handling).or(profile.cut_handling);
+    let keep_intermediates = keep_intermediates.or(preset.keep_intermediates);
+    let mut filters = filters.or(preset.filters).unwrap_or_default();
+    filters.validate()?;
+    let encode = encode.or(preset.encode).or_else(|| quality.as_ref().map(QualityPlan::encode_settings)).unwrap_or_default();
+    encode.validate()?;
+    let corrupt_input = corrupt_input.or(preset.corrupt_input).unwrap_or_default();
+    if profile != ModelProfile::default() {
+        emit_log_limited(&app, &format!("Model {model_name}: applying its profile where the job doesn't say otherwise"));
+    }
+
+    let input = PathBuf::from(video_path.trim());
+    if !input.exists() {
+        return Err("Input video does not exist".into());
+    }
+    let output = PathBuf::from(output_path.trim());
+    if output_path.trim().is_empty() {
+        return Err("Output path is required".into());
+    }
+    check_source_protection(&root, &state.settings(&root), &input, &output, &[])?;
+    let output = claim_library_output(&root, &state.settings(&root), &output)?;
+    validate_output_path(&root, &input, &output, &[])?;
+    let on_conflict = on_conflict.unwrap_or_default();
+    let output = resolve_output_conflict(&output, on_conflict)?;
+    remember_input(&state, &root, &input);
+    let settings = state.settings(&root);
+    let (duration_secs, fps_in) = probe_duration_and_fps(&ffmpeg, &input).unwrap_or((0.0, 30.0));
+    let requested_fps = filters.output_fps;
+    let source_policy = source_policy.unwrap_or(settings.source_policy);
+    let file_name = input.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut source_decisions = Vec::new();
+    for (condition, detail) in detect_source_conditions(&ffmpeg, &input, fps_in) {
+        let action = source_policy.action(condition);
+        let what = format!("{file_name}: {} source ({detail})", condition.label());
+        match action {
+            SourceAction::Ignore => emit_log_limited(&app, &format!("{what}; processing as is")),
+            SourceAction::Fix => {
+                let rotation = (condition == SourceCondition::Rotation).then(|| probe_rotation(&ffmpeg, &input)).flatten();
+                let fix = fix_source_condition(condition, &mut filters, fps_in, rotation);
+                emit_log_limited(&app, &format!("{what}; {fix}"));
+            }
+            SourceAction::Skip => {
+                emit_log_limited(&app, &format!("{what}; skipped by the source policy"));
+                return Ok(ExtractFramesResult {
                     ok: false,
-                    message: format!("FFmpeg failed to start: {e}"),
-                    frames_dir: frames_dir_for_task.clone(),
-                    frame_pattern: frame_pattern_for_task.clone(),
+                    job_id: String::new(),
+                    frames_dir: String::new(),
+                    frame_pattern: String::new(),
+                    output: output.to_string_lossy().to_string(),
+                    skipped: Some(what),
+                    duplicate_of: None,
                 });
-                return;
             }
-        };
-
-        // stream ffmpeg stderr lightly
-        if let Some(stderr) = child.stderr.take() {
-            let reader = std::io::BufReader::new(stderr);
-            for line in reader.lines().flatten() {
-                let line = line.trim().to_string();
-                if !line.is_empty() {
-                    let _ = app_for_task.emit("pipeline_log", line);
+            SourceAction::Fail => return Err(format!("{what}; the source policy doesn't allow it").into()),
+        }
+        source_decisions.push(SourceDecision { condition, detail, action });
+    }
+    if source_decisions.iter().any(|d| d.action == SourceAction::Fix) {
+        filters.validate()?;
+    }
+    let skip_interpolation = source_decisions
+        .iter()
+        .any(|d| d.condition == SourceCondition::HighFps && d.action == SourceAction::Fix)
+        && !matches!(filters.output_fps, Some(fps) if fps > fps_in);
+    // Only the RIFE binary on ncnn takes a target frame count (`-n`).
+    let rife_timestep = rife_runtime == RifeRuntime::NcnnVulkan && profile.supports_timestep(&model_name);
+    // An explicit output rate, or one the source policy set, wins over the fps policy; the
+    // decision still records it.
+    let fps_decision = fps_policy.filter(|_| fps_in > 0.0).map(|policy| {
+        let mut d = conform_fps(fps_in, policy, rife_timestep);
+        match filters.output_fps {
+            Some(fps) => {
+                d.output_fps = fps;
+                d.note = if requested_fps.is_some() { "output fps set explicitly" } else { "output fps set by the source policy" }.into();
+            }
+            None if skip_interpolation => {
+                d.output_fps = fps_in;
+                d.note = "not interpolated (source policy)".into();
+            }
+            None if (d.output_fps - fps_in * 2.0).abs() > 0.001 => filters.output_fps = Some(d.output_fps),
+            None => {}
+        }
+        d
+    });
+    if let Some(d) = &fps_decision {
+        filters.validate()?;
+        emit_log_limited(&app, &format!("Frame rate: {:.3} -> {:.3} fps, {}", d.source_fps, d.output_fps, d.note));
+    }
+    // Above twice the source rate a timestep model makes every output frame itself, instead
+    // of the encode's fps filter repeating frames of a 2x result.
+    let mut extra_passes = 0;
+    let rife_factor = match filters.output_fps {
+        Some(fps) if !skip_interpolation && fps_in > 0.0 && fps > fps_in * 2.0 * 1.002 => {
+            if rife_timestep {
+                filters.output_fps = None;
+                emit_log_limited(&app, &format!("Interpolating straight to {fps:.3} fps (RIFE -n)"));
+                Some(fps / fps_in)
+            } else if profile.chained_passes == Some(true) {
+                // Enough 2x passes to reach the rate; the encode's fps filter drops the rest.
+                let passes = (fps / fps_in).log2().ceil() as u32;
+                extra_passes = passes - 1;
+                let reached = fps_in * f64::from(1u32 << passes);
+                if (reached - fps).abs() < 0.001 * fps {
+                    filters.output_fps = None;
+                }
+                emit_log_limited(&app, &format!(
+                    "{model_name} only makes midpoints: {passes} chained 2x passes to {reached:.3} fps for {fps:.3} fps"
+                ));
+                None
+            } else {
+                emit_log_limited(&app, &format!(
+                    "{model_name} only makes midpoints: interpolating to {:.3} fps and repeating frames to reach {fps:.3} fps",
+                    fps_in * 2.0
+                ));
+                None
+            }
+        }
+        _ => None,
+    };
+    let plugins = plugins.unwrap_or_default();
+    if !plugins.is_empty() && !settings.allow_stage_plugins {
+        return Err("Stage plugins are disabled in settings".into());
+    }
+    if vapoursynth.is_some() && !settings.allow_stage_plugins {
+        return Err("Stage plugins (including VapourSynth scripts) are disabled in settings".into());
+    }
+    for name in &plugins {
+        load_plugin(&root, name)?;
+    }
+    if let Some(vs) = &vapoursynth {
+        if !Path::new(vs.script.trim()).is_file() {
+            return Err(format!("VapourSynth script does not exist: {}", vs.script).into());
+        }
+        state.tools(&root).vspipe.ok_or("vspipe not found (install VapourSynth)")?;
+    }
+    let region = match region {
+        Some(r) => {
+            let end_secs = if duration_secs > 0.0 { r.end_secs.min(duration_secs) } else { r.end_secs };
+            if !(r.start_secs >= 0.0 && end_secs - r.start_secs >= 0.1) {
+                return Err("The region needs an in point before its out point, inside the video".into());
+            }
+            if filters.draft.is_some() {
+                return Err("Draft quality can't be used with a region: the parts around it are full size".into());
+            }
+            if encode.target_size_mb.is_some() {
+                return Err("A target size can't be used with a region; set a quality or bitrate instead".into());
+            }
+            if encode.backend == EncodeBackend::Av1an {
+                return Err("av1an can't encode a region; use the ffmpeg encoder".into());
+            }
+            let whole = r.start_secs <= 0.0 && duration_secs > 0.0 && end_secs >= duration_secs;
+            (!whole).then(|| TimeRegion { start_secs: r.start_secs, end_secs }.on_frames(fps_in))
+        }
+        None => None,
+    };
+    let encode = resolve_encode_settings(&app, &state, encode, &ffmpeg, &input)?;
+    if encode.backend == EncodeBackend::Av1an {
+        state.tools(&root).av1an.ok_or("av1an not found (install av1an)")?;
+    }
+    let source_alpha = encode.keep_alpha && has_alpha(&ffmpeg, &input);
+    let output_ext = output_extension(&output);
+    let alpha = source_alpha && encode.supports_alpha(&output_ext);
+    if source_alpha && !alpha {
+        emit_log_limited(&app, &format!(
+            "Source has an alpha channel, but {} in .{output_ext} can't store it; transparency will be lost \
+             (use prores_ks 4444 or hevc_videotoolbox in .mov)",
+            encode.encoder().ffmpeg_name()
+        ));
+    }
+
+    // Alpha jobs stay on PNG: the alpha checks and key-colour compositing work on PNG frames.
+    let has_webp = state.ffmpeg_capabilities(&ffmpeg).is_ok_and(|c| c.has_encoder("libwebp"));
+    let frame_format = match quality.as_ref().and_then(|q| q.frame_format).unwrap_or(settings.frame_format) {
+        FrameFormat::Webp if alpha => FrameFormat::Png,
+        FrameFormat::Webp if !has_webp => {
+            emit_log_limited(&app, "This ffmpeg build has no libwebp encoder; using PNG frames");
+            FrameFormat::Png
+        }
+        f => f,
+    };
+    // Only the region is extracted and interpolated; the parts around it are stream-copied.
+    let frames_in = region.map_or(duration_secs, |r| r.secs()) * fps_in;
+    let multiplier = if skip_interpolation { 1.0 } else { rife_factor.unwrap_or_else(|| f64::from(2u32 << extra_passes.min(8))) };
+    let frame_bytes = estimate_frame_bytes(&ffmpeg, &input, frames_in, multiplier, extra_passes > 0, encode.backend == EncodeBackend::Av1an);
+    let (frame_format, budget_note) = limits.fit_frame_format(frame_format, frame_bytes, has_webp && !alpha)?;
+    if let Some(note) = budget_note {
+        emit_log_limited(&app, &note);
+    }
+    let store = frame_store_for_job(&app, &root, &settings, frame_bytes);
+    check_source_protection(&root, &settings, &input, &output, &[&store])?;
+    if store.starts_with(temp_root(&root)) {
+        let storage = state.temp_storage_check(&root, false);
+        report_temp_storage(&app, &storage, frame_bytes);
+    }
+
+    let fingerprint = job_fingerprint(&input, &(
+        rife_runtime,
+        &model_name,
+        dedup,
+        cut_handling,
+        &filters,
+        &encode,
+        region,
+        &fps_decision,
+        &plugins,
+        &vapoursynth,
+        quality.as_ref().is_some_and(|q| q.tta),
+        &output_ext,
+    ))
+    .unwrap_or_default();
+    let duplicate = Some(&fingerprint).filter(|f| !f.is_empty()).and_then(|f| find_duplicate_job(&state, &root, f));
+    if let Some(dup) = &duplicate {
+        let what = match dup.ok {
+            None => format!("{file_name}: same input and settings as job {}, which is still queued or running", dup.job_id),
+            _ => format!("{file_name}: same input and settings as job {} ({})", dup.job_id, dup.output),
+        };
+        match on_duplicate.unwrap_or(settings.duplicate_jobs) {
+            DuplicatePolicy::Allow => {}
+            DuplicatePolicy::Warn => emit_log_limited(&app, &format!("{what}; starting it anyway")),
+            DuplicatePolicy::Skip => {
+                emit_log_limited(&app, &format!("{what}; skipped"));
+                return Ok(ExtractFramesResult {
+                    ok: false,
+                    job_id: String::new(),
+                    frames_dir: String::new(),
+                    frame_pattern: String::new(),
+                    output: output.to_string_lossy().to_string(),
+                    skipped: Some(what),
+                    duplicate_of: Some(dup.job_id.clone()),
+                });
+            }
+        }
+    }
+
+    // Create a job folder
+    let job_id = make_job_id();
+    let frames_in_dir = store.join("frames_in").join(&job_id);
+    let frames_out_dir = store.join("frames_out").join(&job_id);
+    std::fs::create_dir_all(&frames_in_dir).map_err(|e| fs_error("create frames_in dir", &frames_in_dir, &e))?;
+    std::fs::create_dir_all(&frames_out_dir).map_err(|e| fs_error("create frames_out dir", &frames_out_dir, &e))?;
+
+    let pattern = frame_format.pattern(&frames_in_dir);
+    let frames_dir_str = frames_in_dir.to_string_lossy().to_string();
+    let frame_pattern_str = pattern.to_string_lossy().to_string();
+
+    // Emit initial stage immediately
+    emit_stage(&app, StageId::Extract, message("stage.extract", &[]));
+    emit_pipeline(&app, "pipeline_progress", 0.0_f64);
+    emit_pipeline(&app, "pipeline_log", format!("Smooth Video job: {}", job_id));
+    record_job_start(&root, &job_id, "smooth_video", &input, &output);
+    record_job_schedule(&root, &job_id, not_before);
+    update_job_record(&root, &job_id, |r| {
+        r.fps_decision = fps_decision;
+        r.source_decisions = source_decisions;
+        r.gpu_sharing = Some(settings.gpu_sharing);
+        r.fingerprint = fingerprint;
+    });
+
+    let stage_weights = settings.stage_weights.unwrap_or_else(|| estimate_stage_weights(&root, &model_name));
+    let source_secs = region.map_or(duration_secs, |r| r.secs());
+    let progress = ProgressResolution::for_estimate(estimate_job_secs(&root, &model_name, source_secs * fps_in));
+    let job = SmoothJob {
+        root: root.clone(),
+        job_id: job_id.clone(),
+        ffmpeg,
+        rife_bin,
+        model_dir,
+        input,
+        output: output.clone(),
+        frames_in_dir,
+        frames_out_dir,
+        threads,
+        on_conflict,
+        settings,
+        dedup: dedup.unwrap_or(false),
+        cut_handling: cut_handling.unwrap_or_default(),
+        keep_intermediates: keep_intermediates.unwrap_or(false),
+        filters,
+        encode,
+        post_job_hook: preset.post_job_hook,
+        not_before,
+        duration_secs: region.map_or(duration_secs, |r| r.secs()),
+        fps_in,
+        start_at: JobStage::Extract,
+        kept_pts: Vec::new(),
+        bars_crop: None,
+        alpha,
+        frame_format,
+        rife_runtime,
+        force_uhd: profile.uhd.unwrap_or(false),
+        tta: quality.is_some_and(|q| q.tta),
+        corrupt_input,
+        stage_weights,
+        progress,
+        region,
+        plugins,
+        vapoursynth,
+        limits,
+        skip_interpolation,
+        rife_factor,
+        extra_passes,
+    };
+    save_job_spec(&job);
+    match not_before.filter(|t| *t > chrono::Local::now().timestamp_millis()) {
+        // The scheduler starts it from the saved spec (see `run_start_scheduler`).
+        Some(t) => {
+            update_schedule(&lock_schedule(), &root, |s| s.push(ScheduledStart { job_id: job_id.clone(), not_before: t }))?;
+            emit_stage(&app, StageId::Scheduled, message("stage.scheduled", &[("at", local_time_label(t))]));
+        }
+        None => spawn_smooth_job(&app, &state, job),
+    }
+
+    Ok(ExtractFramesResult {
+        ok: true,
+        job_id,
+        frames_dir: frames_dir_str,
+        frame_pattern: frame_pattern_str,
+        output: output.to_string_lossy().to_string(),
+        skipped: None,
+        duplicate_of: duplicate.map(|d| d.job_id),
+    })
+}
+
+/// Run a smooth_video job (fresh or retried) on a background thread; completion is reported
+/// through `pipeline_done`.
+fn spawn_smooth_job(app: &AppHandle, state: &AppState, job: SmoothJob) {
+    state.job_started(&job.job_id, "smooth_video", &job.input, &job.output);
+    let running = RunningJobGuard { app: app.clone(), job_id: job.job_id.clone() };
+    let lease = lease_tools(app, &[&job.ffmpeg, &job.rife_bin]);
+
+    let app_for_task = app.clone();
+    let frames_dir_for_task = job.frames_in_dir.to_string_lossy().to_string();
+    let frame_pattern_for_task = job.frame_format.pattern(&job.frames_in_dir).to_string_lossy().to_string();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let _running = running;
+        let _lease = lease;
+        let _scope = JobScope::enter(Some(job.job_id.clone()));
+        let root = &job.root;
+        let job_id = &job.job_id;
+        let done = match smooth_video_worker(&app_for_task, &job) {
+            Ok(msg) => {
+                let expected = match job.region {
+                    Some(_) => probe_duration_and_fps(&job.ffmpeg, &job.input).map_or(job.duration_secs, |(d, _)| d),
+                    None => job.duration_secs,
+                };
+                let expect_audio = probe_has_audio(&job.ffmpeg, &job.input);
+                let verification = verify_output(&job.ffmpeg, &job.output, expected, expect_audio);
+                report_verification(&app_for_task, &verification);
+                if let Some(out) = run_post_job_hook(
+                    &app_for_task,
+                    &job.settings,
+                    job.post_job_hook.as_ref(),
+                    job_id,
+                    &job.input,
+                    &job.output,
+                ) {
+                    update_job_record(root, job_id, |r| r.hook_output = out);
+                }
+                // A suspect output may still need a re-encode from the frames.
+                if !job.keep_intermediates && verification.ok {
+                    remove_job_intermediates(&app_for_task, &job);
+                }
+                PipelineDoneEvent {
+                    ok: true,
+                    message: verification.annotate(msg),
+                    frames_dir: frames_dir_for_task,
+                    frame_pattern: frame_pattern_for_task,
+                    verification: Some(verification),
+                    output_tail: None,
+                    error: None,
+                }
+            }
+            Err(err) => PipelineDoneEvent {
+                ok: false,
+                error: classify_process_error(&err).or_else(|| {
+                    // Unexplained failures are worth a re-hash of the tools and model the job ran.
+                    let paths = [job.ffmpeg.as_path(), job.rife_bin.as_path(), job.model_dir.as_path()];
+                    (!is_cancelled_error(&err)).then(|| integrity_error(root, &paths)).flatten()
+                }),
+                message: err,
+                frames_dir: frames_dir_for_task,
+                frame_pattern: frame_pattern_for_task,
+                verification: None,
+                output_tail: app_for_task.state::<AppState>().take_failure_tail(job_id),
+            },
+        };
+        record_job_finish(root, job_id, done.ok, &done.message);
+        emit_pipeline(&app_for_task, "pipeline_done", done);
+    });
+}
+
+/// Job ids come back from the frontend and end up in file names, so only the characters
+/// `make_job_id` uses are accepted.
+fn validate_job_id(job_id: &str) -> Result<(), String> {
+    if !job_id.is_empty() && job_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        Ok(())
+    } else {
+        Err(format!("Invalid job id: {job_id:?}"))
+    }
+}
+
+fn job_spec_path(root: &Path, job_id: &str) -> PathBuf {
+    temp_root(root).join("jobs").join(format!("{job_id}.json"))
+}
+
+/// Saved next to (not inside) the job's frame folders so `retry_stage` can rebuild the job.
+/// RIFE reads every file in its input folder, so nothing else may be written there.
+fn save_job_spec(job: &SmoothJob) {
+    let path = job_spec_path(&job.root, &job.job_id);
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Ok(json) = serde_json::to_string_pretty(job) {
+        let _ = fs::write(path, json);
+    }
+}
+
+fn load_job_spec(root: &Path, job_id: &str) -> Result<SmoothJob, String> {
+    validate_job_id(job_id)?;
+    let text = fs::read_to_string(job_spec_path(root, job_id))
+        .map_err(|_| format!("No saved job folder for {job_id}; only Smooth Video jobs can be retried"))?;
+    serde_json::from_str(&text).map_err(|e| format!("Saved job {job_id} is unreadable: {e}"))
+}
+
+fn remove_job_intermediates(app: &AppHandle, job: &SmoothJob) {
+    let bytes = dir_size_bytes(&job.frames_in_dir) + dir_size_bytes(&job.frames_out_dir);
+    let _ = fs::remove_dir_all(&job.frames_in_dir);
+    let _ = fs::remove_dir_all(&job.frames_out_dir);
+    // Frame lists of sharded folders (see `FramesInput`).
+    let _ = fs::remove_file(job.frames_in_dir.with_extension("ffconcat"));
+    let _ = fs::remove_file(job.frames_out_dir.with_extension("ffconcat"));
+    let _ = fs::remove_file(job_spec_path(&job.root, &job.job_id).with_extension("ffconcat"));
+    let _ = fs::remove_file(frame_timestamps_path(&job.root, &job.job_id));
+    emit_log_limited(app, &format!("Removed intermediate frames ({:.1} MB)", bytes as f64 / 1_048_576.0));
+}
+
+#[derive(serde::Serialize)]
+struct JobArtifact {
+    /// `frames_in`, `frames_out`, `job_spec`, `frame_timestamps`, `retime_list` or `scene_list`.
+    kind: String,
+    path: String,
+    files: usize,
+    bytes: u64,
+}
+
+/// Intermediates still on disk for a job. Frame folders can be passed back to
+/// `run_rife_pipeline` (frames_in) or `reencode_only` (frames_out).
+#[tauri::command]
+fn list_job_artifacts(app: AppHandle, job_id: String) -> Result<Vec<JobArtifact>, String> {
+    let root = app_root(&app)?;
+    validate_job_id(&job_id)?;
+    let temp = temp_root(&root);
+    let spec = job_spec_path(&root, &job_id);
+    // Frames may live on a RAM disk (see `frame_store_for_job`); the spec knows where.
+    let (frames_in, frames_out) = match load_job_spec(&root, &job_id) {
+        Ok(job) => (job.frames_in_dir, job.frames_out_dir),
+        Err(_) => (temp.join("frames_in").join(&job_id), temp.join("frames_out").join(&job_id)),
+    };
+    let candidates = [
+        ("frames_in", frames_in),
+        ("frames_out", frames_out),
+        ("job_spec", spec.clone()),
+        ("frame_timestamps", frame_timestamps_path(&root, &job_id)),
+        ("retime_list", spec.with_extension("ffconcat")),
+        ("scene_list", spec.with_extension("scenes.json")),
+    ];
+
+    let mut artifacts = Vec::new();
+    for (kind, path) in candidates {
+        let (files, bytes) = if path.is_dir() {
+            (count_files_in_dir(&path), dir_size_bytes(&path))
+        } else if let Ok(m) = fs::metadata(&path) {
+            (1, m.len())
+        } else {
+            continue;
+        };
+        artifacts.push(JobArtifact {
+            kind: kind.to_string(),
+            path: path.to_string_lossy().to_string(),
+            files,
+            bytes,
+        });
+    }
+    Ok(artifacts)
+}
+
+/// Settings a stage retry may change; anything unset keeps the original job's value.
+#[derive(Clone, Debug, Default, serde::Deserialize)]
+#[serde(default)]
+struct StageOverrides {
+    output_path: Option<String>,
+    max_threads: Option<i32>,
+    rife_threads: Option<RifeThreads>,
+    on_conflict: Option<OnConflict>,
+    cut_handling: Option<CutHandling>,
+    keep_intermediates: Option<bool>,
+    encode: Option<EncodeSettings>,
+}
+
+/// Re-run a finished or cancelled Smooth Video job from `stage` onwards, reusing the frames
+/// already in its job folders.
+#[tauri::command]
+fn retry_stage(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    job_id: String,
+    stage: JobStage,
+    overrides: Option<StageOverrides>,
+) -> Result<ExtractFramesResult, CommandError> {
+    validate_job_id(&job_id)?;
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    if lock(&state.jobs).contains_key(&job_id) {
+        return Err("Job is still running; cancel it before retrying a stage".into());
+    }
+
+    let mut job = load_job_spec(&root, &job_id)?;
+    if stage > JobStage::Extract && count_files_in_dir(&job.frames_in_dir) == 0 {
+        return Err("Extracted frames are gone; retry from the extract stage".into());
+    }
+    if stage > JobStage::Interpolate && count_files_in_dir(&job.frames_out_dir) == 0 {
+        return Err("Interpolated frames are gone; retry from the interpolate stage".into());
+    }
+    for tool in [&job.ffmpeg, &job.rife_bin] {
+        if !tool.exists() {
+            return Err(format!("{} no longer exists; start a new job", tool.to_string_lossy()).into());
+        }
+    }
+
+    let overrides = overrides.unwrap_or_default();
+    if let Some(t) = overrides.rife_threads {
+        t.validate()?;
+        job.threads = t;
+    } else if overrides.max_threads.is_some() {
+        job.threads = RifeThreads::from_max_threads(overrides.max_threads);
+    }
+    if let Some(c) = overrides.cut_handling {
+        job.cut_handling = c;
+    }
+    if let Some(c) = overrides.on_conflict {
+        job.on_conflict = c;
+    }
+    if let Some(k) = overrides.keep_intermediates {
+        job.keep_intermediates = k;
+    }
+    if let Some(e) = overrides.encode {
+        e.validate()?;
+        job.encode = resolve_encode_settings(&app, &state, e, &job.ffmpeg, &job.input)?;
+    }
+    if let Some(out) = overrides.output_path.as_deref().map(str::trim).filter(|o| !o.is_empty()) {
+        job.output = PathBuf::from(out);
+    }
+    check_source_protection(&root, &state.settings(&root), &job.input, &job.output, &[])?;
+    validate_output_path(&root, &job.input, &job.output, &[&job.frames_in_dir, &job.frames_out_dir])?;
+    job.output = resolve_output_conflict(&job.output, job.on_conflict)?;
+    job.settings = state.settings(&root);
+    job.not_before = None;
+    job.start_at = stage;
+    save_job_spec(&job);
+
+    update_job_record(&root, &job_id, |r| {
+        r.output = job.output.to_string_lossy().to_string();
+        r.started_at = chrono::Local::now().to_rfc3339();
+        r.finished_at = None;
+        r.ok = None;
+        r.message = format!("Retrying from the {} stage", stage.label());
+    });
+    emit_log_limited(&app, &format!("Retrying job {job_id} from the {} stage", stage.label()));
+
+    let result = ExtractFramesResult {
+        ok: true,
+        job_id: job_id.clone(),
+        frames_dir: job.frames_in_dir.to_string_lossy().to_string(),
+        frame_pattern: job.frame_format.pattern(&job.frames_in_dir).to_string_lossy().to_string(),
+        output: job.output.to_string_lossy().to_string(),
+        skipped: None,
+        duplicate_of: None,
+    };
+    spawn_smooth_job(&app, &state, job);
+    Ok(result)
+}
+
+/// Everything the smooth_video background worker needs, resolved up front by the command.
+/// Saved as the job's spec so stages can be retried.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct SmoothJob {
+    root: PathBuf,
+    job_id: String,
+    ffmpeg: PathBuf,
+    rife_bin: PathBuf,
+    model_dir: PathBuf,
+    input: PathBuf,
+    output: PathBuf,
+    frames_in_dir: PathBuf,
+    frames_out_dir: PathBuf,
+    threads: RifeThreads,
+    on_conflict: OnConflict,
+    /// Settings at the time the job (or retry) was started.
+    #[serde(skip)]
+    settings: AppSettings,
+    /// Drop duplicate source frames (mpdecimate) and retime the result to the source timeline.
+    dedup: bool,
+    cut_handling: CutHandling,
+    #[serde(default)]
+    filters: FilterOptions,
+    #[serde(default)]
+    encode: EncodeSettings,
+    /// Keep frames_in/frames_out after a successful run (failed runs always keep them for
+    /// `retry_stage`).
+    #[serde(default)]
+    keep_intermediates: bool,
+    post_job_hook: Option<PostJobHook>,
+    /// Unix millis before which the job must not start.
+    not_before: Option<i64>,
+    duration_secs: f64,
+    fps_in: f64,
+    /// First stage to run; earlier stages' output is reused from the job folders.
+    #[serde(skip)]
+    start_at: JobStage,
+    /// Source timestamps of the frames kept by dedup, from specs written before the
+    /// timestamps moved to their own sidecar (see `FrameTimestamps`).
+    #[serde(default)]
+    kept_pts: Vec<f64>,
+    /// Black bars cropped at extraction, needed again to pad them back at encode.
+    #[serde(default)]
+    bars_crop: Option<CropRect>,
+    /// The source has an alpha channel and the encode keeps it: frames are extracted as RGBA.
+    #[serde(default)]
+    alpha: bool,
+    #[serde(default)]
+    frame_format: FrameFormat,
+    #[serde(default)]
+    rife_runtime: RifeRuntime,
+    /// The model's profile asks for UHD mode.
+    #[serde(default)]
+    force_uhd: bool,
+    /// RIFE's TTA mode (`-x`), from the job's quality level.
+    #[serde(default)]
+    tta: bool,
+    #[serde(default)]
+    corrupt_input: CorruptInputOptions,
+    /// How the progress bar is split between the stages.
+    #[serde(default)]
+    stage_weights: StageWeights,
+    /// Progress reporting for the job's estimated length.
+    #[serde(default)]
+    progress: ProgressResolution,
+    /// Only this part of the source is interpolated and `duration_secs` is its length; the
+    /// rest is spliced around it at the source rate (see `splice_region`).
+    #[serde(default)]
+    region: Option<TimeRegion>,
+    /// Stage plugins to run, in order, at their positions.
+    #[serde(default)]
+    plugins: Vec<String>,
+    #[serde(default)]
+    vapoursynth: Option<VapourSynthStage>,
+    #[serde(default)]
+    limits: ResourceLimits,
+    /// The source policy kept a high-frame-rate source at its own rate: the frames go to the
+    /// encode as extracted, without a RIFE pass.
+    #[serde(default)]
+    skip_interpolation: bool,
+    /// Output frames per source frame when RIFE interpolates straight to a rate above twice
+    /// the source (`-n`, timestep models only); None for the usual 2x.
+    #[serde(default)]
+    rife_factor: Option<f64>,
+    /// Further 2x RIFE passes over the first pass's output, for models without a timestep
+    /// mode whose profile chains passes (see `ModelProfile::chained_passes`).
+    #[serde(default)]
+    extra_passes: u32,
+}
+
+impl SmoothJob {
+    /// Options for the source input of a decode that runs the extraction filters.
+    fn push_source_input_args(&self, cmd: &mut Command) {
+        self.corrupt_input.push_input_args(cmd);
+        if self.filters.rotate.is_some() {
+            cmd.arg("-noautorotate");
+        }
+    }
+
+    /// Output frames per source frame: 2 for each chained pass, `rife_factor` when set, or 1
+    /// when the source policy skipped interpolation.
+    fn rate_multiplier(&self) -> f64 {
+        if self.skip_interpolation {
+            1.0
+        } else {
+            self.rife_factor.unwrap_or_else(|| f64::from(2u32 << self.extra_passes.min(8)))
+        }
+    }
+}
+
+/// In and out points, in seconds from the start of the source.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+struct TimeRegion {
+    start_secs: f64,
+    end_secs: f64,
+}
+
+impl TimeRegion {
+    /// The region moved onto the frame boundaries of a `fps` source the way segmented paths
+    /// number their chunks (see `chunk_spans`), so the region and the parts spliced around it
+    /// neither share a frame nor miss one.
+    fn on_frames(self, fps: f64) -> TimeRegion {
+        match chunk_spans(&[(self.start_secs, self.end_secs)], fps).first() {
+            Some(c) if fps > 0.0 => TimeRegion { start_secs: (c.first - 1) as f64 / fps, end_secs: (c.end() - 1) as f64 / fps },
+            _ => self,
+        }
+    }
+
+    fn secs(&self) -> f64 {
+        self.end_secs - self.start_secs
+    }
+
+    /// Input options that limit the next `-i` to the region.
+    fn push_input_args(&self, cmd: &mut Command) {
+        cmd.arg("-ss").arg(format!("{:.6}", self.start_secs))
+            .arg("-t").arg(format!("{:.6}", self.secs()));
+    }
+}
+
+/// Working file of a region job next to its spec: "region" is the interpolated middle,
+/// "head" and "tail" the parts around it.
+fn region_part_path(job: &SmoothJob, part: &str) -> PathBuf {
+    job_spec_path(&job.root, &job.job_id).with_extension(format!("{part}.{}", output_extension(&job.output)))
+}
+
+/// Where the encode stage writes: the output, or the middle part of a region job.
+fn encode_target(job: &SmoothJob) -> PathBuf {
+    match job.region {
+        Some(_) => region_part_path(job, "region"),
+        None => job.output.clone(),
+    }
+}
+
+/// MP4/MOV parts that get concatenated share one timescale, whatever their frame rates.
+fn push_splice_timescale(cmd: &mut Command, output_ext: &str) {
+    if matches!(output_ext, "mp4" | "mov" | "m4v") {
+        cmd.arg("-video_track_timescale").arg("90000");
+    }
+}
+
+/// A span of the source in a spliced output, stream-copied or encoded.
+struct SplicePiece {
+    name: &'static str,
+    start: f64,
+    end: f64,
+    copy: bool,
+}
+
+/// Pieces for the source span `start..end` next to a region, cut where it meets the region
+/// (`end` for the span before it, `start` after). With `keyframes` (seconds from the start of
+/// the file), whole GOPs away from the cut are copied and only the frames between the cut
+/// and the nearest keyframe are encoded.
+fn passthrough_pieces(start: f64, end: f64, before_region: bool, keyframes: Option<&[f64]>, frame_secs: f64) -> Vec<SplicePiece> {
+    let (name, copy_name) = if before_region { ("head", "head_copy") } else { ("tail", "tail_copy") };
+    let key = keyframes.and_then(|k| {
+        if before_region {
+            k.iter().copied().rev().find(|t| *t > start && *t <= end)
+        } else {
+            k.iter().copied().find(|t| *t >= start && *t < end)
+        }
+    });
+    let pieces = match (key, before_region) {
+        (Some(k), true) => vec![
+            SplicePiece { name: copy_name, start, end: k, copy: true },
+            SplicePiece { name, start: k, end, copy: false },
+        ],
+        (Some(k), false) => vec![
+            SplicePiece { name, start, end: k, copy: false },
+            SplicePiece { name: copy_name, start: k, end, copy: true },
+        ],
+        (None, _) => vec![SplicePiece { name, start, end, copy: false }],
+    };
+    pieces.into_iter().filter(|p| p.end - p.start >= frame_secs).collect()
+}
+
+/// Why the source around a region can't be stream-copied, if it can't: copied and encoded
+/// pieces end up in one stream, so they need the same codec and pixel format, and an
+/// unfiltered picture.
+fn passthrough_copy_blocker(encode: &EncodeSettings, src: Option<&VideoStreamInfo>, filtered: bool, keep_alpha: bool) -> Option<String> {
+    let Some(src) = src else { return Some("the source stream couldn't be probed".into()) };
+    let pix_fmt = encode.pix_fmt().unwrap_or("yuv420p");
+    if filtered {
+        Some("the filters change the picture".into())
+    } else if keep_alpha {
+        Some("the output keeps an alpha channel".into())
+    } else if encode.encoder().codec_name() != src.codec {
+        Some(format!("the source is {} and the encode {}", src.codec, encode.encoder().codec_name()))
+    } else if pix_fmt != src.pix_fmt {
+        Some(format!("the source is {} and the encode {pix_fmt}", src.pix_fmt))
+    } else {
+        None
+    }
+}
+
+/// Codec parameters two video streams must share to be joined with `-c copy`.
+#[derive(Clone, Debug, PartialEq)]
+struct CopyParams {
+    codec: String,
+    profile: String,
+    level: String,
+    pix_fmt: String,
+    /// Hash of the stream's extradata (SPS/PPS for H.264/HEVC), empty when it has none.
+    extradata: String,
+}
+
+fn probe_copy_params(ffmpeg: &Path, input: &Path) -> Option<CopyParams> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-select_streams", "v:0", "-show_data_hash", "SHA256", "-show_entries", "stream=codec_name,profile,level,pix_fmt,extradata_hash", "-of", "default=noprint_wrappers=1"], input)?;
+    let field = |key: &str| {
+        out.lines()
+            .filter_map(|l| l.trim().split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.to_string())
+            .unwrap_or_default()
+    };
+    let codec = field("codec_name");
+    if codec.is_empty() {
+        return None;
+    }
+    Some(CopyParams { codec, profile: field("profile"), level: field("level"), pix_fmt: field("pix_fmt"), extradata: field("extradata_hash") })
+}
+
+/// Why GOPs copied from `source` can't sit next to the encoded piece `encoded`, if they can't:
+/// a decoder only gets one set of stream parameters for the joined output.
+fn copy_params_mismatch(ffmpeg: &Path, source: &Path, encoded: &Path) -> Option<String> {
+    let (Some(src), Some(enc)) = (probe_copy_params(ffmpeg, source), probe_copy_params(ffmpeg, encoded)) else {
+        return Some("the stream parameters couldn't be compared".into());
+    };
+    let fields = [
+        ("codec", &src.codec, &enc.codec),
+        ("profile", &src.profile, &enc.profile),
+        ("level", &src.level, &enc.level),
+        ("pixel format", &src.pix_fmt, &enc.pix_fmt),
+    ];
+    if let Some((name, a, b)) = fields.iter().find(|(_, a, b)| a != b) {
+        Some(format!("the source {name} is {a} and the encoded {name} {b}"))
+    } else if src.extradata != enc.extradata {
+        Some("the source and the encode use different parameter sets (extradata)".into())
+    } else {
+        None
+    }
+}
+
+/// Put a region job's output together and join the pieces without another encode. The source
+/// around the region keeps its own rate: whole GOPs are stream-copied when `copy_blocker` is
+/// None, and the rest is encoded with the job's audio handling and the video options
+/// `video_args` adds (the encoder and filters of the middle), so the pieces match.
+fn splice_region(
+    app: &AppHandle,
+    job: &SmoothJob,
+    region: TimeRegion,
+    encode: &EncodeSettings,
+    audio: &AudioPlan,
+    video_args: &dyn Fn(&mut Command),
+    copy_blocker: Option<String>,
+) -> Result<(), String> {
+    let source_secs = probe_duration_and_fps(&job.ffmpeg, &job.input).map(|(d, _)| d).unwrap_or(region.end_secs);
+    let output_ext = output_extension(&job.output);
+    let frame_secs = 1.0 / job.fps_in.max(1.0);
+    // The region is already encoded; copied GOPs must match it down to the parameter sets.
+    let copy_blocker = copy_blocker.or_else(|| copy_params_mismatch(&job.ffmpeg, &job.input, &region_part_path(job, "region")));
+    let keyframes = match copy_blocker {
+        Some(reason) => {
+            emit_log_limited(app, &format!("Passthrough is re-encoded: {reason}"));
+            None
+        }
+        None => {
+            // Packet times are absolute; seeks count from the start of the file.
+            let k = keyframe_times(&job.ffmpeg, &job.input);
+            let base = k.first().copied().unwrap_or(0.0);
+            Some(k.iter().map(|t| t - base).collect::<Vec<f64>>())
+        }
+    };
+    let mut files = Vec::new();
+    let spans = [(0.0, region.start_secs, true), (region.end_secs, source_secs, false)];
+    for (start, end, before_region) in spans {
+        if !before_region {
+            files.push(region_part_path(job, "region"));
+        }
+        for piece in passthrough_pieces(start, end, before_region, keyframes.as_deref(), frame_secs) {
+            let part = region_part_path(job, piece.name);
+            let how = if piece.copy { "stream copy" } else { "encoded at the source rate" };
+            emit_log_limited(app, &format!("Passthrough {}: {:.3}s to {:.3}s, {how}", piece.name, piece.start, piece.end));
+            let mut cmd = job.ffmpeg_command();
+            cmd.arg("-hide_banner").arg("-y");
+            // A copy can only start on a keyframe; seeking just past it makes sure it is this one.
+            let seek = if piece.copy && piece.start > 0.0 { piece.start + 0.001 } else { piece.start };
+            TimeRegion { start_secs: seek, end_secs: piece.end }.push_input_args(&mut cmd);
+            if job.filters.rotate.is_some() {
+                cmd.arg("-noautorotate");
+            }
+            cmd.arg("-i").arg(&job.input);
+            if audio.silence {
+                cmd.arg("-f").arg("lavfi")
+                    .arg("-t").arg(format!("{:.3}", piece.end - piece.start))
+                    .arg("-i").arg("anullsrc=channel_layout=stereo:sample_rate=48000");
+            }
+            cmd.arg("-map").arg("0:v:0")
+                .arg("-map").arg(if audio.silence { "1:a:0" } else { "0:a:0?" });
+            if piece.copy {
+                cmd.arg("-c:v").arg("copy");
+            } else {
+                video_args(&mut cmd);
+            }
+            audio.push_args(&mut cmd);
+            push_splice_timescale(&mut cmd, &output_ext);
+            cmd.arg(&part).stdout(Stdio::null()).stderr(Stdio::piped());
+            run_encode(app, job, cmd, (0.0, 1.0))?;
+            files.push(part);
+        }
+    }
+
+    let list_path = job_spec_path(&job.root, &job.job_id).with_extension("splice.ffconcat");
+    let mut list = String::from("ffconcat version 1.0\n");
+    for f in &files {
+        list.push_str(&format!("file {}\n", ffconcat_quote(f)));
+    }
+    fs::write(&list_path, list).map_err(|e| format!("Failed to write the splice list: {e}"))?;
+    emit_log_limited(app, &format!("Splicing {} part(s) into the output", files.len()));
+    let mut cmd = job.ffmpeg_command();
+    cmd.arg("-hide_banner").arg(ffmpeg_overwrite_flag(job.on_conflict))
+        .arg("-f").arg("concat").arg("-safe").arg("0")
+        .arg("-i").arg(&list_path)
+        .arg("-map").arg("0")
+        .arg("-c").arg("copy");
+    encode.mp4_layout.push_args(&mut cmd, &output_ext);
+    cmd.arg(&job.output).stdout(Stdio::null()).stderr(Stdio::piped());
+    run_encode(app, job, cmd, (0.0, 1.0))?;
+    for f in files.iter().chain([&list_path]) {
+        let _ = fs::remove_file(f);
+    }
+    Ok(())
+}
+
+fn smooth_video_worker(app: &AppHandle, job: &SmoothJob) -> Result<String, String> {
+    set_progress_resolution(app, job.progress);
+    wait_for_start_window(app, &job.settings, job.not_before);
+    let _slot = acquire_job_slot(app, &job.settings);
+    for line in job.limits.describe(job.threads) {
+        emit_log_limited(app, &line);
+    }
+    if job.start_at == JobStage::Extract {
+        check_job_decode(app, job)?;
+    }
+
+    // Streamed jobs never write frames: decode, RIFE and encode run together in step 3.
+    let streamed = streamed_frame_size(job).filter(|_| match check_builtin_model(job) {
+        Ok(()) => true,
+        Err(e) => {
+            emit_log_limited(app, &format!("{e}; not streaming frames"));
+            false
+        }
+    });
+    match smooth_video_stages(app, job, streamed) {
+        // The frame-folder pipeline doesn't depend on the built-in backend running the whole
+        // clip, so a failed stream starts over that way.
+        Err(e) if streamed.is_some() && !is_cancelled_error(&e) => {
+            emit_log_limited(app, &format!("Streaming through the built-in RIFE failed ({e}); starting over with frame folders"));
+            let _ = fs::remove_file(encode_target(job));
+            smooth_video_stages(app, job, None)
+        }
+        result => result,
+    }
+}
+
+/// Test-decode the first frame before extraction. A damaged start is what the tolerance
+/// options are for; a missing decoder isn't.
+fn check_job_decode(app: &AppHandle, job: &SmoothJob) -> Result<(), String> {
+    let Some(e) = check_decode(&job.ffmpeg, &job.input).error else { return Ok(()) };
+    if !job.corrupt_input.tolerant() || matches!(e.code.as_str(), "decoder_missing" | "av1_decoder_missing") {
+        return Err(format!("{}: {}\n\n{}", e.title, e.suggestion, e.detail));
+    }
+    emit_log_limited(app, &format!("The first frame didn't decode ({}); continuing with corrupt-input tolerance", e.detail));
+    Ok(())
+}
+
+/// The stages of a Smooth Video job; `streamed` is the frame size when it streams (see
+/// `streamed_frame_size`).
+fn smooth_video_stages(app: &AppHandle, job: &SmoothJob, streamed: Option<(u32, u32)>) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let mut resume_restarts = MAX_RESUME_RESTARTS;
+
+    // STEP 1: Extract frames
+    let extract_started = std::time::Instant::now();
+    let (mut kept_pts, bars) = if streamed.is_some() {
+        emit_log_limited(app, "Streaming frames through RIFE in memory; no frame folders are written");
+        (Vec::new(), None)
+    } else if job.start_at <= JobStage::Extract {
+        // An explicit crop wins over detection.
+        let bars = if job.filters.bars != BarHandling::Off && job.filters.crop.is_none() {
+            let bars = detect_black_bars(&job.ffmpeg, &job.input, job.duration_secs);
+            match bars {
+                Some(c) => emit_log_limited(app, &format!("Black bars detected: cropping to {}x{} at {},{}", c.w, c.h, c.x, c.y)),
+                None => emit_log_limited(app, "No black bars detected"),
+            }
+            bars
+        } else {
+            None
+        };
+        // RGBA extraction needs the software path; hwdownload only yields YUV.
+        let mut hw = if job.settings.hw_decode && !job.alpha {
+            let hw = state.ffmpeg_capabilities(&job.ffmpeg).ok().and_then(|c| HwDecode::pick(&c, &job.ffmpeg, &job.input));
+            if hw.is_none() {
+                emit_log_limited(app, "No hardware decoder passed a test decode of the input; decoding in software");
+            }
+            hw
+        } else {
+            None
+        };
+        let mut kept_pts = loop {
+            let frames_in = job.frames_in_dir.clone();
+            let attempt = run_with_resume_watch(
+                app,
+                job,
+                JobStage::Extract,
+                &mut resume_restarts,
+                move || count_files_in_dir(&frames_in) as u64,
+                || extract_stage(app, job, bars, hw),
+            );
+            match (attempt, hw) {
+                (Err(e), Some(h)) if !is_cancelled_error(&e) => {
+                    // Not every source codec/profile has a hardware decoder; software always works.
+                    emit_log_limited(app, &format!("GPU decoding ({}) failed; extracting again in software", h.hwaccel));
+                    hw = None;
+                }
+                (Err(e), _) if job.corrupt_input.isolate_segments && job.region.is_none() && !is_cancelled_error(&e) => {
+                    emit_log_limited(app, &format!("{e}\nExtracting again segment by segment"));
+                    break extract_segmented(app, job, bars)?;
+                }
+                (attempt, _) => break attempt?,
+            }
+        };
+        if let Err(e) = save_frame_timestamps(job, &kept_pts) {
+            emit_log_limited(app, &format!("{e}; a retried encode will use a fixed frame rate"));
+        }
+        let script_changed = run_vapoursynth_stage(app, job, PluginPosition::BeforeInterpolation, &job.frames_in_dir, job.fps_in)?;
+        if run_stage_plugins(app, job, PluginPosition::BeforeInterpolation, &job.frames_in_dir, job.fps_in)? || script_changed {
+            discard_frame_timestamps(app, job, &mut kept_pts);
+        }
+        let mut spec = job.clone();
+        spec.bars_crop = bars;
+        save_job_spec(&spec);
+        update_job_record(&job.root, &job.job_id, |r| r.extract_secs = extract_started.elapsed().as_secs_f64());
+        (kept_pts, bars)
+    } else {
+        emit_log_limited(app, &format!("Reusing extracted frames: {}", job.frames_in_dir.to_string_lossy()));
+        (load_frame_timestamps(job), job.bars_crop)
+    };
+
+    // Count frames
+    let in_count = count_files_in_dir(&job.frames_in_dir).max(1) as f64;
+
+    // Cuts only need handling when this run interpolates; earlier runs already fixed the frames.
+    let wants_cuts = job.cut_handling != CutHandling::Off || job.encode.chapters_at_cuts;
+    let scene_cuts = if wants_cuts && job.start_at <= JobStage::Interpolate {
+        match detect_scene_cuts(&job.ffmpeg, &job.frames_in_dir, SCENE_CUT_THRESHOLD) {
+            Ok(c) => {
+                emit_pipeline(app, "pipeline_log", format!("Scene cuts detected: {}", c.len()));
+                match write_scene_list(job, &c, &kept_pts) {
+                    Ok(list) => emit_log_limited(app, &format!("Scene list: {}", list.edl_path)),
+                    Err(e) => emit_log_limited(app, &format!("Scene list not saved: {e}")),
+                }
+                c
+            }
+            Err(e) => {
+                emit_pipeline(app, "pipeline_log", format!("{e}; cut protection disabled"));
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    // STEP 2: RIFE
+    let mut model_name = job.model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let mut interpolate_secs = 0.0;
+    let mut retries_left = MAX_AUTO_RETRIES;
+    if streamed.is_some() {
+        // Interpolation happens inside the encode.
+    } else if job.skip_interpolation && job.start_at <= JobStage::Interpolate {
+        let n = link_unheld_frames(&job.frames_in_dir, &job.frames_out_dir, &[])?;
+        emit_log_limited(app, &format!("Not interpolating (source policy); encoding the {n} source frames"));
+        let script_changed = run_vapoursynth_stage(app, job, PluginPosition::AfterInterpolation, &job.frames_out_dir, job.fps_in)?;
+        if run_stage_plugins(app, job, PluginPosition::AfterInterpolation, &job.frames_out_dir, job.fps_in)? || script_changed {
+            discard_frame_timestamps(app, job, &mut kept_pts);
+        }
+    } else if job.start_at <= JobStage::Interpolate {
+        // Dedup already dropped repeated frames, so there are no holds left to find.
+        // Held runs are copied two output frames per source frame.
+        let holds = if job.settings.skip_frame_holds && !job.dedup && job.rife_factor.is_none() && job.extra_passes == 0 {
+            match find_frame_holds(&job.ffmpeg, &job.frames_in_dir, in_count as usize) {
+                Ok(h) => h,
+                Err(e) => {
+                    emit_log_limited(app, &format!("{e}; interpolating every frame"));
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        // RIFE reads a gap-free copy of the frames outside the holds; frames_in stays whole
+        // for the encode's timestamps and for retries.
+        let unheld_dir = job.frames_in_dir.with_extension("unheld");
+        let mut rife_job = if holds.is_empty() {
+            job.clone()
+        } else {
+            let kept = link_unheld_frames(&job.frames_in_dir, &unheld_dir, &holds)?;
+            emit_log_limited(app, &format!(
+                "Static runs: {} ({} of {} frames); interpolating the other {kept}",
+                holds.len(),
+                in_count as usize - kept,
+                in_count as usize
+            ));
+            SmoothJob { frames_in_dir: unheld_dir.clone(), ..job.clone() }
+        };
+        let rife_count = count_files_in_dir(&rife_job.frames_in_dir).max(1) as f64;
+        let mut degrade = RifeDegrade::default();
+        (model_name, interpolate_secs) = loop {
+            let frames_out = job.frames_out_dir.clone();
+            // Sharded runs fill the pass folder and only move a shard on once RIFE finishes it.
+            let pass_out = frames_out.with_extension("pass");
+            let attempt = run_with_resume_watch(
+                app,
+                &rife_job,
+                JobStage::Interpolate,
+                &mut resume_restarts,
+                move || (count_files_in_dir(&frames_out) + count_files_in_dir(&pass_out)) as u64,
+                || interpolate_stage(app, &rife_job, rife_count, degrade),
+            );
+            let err = match attempt {
+                Ok(done) => break done,
+                Err(e) => e,
+            };
+            let Some(cause) = resource_failure(&err).filter(|_| retries_left > 0) else { return Err(err) };
+            // Thread counts and UHD mode are ncnn's; other runtimes step down to ncnn itself.
+            if rife_job.rife_runtime != RifeRuntime::NcnnVulkan {
+                let Some(ncnn) = RifeRuntime::fall_back_to_ncnn(&rife_job, &state.tools(&job.root)) else { return Err(err) };
+                retries_left -= 1;
+                emit_log_limited(app, &format!(
+                    "Auto-retry {}/{MAX_AUTO_RETRIES}: {}; retrying RIFE on {} instead of {}",
+                    MAX_AUTO_RETRIES - retries_left,
+                    cause.title,
+                    RifeRuntime::NcnnVulkan.label(),
+                    rife_job.rife_runtime.label()
+                ));
+                rife_job = ncnn;
+                continue;
+            }
+            let (threads, uhd, _) = planned_rife_settings(&rife_job, &model_name, degrade);
+            let Some((next, change)) = degrade.step_down(threads, uhd, rife_job.tta) else { return Err(err) };
+            retries_left -= 1;
+            emit_log_limited(app, &format!(
+                "Auto-retry {}/{MAX_AUTO_RETRIES}: {}; retrying RIFE with {change}",
+                MAX_AUTO_RETRIES - retries_left,
+                cause.title
+            ));
+            if next.tta_off {
+                rife_job.tta = false;
+            }
+            degrade = next;
+        };
+        // Each chained pass interpolates the last one's output, with the settings that worked.
+        for pass in 1..=job.extra_passes {
+            let chain_in = job.frames_out_dir.with_extension("chain");
+            let _ = fs::remove_dir_all(&chain_in);
+            fs::rename(&job.frames_out_dir, &chain_in).map_err(|e| format!("Failed to move pass {pass} frames: {e}"))?;
+            fs::create_dir_all(&job.frames_out_dir).map_err(|e| e.to_string())?;
+            let count = count_files_in_dir(&chain_in).max(1) as f64;
+            emit_log_limited(app, &format!("Chained pass {}/{}: interpolating {count} frames", pass + 1, job.extra_passes + 1));
+            let pass_job = SmoothJob { frames_in_dir: chain_in.clone(), ..rife_job.clone() };
+            let (_, secs) = interpolate_stage(app, &pass_job, count, degrade)?;
+            interpolate_secs += secs;
+            let _ = fs::remove_dir_all(&chain_in);
+        }
+        if !holds.is_empty() {
+            let copies = expand_held_output(&job.frames_out_dir, job.frame_format, &holds, in_count as usize)?;
+            let _ = fs::remove_dir_all(&unheld_dir);
+            let saved = interpolate_secs * copies as f64 / (rife_count * 2.0);
+            emit_log_limited(app, &format!(
+                "Static runs: repeated {copies} frames instead of interpolating them, saving about {:.1} min of RIFE",
+                saved / 60.0
+            ));
+            update_job_record(&job.root, &job.job_id, |r| {
+                r.held_frames = copies;
+                r.hold_saved_secs = saved;
+            });
+        }
+        if !scene_cuts.is_empty() && job.cut_handling != CutHandling::Off {
+            let fixed = apply_cut_handling(&job.ffmpeg, &job.frames_out_dir, &scene_cuts, job.cut_handling, job.rate_multiplier());
+            emit_pipeline(app,
+                "pipeline_log",
+                format!("Cut protection ({:?}): replaced {fixed} frame(s)", job.cut_handling),
+            );
+        }
+        let fps_out = job.fps_in * job.rate_multiplier();
+        let script_changed = run_vapoursynth_stage(app, job, PluginPosition::AfterInterpolation, &job.frames_out_dir, fps_out)?;
+        if run_stage_plugins(app, job, PluginPosition::AfterInterpolation, &job.frames_out_dir, fps_out)? || script_changed {
+            discard_frame_timestamps(app, job, &mut kept_pts);
+        }
+    } else {
+        emit_log_limited(app, &format!("Reusing interpolated frames: {}", job.frames_out_dir.to_string_lossy()));
+    }
+
+    // STEP 3: Encode video
+    let encode_started = std::time::Instant::now();
+    state.set_job_stage(&job.job_id, JobStage::Encode);
+    match streamed {
+        Some(_) => emit_stage(app, StageId::InterpolateEncode, message("stage.interpolate_encode", &[])),
+        None => emit_stage(app, StageId::Encode, message("stage.encode", &[])),
+    }
+    trash_existing_output(app, &job.settings, &job.output, job.on_conflict);
+    let fps_out = (job.fps_in * job.rate_multiplier()).max(1.0);
+
+    // Give each frame its own duration from the source timestamps instead of a fixed rate:
+    // dedup leaves gaps that would make the video run short and drift away from the audio,
+    // and variable-rate sources have no single rate to use. Evenly spaced frames (nothing
+    // dropped, constant-rate source) keep a fixed rate, so the output stays CFR.
+    let in_frames = count_files_in_dir(&job.frames_in_dir);
+    let variable = is_variable_rate(&kept_pts, job.fps_in);
+    let retimed = variable && (job.dedup || kept_pts.len() == in_frames);
+    if variable && !retimed {
+        emit_log_limited(app, &format!(
+            "{} timestamps for {in_frames} frames; encoding at a fixed {fps_out:.3} fps",
+            kept_pts.len()
+        ));
+    }
+    // Kept out of frames_in: a retried interpolate stage would hand it to RIFE as a frame.
+    let list_path = job_spec_path(&job.root, &job.job_id).with_extension("ffconcat");
+    if retimed {
+        let n = write_retime_concat(&job.frames_out_dir, &kept_pts, job.duration_secs, &list_path)?;
+        let vfr = if !job.dedup { " (variable frame rate source)" } else { "" };
+        emit_pipeline(app, "pipeline_log", format!("Retiming {n} frames to the source timeline{vfr}"));
+    }
+    let frames_input = match streamed {
+        None if !retimed => Some(FramesInput::new(&job.frames_out_dir, fps_out)?),
+        _ => None,
+    };
+    // Tonemapped frames are bt709 SDR whatever the source said.
+    let source_tags = if job.filters.tonemap {
+        ColorTags::bt709_sdr()
+    } else {
+        probe_color_tags(&job.ffmpeg, &job.input)
+    };
+    let color = job.encode.color.or(source_tags);
+    let mut chain = job.filters.encode_chain().color_matrix(&color);
+    if let Some(d) = draft_plan(job, bars).filter(|d| d.upscale) {
+        chain = chain.scale(d.full.0 as i32, d.full.1 as i32);
+    }
+    let restore_bars = match (bars, job.filters.bars) {
+        (Some(c), BarHandling::CropAndRestore) => upright_video_size(&job.ffmpeg, &job.input).map(|(w, h)| (w, h, c)),
+        _ => None,
+    };
+    if let Some((w, h, c)) = restore_bars {
+        chain = chain.pad(w, h, c.x, c.y);
+    }
+    let output_ext = output_extension(&job.output);
+    let audio = AudioPlan::new(&job.ffmpeg, &job.input, &output_ext, &job.encode.audio);
+    emit_log_limited(app, &audio.describe(&output_ext));
+    let chapters = if !job.encode.chapters_at_cuts {
+        None
+    } else if !matches!(output_ext.as_str(), "mkv" | "webm" | "mp4" | "m4v" | "mov") {
+        emit_log_limited(app, &format!("Chapters: .{output_ext} can't hold chapters; skipped"));
+        None
+    } else {
+        match load_scene_list(&job.root, &job.job_id) {
+            Ok(list) if !list.cuts.is_empty() => {
+                let path = job_spec_path(&job.root, &job.job_id).with_extension("chapters.txt");
+                write_chapter_metadata(&list, &path)?;
+                emit_log_limited(app, &format!("Chapters: {} scenes", list.cuts.len() + 1));
+                Some(path)
+            }
+            _ => {
+                emit_log_limited(app, "Chapters: no scene cuts detected; skipped");
+                None
+            }
+        }
+    };
+    // Inputs: frames, source, then the optional silent track and chapter metadata.
+    let chapters_input = if audio.silence { 3 } else { 2 };
+    let keep_alpha = job.alpha && job.encode.supports_alpha(&output_ext);
+    if keep_alpha {
+        let first_out = sorted_files(&job.frames_out_dir).into_iter().next();
+        if first_out.is_some_and(|f| !has_alpha(&job.ffmpeg, &f)) {
+            chain = chain.color_key(ALPHA_KEY_COLOR);
+        }
+    }
+
+    let build_encode = |encode: &EncodeSettings, pass: Option<&EncodePass>| {
+        // The first pass only writes stats: no audio, output discarded.
+        let stats_only = pass.is_some_and(|p| p.pass == 1);
+        let mut enc = job.ffmpeg_command();
+        enc.arg("-hide_banner")
+            .arg(if stats_only || job.region.is_some() || encode.backend == EncodeBackend::Av1an {
+                "-y"
+            } else {
+                ffmpeg_overwrite_flag(job.on_conflict)
+            });
+        if streamed.is_none() {
+            enc.arg("-progress").arg("pipe:1");
+            job.progress.push_args(&mut enc);
+        }
+        if let Some((w, h)) = streamed {
+            enc.arg("-f").arg("rawvideo").arg("-pix_fmt").arg("rgb24")
+                .arg("-s").arg(format!("{w}x{h}"))
+                .arg("-framerate").arg(format!("{:.6}", fps_out))
+                .arg("-i").arg("-");
+        } else if let Some(input) = &frames_input {
+            input.push_args(&mut enc);
+        } else {
+            enc.arg("-f").arg("concat").arg("-safe").arg("0")
+                .arg("-i").arg(&list_path);
+        }
+        if let Some(r) = job.region {
+            r.push_input_args(&mut enc);
+        }
+        enc.arg("-i").arg(&job.input);
+        if !stats_only {
+            audio.push_inputs(&mut enc, job.duration_secs);
+            if let Some(meta) = &chapters {
+                enc.arg("-i").arg(meta);
+            }
+        }
+        enc.arg("-map").arg("0:v:0");
+        if !stats_only {
+            audio.push_map(&mut enc);
+            if chapters.is_some() {
+                enc.arg("-map_chapters").arg(chapters_input.to_string());
+            }
+        }
+        encode.push_args(&mut enc, pass);
+        job.limits.push_encoder_args(&mut enc);
+        if let (GpuSharing::Pin { encode_gpu, .. }, true) = (job.settings.gpu_sharing, encode.encoder().is_nvenc()) {
+            enc.arg("-gpu").arg(encode_gpu.to_string());
+        }
+        if keep_alpha {
+            encode.push_alpha_args(&mut enc);
+        } else {
+            enc.arg("-pix_fmt").arg(encode.pix_fmt().unwrap_or("yuv420p"));
+        }
+        color.push_args(&mut enc);
+        chain.apply(&mut enc);
+        // Retimed frames carry the source timeline; keep it unless an fps filter or the user
+        // asks for a fixed rate.
+        let follows_source = retimed && job.filters.output_fps.is_none();
+        let fps_mode = encode.fps_mode.or(follows_source.then_some(FpsMode::Vfr));
+        if let Some(mode) = fps_mode {
+            if mode == FpsMode::Cfr && follows_source {
+                enc.arg("-r").arg(format!("{:.6}", fps_out));
+            }
+            enc.arg("-fps_mode").arg(mode.as_arg());
+        }
+        if stats_only {
+            enc.arg("-an").arg("-f").arg("null")
+                .arg(if cfg!(windows) { "NUL" } else { "/dev/null" });
+        } else {
+            audio.push_args(&mut enc);
+            if encode.backend == EncodeBackend::Ffmpeg {
+                encode.mp4_layout.push_args(&mut enc, &output_ext);
+            }
+            if job.region.is_some() {
+                push_splice_timescale(&mut enc, &output_ext);
+            }
+            if !retimed {
+                enc.arg("-shortest");
+            }
+            enc.arg(match encode.backend {
+                EncodeBackend::Av1an => av1an_intermediate_path(job),
+                EncodeBackend::Ffmpeg => encode_target(job),
+            });
+        }
+        // Streamed encodes report progress from the frames they are fed instead.
+        enc.stdout(if streamed.is_none() { Stdio::piped() } else { Stdio::null() }).stderr(Stdio::piped());
+        enc
+    };
+
+    let mut encode = job.encode.clone();
+    // Pass 1 only grows its stats files, so count those too.
+    let encode_progress = {
+        let (output, logs) = (encode_target(job), pass_log_dir(job));
+        move || {
+            let stats: u64 = fs::read_dir(&logs)
+                .map(|rd| rd.flatten().filter_map(|e| e.metadata().ok()).map(|m| m.len()).sum())
+                .unwrap_or(0);
+            fs::metadata(&output).map(|m| m.len()).unwrap_or(0) + stats
+        }
+    };
+    let gpu_encode_turn = if encode.encoder().uses_gpu() || streamed.is_some() { gpu_turn(app, &job.settings, "Encode") } else { None };
+    while let Err(err) = run_with_resume_watch(
+        app,
+        job,
+        JobStage::Encode,
+        &mut resume_restarts,
+        encode_progress.clone(),
+        || match streamed {
+            Some(size) => stream_encode(app, job, build_encode(&encode, None), size),
+            None => encode_output(app, job, &encode, &audio, &build_encode),
+        },
+    ) {
+        let Some(cause) = resource_failure(&err).filter(|_| retries_left > 0) else { return Err(err) };
+        let Some(fallback) = encode.software_fallback() else { return Err(err) };
+        retries_left -= 1;
+        emit_log_limited(app, &format!(
+            "Auto-retry {}/{MAX_AUTO_RETRIES}: {}; retrying the encode with {} instead of {}",
+            MAX_AUTO_RETRIES - retries_left,
+            cause.title,
+            fallback.encoder().ffmpeg_name(),
+            encode.encoder().ffmpeg_name()
+        ));
+        encode = fallback;
+    }
+    drop(gpu_encode_turn);
+    let (label, frames) = match streamed {
+        Some(_) => ("RIFE + encode", (job.duration_secs * job.fps_in * job.rate_multiplier()) as u64),
+        None => ("Encode", count_files_in_dir(&job.frames_out_dir) as u64),
+    };
+    log_throughput(app, label, frames, encode_started.elapsed().as_secs_f64());
+    if let Some(region) = job.region {
+        // The source around the region goes through the same decode filters as the frames.
+        let mut pass_chain = job_extraction_chain(job, bars);
+        let filtered = !pass_chain.is_empty() || restore_bars.is_some();
+        pass_chain = pass_chain.color_matrix(&color);
+        if let Some((w, h, c)) = restore_bars {
+            pass_chain = pass_chain.pad(w, h, c.x, c.y);
+        }
+        let source = probe_video_stream(&job.ffmpeg, &job.input);
+        let copy_blocker = passthrough_copy_blocker(&encode, source.as_ref(), filtered, keep_alpha);
+        let video_args = |cmd: &mut Command| {
+            encode.push_args(cmd, None);
+            job.limits.push_encoder_args(cmd);
+            if keep_alpha {
+                encode.push_alpha_args(cmd);
+            } else {
+                cmd.arg("-pix_fmt").arg(encode.pix_fmt().unwrap_or("yuv420p"));
+            }
+            color.push_args(cmd);
+            pass_chain.apply(cmd);
+        };
+        splice_region(app, job, region, &encode, &audio, &video_args, copy_blocker)?;
+    }
+
+    emit_pipeline(app, "pipeline_progress", 100.0_f64);
+
+
+    let frames_in = count_files_in_dir(&job.frames_in_dir) as u64;
+    let frames_out = count_files_in_dir(&job.frames_out_dir) as u64;
+    let bytes_written = dir_size_bytes(&job.frames_in_dir)
+        + dir_size_bytes(&job.frames_out_dir)
+        + fs::metadata(&job.output).map(|m| m.len()).unwrap_or(0);
+    update_job_record(&job.root, &job.job_id, |r| {
+        r.model = model_name;
+        r.source_duration_secs = job.duration_secs;
+        r.frames_in = frames_in;
+        r.frames_out = frames_out;
+        r.interpolate_secs = interpolate_secs;
+        // Streamed encodes include RIFE, which would skew the encode speed.
+        r.encode_secs = if streamed.is_some() { 0.0 } else { encode_started.elapsed().as_secs_f64() };
+        r.bytes_written = bytes_written;
+    });
+
+    Ok(format!("Done: {}", job.output.to_string_lossy()))
+}
+
+/// Encode the job's frames with `encode`: one pass, or two when aiming at a target size.
+fn encode_output(
+    app: &AppHandle,
+    job: &SmoothJob,
+    encode: &EncodeSettings,
+    audio: &AudioPlan,
+    build_encode: &dyn Fn(&EncodeSettings, Option<&EncodePass>) -> Command,
+) -> Result<(), String> {
+    match encode.target_size_mb {
+        Some(target_mb) => {
+            let audio_kbps = audio.bitrate_kbps(&job.ffmpeg, &job.input);
+            let bitrate_kbps = target_video_kbps(target_mb, job.duration_secs, audio_kbps)?;
+            emit_log_limited(app, &format!(
+                "Target {target_mb} MB: video {bitrate_kbps} kbps + audio {audio_kbps} kbps, two-pass"
+            ));
+            let log_dir = pass_log_dir(job);
+            let _ = fs::remove_dir_all(&log_dir);
+            fs::create_dir_all(&log_dir).map_err(|e| fs_error("create pass log dir", &log_dir, &e))?;
+            let passes = (1..=2u8).try_for_each(|pass| {
+                emit_stage(app, StageId::Encode, message("stage.encode_pass", &[("pass", pass.to_string())]));
+                let p = EncodePass { pass, bitrate_kbps, log_prefix: log_dir.join("pass") };
+                run_encode(app, job, build_encode(encode, Some(&p)), (0.5 * (pass - 1) as f64, 0.5))
+            });
+            // Failed and cancelled passes leave stats files too.
+            let _ = fs::remove_dir_all(&log_dir);
+            passes?;
+            let achieved = fs::metadata(&job.output).map(|m| m.len()).unwrap_or(0) as f64 / (1024.0 * 1024.0);
+            emit_log_limited(app, &format!(
+                "Output size: {achieved:.1} MB (target {target_mb} MB, {:+.1}%)",
+                (achieved / target_mb - 1.0) * 100.0
+            ));
+        }
+        None if encode.backend == EncodeBackend::Av1an => {
+            emit_log_limited(app, "Writing a lossless intermediate for av1an");
+            run_encode(app, job, build_encode(encode, None), (0.0, 0.2))?;
+            run_av1an(app, job, encode, (0.2, 0.8))?;
+        }
+        None => run_encode(app, job, build_encode(encode, None), (0.0, 1.0))?,
+    }
+    Ok(())
+}
+
+/// Lossless video (with the output's audio) that av1an encodes; next to the frames so it
+/// lands on the same volume.
+fn av1an_intermediate_path(job: &SmoothJob) -> PathBuf {
+    job.frames_out_dir.with_extension("av1an.mkv")
+}
+
+/// Frames av1an has encoded so far and the total, from the `done.json` it keeps in its temp
+/// folder. Older releases store a count per chunk, newer ones an object with `frames`.
+fn av1an_done_frames(temp: &Path) -> Option<(u64, u64)> {
+    let text = fs::read_to_string(temp.join("done.json")).ok()?;
+    let v: serde_json::Value = serde_json::from_str(&text).ok()?;
+    let total = v.get("frames")?.as_u64()?;
+    let done = v
+        .get("done")?
+        .as_object()?
+        .values()
+        .filter_map(|c| c.as_u64().or_else(|| c.get("frames").and_then(|f| f.as_u64())).or_else(|| c.get(0).and_then(|f| f.as_u64())))
+        .sum();
+    Some((done, total))
+}
+
+/// Length of the chunks av1an encodes in parallel.
+const AV1AN_CHUNK_SECS: f64 = 10.0;
+
+/// av1an's scenes file for `frames` frames at `fps`: chunks cut by `chunk_spans`, like every
+/// other segmented path, instead of av1an's own scene detection.
+fn av1an_scenes(frames: usize, fps: f64) -> serde_json::Value {
+    let secs = frames as f64 / fps.max(1.0);
+    let count = (secs / AV1AN_CHUNK_SECS).ceil().max(1.0) as usize;
+    let bounds: Vec<(f64, f64)> =
+        (0..count).map(|i| (i as f64 * AV1AN_CHUNK_SECS, ((i + 1) as f64 * AV1AN_CHUNK_SECS).min(secs))).collect();
+    let mut spans = chunk_spans(&bounds, fps.max(1.0));
+    if let Some(last) = spans.last_mut() {
+        last.count = (frames + 1).saturating_sub(last.first);
+    }
+    let scenes: Vec<serde_json::Value> = spans
+        .iter()
+        .filter(|c| c.count > 0)
+        .map(|c| serde_json::json!({ "start_frame": c.first - 1, "end_frame": c.end() - 1, "zone_overrides": null }))
+        .collect();
+    serde_json::json!({ "scenes": scenes, "frames": frames })
+}
+
+/// Encode the intermediate into the output with av1an, copying its audio. av1an needs
+/// ffmpeg on PATH, so the job's ffmpeg folder goes first.
+fn run_av1an(app: &AppHandle, job: &SmoothJob, encode: &EncodeSettings, span: (f64, f64)) -> Result<(), String> {
+    let av1an = app.state::<AppState>().tools(&job.root).av1an.ok_or("av1an not found (install av1an)")?;
+    let input = av1an_intermediate_path(job);
+    let temp = input.with_extension("chunks");
+    let scenes_path = input.with_extension("scenes.json");
+    let frames = count_files_in_dir(&job.frames_out_dir);
+    fs::write(&scenes_path, av1an_scenes(frames, job.fps_in * job.rate_multiplier()).to_string())
+        .map_err(|e| format!("Failed to write {}: {e}", scenes_path.to_string_lossy()))?;
+    let mut cmd = tool_command(&av1an);
+    cmd.arg("-i").arg(&input)
+        .arg("-o").arg(&job.output)
+        .arg("--temp").arg(&temp)
+        .arg("--scenes").arg(&scenes_path)
+        .arg("-e").arg("svt-av1")
+        .arg("-v").arg(encode.av1an_video_params())
+        .arg("-a").arg("-c:a copy")
+        .arg("--pix-format").arg(encode.pix_fmt().unwrap_or("yuv420p"))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    job.limits.push_av1an_args(&mut cmd);
+    // av1an runs an encoder process per chunk; cancelling has to stop those too.
+    new_process_group(&mut cmd);
+    if ffmpeg_overwrite_flag(job.on_conflict) == "-y" {
+        cmd.arg("-y");
+    }
+    if let Some(dir) = job.ffmpeg.parent() {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        let dirs = std::iter::once(dir.to_path_buf()).chain(std::env::split_paths(&path));
+        if let Ok(joined) = std::env::join_paths(dirs) {
+            cmd.env("PATH", joined);
+        }
+    }
+    emit_log_limited(app, &format!("av1an: {} with SVT-AV1 {}", av1an.to_string_lossy(), encode.av1an_video_params()));
+    log_stage_command(app, "encode", &mut cmd);
+    let mut process = spawn_stage_process(app, &job.job_id, "av1an", &mut cmd)?;
+    process.relay_to_log(app, Pipe::Stderr);
+    // Chunks finish out of order, so progress is the frames done across all of them.
+    while process.child.try_wait().ok().flatten().is_none() {
+        check_unkillable(app, &job.job_id, process.child.id())?;
+        if let Some((done, total)) = av1an_done_frames(&temp) {
+            let fraction = span.0 + span.1 * (done as f64 / total.max(1) as f64).min(1.0);
+            emit_stage_progress(app, job, JobStage::Encode, fraction, done);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
+    let status = process.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, JobStage::Encode)?;
+    let _ = fs::remove_file(&scenes_path);
+    process.check(app, "av1an failed", status)?;
+    let _ = fs::remove_file(&input);
+    let _ = fs::remove_dir_all(&temp);
+    Ok(())
+}
+
+/// Run one ffmpeg encode of the job's frames, streaming its log and honouring cancellation.
+/// Progress is reported as `span` (start, width) of the encode stage.
+fn run_encode(app: &AppHandle, job: &SmoothJob, mut enc: Command, span: (f64, f64)) -> Result<(), String> {
+    log_stage_command(app, "encode", &mut enc);
+    let expected = count_files_in_dir(&job.frames_out_dir) as f64;
+    let mut encoder = spawn_stage_process(app, &job.job_id, "Encode", &mut enc)?;
+    if let Some(out) = encoder.child.stdout.take() {
+        encoder.readers.push(follow_ffmpeg_progress(app, job, JobStage::Encode, out, expected, span));
+    }
+    encoder.relay_to_log(app, Pipe::Stderr);
+    let status = encoder.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, JobStage::Encode)?;
+    encoder.check(app, "Encoding failed", status)
+}
+
+/// Empty a job folder before a stage rewrites it, so a retry doesn't mix in stale frames.
+fn clear_dir(dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| format!("Failed to clear {}: {e}", dir.to_string_lossy()))?;
+    }
+    fs::create_dir_all(dir).map_err(|e| fs_error("create", dir, &e))
+}
+
+/// Extract the source frames into the job's frames_in folder. Returns the source timestamps
+/// of the frames dedup kept (empty without dedup).
+fn extract_stage(app: &AppHandle, job: &SmoothJob, bars: Option<CropRect>, hw: Option<HwDecode>) -> Result<Vec<f64>, String> {
+    let state = app.state::<AppState>();
+    state.set_job_stage(&job.job_id, JobStage::Extract);
+    emit_stage(app, StageId::Extract, message("stage.extract", &[]));
+    if count_files_in_dir(&job.frames_in_dir) > 0 || is_sharded(&job.frames_in_dir) {
+        clear_dir(&job.frames_in_dir)?;
+    }
+    let shards = shard_for_job(job, &job.frames_in_dir)?.then(|| {
+        emit_log_limited(app, &format!("Frames are kept in folders of {SHARD_FRAMES}"));
+        ShardMover::start(&job.frames_in_dir, job.frame_format)
+    });
+    emit_pipeline(app, "pipeline_log", format!("FFmpeg: {}", job.ffmpeg.to_string_lossy()));
+    emit_pipeline(app, "pipeline_log", format!("Input: {}", job.input.to_string_lossy()));
+    emit_pipeline(app, "pipeline_log", format!("Frames in: {}", job.frames_in_dir.to_string_lossy()));
+
+    let mut cmd = job.ffmpeg_command();
+    cmd.arg("-hide_banner").arg("-y");
+    if let Some(hw) = hw {
+        emit_log_limited(app, &format!("Decoding on the GPU ({})", hw.hwaccel));
+        hw.push_input_args(&mut cmd);
+    }
+    job.push_source_input_args(&mut cmd);
+    if let Some(r) = job.region {
+        r.push_input_args(&mut cmd);
+    }
+    cmd.arg("-i").arg(&job.input);
+    job.corrupt_input.push_output_args(&mut cmd);
+    if let Some(d) = draft_plan(job, bars) {
+        let upscale = if d.upscale { format!(", encoding at {}x{}", d.full.0, d.full.1) } else { String::new() };
+        emit_log_limited(app, &format!("Draft quality: interpolating at {}x{}{upscale}", d.scaled.0, d.scaled.1));
+    }
+    let mut chain = job_extraction_chain(job, bars);
+    if hw.is_some() {
+        chain = chain.hw_download();
+    }
+    if job.dedup {
+        // Drop near-identical frames.
+        chain = chain.decimate();
+    }
+    // showinfo reports the source timestamp of every frame written, for exact retiming.
+    chain = chain.show_info();
+    chain.apply(&mut cmd);
+    if job.alpha {
+        cmd.arg("-pix_fmt").arg("rgba");
+    }
+    job.frame_format.push_encoder_args(&mut cmd);
+    cmd.arg("-vsync").arg("0")
+        .arg("-progress").arg("pipe:1");
+    job.progress.push_args(&mut cmd);
+    cmd.arg(job.frame_format.pattern(&job.frames_in_dir))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    log_stage_command(app, "extract", &mut cmd);
+    let started = std::time::Instant::now();
+    let mut ffmpeg = spawn_stage_process(app, &job.job_id, "FFmpeg", &mut cmd)?;
+    if let Some(out) = ffmpeg.child.stdout.take() {
+        ffmpeg.readers.push(follow_ffmpeg_progress(app, job, JobStage::Extract, out, job.duration_secs * job.fps_in, (0.0, 1.0)));
+    }
+
+    // stream ffmpeg stderr lightly (showinfo lines are collected, not logged)
+    let mut kept_pts: Vec<f64> = Vec::new();
+    let mut damage = DamageReport::default();
+    let mut extracted_so_far = FrameCounter::new(&job.frames_in_dir, job.frame_format);
+    if let Some(stderr) = ffmpeg.child.stderr.take() {
+        let reader = std::io::BufReader::new(stderr);
+        for line in reader.lines().flatten() {
+            let line = line.trim().to_string();
+            if line.contains("Parsed_showinfo") {
+                if let Some(t) = parse_showinfo_pts_time(&line) {
+                    kept_pts.push(t);
+                }
+                continue;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            if is_decode_error(&line) {
+                // Dedup knows the source time of the last kept frame; otherwise count frames.
+                let at = kept_pts.last().copied()
+                    .unwrap_or_else(|| extracted_so_far.count() as f64 / job.fps_in.max(1.0));
+                damage.note(at);
+                if job.corrupt_input.max_errors.is_some_and(|max| damage.errors > max) {
+                    let _ = ffmpeg.child.kill();
+                    break;
                 }
             }
+            ffmpeg.tail.push(&line);
+            emit_pipeline(app, "pipeline_log", line);
         }
-        let ok = child.wait().map(|s| s.success()).unwrap_or(false);
-        if !ok {
-            let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
-                ok: false,
-                message: "Frame extraction failed".into(),
-                frames_dir: frames_dir_for_task.clone(),
-                frame_pattern: frame_pattern_for_task.clone(),
-            });
-            return;
-        }
-
-        // Count frames
-        let in_count = count_files_in_dir(&frames_in_for_task).max(1) as f64;
+    }
+    let status = ffmpeg.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, JobStage::Extract)?;
+    if damage.errors > 0 {
+        emit_log_limited(app, &damage.describe(job.fps_in));
+    }
+    if let Some(max) = job.corrupt_input.max_errors.filter(|max| damage.errors > *max) {
+        return Err(format!("Frame extraction stopped: more than {max} decode errors in the input"));
+    }
+    ffmpeg.check(app, "Frame extraction failed", status)?;
+    if let Some(mover) = shards {
+        mover.finish()?;
+    }
+    let extracted = count_files_in_dir(&job.frames_in_dir);
+    let secs = started.elapsed().as_secs_f64().max(0.001);
+    emit_log_limited(app, &format!(
+        "Extracted {extracted} frames in {secs:.1}s ({:.1} fps, {} decode)",
+        extracted as f64 / secs,
+        hw.map(|h| h.hwaccel).unwrap_or("software")
+    ));
+    if job.dedup {
+        let total_est = (job.duration_secs * job.fps_in).round() as i64;
+        emit_pipeline(app,
+            "pipeline_log",
+            format!("Dedup: kept {} of ~{} frames", kept_pts.len(), total_est),
+        );
+    }
+    Ok(kept_pts)
+}
 
-        // STEP 2: RIFE
-        emit_stage(&app_for_task, "Interpolating (RIFE)… (step 2/3)");
-        let _ = app_for_task.emit("pipeline_log", format!("RIFE: {}", rife_for_task.to_string_lossy()));
-        let _ = app_for_task.emit("pipeline_log", format!("Model dir: {}", model_dir_for_task.to_string_lossy()));
-        let _ = app_for_task.emit("pipeline_log", format!("Threads (-j): {}", threads_for_task));
+/// Interpolate frames_in into frames_out with RIFE. Returns the model name and the time spent.
+fn interpolate_stage(app: &AppHandle, job: &SmoothJob, in_count: f64, degrade: RifeDegrade) -> Result<(String, f64), String> {
+    let state = app.state::<AppState>();
+    wait_for_power(app, &job.settings);
+    state.set_job_stage(&job.job_id, JobStage::Interpolate);
+    if count_files_in_dir(&job.frames_out_dir) > 0 || is_sharded(&job.frames_out_dir) {
+        clear_dir(&job.frames_out_dir)?;
+    }
+    if is_sharded(&job.frames_in_dir) {
+        make_sharded(&job.frames_out_dir)?;
+    }
+    emit_stage(app, StageId::Interpolate, message("stage.interpolate", &[]));
+    emit_pipeline(app, "pipeline_log", format!("RIFE: {}", job.rife_bin.to_string_lossy()));
+    emit_pipeline(app, "pipeline_log", format!("Model dir: {}", job.model_dir.to_string_lossy()));
+    emit_pipeline(app, "pipeline_log", format!("Runtime: {}", job.rife_runtime.label()));
+    emit_pipeline(app, "pipeline_log", format!("Threads (-j): {}", job.threads));
+    if job.alpha && !rife_keeps_alpha(job) {
+        emit_log_limited(app, "This RIFE build drops alpha: compositing over a key colour and re-keying at encode");
+        composite_over_key(&job.ffmpeg, &job.frames_in_dir)?;
+    }
 
-        let (cwd, model_arg) = compute_rife_cwd_and_model_arg(&rife_for_task, &model_dir_for_task);
-        let mut rife_cmd = Command::new(&rife_for_task);
-        if let Some(d) = cwd {
-            rife_cmd.current_dir(d);
-        }
-        rife_cmd.arg("-v")
-            .arg("-i").arg(&frames_in_for_task)
-            .arg("-o").arg(&frames_out_for_task)
-            .arg("-m").arg(model_arg)
-            .arg("-f").arg("%08d.png")
-            .arg("-j").arg(&threads_for_task)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped());
+    let model_name = job.model_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let (threads, uhd, note) = planned_rife_settings(job, &model_name, degrade);
+    if let Some(note) = note {
+        emit_log_limited(app, &note);
+    }
 
-        let mut rife_child = match rife_cmd.spawn() {
-            Ok(c) => c,
+    let _gpu_turn = gpu_turn(app, &job.settings, "RIFE");
+    let rife_started = std::time::Instant::now();
+    if job.settings.rife_backend == RifeBackend::Builtin && job.rife_runtime == RifeRuntime::NcnnVulkan && job.rife_factor.is_some() {
+        emit_log_limited(app, "The built-in RIFE backend only doubles the rate; using the RIFE binary for -n");
+    } else if job.settings.rife_backend == RifeBackend::Builtin && job.rife_runtime == RifeRuntime::NcnnVulkan {
+        emit_pipeline(app, "pipeline_log", "RIFE backend: built-in (ncnn)".to_string());
+        match interpolate_builtin(app, job, in_count, threads) {
+            Ok(()) => {
+                sample_interpolated_frames(app, job);
+                return Ok((model_name, rife_started.elapsed().as_secs_f64()));
+            }
+            Err(e) if is_cancelled_error(&e) => return Err(e),
             Err(e) => {
-                let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
-                    ok: false,
-                    message: format!("RIFE failed to start: {e}"),
-                    frames_dir: frames_dir_for_task.clone(),
-                    frame_pattern: frame_pattern_for_task.clone(),
-                });
-                return;
+                emit_log_limited(app, &format!("{e}; using {} instead", job.rife_bin.to_string_lossy()));
+                clear_dir(&job.frames_out_dir)?;
+                if is_sharded(&job.frames_in_dir) {
+                    make_sharded(&job.frames_out_dir)?;
+                }
             }
-        };
-
-        // stream logs from RIFE stderr on a background thread (prevents pipe buffer deadlocks)
-        use std::sync::{Arc, Mutex};
-        let stderr_tail: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
-        let stderr_tail_for_thread = stderr_tail.clone();
+        }
+    }
+    if is_sharded(&job.frames_in_dir) {
+        interpolate_shards(app, job, (threads, uhd), in_count)?;
+    } else {
+        run_rife_pass(app, job, &job.frames_in_dir, &job.frames_out_dir, (threads, uhd), 0, in_count)?;
+    }
 
-        let stderr_handle = rife_child.stderr.take().map(|st| {
-            let app = app_for_task.clone();
-            std::thread::spawn(move || {
-                let reader = std::io::BufReader::new(st);
-                for line in reader.lines().flatten() {
-                    let line = line.trim().to_string();
-                    if line.is_empty() { continue; }
-                    // keep a small tail for error reporting
-                    {
-                        let mut t = stderr_tail_for_thread.lock().unwrap();
-                        t.push(line.clone());
-                        let len = t.len();
-                        if len > 64 {
-                            t.drain(0..(len - 64));
-                        }
-                    }
-                    let _ = app.emit("pipeline_log", line);
-                }
-            })
-        });
+    log_throughput(app, "RIFE", count_files_in_dir(&job.frames_out_dir) as u64, rife_started.elapsed().as_secs_f64());
+    sample_interpolated_frames(app, job);
+    Ok((model_name, rife_started.elapsed().as_secs_f64()))
+}
 
-        // update progress based on output frame count while RIFE runs
-        while rife_child.try_wait().ok().flatten().is_none() {
-            let out_count = count_files_in_dir(&frames_out_for_task) as f64;
-            // For 2x interpolation, output is roughly ~2x input frames. Clamp to the middle-third segment.
-            let pct = 33.0 + ((out_count / (in_count * 2.0)) * 33.0).max(0.0).min(33.0);
-            let _ = app_for_task.emit("pipeline_progress", pct);
-            std::thread::sleep(std::time::Duration::from_millis(300));
+/// RIFE over a sharded frames_in, a shard at a time since it only reads one folder. Each pass
+/// also gets the frames just before and after its shard so the in-between frames at the shard
+/// edges come from the real neighbours; the outputs for those extra frames are dropped.
+fn interpolate_shards(app: &AppHandle, job: &SmoothJob, settings: (RifeThreads, bool), in_count: f64) -> Result<(), String> {
+    let inputs = sorted_files(&job.frames_in_dir);
+    let in_ext = FrameFormat::in_dir(&job.frames_in_dir).ext();
+    let out = FrameFiles::new(&job.frames_out_dir, job.frame_format);
+    let pass_in = job.frames_in_dir.with_extension("pass");
+    let pass_out = job.frames_out_dir.with_extension("pass");
+    let link_pass = |files: &[PathBuf]| -> Result<(), String> {
+        clear_dir(&pass_in)?;
+        clear_dir(&pass_out)?;
+        for (i, src) in files.iter().enumerate() {
+            let dest = pass_in.join(format!("{:08}.{in_ext}", i + 1));
+            if fs::hard_link(src, &dest).is_err() {
+                fs::copy(src, &dest).map_err(|e| format!("Failed to write {}: {e}", dest.to_string_lossy()))?;
+            }
         }
-
-        // ensure stderr thread finishes draining
-        if let Some(h) = stderr_handle {
-            let _ = h.join();
+        Ok(())
+    };
+    if let Some(factor) = job.rife_factor {
+        // RIFE spreads a pass's `-n` outputs evenly over its inputs, so each pass gets the
+        // next shard's first frame too and keeps the outputs that come before it.
+        let mut done = 0;
+        for (k, shard) in inputs.chunks(SHARD_FRAMES).enumerate() {
+            check_stage_cancelled(app, &job.job_id, JobStage::Interpolate)?;
+            let first = k * SHARD_FRAMES;
+            let end = (first + shard.len() + 1).min(inputs.len());
+            link_pass(&inputs[first..end])?;
+            run_rife_pass(app, job, &pass_in, &pass_out, settings, done as u64, in_count)?;
+            let produced = sorted_files(&pass_out);
+            let keep = if end == inputs.len() { produced.len() } else { (shard.len() as f64 * factor).round() as usize };
+            if produced.len() < keep {
+                return Err(format!("RIFE made {} frames for shard {k}; expected at least {keep}", produced.len()));
+            }
+            for src in &produced[..keep] {
+                done += 1;
+                fs::rename(src, out.create(done)?).map_err(|e| format!("Failed to move {}: {e}", src.to_string_lossy()))?;
+            }
         }
-
-        let ok = rife_child.wait().map(|s| s.success()).unwrap_or(false);
-        if !ok {
-            let tail = {
-                let t = stderr_tail.lock().unwrap();
-                t.iter().rev().take(8).cloned().collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n")
-            };
-            let msg = if tail.trim().is_empty() { "RIFE failed".into() } else { tail };
-            let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
-                ok: false,
-                message: msg,
-                frames_dir: frames_dir_for_task.clone(),
-                frame_pattern: frame_pattern_for_task.clone(),
-            });
-            return;
+        let _ = fs::remove_dir_all(&pass_in);
+        let _ = fs::remove_dir_all(&pass_out);
+        return Ok(());
+    }
+    for (k, shard) in inputs.chunks(SHARD_FRAMES).enumerate() {
+        check_stage_cancelled(app, &job.job_id, JobStage::Interpolate)?;
+        let first = k * SHARD_FRAMES;
+        let lead = usize::from(first > 0);
+        let end = (first + shard.len() + 1).min(inputs.len());
+        link_pass(&inputs[first - lead..end])?;
+        run_rife_pass(app, job, &pass_in, &pass_out, settings, 2 * first as u64, in_count)?;
+        // Outputs 2j-1 and 2j belong to input j, in the pass and in the whole sequence.
+        for j in 1..=shard.len() {
+            for back in [1, 0] {
+                let src = pass_out.join(format!("{:08}.{}", 2 * (lead + j) - back, job.frame_format.ext()));
+                fs::rename(&src, out.create(2 * (first + j) - back)?)
+                    .map_err(|e| format!("RIFE output {} is missing: {e}", src.to_string_lossy()))?;
+            }
         }
+    }
+    let _ = fs::remove_dir_all(&pass_in);
+    let _ = fs::remove_dir_all(&pass_out);
+    Ok(())
+}
 
-        // STEP 3: Encode video
-        emit_stage(&app_for_task, "Encoding video… (step 3/3)");
-        let out_pattern = frames_out_for_task.join("%08d.png");
-        let mut enc = Command::new(&ffmpeg_for_task);
-        enc.arg("-hide_banner").arg("-y")
-            .arg("-framerate").arg("30")
-            .arg("-i").arg(out_pattern)
-            .arg("-c:v").arg("libx264")
-            .arg("-pix_fmt").arg("yuv420p")
-            .arg(&output_for_task)
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped());
-
-        let mut enc_child = match enc.spawn() {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
-                    ok: false,
-                    message: format!("Encode failed to start: {e}"),
-                    frames_dir: frames_dir_for_task.clone(),
-                    frame_pattern: frame_pattern_for_task.clone(),
-                });
-                return;
-            }
-        };
+/// One run of the RIFE binary from `input` to `output`. Progress counts `done` output frames
+/// of earlier passes.
+fn run_rife_pass(
+    app: &AppHandle,
+    job: &SmoothJob,
+    input: &Path,
+    output: &Path,
+    (threads, uhd): (RifeThreads, bool),
+    done: u64,
+    in_count: f64,
+) -> Result<(), String> {
+    let (cwd, model_arg) = compute_rife_cwd_and_model_arg(&job.model_dir);
+    let mut rife_cmd = tool_command(&job.rife_bin);
+    if let Some(d) = cwd {
+        rife_cmd.current_dir(d);
+    }
+    rife_cmd.arg("-v")
+        .arg("-i").arg(input)
+        .arg("-o").arg(output)
+        .arg("-m").arg(model_arg)
+        .arg("-f").arg(format!("%08d.{}", job.frame_format.ext()));
+    job.rife_runtime.push_args(&mut rife_cmd, threads, uhd, rife_gpu(&job.settings));
+    if job.tta && job.rife_runtime == RifeRuntime::NcnnVulkan {
+        rife_cmd.arg("-x");
+    }
+    if let Some(factor) = job.rife_factor {
+        rife_cmd.arg("-n").arg(((count_files_in_dir(input) as f64 * factor).round() as usize).to_string());
+    }
+    rife_cmd
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
 
-        if let Some(stderr) = enc_child.stderr.take() {
-            let reader = std::io::BufReader::new(stderr);
-            for line in reader.lines().flatten() {
-                let line = line.trim().to_string();
-                if !line.is_empty() {
-                    let _ = app_for_task.emit("pipeline_log", line);
+    log_stage_command(app, "interpolate", &mut rife_cmd);
+    let mut rife = spawn_stage_process(app, &job.job_id, "RIFE", &mut rife_cmd)?;
+    // stream logs from RIFE stderr on a background thread (prevents pipe buffer deadlocks)
+    rife.relay_to_log(app, Pipe::Stderr);
+
+    // update progress based on output frame count while RIFE runs
+    let mut out_frames = FrameCounter::new(output, job.frame_format);
+    let mut last_power_check = std::time::Instant::now();
+    let mut suspended = false;
+    while rife.child.try_wait().ok().flatten().is_none() {
+        check_unkillable(app, &job.job_id, rife.child.id())?;
+        if job.settings.battery_min_percent.is_some() && last_power_check.elapsed().as_secs() >= 15 {
+            last_power_check = std::time::Instant::now();
+            let hold = battery_hold_reason(&job.settings);
+            if hold.is_some() != suspended && set_process_suspended(rife.child.id(), hold.is_some()) {
+                suspended = hold.is_some();
+                match hold {
+                    Some(reason) => {
+                        emit_log_limited(app, &format!("RIFE paused: {}", reason.text));
+                        emit_stage(app, StageId::Paused, paused_message(&reason));
+                    }
+                    None => {
+                        emit_stage(app, StageId::Interpolate, message("stage.interpolate", &[]));
+                        emit_log_limited(app, "Power restored: RIFE resumed");
+                    }
                 }
             }
         }
-        let ok = enc_child.wait().map(|s| s.success()).unwrap_or(false);
-        if !ok {
-            let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
-                ok: false,
-                message: "Encoding failed".into(),
-                frames_dir: frames_dir_for_task.clone(),
-                frame_pattern: frame_pattern_for_task.clone(),
-            });
-            return;
-        }
+        let out_count = done + out_frames.count() as u64;
+        emit_stage_progress(app, job, JobStage::Interpolate, out_count as f64 / (in_count * job.rate_multiplier()), out_count);
+        std::thread::sleep(std::time::Duration::from_millis(300));
+    }
 
-        let _ = app_for_task.emit("pipeline_progress", 100.0_f64);
-        let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
-            ok: true,
-            message: format!("Done: {}", output_for_task.to_string_lossy()),
-            frames_dir: frames_dir_for_task.clone(),
-            frame_pattern: frame_pattern_for_task.clone(),
-        });
-    });
+    let status = rife.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, JobStage::Interpolate)?;
+    rife.check(app, "RIFE failed", status)
+}
 
-    Ok(ExtractFramesResult {
-        ok: true,
-        frames_dir: frames_dir_str,
-        frame_pattern: frame_pattern_str,
-        output: output.to_string_lossy().to_string(),
-    })
+/// Warn about a silently broken model/driver here rather than after a long encode. The job
+/// goes on: a dark or flat source can look the same.
+fn sample_interpolated_frames(app: &AppHandle, job: &SmoothJob) {
+    emit_log_limited(app, "Sampling interpolated frames…");
+    if let Some(problem) = check_interpolated_frames(&job.ffmpeg, &job.frames_in_dir, &job.frames_out_dir, job.rate_multiplier()) {
+        emit_log_limited(app, &format!("Warning: {problem}"));
+        if let Some(hint) = classify_process_error(&problem) {
+            emit_log_limited(app, &hint.suggestion);
+        }
+    }
 }
 
-#[tauri::command]
-fn reencode_only(
-    app: AppHandle,
+/// Arguments of `reencode_only`; like `SmoothVideoRequest`, new options go here.
+#[derive(serde::Deserialize)]
+struct ReencodeRequest {
     video_path: String,
     output_path: String,
     frames_dir: Option<String>,
     max_threads: Option<i32>,
-) -> Result<ExtractFramesResult, String> {
+    on_conflict: Option<OnConflict>,
+    encode: Option<EncodeSettings>,
+    preset: Option<String>,
+    not_before: Option<i64>,
+}
+
+#[tauri::command]
+fn reencode_only(app: AppHandle, state: State<'_, AppState>, request: ReencodeRequest) -> Result<ExtractFramesResult, CommandError> {
+    let ReencodeRequest {
+        video_path,
+        output_path,
+        frames_dir,
+        max_threads,
+        on_conflict,
+        encode,
+        preset,
+        not_before,
+    } = request;
     // Non-blocking: returns immediately; work is done on a background thread.
     let root = app_root(&app)?;
     ensure_dirs(&root)?;
 
-    let (ffmpeg_path, _rife_path, _rife_models) = find_installed_tool_paths(&root);
+    let preset = resolve_preset(&root, preset.as_deref())?;
+    let max_threads = max_threads.or(preset.max_threads);
+    let on_conflict = on_conflict.or(preset.on_conflict);
+    let post_job_hook = preset.post_job_hook;
+    // Re-encode is a quick-turnaround path, so it defaults to a fast, high-quality encode.
+    let encode = encode.or(preset.encode).unwrap_or_else(|| EncodeSettings {
+        crf: Some(18),
+        preset: Some("ultrafast".into()),
+        ..Default::default()
+    });
+    encode.validate()?;
+    if encode.target_size_mb.is_some() {
+        return Err("Target file size is only available for Smooth Video jobs".into());
+    }
+    if encode.backend == EncodeBackend::Av1an {
+        return Err("av1an is only available for Smooth Video jobs".into());
+    }
+
+    let ffmpeg_path = state.tools(&root).ffmpeg;
     let ffmpeg = preferred_ffmpeg_path()
         .or(ffmpeg_path)
         .ok_or("ffmpeg not installed (install ffmpeg first)")?;
@@ -1126,22 +12987,40 @@ fn reencode_only(
     if !frames_dir_path.exists() {
         return Err("Frames folder does not exist".into());
     }
-
-    let pattern = frames_dir_path.join("%08d.png");
+    check_source_protection(&root, &state.settings(&root), &input, &output, &[])?;
+    let output = claim_library_output(&root, &state.settings(&root), &output)?;
+    validate_output_path(&root, &input, &output, &[&frames_dir_path])?;
+    let on_conflict = on_conflict.unwrap_or_default();
+    let output = resolve_output_conflict(&output, on_conflict)?;
+    let settings = state.settings(&root);
+
+    let pattern = FrameFormat::in_dir(&frames_dir_path).pattern(&frames_dir_path);
     let frame_pattern_str = pattern.to_string_lossy().to_string();
 
     // Estimate total frames for progress
     let total_frames_est = count_files_in_dir(&frames_dir_path).max(0) as i64;
 
-    let (_dur, fps_in) = probe_duration_and_fps(&ffmpeg, &input).unwrap_or((0.0, 30.0));
+    let (dur_in, fps_in) = probe_duration_and_fps(&ffmpeg, &input).unwrap_or((0.0, 30.0));
     let fps_out = (fps_in * 2.0).max(1.0);
-    let fps_out_str = format!("{:.6}", fps_out);
+    let frames_input = FramesInput::new(&frames_dir_path, fps_out)?;
 
-    let output_ext = output
-        .extension()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+    // With -shortest the output ends with whichever of frames/audio runs out first.
+    let frames_secs = total_frames_est as f64 / fps_out;
+    let expected_duration = if dur_in > 0.0 { dur_in.min(frames_secs) } else { frames_secs };
+    let expect_audio = probe_has_audio(&ffmpeg, &input);
+    let encode = resolve_encode_settings(&app, &state, encode, &ffmpeg, &input)?;
+    let color = encode.color.or(probe_color_tags(&ffmpeg, &input));
+
+    let output_ext = output_extension(&output);
+    let audio = AudioPlan::new(&ffmpeg, &input, &output_ext, &encode.audio);
+
+    let job_id = make_job_id();
+    record_job_start(&root, &job_id, "reencode_only", &input, &output);
+    record_job_schedule(&root, &job_id, not_before);
+    state.job_started(&job_id, "reencode_only", &input, &output);
+    let running = RunningJobGuard { app: app.clone(), job_id: job_id.clone() };
+    let lease = lease_tools(&app, &[&ffmpeg]);
+    let result_job_id = job_id.clone();
 
     let app_for_task = app.clone();
     let input_for_task = input.clone();
@@ -1152,65 +13031,80 @@ fn reencode_only(
     let max_threads_for_task = max_threads.unwrap_or(0);
 
     std::thread::spawn(move || {
-        let _ = app_for_task.emit("pipeline_progress", 0.0_f64);
+        let _running = running;
+        let _lease = lease;
+        let _scope = JobScope::enter(Some(job_id.clone()));
+        wait_for_start_window(&app_for_task, &settings, not_before);
+        let _slot = acquire_job_slot(&app_for_task, &settings);
+        // Re-encodes run at roughly the speed of playback or faster.
+        let progress = ProgressResolution::for_estimate(dur_in);
+        set_progress_resolution(&app_for_task, progress);
+        let state = app_for_task.state::<AppState>();
+        state.set_job_stage(&job_id, JobStage::Encode);
+        emit_pipeline(&app_for_task, "pipeline_progress", 0.0_f64);
         emit_log_limited(&app_for_task, "Re-encode only: starting ffmpeg…");
+        emit_log_limited(&app_for_task, &audio.describe(&output_ext));
+        trash_existing_output(&app_for_task, &settings, &output_for_task, on_conflict);
 
-        let mut cmd = Command::new(&ffmpeg_for_task);
-        cmd.arg("-hide_banner").arg("-y");
+        let mut cmd = tool_command(&ffmpeg_for_task);
+        cmd.arg("-hide_banner").arg(ffmpeg_overwrite_flag(on_conflict));
 
         if max_threads_for_task > 0 {
             cmd.arg("-threads").arg(max_threads_for_task.to_string());
         }
 
-        cmd.arg("-progress").arg("pipe:1")
-            .arg("-nostats")
-            .arg("-framerate").arg(&fps_out_str)
-            .arg("-i").arg(&frame_pattern_for_task)
-            .arg("-i").arg(&input_for_task)
-            .arg("-map").arg("0:v:0")
-            .arg("-map").arg("1:a:0?")
-            .arg("-c:v").arg("libx264")
-            .arg("-preset").arg("ultrafast")
-            .arg("-crf").arg("18");
-
-        // Audio: Opus-in-MP4 can be finicky; AAC is safest for mp4/mov.
-        if output_ext == "mp4" || output_ext == "mov" || output_ext == "m4v" {
-            cmd.arg("-c:a").arg("aac").arg("-b:a").arg("192k");
-        } else {
-            cmd.arg("-c:a").arg("copy");
+        cmd.arg("-progress").arg("pipe:1");
+        progress.push_args(&mut cmd);
+        cmd.arg("-nostats");
+        frames_input.push_args(&mut cmd);
+        cmd.arg("-i").arg(&input_for_task);
+        audio.push_inputs(&mut cmd, expected_duration);
+        cmd.arg("-map").arg("0:v:0");
+        audio.push_map(&mut cmd);
+        encode.push_args(&mut cmd, None);
+        if let Some(pix_fmt) = encode.pix_fmt() {
+            cmd.arg("-pix_fmt").arg(pix_fmt);
+        }
+        if let Some(mode) = encode.fps_mode {
+            cmd.arg("-fps_mode").arg(mode.as_arg());
         }
+        color.push_args(&mut cmd);
+        FilterChain::new().color_matrix(&color).apply(&mut cmd);
+
+        audio.push_args(&mut cmd);
+        encode.mp4_layout.push_args(&mut cmd, &output_ext);
 
         cmd.arg("-shortest").arg(&output_for_task)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
+        log_stage_command(&app_for_task, "encode", &mut cmd);
 
-        let mut child = match cmd.spawn() {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
+        let mut ffmpeg = match spawn_stage_process(&app_for_task, &job_id, "FFmpeg", &mut cmd) {
+            Ok(p) => p,
+            Err(message) => {
+                record_job_finish(&root, &job_id, false, &message);
+                emit_pipeline(&app_for_task, "pipeline_done", PipelineDoneEvent {
                     ok: false,
-                    message: format!("ffmpeg failed to start: {e}"),
+                    error: classify_process_error(&message),
+                    message,
                     frames_dir: frames_dir_for_task.clone(),
                     frame_pattern: frame_pattern_for_task.clone(),
+                    verification: None,
+                    output_tail: None,
                 });
                 return;
             }
         };
-
-        // stderr -> log
-        if let Some(stderr) = child.stderr.take() {
-            let app_log = app_for_task.clone();
-            std::thread::spawn(move || {
-                let reader = BufReader::new(stderr);
-                for line in reader.lines().flatten() {
-                    emit_log_limited(&app_log, &line);
-                }
-            });
-        }
+        // stderr -> log (keeping a tail for the failure report)
+        let app_log = app_for_task.clone();
+        ffmpeg.relay(Pipe::Stderr, move |line| {
+            emit_log_limited(&app_log, line);
+            true
+        });
 
         // stdout (-progress) -> progress percent
         let mut last_emit = std::time::Instant::now();
-        if let Some(stdout) = child.stdout.take() {
+        if let Some(stdout) = ffmpeg.child.stdout.take() {
             let reader = BufReader::new(stdout);
             let mut frame: i64 = 0;
             for line in reader.lines().flatten() {
@@ -1223,7 +13117,7 @@ fn reencode_only(
                     if last_emit.elapsed().as_millis() >= 250 {
                         if total_frames_est > 0 && frame > 0 {
                             let pct = ((frame as f64 / total_frames_est as f64) * 100.0).min(99.9);
-                            let _ = app_for_task.emit("pipeline_progress", pct);
+                            emit_pipeline(&app_for_task, "pipeline_progress", pct);
                         }
                         last_emit = std::time::Instant::now();
                     }
@@ -1231,44 +13125,492 @@ fn reencode_only(
             }
         }
 
-        let ok = child.wait().map(|s| s.success()).unwrap_or(false);
-        if ok {
-            let _ = app_for_task.emit("pipeline_progress", 100.0_f64);
-            let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
+        let waited = ffmpeg.wait(&app_for_task);
+        let outcome = waited.and_then(|status| {
+            check_stage_cancelled(&app_for_task, &job_id, JobStage::Encode)?;
+            ffmpeg.check(&app_for_task, "Re-encode failed", status)
+        });
+        if outcome.is_ok() {
+            emit_pipeline(&app_for_task, "pipeline_progress", 100.0_f64);
+            let verification = verify_output(&ffmpeg_for_task, &output_for_task, expected_duration, expect_audio);
+            report_verification(&app_for_task, &verification);
+            let message = verification.annotate(format!("Done: {}", output_for_task.to_string_lossy()));
+            let bytes_written = fs::metadata(&output_for_task).map(|m| m.len()).unwrap_or(0);
+            update_job_record(&root, &job_id, |r| {
+                r.source_duration_secs = dur_in;
+                r.bytes_written = bytes_written;
+            });
+            if let Some(out) = run_post_job_hook(
+                &app_for_task,
+                &settings,
+                post_job_hook.as_ref(),
+                &job_id,
+                &input_for_task,
+                &output_for_task,
+            ) {
+                update_job_record(&root, &job_id, |r| r.hook_output = out);
+            }
+            record_job_finish(&root, &job_id, true, &message);
+            emit_pipeline(&app_for_task, "pipeline_done", PipelineDoneEvent {
                 ok: true,
-                message: format!("Done: {}", output_for_task.to_string_lossy()),
+                message,
+                frames_dir: frames_dir_for_task.clone(),
+                frame_pattern: frame_pattern_for_task.clone(),
+                verification: Some(verification),
+                output_tail: None,
+                error: None,
+            });
+        } else {
+            let message = outcome.err().unwrap_or_default();
+            record_job_finish(&root, &job_id, false, &message);
+            emit_pipeline(&app_for_task, "pipeline_done", PipelineDoneEvent {
+                ok: false,
+                error: classify_process_error(&message),
+                message,
                 frames_dir: frames_dir_for_task.clone(),
                 frame_pattern: frame_pattern_for_task.clone(),
+                verification: None,
+                output_tail: state.take_failure_tail(&job_id),
+            });
+        }
+    });
+
+    Ok(ExtractFramesResult {
+        ok: true,
+        job_id: result_job_id,
+        frames_dir: frames_dir_str,
+        frame_pattern: frame_pattern_str,
+        output: output.to_string_lossy().to_string(),
+        skipped: None,
+        duplicate_of: None,
+    })
+}
+
+
+// -------------------- ffmpeg capabilities --------------------
+
+/// One line of `ffmpeg -encoders` or `-decoders`.
+#[derive(Clone, Debug, serde::Serialize)]
+struct CodecEntry {
+    name: String,
+    /// "video", "audio", "subtitle" or "data".
+    kind: String,
+    description: String,
+}
+
+/// Whether one of our encoder options can be used with this ffmpeg build.
+#[derive(Clone, Debug, serde::Serialize)]
+struct EncoderSupport {
+    encoder: VideoEncoder,
+    available: bool,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct FfmpegCapabilities {
+    ffmpeg: String,
+    version: String,
+    encoders: Vec<CodecEntry>,
+    decoders: Vec<CodecEntry>,
+    hwaccels: Vec<String>,
+    filters: Vec<String>,
+    /// Every `VideoEncoder` option, for graying out the ones this build lacks.
+    video_encoders: Vec<EncoderSupport>,
+    /// Size and mtime of the binary when parsed; a replaced binary is parsed again.
+    #[serde(skip)]
+    identity: Option<(u64, u128)>,
+}
+
+impl FfmpegCapabilities {
+    fn has_encoder(&self, name: &str) -> bool {
+        self.encoders.iter().any(|e| e.name == name)
+    }
+}
+
+fn ffmpeg_list_output(ffmpeg: &Path, flag: &str) -> Result<String, String> {
+    let out = run_tool(log_command("probe", tool_command(ffmpeg).arg("-hide_banner").arg(flag)), QUERY_TIMEOUT_SECS)
+        .map_err(|e| format!("Failed to run ffmpeg {flag}: {e}"))?;
+    if !out.status.success() {
+        return Err(format!("ffmpeg {flag} failed: {}", String::from_utf8_lossy(&out.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+/// Entries of `-encoders`/`-decoders`: everything after the "------" line that ends the legend,
+/// as `<flags> <name> <description>` where the first flag is the media type.
+fn parse_codec_list(text: &str) -> Vec<CodecEntry> {
+    text.lines()
+        .skip_while(|l| !l.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|l| {
+            let mut parts = l.split_whitespace();
+            let flags = parts.next()?;
+            let name = parts.next()?;
+            let kind = match flags.chars().next()? {
+                'V' => "video",
+                'A' => "audio",
+                'S' => "subtitle",
+                _ => "data",
+            };
+            Some(CodecEntry {
+                name: name.to_string(),
+                kind: kind.to_string(),
+                description: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect()
+}
+
+/// Filter names from `-filters`; lines look like ` TSC scale  V->V  Scale the input video size`.
+fn parse_filter_list(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|l| {
+            let parts: Vec<&str> = l.split_whitespace().collect();
+            (parts.len() >= 3 && parts[1] != "=" && parts[2].contains("->")).then(|| parts[1].to_string())
+        })
+        .collect()
+}
+
+fn probe_ffmpeg_capabilities(ffmpeg: &Path) -> Result<FfmpegCapabilities, String> {
+    let version = ffmpeg_list_output(ffmpeg, "-version")?
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    let encoders = parse_codec_list(&ffmpeg_list_output(ffmpeg, "-encoders")?);
+    let decoders = parse_codec_list(&ffmpeg_list_output(ffmpeg, "-decoders")?);
+    let hwaccels = ffmpeg_list_output(ffmpeg, "-hwaccels")?
+        .lines()
+        .skip_while(|l| !l.trim_end().ends_with(':'))
+        .skip(1)
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    let filters = parse_filter_list(&ffmpeg_list_output(ffmpeg, "-filters")?);
+    let video_encoders = VideoEncoder::ALL
+        .iter()
+        .map(|&encoder| EncoderSupport {
+            encoder,
+            available: encoders.iter().any(|e| e.name == encoder.ffmpeg_name()),
+        })
+        .collect();
+    Ok(FfmpegCapabilities {
+        ffmpeg: ffmpeg.to_string_lossy().to_string(),
+        version,
+        encoders,
+        decoders,
+        hwaccels,
+        filters,
+        video_encoders,
+        identity: file_identity(ffmpeg),
+    })
+}
+
+/// Hardware decoders in order of preference, with the `-hwaccel_output_format` that keeps
+/// decoded frames on the GPU until `hwdownload`.
+const HW_DECODERS: &[(&str, &str)] = &[
+    ("cuda", "cuda"),
+    ("videotoolbox", "videotoolbox_vld"),
+    ("d3d11va", "d3d11"),
+    ("qsv", "qsv"),
+    ("vaapi", "vaapi"),
+];
+
+/// Hardware decoder for frame extraction. Frames stay in GPU memory through decoding and are
+/// downloaded once, straight into the format the rest of the chain works on, instead of
+/// ffmpeg's default hwaccel path copying every frame back in its own pixel format.
+#[derive(Clone, Copy, Debug)]
+struct HwDecode {
+    hwaccel: &'static str,
+    output_format: &'static str,
+}
+
+impl HwDecode {
+    /// First hardware decoder the build lists that also decodes a frame of `input`: a listed
+    /// hwaccel says nothing about whether the device exists or handles this codec.
+    fn pick(caps: &FfmpegCapabilities, ffmpeg: &Path, input: &Path) -> Option<HwDecode> {
+        HW_DECODERS
+            .iter()
+            .filter(|(name, _)| caps.hwaccels.iter().any(|h| h == name))
+            .map(|&(hwaccel, output_format)| HwDecode { hwaccel, output_format })
+            .find(|hw| hw.decodes(ffmpeg, input))
+    }
+
+    fn decodes(&self, ffmpeg: &Path, input: &Path) -> bool {
+        let mut cmd = tool_command(ffmpeg);
+        cmd.arg("-hide_banner").arg("-nostdin").arg("-v").arg("error");
+        self.push_input_args(&mut cmd);
+        cmd.arg("-i").arg(input)
+            .arg("-map").arg("0:v:0")
+            .arg("-frames:v").arg("1")
+            .arg("-f").arg("null").arg("-");
+        run_tool(log_command("hw_decode_check", &mut cmd), PROBE_TIMEOUT_SECS).is_ok_and(|out| out.status.success())
+    }
+
+    /// Input options; they go before `-i`.
+    fn push_input_args(&self, cmd: &mut Command) {
+        cmd.arg("-hwaccel").arg(self.hwaccel)
+            .arg("-hwaccel_output_format").arg(self.output_format);
+    }
+}
+
+/// Encoders, decoders, hardware accelerations and filters of the ffmpeg jobs will use.
+#[tauri::command]
+fn get_ffmpeg_capabilities(app: AppHandle, state: State<'_, AppState>) -> Result<FfmpegCapabilities, String> {
+    let root = app_root(&app)?;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    state.ffmpeg_capabilities(&ffmpeg)
+}
+
+// -------------------- Remux --------------------
+
+/// Whether `container` can hold a `codec` video stream without re-encoding.
+fn container_accepts_video(container: &str, codec: &str) -> bool {
+    match container {
+        "mp4" | "m4v" => matches!(codec, "h264" | "hevc" | "av1" | "vp9" | "mpeg4"),
+        "mov" => matches!(codec, "h264" | "hevc" | "prores" | "mpeg4" | "mjpeg" | "dnxhd" | "png" | "qtrle"),
+        "webm" => matches!(codec, "vp8" | "vp9" | "av1"),
+        "avi" => matches!(codec, "h264" | "mpeg4" | "mjpeg" | "ffv1" | "huffyuv"),
+        "ts" | "m2ts" => matches!(codec, "h264" | "hevc" | "mpeg2video"),
+        _ => true,
+    }
+}
+
+fn container_accepts_subtitle(container: &str, codec: &str) -> bool {
+    match container {
+        "mp4" | "m4v" | "mov" => codec == "mov_text",
+        "webm" => codec == "webvtt",
+        "avi" => false,
+        "ts" | "m2ts" => matches!(codec, "dvb_subtitle" | "dvb_teletext"),
+        _ => true,
+    }
+}
+
+/// One stream of the input, as ffprobe reports it.
+#[derive(Clone, Debug)]
+struct ProbedStream {
+    index: u32,
+    kind: String,
+    codec: String,
+}
+
+impl ProbedStream {
+    fn label(&self) -> String {
+        format!("#{} {} ({})", self.index, self.kind, self.codec)
+    }
+
+    fn copyable_into(&self, container: &str) -> bool {
+        match self.kind.as_str() {
+            "video" => container_accepts_video(container, &self.codec),
+            "audio" => container_accepts_audio(container, &self.codec),
+            "subtitle" => container_accepts_subtitle(container, &self.codec),
+            // Fonts, chapters' data tracks, timecode: only Matroska takes arbitrary streams.
+            _ => matches!(container, "mkv" | "mka"),
+        }
+    }
+}
+
+fn probe_streams(ffmpeg: &Path, input: &Path) -> Option<Vec<ProbedStream>> {
+    let ffprobe = ffprobe_path(ffmpeg)?;
+    let out = run_ffprobe(&ffprobe, &["-show_entries", "stream=index,codec_type,codec_name", "-of", "default"], input)?;
+    let mut streams = Vec::new();
+    let (mut index, mut kind, mut codec) = (None, String::new(), String::new());
+    for line in out.lines() {
+        let line = line.trim();
+        if line == "[/STREAM]" {
+            if let Some(index) = index.take() {
+                streams.push(ProbedStream { index, kind: std::mem::take(&mut kind), codec: std::mem::take(&mut codec) });
+            }
+            continue;
+        }
+        let Some((k, v)) = line.split_once('=') else { continue };
+        match k {
+            "index" => index = v.parse().ok(),
+            "codec_type" => kind = v.to_string(),
+            "codec_name" => codec = v.to_string(),
+            _ => {}
+        }
+    }
+    Some(streams)
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct RemuxResult {
+    job_id: String,
+    output: String,
+    /// Streams copied into the output.
+    copied: Vec<String>,
+    /// Streams the output container can't hold without re-encoding.
+    dropped: Vec<String>,
+}
+
+/// Copy every stream the output container can hold into a new container, without
+/// re-encoding. Streams that can't be copied are dropped with a warning; a video stream that
+/// can't be copied is an error (use Re-encode instead).
+#[tauri::command]
+fn remux(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    input_path: String,
+    output_path: String,
+    on_conflict: Option<OnConflict>,
+) -> Result<RemuxResult, CommandError> {
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+
+    let ffmpeg_path = state.tools(&root).ffmpeg;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(ffmpeg_path)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+
+    let input = PathBuf::from(input_path.trim());
+    if !input.exists() {
+        return Err("Input video does not exist".into());
+    }
+    let output = PathBuf::from(output_path.trim());
+    check_source_protection(&root, &state.settings(&root), &input, &output, &[])?;
+    validate_output_path(&root, &input, &output, &[])?;
+    let on_conflict = on_conflict.unwrap_or_default();
+    let output = resolve_output_conflict(&output, on_conflict)?;
+    let container = output_extension(&output);
+
+    let streams = probe_streams(&ffmpeg, &input).ok_or("Could not read the input's streams")?;
+    let (copied, dropped): (Vec<ProbedStream>, Vec<ProbedStream>) =
+        streams.into_iter().partition(|s| s.copyable_into(&container));
+    if let Some(v) = dropped.iter().find(|s| s.kind == "video") {
+        return Err(format!(
+            "The {} video stream can't be stream-copied into .{container}; use Re-encode instead",
+            v.codec
+        ).into());
+    }
+    if copied.is_empty() {
+        return Err(format!("No stream of the input can be copied into .{container}").into());
+    }
+    for s in &dropped {
+        emit_log_limited(&app, &format!("Remux: dropping stream {}; .{container} can't hold it", s.label()));
+    }
+
+    let expected_duration = probe_duration_and_fps(&ffmpeg, &input).map(|(d, _)| d).unwrap_or(0.0);
+    let expect_audio = copied.iter().any(|s| s.kind == "audio");
+
+    let job_id = make_job_id();
+    record_job_start(&root, &job_id, "remux", &input, &output);
+    state.job_started(&job_id, "remux", &input, &output);
+    let running = RunningJobGuard { app: app.clone(), job_id: job_id.clone() };
+    let lease = lease_tools(&app, &[&ffmpeg]);
+    let settings = state.settings(&root);
+
+    let mut cmd = tool_command(&ffmpeg);
+    cmd.arg("-hide_banner").arg(ffmpeg_overwrite_flag(on_conflict))
+        .arg("-i").arg(&input);
+    for s in &copied {
+        cmd.arg("-map").arg(format!("0:{}", s.index));
+    }
+    cmd.arg("-c").arg("copy");
+    if matches!(container.as_str(), "mp4" | "mov" | "m4v") {
+        cmd.arg("-movflags").arg("+faststart");
+    }
+    cmd.arg(&output)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+
+    let result = RemuxResult {
+        job_id: job_id.clone(),
+        output: output.to_string_lossy().to_string(),
+        copied: copied.iter().map(ProbedStream::label).collect(),
+        dropped: dropped.iter().map(ProbedStream::label).collect(),
+    };
+
+    let app_for_task = app.clone();
+    std::thread::spawn(move || {
+        let _running = running;
+        let _lease = lease;
+        let _scope = JobScope::enter(Some(job_id.clone()));
+        trash_existing_output(&app_for_task, &settings, &output, on_conflict);
+        let outcome = remux_worker(&app_for_task, &job_id, cmd).map(|()| {
+            let verification = verify_output(&ffmpeg, &output, expected_duration, expect_audio);
+            report_verification(&app_for_task, &verification);
+            let bytes_written = fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+            update_job_record(&root, &job_id, |r| {
+                r.source_duration_secs = expected_duration;
+                r.bytes_written = bytes_written;
             });
-        } else {
-            let _ = app_for_task.emit("pipeline_done", PipelineDoneEvent {
+            verification
+        });
+        let done = match outcome {
+            Ok(verification) => PipelineDoneEvent {
+                ok: true,
+                message: verification.annotate(format!("Done: {}", output.to_string_lossy())),
+                frames_dir: String::new(),
+                frame_pattern: String::new(),
+                verification: Some(verification),
+                output_tail: None,
+                error: None,
+            },
+            Err(message) => PipelineDoneEvent {
                 ok: false,
-                message: "Re-encode failed".into(),
-                frames_dir: frames_dir_for_task.clone(),
-                frame_pattern: frame_pattern_for_task.clone(),
-            });
-        }
+                error: classify_process_error(&message),
+                message,
+                frames_dir: String::new(),
+                frame_pattern: String::new(),
+                verification: None,
+                output_tail: app_for_task.state::<AppState>().take_failure_tail(&job_id),
+            },
+        };
+        record_job_finish(&root, &job_id, done.ok, &done.message);
+        emit_pipeline(&app_for_task, "pipeline_done", done);
     });
 
-    Ok(ExtractFramesResult {
-        ok: true,
-        frames_dir: frames_dir_str,
-        frame_pattern: frame_pattern_str,
-        output: output.to_string_lossy().to_string(),
-    })
+    Ok(result)
+}
+
+fn remux_worker(app: &AppHandle, job_id: &str, mut cmd: Command) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.set_job_stage(job_id, JobStage::Encode);
+    emit_stage(app, StageId::Remux, message("stage.remux", &[]));
+    log_stage_command(app, "remux", &mut cmd);
+    let mut ffmpeg = spawn_stage_process(app, job_id, "FFmpeg", &mut cmd)?;
+    ffmpeg.relay_to_log(app, Pipe::Stderr);
+    let status = ffmpeg.wait(app)?;
+    check_stage_cancelled(app, job_id, JobStage::Encode)?;
+    ffmpeg.check(app, "Remux failed", status)?;
+    emit_pipeline(app, "pipeline_progress", 100.0_f64);
+    Ok(())
 }
 
 
 #[tauri::command]
-fn validate_tools(app: AppHandle) -> Result<ValidateToolsResult, String> {
+fn validate_tools(app: AppHandle, state: State<'_, AppState>) -> Result<ValidateToolsResult, String> {
     let root = app_root(&app)?;
     ensure_dirs(&root)?;
 
-    let (ffmpeg_path, rife_path, rife_models) = find_installed_tool_paths(&root);
+    // Validation is the user's "re-check" action, so rescan rather than trust the cache.
+    let tools = state.refresh_tools(&root);
+    let (ffmpeg_path, rife_path, rife_models) = tools.paths();
+
+    let ffmpeg = validate_ffmpeg_install(ffmpeg_path);
+    let rife = validate_rife_install(rife_path, rife_models);
+    let vspipe = validate_optional_tool(tools.vspipe, "--version");
+    let av1an = validate_optional_tool(tools.av1an, "--version");
+
+    Ok(ValidateToolsResult { ffmpeg, rife, vspipe, av1an })
+}
+
+/// Check an optional tool runs, if it is installed at all.
+fn validate_optional_tool(path: Option<PathBuf>, version_arg: &str) -> Option<ToolValidation> {
+    let p = path?;
+    let mut cmd = tool_command(&p);
+    cmd.arg(version_arg);
+    let mut tv = run_and_capture(cmd);
+    tv.path = Some(p.to_string_lossy().to_string());
+    Some(tv)
+}
 
-    // ffmpeg
-    let ffmpeg = if let Some(p) = ffmpeg_path {
-        let mut cmd = Command::new(&p);
+fn validate_ffmpeg_install(ffmpeg_path: Option<PathBuf>) -> ToolValidation {
+    if let Some(p) = ffmpeg_path {
+        let mut cmd = tool_command(&p);
         cmd.arg("-version");
         let mut tv = run_and_capture(cmd);
         tv.path = Some(p.to_string_lossy().to_string());
@@ -1279,12 +13621,13 @@ fn validate_tools(app: AppHandle) -> Result<ValidateToolsResult, String> {
             path: None,
             output: "ffmpeg not installed (no binary found in app-managed bin/ffmpeg)".into(),
         }
-    };
+    }
+}
 
-    // rife
-    let rife = if let Some(p) = rife_path {
+fn validate_rife_install(rife_path: Option<PathBuf>, rife_models: Option<PathBuf>) -> ToolValidation {
+    if let Some(p) = rife_path {
         // This RIFE build expects model folders like 'rife-v2.3' next to the binary and uses '-h' for help.
-        let mut cmd = Command::new(&p);
+        let mut cmd = tool_command(&p);
         cmd.arg("-h");
         let mut tv = run_and_capture(cmd);
 
@@ -1315,15 +13658,541 @@ fn validate_tools(app: AppHandle) -> Result<ValidateToolsResult, String> {
             path: None,
             output: "RIFE not installed (no 'rife*' binary found in app-managed bin/rife)".into(),
         }
+    }
+}
+
+// -------------------- Quality check --------------------
+
+/// Width of one contact-sheet tile; height follows the video's aspect ratio.
+const CONTACT_SHEET_TILE_WIDTH: u32 = 320;
+
+/// Tile `cols` x `rows` evenly spaced frames of a video into one JPEG under
+/// `cache/contact_sheets`. With `job_id`, the sheet becomes that job's history thumbnail.
+#[tauri::command]
+fn make_contact_sheet(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    video: String,
+    cols: u32,
+    rows: u32,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    if !(1..=12).contains(&cols) || !(1..=12).contains(&rows) {
+        return Err("Contact sheet needs 1-12 columns and rows".into());
+    }
+    if let Some(id) = job_id.as_deref() {
+        validate_job_id(id)?;
+    }
+    let root = app_root(&app)?;
+    ensure_dirs(&root)?;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    let input = PathBuf::from(video.trim());
+    if !input.is_file() {
+        return Err("Video does not exist".into());
+    }
+    let duration = probe_duration_and_fps(&ffmpeg, &input)
+        .map(|(d, _)| d)
+        .filter(|d| *d > 0.0)
+        .ok_or("Could not read the video duration")?;
+
+    let dir = root.join("cache").join("contact_sheets");
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {e}", dir.to_string_lossy()))?;
+    let name = match job_id.as_deref() {
+        Some(id) => format!("{id}.jpg"),
+        None => format!(
+            "{}-{cols}x{rows}-{}.jpg",
+            input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default(),
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ),
+    };
+    let out = dir.join(name);
+    let _in_use = CacheInUse::hold(&out);
+
+    // One fast seek per tile, taken from the middle of each slice so the first tile isn't
+    // a fade-in frame. Every seek is a decoder of its own, so tiles are taken a core's worth
+    // at a time and tiled in a last run.
+    let count = cols * rows;
+    let tiles = out.with_extension("tiles");
+    clear_dir(&tiles)?;
+    fs::create_dir_all(&tiles).map_err(|e| format!("Failed to create {}: {e}", tiles.to_string_lossy()))?;
+    let run = |cmd: &mut Command, frames: u32| -> Result<(), String> {
+        let output = run_tool(log_command("contact_sheet", cmd), analysis_timeout_secs(frames as f64 * 10.0))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let err = String::from_utf8_lossy(&output.stderr);
+        Err(format!("Contact sheet failed: {}", err.lines().last().unwrap_or("").trim()))
+    };
+    let cores = std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(4);
+    let mut first = 0;
+    let result = loop {
+        if first == count {
+            let mut cmd = tool_command(&ffmpeg);
+            cmd.arg("-hide_banner").arg("-y").arg("-v").arg("error")
+                .arg("-framerate").arg("1")
+                .arg("-i").arg(tiles.join("%04d.png"))
+                .arg("-vf").arg(format!("tile={cols}x{rows}:padding=4:margin=4"))
+                .arg("-frames:v").arg("1")
+                .arg("-q:v").arg("3")
+                .arg(&out);
+            break run(&mut cmd, count);
+        }
+        let batch = first..(first + cores).min(count);
+        let mut cmd = tool_command(&ffmpeg);
+        cmd.arg("-hide_banner").arg("-y").arg("-v").arg("error");
+        for k in batch.clone() {
+            let t = duration * (k as f64 + 0.5) / count as f64;
+            cmd.arg("-ss").arg(format!("{t:.3}")).arg("-i").arg(&input);
+        }
+        for (i, k) in batch.clone().enumerate() {
+            cmd.arg("-map").arg(format!("{i}:v:0"))
+                .arg("-vf").arg(format!("scale={CONTACT_SHEET_TILE_WIDTH}:-2,setsar=1"))
+                .arg("-frames:v").arg("1")
+                .arg(tiles.join(format!("{:04}.png", k + 1)));
+        }
+        if let Err(e) = run(&mut cmd, batch.end - batch.start) {
+            break Err(e);
+        }
+        first = batch.end;
+    };
+    let _ = fs::remove_dir_all(&tiles);
+    result?;
+    if !out.is_file() {
+        return Err("Contact sheet failed: ffmpeg wrote no image".into());
+    }
+
+    let path = out.to_string_lossy().to_string();
+    if let Some(id) = job_id.as_deref() {
+        update_job_record(&root, id, |r| r.thumbnail = Some(path.clone()));
+    }
+    Ok(path)
+}
+
+/// One analysed interpolated frame.
+#[derive(Clone, Debug, serde::Serialize)]
+struct ArtifactSample {
+    frame: String,
+    /// Heatmap of |frame - previous| and |frame - next| side by side, amplified 4x.
+    image: String,
+    /// Mean absolute luma difference (0-255) to the previous and next output frame.
+    diff_prev: f64,
+    diff_next: f64,
+    /// 0 when the frame sits halfway between its neighbours, 1 when it duplicates one of
+    /// them. High values point at wobble or a frame that snapped to one side.
+    asymmetry: f64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct ArtifactReport {
+    job_id: String,
+    samples: Vec<ArtifactSample>,
+    mean_diff: f64,
+    mean_asymmetry: f64,
+    /// Sample with the highest asymmetry.
+    worst_frame: Option<String>,
+}
+
+/// Diff heatmaps and statistics for `sample_count` evenly spaced interpolated frames of a
+/// job, to spot ghosting and wobble. Needs the job's frames_out folder (keep intermediates,
+/// or a failed/cancelled job). Images go to `temp/jobs/<job>.artifacts/`.
+#[tauri::command]
+fn analyze_artifacts(app: AppHandle, job_id: String, sample_count: u32) -> Result<ArtifactReport, String> {
+    let root = app_root(&app)?;
+    let job = load_job_spec(&root, &job_id)?;
+    let outputs = sorted_files(&job.frames_out_dir);
+    // Odd positions are the synthesized frames; each needs a neighbour on both sides.
+    let synthesized: Vec<usize> = (1..outputs.len().saturating_sub(1)).step_by(2).collect();
+    if synthesized.is_empty() {
+        return Err("Interpolated frames are gone; keep intermediates to analyze a job".into());
+    }
+    let count = (sample_count.clamp(1, 50) as usize).min(synthesized.len());
+    let dir = job_spec_path(&root, &job_id).with_extension("artifacts");
+    clear_dir(&dir)?;
+
+    let mut samples = Vec::with_capacity(count);
+    for i in 0..count {
+        let j = synthesized[(i * synthesized.len() + synthesized.len() / 2) / count];
+        let (prev, frame, next) = (&outputs[j - 1], &outputs[j], &outputs[j + 1]);
+        let name = frame.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let image = dir.join(Path::new(&name).with_extension("jpg"));
+        let ok = run_tool(log_command("analyze", tool_command(&job.ffmpeg)
+            .arg("-hide_banner").arg("-y").arg("-v").arg("error")
+            .arg("-i").arg(prev)
+            .arg("-i").arg(frame)
+            .arg("-i").arg(next)
+            .arg("-filter_complex")
+            .arg("[0:v][1:v]blend=all_mode=difference,format=gray,lutyuv=y=val*4[a];\
+                  [2:v][1:v]blend=all_mode=difference,format=gray,lutyuv=y=val*4[b];\
+                  [a][b]hstack,pseudocolor=p=heat")
+            .arg("-frames:v").arg("1")
+            .arg(&image)), PROBE_TIMEOUT_SECS)
+            .is_ok_and(|o| o.status.success());
+        if !ok {
+            return Err(format!("Could not build a diff image for {name}"));
+        }
+
+        let (Some(a), Some(b), Some(c)) = (
+            gray_thumbnail(&job.ffmpeg, prev, "analyze"),
+            gray_thumbnail(&job.ffmpeg, frame, "analyze"),
+            gray_thumbnail(&job.ffmpeg, next, "analyze"),
+        ) else {
+            return Err(format!("Could not decode the frames around {name}"));
+        };
+        let diff_prev = mean_abs_diff(&b, &a);
+        let diff_next = mean_abs_diff(&b, &c);
+        let total = diff_prev + diff_next;
+        let asymmetry = if total > 0.5 { (diff_prev - diff_next).abs() / total } else { 0.0 };
+        samples.push(ArtifactSample {
+            frame: name,
+            image: image.to_string_lossy().to_string(),
+            diff_prev,
+            diff_next,
+            asymmetry,
+        });
+    }
+
+    let n = samples.len() as f64;
+    let mean_diff = samples.iter().map(|s| (s.diff_prev + s.diff_next) / 2.0).sum::<f64>() / n;
+    let mean_asymmetry = samples.iter().map(|s| s.asymmetry).sum::<f64>() / n;
+    let worst_frame = samples
+        .iter()
+        .max_by(|a, b| a.asymmetry.total_cmp(&b.asymmetry))
+        .map(|s| s.frame.clone());
+    Ok(ArtifactReport { job_id, samples, mean_diff, mean_asymmetry, worst_frame })
+}
+
+fn mean_abs_diff(a: &[u8], b: &[u8]) -> f64 {
+    let n = a.len().min(b.len()).max(1) as f64;
+    a.iter().zip(b).map(|(&x, &y)| (x as f64 - y as f64).abs()).sum::<f64>() / n
+}
+
+/// Points in the waveform returned by `analyze_audio`.
+const WAVEFORM_POINTS: usize = 1000;
+/// Sample rate the waveform is computed at; plenty for a 1000-point overview.
+const WAVEFORM_RATE: u32 = 2000;
+const SILENCE_THRESHOLD_DB: f64 = -50.0;
+const SILENCE_MIN_SECS: f64 = 0.5;
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct SilenceRange {
+    start: f64,
+    end: f64,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+struct AudioAnalysis {
+    duration_secs: f64,
+    /// Overall levels of the first audio track in dBFS, from the full-rate signal.
+    peak_db: Option<f64>,
+    rms_db: Option<f64>,
+    /// Peak amplitude (0-1) of each of `WAVEFORM_POINTS` equal slices, for the timeline.
+    waveform: Vec<f32>,
+    /// Stretches quieter than `SILENCE_THRESHOLD_DB` for at least `SILENCE_MIN_SECS`.
+    silences: Vec<SilenceRange>,
+}
+
+/// Levels, an overview waveform and silent stretches of a file's first audio track, in one
+/// decode: astats and silencedetect report on stderr while a mono low-rate copy streams to
+/// stdout for the waveform.
+#[tauri::command]
+fn analyze_audio(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<AudioAnalysis, String> {
+    let root = app_root(&app)?;
+    let ffmpeg = preferred_ffmpeg_path()
+        .or(state.tools(&root).ffmpeg)
+        .ok_or("ffmpeg not installed (install ffmpeg first)")?;
+    let input = PathBuf::from(path.trim());
+    if !input.is_file() {
+        return Err("File does not exist".into());
+    }
+    if probe_audio_stream(&ffmpeg, &input).is_none() {
+        return Err("File has no audio track".into());
+    }
+    let duration = probe_duration_and_fps(&ffmpeg, &input).map(|(d, _)| d).unwrap_or(0.0);
+
+    let mut cmd = tool_command(&ffmpeg);
+    cmd.arg("-hide_banner").arg("-nostdin").arg("-v").arg("info")
+        .arg("-i").arg(&input)
+        .arg("-map").arg("0:a:0")
+        .arg("-af").arg(format!(
+            "silencedetect=n={SILENCE_THRESHOLD_DB}dB:d={SILENCE_MIN_SECS},\
+             astats=measure_perchannel=none:measure_overall=Peak_level+RMS_level,\
+             aformat=sample_fmts=flt:channel_layouts=mono,aresample={WAVEFORM_RATE}"
+        ))
+        .arg("-f").arg("f32le").arg("-")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = log_command("analyze", &mut cmd)
+        .spawn()
+        .map_err(|e| format!("FFmpeg failed to start: {e}"))?;
+    let deadline = ToolDeadline::start(child.id(), PROBE_TIMEOUT_SECS + duration as u64);
+
+    let stderr_handle = child.stderr.take().map(|st| {
+        std::thread::spawn(move || {
+            std::io::BufReader::new(st).lines().map_while(Result::ok).collect::<Vec<String>>()
+        })
+    });
+
+    let expected = (duration * WAVEFORM_RATE as f64).max(1.0) as usize;
+    let per_point = expected.div_ceil(WAVEFORM_POINTS).max(1);
+    let mut waveform: Vec<f32> = Vec::with_capacity(WAVEFORM_POINTS + 1);
+    if let Some(mut stdout) = child.stdout.take() {
+        let (mut bucket_peak, mut in_bucket) = (0.0_f32, 0);
+        let mut buf = [0u8; 4096];
+        let mut carry: Vec<u8> = Vec::new();
+        loop {
+            let n = match stdout.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            carry.extend_from_slice(&buf[..n]);
+            let whole = carry.len() / 4 * 4;
+            for chunk in carry[..whole].chunks_exact(4) {
+                let v = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]).abs();
+                bucket_peak = bucket_peak.max(v.min(1.0));
+                in_bucket += 1;
+                if in_bucket == per_point {
+                    waveform.push(bucket_peak);
+                    bucket_peak = 0.0;
+                    in_bucket = 0;
+                }
+            }
+            carry.drain(..whole);
+        }
+        if in_bucket > 0 {
+            waveform.push(bucket_peak);
+        }
+    }
+    let status = child.wait().ok();
+    let lines = stderr_handle.and_then(|h| h.join().ok()).unwrap_or_default();
+    if deadline.finish() {
+        return Err("Audio analysis did not finish in time".into());
+    }
+    if !status.map(|s| s.success()).unwrap_or(false) {
+        return Err(failure_message("Audio analysis failed", status, &lines));
+    }
+
+    let db_after = |line: &str, key: &str| -> Option<f64> {
+        line.split_once(key).and_then(|(_, v)| v.trim().parse::<f64>().ok())
+    };
+    let mut peak_db = None;
+    let mut rms_db = None;
+    let mut silences = Vec::new();
+    let mut open_start: Option<f64> = None;
+    for line in &lines {
+        if let Some(v) = db_after(line, "Peak level dB:") {
+            peak_db = Some(v);
+        } else if let Some(v) = db_after(line, "RMS level dB:") {
+            rms_db = Some(v);
+        } else if let Some(v) = db_after(line, "silence_start:") {
+            open_start = Some(v.max(0.0));
+        } else if let Some((_, rest)) = line.split_once("silence_end:") {
+            let end = rest.split('|').next().and_then(|v| v.trim().parse::<f64>().ok());
+            if let (Some(start), Some(end)) = (open_start.take(), end) {
+                silences.push(SilenceRange { start, end });
+            }
+        }
+    }
+    // Silence running into the end of the file has no silence_end line.
+    if let Some(start) = open_start {
+        silences.push(SilenceRange { start, end: duration.max(start) });
+    }
+
+    Ok(AudioAnalysis {
+        duration_secs: duration,
+        // astats reports -inf for digital silence.
+        peak_db: peak_db.filter(|v: &f64| v.is_finite()),
+        rms_db: rms_db.filter(|v: &f64| v.is_finite()),
+        waveform,
+        silences,
+    })
+}
+
+// -------------------- Startup self-check --------------------
+
+/// Version of the on-disk app-data layout. Bump it and add a step to `migrate_data_layout`
+/// whenever files move.
+///
+/// 1: RIFE models live inside each `bin/rife/<version>` folder.
+/// 2: RIFE models live in the shared `models/` folder.
+const DATA_LAYOUT_VERSION: u32 = 2;
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct DataLayout {
+    version: u32,
+}
+
+fn layout_path(root: &Path) -> PathBuf {
+    root.join("layout.json")
+}
+
+/// Existing installs without a layout file predate versioning and are treated as layout 1.
+fn read_layout_version(root: &Path) -> u32 {
+    fs::read_to_string(layout_path(root))
+        .ok()
+        .and_then(|t| serde_json::from_str::<DataLayout>(&t).ok())
+        .map(|l| l.version)
+        .unwrap_or(1)
+}
+
+fn write_layout_version(root: &Path, version: u32) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&DataLayout { version }).map_err(|e| e.to_string())?;
+    fs::write(layout_path(root), json).map_err(|e| format!("Failed to write layout version: {e}"))
+}
+
+/// Move model folders (`models/`, `rife-*`) out of the RIFE version folders into the shared
+/// `models/` folder. Folders whose name is already taken there are left in place.
+fn migrate_models_out_of_rife_bin(root: &Path, log: &mut Vec<String>) -> Result<(), String> {
+    let models_root = root.join("models");
+    let Ok(versions) = fs::read_dir(root.join("bin/rife")) else {
+        return Ok(());
+    };
+    for v in versions.flatten() {
+        let version_dir = v.path();
+        if !version_dir.is_dir() {
+            continue;
+        }
+        for e in fs::read_dir(&version_dir).map_err(|e| e.to_string())?.flatten() {
+            let p = e.path();
+            let name = e.file_name().to_string_lossy().to_string();
+            if !p.is_dir() || !(name == "models" || name.starts_with("rife-")) {
+                continue;
+            }
+            let dest = models_root.join(&name);
+            if dest.exists() {
+                log.push(format!(
+                    "Kept {} in place: {} already exists",
+                    p.to_string_lossy(),
+                    dest.to_string_lossy()
+                ));
+                continue;
+            }
+            fs::rename(&p, &dest).map_err(|e| {
+                format!("Failed to move {} to {}: {e}", p.to_string_lossy(), dest.to_string_lossy())
+            })?;
+            log.push(format!("Moved {} to {}", p.to_string_lossy(), dest.to_string_lossy()));
+        }
+    }
+    Ok(())
+}
+
+/// Bring an older app-data layout up to `DATA_LAYOUT_VERSION`, one step at a time. The layout
+/// version is saved after each step so an interrupted migration resumes where it stopped.
+fn migrate_data_layout(root: &Path, log: &mut Vec<String>) -> Result<u32, String> {
+    let mut version = read_layout_version(root);
+    if version > DATA_LAYOUT_VERSION {
+        return Err(format!(
+            "App data layout version {version} is newer than this build supports ({DATA_LAYOUT_VERSION}); update the app"
+        ));
+    }
+    while version < DATA_LAYOUT_VERSION {
+        if version == 1 {
+            migrate_models_out_of_rife_bin(root, log)?;
+        }
+        version += 1;
+        write_layout_version(root, version)?;
+        log.push(format!("Migrated app data layout to version {version}"));
+    }
+    Ok(version)
+}
+
+#[derive(Clone, serde::Serialize)]
+struct StartupReport {
+    layout_version: u32,
+    migrations: Vec<String>,
+    ffmpeg: Option<ToolValidation>,
+    rife: Option<ToolValidation>,
+    issues: Vec<String>,
+    needs_attention: bool,
+}
+
+/// Latest startup report, kept so the UI can fetch it if it missed the event.
+static STARTUP_REPORT: std::sync::Mutex<Option<StartupReport>> = std::sync::Mutex::new(None);
+
+fn run_startup_check(root: &Path) -> StartupReport {
+    let mut migrations = Vec::new();
+    let mut issues = Vec::new();
+
+    if let Err(e) = ensure_dirs(root) {
+        issues.push(e);
+    }
+    let layout_version = match migrate_data_layout(root, &mut migrations) {
+        Ok(v) => v,
+        Err(e) => {
+            issues.push(format!("Data migration failed: {e}"));
+            read_layout_version(root)
+        }
     };
 
+    let (ffmpeg_path, rife_path, rife_models) = find_installed_tool_paths(root);
+    let ffmpeg = validate_ffmpeg_install(ffmpeg_path);
+    let rife = validate_rife_install(rife_path, rife_models);
+    if !ffmpeg.ok {
+        issues.push(format!("ffmpeg: {}", ffmpeg.output.lines().next().unwrap_or("not usable")));
+    }
+    if !rife.ok {
+        issues.push(format!("RIFE: {}", rife.output.lines().next().unwrap_or("not usable")));
+    }
+
+    StartupReport {
+        layout_version,
+        migrations,
+        needs_attention: !issues.is_empty(),
+        ffmpeg: Some(ffmpeg),
+        rife: Some(rife),
+        issues,
+    }
+}
 
-    Ok(ValidateToolsResult { ffmpeg, rife })
+#[tauri::command]
+fn get_startup_report() -> Option<StartupReport> {
+    STARTUP_REPORT.lock().unwrap_or_else(|e| e.into_inner()).clone()
 }
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .manage(AppState::default())
+        .setup(|app| {
+            let app = app.handle().clone();
+            if let Ok(root) = app_root(&app) {
+                let _ = JOB_LOG_DIR.set(root.join("logs"));
+                let _ = PROBE_CACHE_DIR.set(root.join("cache").join("probe"));
+                apply_event_settings(&app.state::<AppState>().settings(&root));
+            }
+            let scheduler_app = app.clone();
+            std::thread::spawn(move || run_start_scheduler(scheduler_app));
+            // Tool checks spawn processes, so keep them off the setup path.
+            std::thread::spawn(move || {
+                let report = match app_root(&app) {
+                    Ok(root) => {
+                        let mut report = run_startup_check(&root);
+                        let env = env_overrides();
+                        if !env.problems.is_empty() {
+                            report.issues.extend(env.problems.iter().map(|p| format!("{p}; ignored")));
+                            report.needs_attention = true;
+                        }
+                        enforce_cache_limit(&root, &load_settings(&root));
+                        // Migration may have moved models, so resolve tools again.
+                        app.state::<AppState>().refresh_tools(&root);
+                        report
+                    }
+                    Err(e) => StartupReport {
+                        layout_version: 0,
+                        migrations: Vec::new(),
+                        ffmpeg: None,
+                        rife: None,
+                        issues: vec![e],
+                        needs_attention: true,
+                    },
+                };
+                *STARTUP_REPORT.lock().unwrap_or_else(|e| e.into_inner()) = Some(report.clone());
+                let _ = app.emit("startup_report", report);
+            });
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             check_environment,
             get_app_paths,
@@ -1333,10 +14202,556 @@ fn main() {
             extract_frames,
             smooth_video,
             reencode_only,
-            get_max_threads_string,
+            get_max_rife_threads,
             get_default_rife_model_dir,
-            run_rife_pipeline
+            run_rife_pipeline,
+            get_frame_naming,
+            get_settings,
+            update_settings,
+            list_jobs,
+            set_job_metadata,
+            get_stats,
+            list_presets,
+            save_preset,
+            delete_preset,
+            repair_tool,
+            get_startup_report,
+            list_running_jobs,
+            cancel_stage,
+            retry_stage,
+            list_job_artifacts,
+            copy_command,
+            remux,
+            make_contact_sheet,
+            analyze_artifacts,
+            analyze_audio,
+            get_scene_list,
+            invalidate_probe_cache,
+            probe_media_batch,
+            plan_fps_conformance,
+            get_ffmpeg_capabilities,
+            get_rife_runtimes,
+            inspect_model,
+            import_model,
+            list_models,
+            save_model_profile,
+            check_input_decode,
+            check_for_tool_updates,
+            install_tool_update,
+            export_bundle,
+            import_bundle,
+            verify_installation,
+            get_cache_breakdown,
+            fix_av_offset,
+            suggest_output_path,
+            get_recent_inputs,
+            pin_favorite,
+            list_profiles,
+            plugins::list_plugins,
+            gpu_sharing_stats,
+            get_quality_plan,
+            library::list_library,
+            library::delete_library_item,
+            switch_profile,
+            export_config,
+            import_config,
+            messages::get_message_catalog,
+            check_temp_storage,
+            builtin_rife_available,
+            list_scheduled_jobs
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rife-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_frames(dir: &Path, count: usize) {
+        for n in 1..=count {
+            fs::write(dir.join(format!("{n:08}.png")), n.to_string()).unwrap();
+        }
+    }
+
+    // -------------------- Error catalog --------------------
+
+    #[test]
+    fn error_codes_are_unique() {
+        let mut codes: Vec<&str> = ERROR_PATTERNS.iter().map(|e| e.1).collect();
+        codes.sort_unstable();
+        let count = codes.len();
+        codes.dedup();
+        assert_eq!(codes.len(), count);
+        for (needles, code, ..) in ERROR_PATTERNS {
+            assert!(needles.iter().all(|n| *n == n.to_lowercase()), "{code}");
+        }
+    }
+
+    #[test]
+    fn errors_classify_by_line() {
+        let err = classify_process_error("frame 10\nvkAllocateMemory failed: VK_ERROR_OUT_OF_DEVICE_MEMORY").unwrap();
+        assert_eq!(err.code, "gpu_out_of_memory");
+        assert_eq!(err.detail, "vkAllocateMemory failed: VK_ERROR_OUT_OF_DEVICE_MEMORY");
+        assert_eq!(classify_process_error("Invalid job id: \"../x\"").unwrap().code, "invalid_name");
+        assert!(classify_process_error("all good").is_none());
+        assert_eq!(catalog_error("decode_failed", String::new()).title, "The input's video could not be decoded");
+    }
+
+    #[test]
+    fn paused_reason_is_an_id() {
+        let reason = message("power.on_battery", &[("percent", "15".into()), ("limit", "20".into())]);
+        let paused = paused_message(&reason);
+        assert_eq!(paused.params["reason"], "power.on_battery");
+        assert_eq!(paused.params["percent"], "15");
+        assert_eq!(paused.text, format!("Paused: {}", reason.text));
+    }
+
+    // -------------------- Caches --------------------
+
+    #[test]
+    fn cache_keys_are_fixed() {
+        // Persisted names must not change between builds.
+        assert_eq!(cache_key([b"abc".as_slice()]), cache_key([b"abc".as_slice()]));
+        assert_eq!(cache_key([b"".as_slice()]), "6e340b9cffb37a98");
+        assert_ne!(cache_key([b"a".as_slice(), b"bc".as_slice()]), cache_key([b"ab".as_slice(), b"c".as_slice()]));
+    }
+
+    // -------------------- Jobs --------------------
+
+    #[test]
+    fn job_ids_are_plain_names() {
+        assert!(validate_job_id(&make_job_id()).is_ok());
+        assert!(validate_job_id("job_1-a").is_ok());
+        for bad in ["", "..", "../x", "a/b", "a\\b", "job.json", "C:x", "a b"] {
+            assert!(validate_job_id(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_tool_stops_at_its_timeout() {
+        let started = std::time::Instant::now();
+        let mut cmd = tool_command("sh");
+        cmd.arg("-c").arg("sleep 30 & sleep 30");
+        new_process_group(&mut cmd);
+        let err = run_tool(&mut cmd, 1).unwrap_err();
+        assert!(err.contains("within 1s"), "{err}");
+        assert!(started.elapsed().as_secs() < 10);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn killing_a_group_leader_stops_its_members() {
+        let mut cmd = tool_command("sleep");
+        new_process_group(cmd.arg("30"));
+        let mut leader = cmd.spawn().unwrap();
+        let mut cmd = tool_command("sleep");
+        join_process_group(cmd.arg("30"), leader.id());
+        let mut member = cmd.spawn().unwrap();
+        signal_process(leader.id(), "-KILL");
+        assert!(!leader.wait().unwrap().success());
+        assert!(!member.wait().unwrap().success());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tool_deadline_kills_a_streamed_tool() {
+        let mut child = tool_command("sleep").arg("30").spawn().unwrap();
+        let deadline = ToolDeadline::start(child.id(), 1);
+        let status = child.wait().unwrap();
+        assert!(deadline.finish() && !status.success());
+
+        let mut child = tool_command("true").spawn().unwrap();
+        let deadline = ToolDeadline::start(child.id(), 30);
+        assert!(child.wait().unwrap().success() && !deadline.finish());
+    }
+
+    #[test]
+    fn schedule_round_trips() {
+        let root = scratch_dir("schedule");
+        let held = lock_schedule();
+        update_schedule(&held, &root, |s| s.push(ScheduledStart { job_id: "job_a".into(), not_before: 5 })).unwrap();
+        update_schedule(&held, &root, |s| s.push(ScheduledStart { job_id: "job_b".into(), not_before: 9 })).unwrap();
+        let due = take_due_starts(&held, &root, 6);
+        assert_eq!(due.iter().map(|e| e.job_id.as_str()).collect::<Vec<_>>(), ["job_a"]);
+        assert_eq!(load_schedule(&root).len(), 1);
+        assert!(take_due_starts(&held, &root, 6).is_empty());
+    }
+
+    #[test]
+    fn existing_output_fails_with_a_code() {
+        let dir = scratch_dir("conflict");
+        let out = dir.join("clip.mp4");
+        fs::write(&out, b"x").unwrap();
+        assert_eq!(resolve_output_conflict(&out, OnConflict::Fail).unwrap_err().code, "output_exists");
+        assert_eq!(resolve_output_conflict(&out, OnConflict::Rename).unwrap(), dir.join("clip (2).mp4"));
+        assert_eq!(resolve_output_conflict(&dir.join("new.mp4"), OnConflict::Fail).unwrap(), dir.join("new.mp4"));
+    }
+
+    #[test]
+    fn rife_frame_progress_is_a_percentage() {
+        assert_eq!(frames_percent(50, 200), 25.0);
+        assert_eq!(frames_percent(200, 200), 99.0);
+        assert_eq!(frames_percent(3, 0), 99.0);
+    }
+
+    // -------------------- Library --------------------
+
+    #[test]
+    fn library_items_get_their_own_folder() {
+        let root = scratch_dir("library");
+        let settings = AppSettings { library_dir: Some(root.join("lib").to_string_lossy().to_string()), ..AppSettings::default() };
+        let suggested = root.join("lib").join("2026").join("10").join("clip").join("clip.mp4");
+        let first = claim_library_output(&root, &settings, &suggested).unwrap();
+        let second = claim_library_output(&root, &settings, &suggested).unwrap();
+        assert_eq!(first, suggested);
+        assert_eq!(second, root.join("lib").join("2026").join("10").join("clip (2)").join("clip.mp4"));
+        let third = claim_library_output(&root, &settings, &second).unwrap();
+        assert!(third.ends_with("clip (3)/clip.mp4"), "{}", third.display());
+        let loose = root.join("lib").join("2026").join("clip.mp4");
+        assert_eq!(claim_library_output(&root, &settings, &loose).unwrap(), loose);
+    }
+
+    // -------------------- Interpolation sanity --------------------
+
+    #[test]
+    fn luma_stats_flag_black_and_flat_frames() {
+        assert!(LumaStats::of(&[4; GRAY_THUMBNAIL_BYTES]).looks_blank());
+        assert!(LumaStats::of(&[128; GRAY_THUMBNAIL_BYTES]).looks_blank());
+        let picture: Vec<u8> = (0..GRAY_THUMBNAIL_BYTES).map(|i| (i % 256) as u8).collect();
+        assert!(!LumaStats::of(&picture).looks_blank());
+    }
+
+    #[test]
+    fn held_output_needs_every_interpolated_frame() {
+        let dir = scratch_dir("holds");
+        let holds = [FrameHold { first: 2, last: 5 }];
+        write_frames(&dir, 7);
+        assert!(expand_held_output(&dir, FrameFormat::Png, &holds, 6).unwrap_err().contains("frame 8"));
+        fs::write(dir.join("00000008.png"), "8").unwrap();
+        assert_eq!(expand_held_output(&dir, FrameFormat::Png, &holds, 6).unwrap(), 4);
+        let read = |n: usize| fs::read_to_string(dir.join(format!("{n:08}.png"))).unwrap();
+        assert_eq!((read(6), read(9), read(12)), ("4".to_string(), "5".to_string(), "8".to_string()));
+        let _ = fs::remove_dir_all(&dir);
+
+        // Sharded: the last kept frames move into a shard that doesn't exist yet.
+        let dir = scratch_dir("holds-sharded");
+        let shard = dir.join(shard_name(1));
+        fs::create_dir_all(&shard).unwrap();
+        write_frames(&shard, 8);
+        let count = SHARD_FRAMES / 2 + 10;
+        let holds = [FrameHold { first: 2, last: count - 1 }];
+        let copies = expand_held_output(&dir, FrameFormat::Png, &holds, count).unwrap();
+        assert_eq!(copies as usize, 2 * (count - 4));
+        let files = FrameFiles::new(&dir, FrameFormat::Png);
+        assert_eq!(fs::read_to_string(files.path(2 * count)).unwrap(), "8");
+        assert_eq!(fs::read_to_string(files.path(SHARD_FRAMES + 1)).unwrap(), "4");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    // -------------------- Tool installs --------------------
+
+    #[test]
+    fn replace_dir_swaps_in_a_complete_copy() {
+        let base = scratch_dir("replace");
+        let dest = base.join("tool").join("1.0");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("old"), "old").unwrap();
+        replace_dir(&base.join(".staging").join("tool-1.0"), &dest, |dir| {
+            fs::write(dir.join("new"), "new").map_err(|e| e.to_string())
+        })
+        .unwrap();
+        assert!(dest.join("new").is_file());
+        assert!(!dest.join("old").exists());
+        assert_eq!(fs::read_dir(base.join(".staging")).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn replace_dir_keeps_the_old_install_when_the_copy_fails() {
+        let base = scratch_dir("replace-fail");
+        let dest = base.join("tool").join("1.0");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("old"), "old").unwrap();
+        let result = replace_dir(&base.join(".staging").join("tool-1.0"), &dest, |dir| {
+            fs::write(dir.join("partial"), "x").unwrap();
+            Err("disk full".to_string())
+        });
+        assert!(result.is_err());
+        assert!(dest.join("old").is_file());
+        assert!(!dest.join("partial").exists());
+    }
+
+    // -------------------- Filter graph --------------------
+
+    #[test]
+    fn filter_chain_orders_by_rank() {
+        let vf = FilterChain::new()
+            .fps(60.0)
+            .scale(1280, -2)
+            .crop(1920, 800, 0, 140)
+            .deinterlace()
+            .to_arg()
+            .unwrap();
+        assert_eq!(vf, "bwdif=mode=send_frame,crop=1920:800:0:140,scale=1280:-2:flags=lanczos,fps=60.000000");
+    }
+
+    #[test]
+    fn filter_chain_replaces_a_repeated_filter() {
+        let vf = FilterChain::new().scale(640, 360).denoise(2.0).scale(1280, 720).to_arg().unwrap();
+        assert_eq!(vf, "scale=1280:720:flags=lanczos,hqdn3d=2.00:1.50:3.00:2.25");
+    }
+
+    #[test]
+    fn filter_chain_empty_has_no_arg() {
+        assert!(FilterChain::new().to_arg().is_none());
+        assert!(FilterOptions::default().extraction_chain().is_empty());
+        assert!(FilterOptions::default().encode_chain().is_empty());
+    }
+
+    #[test]
+    fn filter_chain_rotates_before_cropping() {
+        let vf = FilterChain::new().crop(100, 100, 0, 0).rotate(-90).to_arg().unwrap();
+        assert_eq!(vf, "transpose=cclock,crop=100:100:0:0");
+        assert_eq!(VideoFilter::Rotate(180).to_filter_string(), "hflip,vflip");
+        assert_eq!(VideoFilter::Rotate(450).to_filter_string(), "transpose=clock");
+        assert_eq!(VideoFilter::Rotate(0).to_filter_string(), "null");
+    }
+
+    #[test]
+    fn filter_chain_color_matrix_only_for_known_matrices() {
+        let tags = |m: &str, r: Option<&str>| ColorTags {
+            matrix: Some(m.into()),
+            range: r.map(Into::into),
+            ..Default::default()
+        };
+        assert_eq!(
+            FilterChain::new().color_matrix(&tags("bt2020nc", Some("pc"))).to_arg().as_deref(),
+            Some("scale=out_color_matrix=bt2020:out_range=pc")
+        );
+        assert_eq!(
+            FilterChain::new().color_matrix(&tags("bt709", None)).to_arg().as_deref(),
+            Some("scale=out_color_matrix=bt709:out_range=tv")
+        );
+        assert!(FilterChain::new().color_matrix(&tags("ictcp", None)).is_empty());
+    }
+
+    #[test]
+    fn filter_options_build_extraction_chain() {
+        let options = FilterOptions {
+            tonemap: true,
+            denoise: Some(0.0),
+            scale: Some(ScaleSize { w: -2, h: 720 }),
+            rotate: Some(90),
+            output_fps: Some(59.94),
+            ..Default::default()
+        };
+        assert_eq!(
+            options.extraction_chain().to_arg().unwrap(),
+            format!("transpose=clock,{},scale=-2:720:flags=lanczos", VideoFilter::Tonemap.to_filter_string())
+        );
+        assert_eq!(options.encode_chain().to_arg().as_deref(), Some("fps=59.940000"));
+    }
+
+    #[test]
+    fn filter_options_validate() {
+        let scale = |w, h| FilterOptions { scale: Some(ScaleSize { w, h }), ..Default::default() };
+        assert!(scale(-2, 720).validate().is_ok());
+        assert!(scale(-2, -2).validate().is_err());
+        assert!(scale(0, 720).validate().is_err());
+        assert!(scale(-3, 720).validate().is_err());
+        let crop = FilterOptions { crop: Some(CropRect { w: 0, h: 10, x: 0, y: 0 }), ..Default::default() };
+        assert!(crop.validate().is_err());
+        let fps = FilterOptions { output_fps: Some(0.0), ..Default::default() };
+        assert!(fps.validate().is_err());
+        let restore = FilterOptions { bars: BarHandling::CropAndRestore, ..scale(1280, 720) };
+        assert!(restore.validate().is_err());
+    }
+
+    #[test]
+    fn filter_paths_are_escaped_for_both_levels() {
+        assert_eq!(escape_filter_path(Path::new("C:\\luts\\a.cube")), "C\\\\:/luts/a.cube");
+        assert_eq!(escape_filter_path(Path::new("/x/it's [v1].cube")), "/x/it\\\\\\'s \\[v1\\].cube");
+    }
+
+    #[test]
+    fn showinfo_pts_time_parses() {
+        let line = "[Parsed_showinfo_1 @ 0x55] n:   3 pts:  3003 pts_time:0.1001 duration:1001";
+        assert_eq!(parse_showinfo_pts_time(line), Some(0.1001));
+        assert_eq!(parse_showinfo_pts_time("[Parsed_showinfo_1 @ 0x55] config in"), None);
+    }
+
+    // -------------------- Encode settings --------------------
+
+    #[test]
+    fn match_source_keeps_an_explicit_encoder() {
+        let src = VideoStreamInfo { codec: "hevc".into(), profile: Some("Main".into()), pix_fmt: "yuv420p".into(), bit_rate_kbps: None };
+        let picked = EncodeSettings { match_source: true, ..EncodeSettings::default() }.matched_to_source(&src);
+        assert_eq!(picked.encoder(), VideoEncoder::Libx265);
+        let explicit = EncodeSettings { encoder: Some(VideoEncoder::Libx264), match_source: true, ..EncodeSettings::default() };
+        assert_eq!(explicit.matched_to_source(&src).encoder(), VideoEncoder::Libx264);
+    }
+
+    #[test]
+    fn pix_fmt_layouts_parse() {
+        assert_eq!(pix_fmt_layout("yuv420p"), (420, 8));
+        assert_eq!(pix_fmt_layout("yuv422p10le"), (422, 10));
+        assert_eq!(pix_fmt_layout("yuv444p12le"), (444, 12));
+        assert_eq!(pix_fmt_layout("nv12"), (420, 8));
+        assert_eq!(pix_fmt_layout("p010le"), (420, 10));
+        assert_eq!(pix_fmt_layout("p216le"), (422, 16));
+        assert_eq!(pix_fmt_layout("yuyv422"), (422, 8));
+        assert_eq!(pix_fmt_layout("gbrp10le"), (444, 10));
+    }
+
+    #[test]
+    fn pix_fmt_keeps_source_chroma_and_depth() {
+        let src = VideoStreamInfo { codec: "h264".into(), profile: Some("High 4:2:2".into()), pix_fmt: "yuv422p10le".into(), bit_rate_kbps: None };
+        let x264 = EncodeSettings { match_source: true, ..EncodeSettings::default() }.matched_to_source(&src);
+        assert_eq!(x264.profile.as_deref(), Some("high422"));
+        assert_eq!(x264.pix_fmt(), Some("yuv422p10le"));
+        // SVT-AV1 only takes 4:2:0, so the chroma drops but the depth stays.
+        let av1 = EncodeSettings { encoder: Some(VideoEncoder::Libsvtav1), match_source: true, ..EncodeSettings::default() };
+        assert_eq!(av1.matched_to_source(&src).pix_fmt(), Some("yuv420p10le"));
+        let x265 = EncodeSettings { encoder: Some(VideoEncoder::Libx265), profile: Some("main444-10".into()), ..EncodeSettings::default() };
+        assert_eq!(x265.pix_fmt(), Some("yuv444p10le"));
+        assert_eq!(EncodeSettings::default().pix_fmt(), None);
+    }
+
+    // -------------------- Model profiles --------------------
+
+    #[test]
+    fn old_model_profiles_keep_their_behaviour() {
+        let old: ModelProfile = serde_json::from_str(r#"{"dedup":true,"uhd":null}"#).unwrap();
+        assert_eq!(old, ModelProfile { dedup: Some(true), ..ModelProfile::default() });
+        assert!(old.supports_timestep("rife-v4.15"));
+        assert!(!old.supports_timestep("rife-v2.3"));
+        assert_eq!(old.chained_passes, None);
+        let forced = ModelProfile { timestep: Some(false), ..old };
+        assert!(!forced.supports_timestep("rife-v4.15"));
+    }
+
+    // -------------------- Resource limits --------------------
+
+    #[test]
+    fn frame_estimate_follows_the_output_frame_count() {
+        let px = 1920.0 * 1080.0;
+        let at_2x = frame_folder_bytes(px, 100.0, 2.0, false, false);
+        assert_eq!(at_2x, (300.0 * px * FRAME_PNG_BYTES_PER_PIXEL) as u64);
+        assert_eq!(frame_folder_bytes(px, 100.0, 4.0, false, false), (500.0 * px * FRAME_PNG_BYTES_PER_PIXEL) as u64);
+        assert_eq!(frame_folder_bytes(px, 100.0, 4.0, true, false), (700.0 * px * FRAME_PNG_BYTES_PER_PIXEL) as u64);
+        assert_eq!(frame_folder_bytes(px, 10.0, 2.0, false, false) * 10, at_2x);
+        assert!(frame_folder_bytes(px, 100.0, 2.0, false, true) > at_2x);
+    }
+
+    // -------------------- RIFE retries --------------------
+
+    #[test]
+    fn degrade_turns_tta_off_first() {
+        let threads = RifeThreads { load: 1, proc: 4, save: 1 };
+        let (first, change) = RifeDegrade::default().step_down(threads, false, true).unwrap();
+        assert!(first.tta_off && first.proc_cap.is_none(), "{change}");
+        let (second, _) = first.step_down(threads, false, false).unwrap();
+        assert_eq!(second.proc_cap, Some(2));
+        let single = RifeThreads { proc: 1, ..threads };
+        assert!(RifeDegrade { uhd: true, ..second }.step_down(single, true, false).is_none());
+    }
+
+    #[test]
+    fn nvidia_smi_row_follows_the_rife_gpu() {
+        let text = "0, NVIDIA GeForce RTX 3060, 11000\n1, NVIDIA GeForce RTX 4090, 22000\n";
+        assert_eq!(parse_nvidia_smi_memory(text, None).unwrap().name, "NVIDIA GeForce RTX 3060");
+        let second = parse_nvidia_smi_memory(text, Some(1)).unwrap();
+        assert_eq!((second.name.as_str(), second.free_bytes), ("NVIDIA GeForce RTX 4090", 22000 * 1024 * 1024));
+        assert_eq!(parse_nvidia_smi_memory(text, Some(7)).unwrap().name, "NVIDIA GeForce RTX 3060");
+        assert!(parse_nvidia_smi_memory("", Some(0)).is_none());
+    }
+
+    // -------------------- Chunk boundaries --------------------
+
+    #[test]
+    fn chunk_spans_are_contiguous() {
+        let spans = chunk_spans(&[(0.0, 1.01), (1.01, 2.02), (2.02, 3.0)], 30.0);
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].first, 1);
+        for pair in spans.windows(2) {
+            assert_eq!(pair[0].end(), pair[1].first);
+        }
+        assert_eq!(spans[2].end(), 91);
+    }
+
+    #[test]
+    fn chunk_spans_round_from_absolute_times() {
+        // 0.35 s at 10 fps is 3.5 frames; rounding each chunk's length would lose one.
+        let bounds: Vec<(f64, f64)> = (0..4).map(|i| (i as f64 * 0.35, (i + 1) as f64 * 0.35)).collect();
+        let spans = chunk_spans(&bounds, 10.0);
+        assert_eq!(spans.iter().map(|c| c.count).sum::<usize>(), 14);
+    }
+
+    #[test]
+    fn chunk_spans_drop_empty_chunks() {
+        let spans = chunk_spans(&[(0.0, 0.01), (0.01, 1.0)], 25.0);
+        assert_eq!(spans.len(), 1);
+        assert_eq!((spans[0].first, spans[0].count), (1, 25));
+    }
+
+    #[test]
+    fn place_chunk_keeps_the_overlap_out() {
+        let (staging, dir) = (scratch_dir("place-staging"), scratch_dir("place-dir"));
+        write_frames(&staging, 11);
+        let span = ChunkSpan { first: 21, count: 10, start_secs: 0.0, end_secs: 0.0 };
+        let placed = place_chunk(&staging, &dir, FrameFormat::Png, &span, false).unwrap();
+        assert_eq!((placed.frames, placed.padded), (10, 0));
+        assert_eq!(placed.overlap.as_deref(), Some(b"11".as_slice()));
+        assert_eq!(count_files_in_dir(&dir), 10);
+        assert_eq!(fs::read_to_string(dir.join("00000021.png")).unwrap(), "1");
+        assert!(!dir.join("00000031.png").exists());
+    }
+
+    #[test]
+    fn place_chunk_pads_a_short_chunk() {
+        let (staging, dir) = (scratch_dir("pad-staging"), scratch_dir("pad-dir"));
+        write_frames(&staging, 7);
+        let span = ChunkSpan { first: 1, count: 10, start_secs: 0.0, end_secs: 0.0 };
+        let placed = place_chunk(&staging, &dir, FrameFormat::Png, &span, false).unwrap();
+        assert_eq!((placed.frames, placed.padded), (10, 3));
+        assert!(placed.overlap.is_none());
+        assert_eq!(fs::read_to_string(dir.join("00000010.png")).unwrap(), "7");
+    }
+
+    #[test]
+    fn place_chunk_last_keeps_everything() {
+        let (staging, dir) = (scratch_dir("last-staging"), scratch_dir("last-dir"));
+        write_frames(&staging, 12);
+        let span = ChunkSpan { first: 1, count: 10, start_secs: 0.0, end_secs: 0.0 };
+        let placed = place_chunk(&staging, &dir, FrameFormat::Png, &span, true).unwrap();
+        assert_eq!((placed.frames, placed.padded), (12, 0));
+        assert_eq!(count_files_in_dir(&dir), 12);
+    }
+
+    #[test]
+    fn av1an_scenes_cover_every_frame() {
+        let scenes = av1an_scenes(1234, 48.0);
+        let list = scenes["scenes"].as_array().unwrap();
+        assert_eq!(list.first().unwrap()["start_frame"], 0);
+        assert_eq!(list.last().unwrap()["end_frame"], 1234);
+        for pair in list.windows(2) {
+            assert_eq!(pair[0]["end_frame"], pair[1]["start_frame"]);
+        }
+    }
+
+    #[test]
+    fn region_lands_on_frame_boundaries() {
+        let r = TimeRegion { start_secs: 1.013, end_secs: 2.49 }.on_frames(30.0);
+        assert!((r.start_secs * 30.0 - (r.start_secs * 30.0).round()).abs() < 1e-9);
+        assert!((r.end_secs * 30.0 - (r.end_secs * 30.0).round()).abs() < 1e-9);
+        assert_eq!((r.start_secs * 30.0).round() as usize, 30);
+    }
+}
@@ -0,0 +1,67 @@
+//! User-facing backend text with a stable id. Events carry the id and its parameters next to
+//! the English rendering, so the frontend can translate by id instead of matching strings.
+//! Classified errors are keyed by their `ActionableError` code as `error.<code>.title` and
+//! `error.<code>.suggestion`.
+
+use super::*;
+
+/// (id, English template); `{name}` stands for the parameter of that name.
+const MESSAGES: &[(&str, &str)] = &[
+    ("stage.extract", "Extracting frames…"),
+    ("stage.interpolate", "Interpolating (RIFE)…"),
+    ("stage.interpolate_encode", "Interpolating and encoding…"),
+    ("stage.encode", "Encoding video…"),
+    ("stage.encode_pass", "Encoding video, pass {pass}/2…"),
+    ("stage.waiting_for_slot", "Waiting for a free job slot…"),
+    ("stage.scheduled", "Scheduled: starts at {at}"),
+    ("stage.quiet_hours", "Waiting for quiet hours to end ({end_hour}:00)"),
+    ("stage.paused", "Paused: {reason}"),
+    ("stage.post_hook", "Running post-job hook…"),
+    ("stage.remux", "Remuxing…"),
+    ("stage.plugin", "Running plugin {name}…"),
+    ("power.on_battery", "On battery at {percent}% (limit {limit}%): waiting for AC power"),
+];
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct Message {
+    pub(crate) id: &'static str,
+    pub(crate) params: std::collections::BTreeMap<String, String>,
+    /// English rendering.
+    pub(crate) text: String,
+}
+
+pub(crate) fn message(id: &'static str, params: &[(&str, String)]) -> Message {
+    let template = MESSAGES.iter().find(|(k, _)| *k == id).map_or(id, |(_, t)| *t);
+    let mut text = template.to_string();
+    for (key, value) in params {
+        text = text.replace(&format!("{{{key}}}"), value);
+    }
+    Message {
+        id,
+        params: params.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+        text,
+    }
+}
+
+/// `stage.paused` for `reason`. The `reason` parameter is the reason's message id, with the
+/// reason's own parameters next to it, so the frontend translates it like any other message;
+/// only the English text has the reason spelled out.
+pub(crate) fn paused_message(reason: &Message) -> Message {
+    let mut params: Vec<(&str, String)> = reason.params.iter().map(|(k, v)| (k.as_str(), v.clone())).collect();
+    params.push(("reason", reason.text.clone()));
+    let mut paused = message("stage.paused", &params);
+    paused.params.insert("reason".into(), reason.id.to_string());
+    paused
+}
+
+/// Every message id with its English template, as the base for translations.
+#[tauri::command]
+pub(crate) fn get_message_catalog() -> std::collections::BTreeMap<String, String> {
+    let mut catalog: std::collections::BTreeMap<String, String> =
+        MESSAGES.iter().map(|(id, text)| (id.to_string(), text.to_string())).collect();
+    for (_, code, title, suggestion) in ERROR_PATTERNS {
+        catalog.insert(format!("error.{code}.title"), title.to_string());
+        catalog.insert(format!("error.{code}.suggestion"), suggestion.to_string());
+    }
+    catalog
+}
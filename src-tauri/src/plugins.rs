@@ -0,0 +1,241 @@
+//! Stage plugins: manifest-described tools that run on the frames before or after interpolation.
+
+use super::*;
+
+/// Where a plugin runs in a Smooth Video job.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PluginPosition {
+    /// On the extracted frames, so RIFE sees its result.
+    #[default]
+    BeforeInterpolation,
+    /// On the interpolated frames, before they are encoded.
+    AfterInterpolation,
+}
+
+/// What a plugin reads or writes: a folder of numbered frames, or a single lossless video
+/// (FFV1 in Matroska) that the app encodes from / extracts back into frames.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PluginIo {
+    #[default]
+    Frames,
+    Video,
+}
+
+/// `plugins/<name>/plugin.json`. `args` may use `{input}`, `{output}`, `{fps}`, `{frame_ext}`,
+/// `{plugin_dir}` and `{job_id}`; like hooks, no shell is involved. A plugin may print
+/// `progress=<0..1>` lines; everything else it prints goes to the job log.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub(crate) struct PluginManifest {
+    description: String,
+    /// Absolute, or relative to the plugin's folder.
+    program: String,
+    args: Vec<String>,
+    position: PluginPosition,
+    input: PluginIo,
+    output: PluginIo,
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub(crate) struct PluginInfo {
+    name: String,
+    manifest: Option<PluginManifest>,
+    /// Why the plugin can't be used.
+    problem: Option<String>,
+}
+
+#[derive(Clone, serde::Serialize)]
+pub(crate) struct PluginProgress {
+    pub(crate) name: String,
+    pub(crate) fraction: f64,
+}
+
+fn plugins_root(root: &Path) -> PathBuf {
+    root.join("plugins")
+}
+
+pub(crate) fn load_plugin(root: &Path, name: &str) -> Result<(PathBuf, PluginManifest), String> {
+    if !is_plain_name(name) {
+        return Err(format!("Invalid plugin name: {name}"));
+    }
+    let dir = plugins_root(root).join(name);
+    let text = fs::read_to_string(dir.join("plugin.json")).map_err(|_| format!("Plugin {name} has no plugin.json"))?;
+    let manifest: PluginManifest = serde_json::from_str(&text).map_err(|e| format!("Plugin {name}: bad plugin.json: {e}"))?;
+    if manifest.program.trim().is_empty() {
+        return Err(format!("Plugin {name}: plugin.json sets no program"));
+    }
+    Ok((dir, manifest))
+}
+
+/// Plugins installed under `plugins/`, with the reason any of them can't be loaded.
+#[tauri::command]
+pub(crate) fn list_plugins(app: AppHandle) -> Result<Vec<PluginInfo>, String> {
+    let root = app_root(&app)?;
+    let Ok(entries) = fs::read_dir(plugins_root(&root)) else { return Ok(Vec::new()) };
+    let mut plugins: Vec<PluginInfo> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            match load_plugin(&root, &name) {
+                Ok((_, manifest)) => PluginInfo { name, manifest: Some(manifest), problem: None },
+                Err(e) => PluginInfo { name, manifest: None, problem: Some(e) },
+            }
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(plugins)
+}
+
+/// Run the job's plugins for `position` over `frames_dir`, replacing its frames with each
+/// plugin's output in turn. The work folders sit next to `frames_dir` so the swap is a rename
+/// even when the frames are on a RAM disk, and the new frames keep the folder's layout.
+/// Plugins that read frames get one folder, so a sharded folder goes through them a shard at
+/// a time; plugins that read video get all shards as one stream. Returns whether a plugin
+/// changed the number of frames.
+pub(crate) fn run_stage_plugins(app: &AppHandle, job: &SmoothJob, position: PluginPosition, frames_dir: &Path, fps: f64) -> Result<bool, String> {
+    let stage = match position {
+        PluginPosition::BeforeInterpolation => JobStage::Extract,
+        PluginPosition::AfterInterpolation => JobStage::Interpolate,
+    };
+    let mut changed = false;
+    for name in &job.plugins {
+        let (plugin_dir, manifest) = load_plugin(&job.root, name)?;
+        if manifest.position != position {
+            continue;
+        }
+        emit_stage(app, StageId::Plugin, message("stage.plugin", &[("name", name.clone())]));
+        let sharded = is_sharded(frames_dir);
+        let work = frames_dir.with_file_name(format!("{}.{name}", job.job_id));
+        let _ = fs::remove_dir_all(&work);
+        let new_frames = work.join("frames");
+        fs::create_dir_all(&new_frames).map_err(|e| format!("Failed to create plugin folder: {e}"))?;
+        if sharded {
+            make_sharded(&new_frames)?;
+        }
+        let new_files = FrameFiles::new(&new_frames, job.frame_format);
+        let parts: Vec<PathBuf> = if sharded && manifest.input == PluginIo::Frames {
+            shard_dirs(frames_dir).into_iter().map(|(dir, _)| dir).collect()
+        } else {
+            vec![frames_dir.to_path_buf()]
+        };
+        let ext = job.frame_format.ext();
+        let started = std::time::Instant::now();
+        let mut next = 1;
+        for (k, part) in parts.iter().enumerate() {
+            let part_work = work.join(format!("part{k}"));
+            let out_frames = part_work.join("frames");
+            fs::create_dir_all(&out_frames).map_err(|e| format!("Failed to create plugin folder: {e}"))?;
+            let input = match manifest.input {
+                PluginIo::Frames => part.clone(),
+                PluginIo::Video => {
+                    let video = part_work.join("input.mkv");
+                    let mut cmd = job.ffmpeg_command();
+                    cmd.arg("-hide_banner").arg("-loglevel").arg("error").arg("-y");
+                    FramesInput::new(part, fps)?.push_args(&mut cmd);
+                    cmd.arg("-c:v").arg("ffv1").arg(&video);
+                    run_plugin_ffmpeg(app, job, stage, cmd, "encode frames for")?;
+                    video
+                }
+            };
+            let output = match manifest.output {
+                PluginIo::Frames => out_frames.clone(),
+                PluginIo::Video => part_work.join("output.mkv"),
+            };
+            let vars = [
+                ("{input}", input.to_string_lossy().to_string()),
+                ("{output}", output.to_string_lossy().to_string()),
+                ("{fps}", format!("{fps:.6}")),
+                ("{frame_ext}", ext.to_string()),
+                ("{plugin_dir}", plugin_dir.to_string_lossy().to_string()),
+                ("{job_id}", job.job_id.clone()),
+            ];
+            let args: Vec<String> = manifest
+                .args
+                .iter()
+                .map(|a| vars.iter().fold(a.clone(), |acc, (k, v)| acc.replace(k, v)))
+                .collect();
+            let program = plugin_dir.join(manifest.program.trim());
+            let mut cmd = tool_command(&program);
+            cmd.args(&args).current_dir(&plugin_dir).stdout(Stdio::piped()).stderr(Stdio::piped());
+            log_stage_command(app, "plugin", &mut cmd);
+            run_plugin_process(app, job, stage, name, cmd)?;
+
+            if manifest.output == PluginIo::Video {
+                // A whole sharded job's frames would otherwise land in one flat folder.
+                let mover = sharded.then(|| ShardMover::start(&out_frames, job.frame_format));
+                let mut cmd = job.ffmpeg_command();
+                cmd.arg("-hide_banner").arg("-loglevel").arg("error").arg("-y")
+                    .arg("-i").arg(&output)
+                    .arg("-start_number").arg("1")
+                    .arg(job.frame_format.pattern(&out_frames));
+                run_plugin_ffmpeg(app, job, stage, cmd, "extract frames from")?;
+                if let Some(mover) = mover {
+                    mover.finish()?;
+                }
+            }
+            next = append_frames(&out_frames, &new_files, next)?;
+        }
+        let count = next - 1;
+        if count == 0 {
+            return Err(format!("Plugin {name} wrote no frames"));
+        }
+        let before = count_files_in_dir(frames_dir);
+        changed |= count != before;
+        fs::remove_dir_all(frames_dir).map_err(|e| format!("Failed to replace frames with plugin {name}'s: {e}"))?;
+        fs::rename(&new_frames, frames_dir).map_err(|e| format!("Failed to replace frames with plugin {name}'s: {e}"))?;
+        let _ = fs::remove_dir_all(&work);
+        emit_log_limited(app, &format!(
+            "Plugin {name}: {before} → {count} frames in {:.1}s",
+            started.elapsed().as_secs_f64()
+        ));
+    }
+    Ok(changed)
+}
+
+/// Frame timestamps map one to one onto the extracted frames. Once a plugin stage changed the
+/// frame count they would retime the wrong frames, so they are dropped and the job encodes at
+/// a fixed rate.
+pub(crate) fn discard_frame_timestamps(app: &AppHandle, job: &SmoothJob, kept_pts: &mut Vec<f64>) {
+    if kept_pts.is_empty() {
+        return;
+    }
+    kept_pts.clear();
+    let _ = fs::remove_file(frame_timestamps_path(&job.root, &job.job_id));
+    emit_log_limited(app, "The frame count changed, so the source timestamps no longer fit: encoding at a fixed frame rate");
+}
+
+/// ffmpeg converting frames for or from a plugin, as the job's child like the plugin itself.
+fn run_plugin_ffmpeg(app: &AppHandle, job: &SmoothJob, stage: JobStage, mut cmd: Command, what: &str) -> Result<(), String> {
+    cmd.stdout(Stdio::null()).stderr(Stdio::piped());
+    log_stage_command(app, "plugin", &mut cmd);
+    let mut ffmpeg = spawn_stage_process(app, &job.job_id, "FFmpeg", &mut cmd)?;
+    ffmpeg.relay(Pipe::Stderr, |_| true);
+    let status = ffmpeg.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, stage)?;
+    ffmpeg.check(app, &format!("ffmpeg failed to {what} a plugin"), status)
+}
+
+/// Run a plugin as the job's child (so `cancel_stage` stops it), relaying its `progress=`
+/// lines as `plugin_progress` events and everything else to the log.
+fn run_plugin_process(app: &AppHandle, job: &SmoothJob, stage: JobStage, name: &str, mut cmd: Command) -> Result<(), String> {
+    let mut plugin = spawn_stage_process(app, &job.job_id, &format!("Plugin {name}"), &mut cmd)?;
+    for pipe in [Pipe::Stdout, Pipe::Stderr] {
+        let (app, name) = (app.clone(), name.to_string());
+        plugin.relay(pipe, move |line| match line.strip_prefix("progress=").and_then(|v| v.trim().parse::<f64>().ok()) {
+            Some(fraction) => {
+                let _ = app.emit("plugin_progress", PluginProgress { name: name.clone(), fraction: fraction.clamp(0.0, 1.0) });
+                false
+            }
+            None => {
+                emit_pipeline(&app, "pipeline_log", format!("[{name}] {line}"));
+                true
+            }
+        });
+    }
+    let status = plugin.wait(app)?;
+    check_stage_cancelled(app, &job.job_id, stage)?;
+    plugin.check(app, &format!("Plugin {name} failed"), status)
+}
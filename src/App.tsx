@@ -14,19 +14,36 @@ type ToolValidation = {
 type ValidateToolsResult = {
   ffmpeg: ToolValidation;
   rife: ToolValidation;
+  vspipe?: ToolValidation | null;
+  av1an?: ToolValidation | null;
 };
 
 type ExtractFramesResult = {
   ok: boolean;
+  job_id: string;
   frames_dir: string;
   frame_pattern: string;
   output: string;
+  skipped?: string | null;
 };
 
 function isTauriRuntime(): boolean {
   return typeof window !== "undefined" && Boolean((window as any).__TAURI__);
 }
 
+// Commands that report a typed error reject with `{ code, message }`; the rest with a string.
+function errorMessage(e: any): string {
+  return typeof e?.message === "string" ? e.message : String(e);
+}
+
+function formatEta(secs: number): string {
+  const total = Math.max(0, Math.round(secs));
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = total % 60;
+  return h > 0 ? `${h}h ${m}m` : m > 0 ? `${m}m ${s}s` : `${s}s`;
+}
+
 
 const darkTheme: React.CSSProperties = {
   background: "#1e1e1e",
@@ -67,6 +84,7 @@ export default function App() {
   const [error, setError] = useState("");
 
   const [inputVideo, setInputVideo] = useState("");
+  const [recentInputs, setRecentInputs] = useState<string[]>([]);
   const [outputVideo, setOutputVideo] = useState("");
   const [framesDir, setFramesDir] = useState("");
   const [reencodeOnly, setReencodeOnly] = useState(false);
@@ -74,6 +92,13 @@ export default function App() {
     try { return localStorage.getItem("lastFramesOutDir") || ""; } catch { return ""; }
   });
   const [maxThreads, setMaxThreads] = useState<number>(0);
+  const [rifeRuntimes, setRifeRuntimes] = useState<any[]>([]);
+  const [rifeRuntime, setRifeRuntime] = useState<string>("ncnn_vulkan");
+  const [modelImportStatus, setModelImportStatus] = useState<string>("");
+  const [toolUpdates, setToolUpdates] = useState<any[]>([]);
+  const [toolUpdateStatus, setToolUpdateStatus] = useState<string>("");
+  const [models, setModels] = useState<any[]>([]);
+  const [model, setModel] = useState<string>("");
 
   const [extracting, setExtracting] = useState(false);
 
@@ -99,6 +124,9 @@ export default function App() {
       const r = await invoke<string>("tool_status", { tool: "rife" });
       setFfmpegStatus((f as Status) ?? "unknown");
       setRifeStatus((r as Status) ?? "unknown");
+      setRifeRuntimes(await invoke<any[]>("get_rife_runtimes"));
+      setModels(await invoke<any[]>("list_models"));
+      await loadRecentInputs();
     } catch (e: any) {
       setError(String(e));
     }
@@ -149,11 +177,38 @@ export default function App() {
       multiple: false,
       filters: [{ name: "Video", extensions: ["mp4", "mov", "mkv", "avi", "webm"] }],
     });
-    if (typeof file === "string") setInputVideo(file);
+    if (typeof file === "string") await chooseInput(file);
+  }
+
+  async function loadRecentInputs() {
+    try {
+      const r = await invoke<{ recent: { path: string }[] }>("get_recent_inputs");
+      setRecentInputs(r.recent.map((e) => e.path));
+    } catch {}
+  }
+
+  async function chooseInput(file: string) {
+    setInputVideo(file);
+    if (!outputVideo) {
+      try {
+        const suggested = await invoke<{ path: string; problem: string | null; alternative?: string }>("suggest_output_path", {
+          input: file,
+        });
+        if (suggested.alternative && window.confirm(`${suggested.problem}\n\nSave to ${suggested.alternative} instead?`)) {
+          setOutputVideo(suggested.alternative);
+          return;
+        }
+        setOutputVideo(suggested.path);
+        if (suggested.problem) setError(suggested.problem);
+      } catch (e: any) {
+        setError(String(e));
+      }
+    }
   }
 
   async function pickOutputVideo() {
     const file = await save({
+      defaultPath: outputVideo || undefined,
       filters: [{ name: "Video", extensions: ["mp4", "mov", "mkv"] }],
     });
     if (typeof file === "string") setOutputVideo(file);
@@ -170,6 +225,62 @@ export default function App() {
     }
   }
 
+  // Picking a model applies its profile to the fields shown here; the rest of the profile
+  // (dedup, cut handling, UHD) is applied by the backend when the job starts.
+  function selectModel(name: string) {
+    setModel(name);
+    const m = models.find((x) => x.name === name);
+    if (m?.profile?.max_threads != null) setMaxThreads(m.profile.max_threads);
+  }
+
+  function describeProfile(p: any): string {
+    const parts: string[] = [];
+    if (p?.dedup != null) parts.push(`dedup ${p.dedup ? "on" : "off"}`);
+    if (p?.cut_handling) parts.push(`cuts: ${p.cut_handling}`);
+    if (p?.max_threads != null) parts.push(`threads ${p.max_threads}`);
+    if (p?.uhd) parts.push("UHD mode");
+    return parts.join(", ");
+  }
+
+  async function importModel() {
+    const file = await open({
+      multiple: false,
+      filters: [{ name: "RIFE model", extensions: ["pkl", "pth", "pt", "param", "onnx"] }],
+    });
+    if (typeof file !== "string") return;
+    try {
+      const info = await invoke<any>("inspect_model", { path: file });
+      setModelImportStatus(info.message);
+      if (!info.importable) return;
+      await invoke<string>("import_model", { path: file });
+      setModelImportStatus(`Importing ${info.name}…`);
+    } catch (e: any) {
+      setModelImportStatus(String(e));
+    }
+  }
+
+  async function checkToolUpdates() {
+    setToolUpdateStatus("Checking…");
+    try {
+      const updates = await invoke<any[]>("check_for_tool_updates");
+      const available = updates.filter((u) => u.update_available);
+      setToolUpdates(available);
+      setToolUpdateStatus(available.length ? "" : "Everything is up to date.");
+    } catch (e: any) {
+      setToolUpdates([]);
+      setToolUpdateStatus(String(e));
+    }
+  }
+
+  async function installToolUpdate(update: any) {
+    try {
+      await invoke("install_tool_update", { update });
+      setToolUpdateStatus(`Installing ${update.name ?? update.tool} ${update.latest}…`);
+    } catch (e: any) {
+      setToolUpdateStatus(String(e));
+    }
+  }
+
   async function smoothVideo() {
     if (!canRun) return;
 
@@ -195,10 +306,12 @@ export default function App() {
         }
         try { localStorage.setItem("lastFramesOutDir", dirToUse); } catch {}
         await invoke("reencode_only", {
-          videoPath: inputVideo,
-          outputPath: outputVideo,
-          framesDir: dirToUse,
-          maxThreads,
+          request: {
+            video_path: inputVideo,
+            output_path: outputVideo,
+            frames_dir: dirToUse,
+            max_threads: maxThreads,
+          },
         });
 
         // re-encode runs synchronously; if the backend doesn't emit pipeline_done,
@@ -211,17 +324,27 @@ export default function App() {
       }
 
       const res = await invoke<ExtractFramesResult>("smooth_video", {
-        videoPath: inputVideo,
-        outputPath: outputVideo,
-        maxThreads,
+        request: {
+          video_path: inputVideo,
+          output_path: outputVideo,
+          max_threads: maxThreads,
+          rife_runtime: rifeRuntime,
+          model: model || null,
+        },
       });
+      if (res.skipped) {
+        setPipelineStatus(`Skipped: ${res.skipped}`);
+        setExtracting(false);
+        return;
+      }
       // backend returns frames_in/out folder (useful for debugging / reuse)
       setFramesDir(res.frames_dir);
       if (!framesOutDir) setFramesOutDir(res.frames_dir);
       try { localStorage.setItem("lastFramesOutDir", res.frames_dir); } catch {}
+      loadRecentInputs();
       // stage/progress/done are driven by events
     } catch (e: any) {
-      const msg = String(e);
+      const msg = errorMessage(e);
       setPipelineStatus("Failed.");
       setPipelineError(msg);
       setError(msg);
@@ -239,27 +362,74 @@ export default function App() {
     let unlistenProgress: null | (() => void) = null;
     let unlistenDone: null | (() => void) = null;
     let unlistenLog: null | (() => void) = null;
+    let unlistenStartup: null | (() => void) = null;
+    let unlistenModelLog: null | (() => void) = null;
+    let unlistenModelDone: null | (() => void) = null;
+    let unlistenUpdateLog: null | (() => void) = null;
+    let unlistenUpdateDone: null | (() => void) = null;
+
+    const showStartupReport = (r: any) => {
+      if (!r?.needs_attention) return;
+      const lines = [...(r.migrations ?? []), ...(r.issues ?? [])];
+      setError(`Startup check needs attention:\n${lines.join("\n")}`);
+    };
 
     (async () => {
       try {
-        unlistenProgress = await listen<number>("pipeline_progress", (e) => {
-          const pct = e.payload ?? 0;
-          setPipelineStatus(`Running… ${Math.round(pct)}%`);
+        unlistenStartup = await listen<any>("startup_report", (e) => showStartupReport(e.payload));
+        showStartupReport(await invoke<any>("get_startup_report"));
+
+        unlistenProgress = await listen<any>("pipeline_status", (e) => {
+          const s: any = e.payload ?? {};
+          const parts = [`Running… ${(s.progress ?? 0).toFixed(s.precision ?? 0)}%`];
+          const fps = s.fps_smoothed ?? s.fps;
+          if (fps != null) parts.push(`${fps.toFixed(1)} fps`);
+          setPipelineStatus(parts.join(" · "));
+          setPipelineEta(s.eta_secs != null ? formatEta(s.eta_secs) : "");
         });
 
-        unlistenLog = await listen<string>("pipeline_log", (e) => {
-          const msg = String(e.payload ?? "");
+        // Log lines arrive batched; one state update per batch keeps long runs cheap to render.
+        unlistenLog = await listen<string[]>("pipeline_log_batch", (e) => {
+          const msg = (e.payload ?? []).join("\n");
+          if (!msg) return;
           setPipelineLog((prev) => (prev ? prev + "\n" + msg : msg));
         });
 
+        unlistenModelLog = await listen<string>("model_import_log", (e) => {
+          setModelImportStatus(String(e.payload ?? ""));
+        });
+
+        unlistenModelDone = await listen<any>("model_import_done", (e) => {
+          const p: any = e.payload ?? {};
+          setModelImportStatus(p.ok ? `Imported to ${p.path}` : `Import failed: ${p.message}`);
+        });
+
+        unlistenUpdateLog = await listen<string>("tool_update_log", (e) => {
+          setToolUpdateStatus(String(e.payload ?? ""));
+        });
+
+        unlistenUpdateDone = await listen<any>("tool_update_done", (e) => {
+          const p: any = e.payload ?? {};
+          setToolUpdateStatus(p.ok ? `${p.tool} ${p.version}: ${p.message}` : `Update failed: ${p.message}`);
+          if (p.ok) {
+            setToolUpdates((prev) => prev.filter((u) => (u.name ?? u.tool) !== p.tool));
+            refresh();
+          }
+        });
+
         unlistenDone = await listen<any>("pipeline_done", (e) => {
           const p: any = e.payload as any;
           setExtracting(false);
+          setPipelineEta("");
           if (p?.ok) {
             setPipelineStatus(p.message || "Done.");
             setPipelineError("");
           } else {
-            const msg = p?.message ? String(p.message) : "Failed";
+            let msg = p?.message ? String(p.message) : "Failed";
+            if (p?.error?.title) msg = `${p.error.title}: ${p.error.suggestion}\n\n${msg}`;
+            if (p?.output_tail?.lines?.length) {
+              msg += `\n\nLast output of ${p.output_tail.process}:\n${p.output_tail.lines.join("\n")}`;
+            }
             setPipelineStatus("Failed.");
             setPipelineError(msg);
             setError(msg);
@@ -274,6 +444,11 @@ export default function App() {
       try { unlistenProgress?.(); } catch {}
       try { unlistenLog?.(); } catch {}
       try { unlistenDone?.(); } catch {}
+      try { unlistenStartup?.(); } catch {}
+      try { unlistenModelLog?.(); } catch {}
+      try { unlistenModelDone?.(); } catch {}
+      try { unlistenUpdateLog?.(); } catch {}
+      try { unlistenUpdateDone?.(); } catch {}
     };
   }, []);
 
@@ -329,6 +504,31 @@ export default function App() {
               </div>
             </div>
 
+            <div>
+              <div style={{ display: "flex", gap: 8, alignItems: "center" }}>
+                <button style={buttonStyle} onClick={importModel}>Import model…</button>
+                <span style={{ fontSize: 12, opacity: 0.85 }}>ncnn folder (flownet.param) or PyTorch checkpoint</span>
+              </div>
+              {modelImportStatus && (
+                <div style={{ marginTop: 6, fontFamily: "monospace", fontSize: 12, whiteSpace: "pre-wrap", opacity: 0.9 }}>
+                  {modelImportStatus}
+                </div>
+              )}
+            </div>
+
+            <div>
+              <button style={buttonStyle} onClick={checkToolUpdates}>Check for updates</button>
+              {toolUpdates.map((u) => (
+                <div key={`${u.tool}-${u.name ?? ""}`} style={{ display: "flex", gap: 8, alignItems: "center", marginTop: 6, fontSize: 12 }}>
+                  <span title={u.changelog} style={{ flex: 1 }}>
+                    {u.name ?? u.tool}: {u.installed ? `${u.installed} → ` : ""}{u.latest}
+                  </span>
+                  <button style={buttonStyle} onClick={() => installToolUpdate(u)} disabled={!u.download_url}>Install</button>
+                </div>
+              ))}
+              {toolUpdateStatus && <div style={{ marginTop: 6, fontSize: 12, opacity: 0.85 }}>{toolUpdateStatus}</div>}
+            </div>
+
             {paths.length > 0 && (
               <div style={{ fontFamily: "monospace", fontSize: 12, whiteSpace: "pre-wrap", opacity: 0.9 }}>
                 {paths.join("\n")}
@@ -346,6 +546,21 @@ export default function App() {
           <div style={{ display: "flex", gap: 8, marginBottom: 10 }}>
             <input style={{ ...inputStyle, flex: 1 }} value={inputVideo} readOnly placeholder="Choose input video…" />
             <button style={buttonStyle} onClick={pickInputVideo}>Browse…</button>
+            {recentInputs.length > 0 && (
+              <select style={inputStyle} value="" onChange={(e) => e.target.value && chooseInput(e.target.value)}>
+                <option value="">Recent…</option>
+                {recentInputs.map((p) => (
+                  <option key={p} value={p}>{p.split(/[\\/]/).pop()}</option>
+                ))}
+              </select>
+            )}
+            <button
+              style={buttonStyle}
+              disabled={!inputVideo}
+              onClick={() => invoke("pin_favorite", { path: inputVideo }).catch((e) => setError(String(e)))}
+            >
+              Pin folder
+            </button>
           </div>
 
           <div style={{ marginBottom: 6, opacity: 0.9 }}>
@@ -415,9 +630,48 @@ export default function App() {
             />
           </div>
 
+          <div style={{ marginTop: 10 }}>
+            <label style={{ fontSize: 12, opacity: 0.8 }}>Model</label>
+            <select value={model} onChange={(e) => selectModel(e.currentTarget.value)} style={{ marginLeft: 8 }}>
+              <option value="">Default</option>
+              {models.map((m) => (
+                <option key={m.name} value={m.name}>
+                  {m.name}
+                  {m.is_default ? " (default)" : ""}
+                </option>
+              ))}
+            </select>
+            {(() => {
+              const summary = describeProfile(models.find((x) => x.name === model)?.profile);
+              return summary && <span style={{ fontSize: 12, opacity: 0.7, marginLeft: 8 }}>Profile: {summary}</span>;
+            })()}
+          </div>
+
+          <div style={{ marginTop: 10 }}>
+            <label style={{ fontSize: 12, opacity: 0.8 }}>RIFE runtime</label>
+            <select
+              value={rifeRuntime}
+              onChange={(e) => setRifeRuntime(e.currentTarget.value)}
+              style={{ marginLeft: 8 }}
+            >
+              {rifeRuntimes.map((r) => (
+                <option
+                  key={r.runtime}
+                  value={r.runtime}
+                  disabled={!r.supported || !r.installed}
+                  title={r.reason ?? (r.installed ? r.installed : `Install the ${r.tool} tool first`)}
+                >
+                  {r.label}
+                  {!r.supported ? " (not supported here)" : !r.installed ? " (not installed)" : ""}
+                </option>
+              ))}
+            </select>
+          </div>
+
           {pipelineStatus && (
             <div style={{ marginTop: 12, fontFamily: "monospace", fontSize: 12, opacity: 0.9 }}>
               {pipelineStatus}
+              {pipelineEta && ` · ${pipelineEta} left`}
             </div>
           )}
 